@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS scores (
+  id TEXT PRIMARY KEY,
+  name TEXT NOT NULL,
+  score BIGINT NOT NULL,
+  created_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS scores_score_idx ON scores (score DESC, created_at ASC);
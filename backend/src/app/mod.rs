@@ -1,2 +1 @@
-pub mod room_name;
 pub mod time;
@@ -6,6 +6,7 @@ pub struct RoomCloudInitConfig<'a> {
     pub control_plane_url: &'a str,
     pub heartbeat_token: &'a str,
     pub room_proxy_secret: &'a str,
+    pub room_admin_token: Option<&'a str>,
     pub max_human_players: usize,
     pub port: u16,
 }
@@ -18,6 +19,10 @@ pub fn build_room_cloud_init(config: &RoomCloudInitConfig<'_>) -> String {
     let control_plane_url = shell_escape(config.control_plane_url);
     let heartbeat_token = shell_escape(config.heartbeat_token);
     let room_proxy_secret = shell_escape(config.room_proxy_secret);
+    let admin_token_line = config
+        .room_admin_token
+        .map(|token| format!("\n    -e ROOM_ADMIN_TOKEN={}", shell_escape(token)))
+        .unwrap_or_default();
     let port = config.port;
     let max_human_players = config.max_human_players;
 
@@ -47,7 +52,7 @@ runcmd:
     -e MAX_HUMAN_PLAYERS={max_human_players}
     -e CONTROL_PLANE_URL={control_plane_url}
     -e ROOM_HEARTBEAT_TOKEN={heartbeat_token}
-    -e ROOM_PROXY_SECRET={room_proxy_secret}
+    -e ROOM_PROXY_SECRET={room_proxy_secret}{admin_token_line}
     {image}
 "#,
     )
@@ -72,11 +77,30 @@ mod tests {
             control_plane_url: "https://control.example.com",
             heartbeat_token: "heart",
             room_proxy_secret: "proxy",
+            room_admin_token: None,
             max_human_players: 25,
             port: 8787,
         });
         assert!(script.contains("ROOM_ID='room-abc'"));
         assert!(script.contains("ghcr.io/example/snake:latest"));
+        assert!(!script.contains("ROOM_ADMIN_TOKEN"));
+    }
+
+    #[test]
+    fn cloud_init_passes_admin_token_when_configured() {
+        let script = build_room_cloud_init(&RoomCloudInitConfig {
+            image: "ghcr.io/example/snake:latest",
+            registry_username: None,
+            registry_password: None,
+            room_id: "room-admin",
+            control_plane_url: "https://control.example.com",
+            heartbeat_token: "heart",
+            room_proxy_secret: "proxy",
+            room_admin_token: Some("admin"),
+            max_human_players: 25,
+            port: 8787,
+        });
+        assert!(script.contains("-e ROOM_PROXY_SECRET='proxy'\n    -e ROOM_ADMIN_TOKEN='admin'\n"));
     }
 
     #[test]
@@ -89,6 +113,7 @@ mod tests {
             control_plane_url: "https://control.example.com",
             heartbeat_token: "heart",
             room_proxy_secret: "proxy",
+            room_admin_token: None,
             max_human_players: 25,
             port: 8787,
         });
@@ -105,6 +130,7 @@ mod tests {
             control_plane_url: "https://control.example.com",
             heartbeat_token: "heart",
             room_proxy_secret: "proxy",
+            room_admin_token: None,
             max_human_players: 25,
             port: 8787,
         });
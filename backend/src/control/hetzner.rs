@@ -75,6 +75,14 @@ impl HetznerClient {
         }
     }
 
+    #[cfg(test)]
+    pub fn with_base_url(token: String, base_url: String) -> Self {
+        Self {
+            base_url,
+            ..Self::new(token)
+        }
+    }
+
     pub async fn create_server(
         &self,
         params: &CreateServerParams<'_>,
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+/// Longest idempotency key accepted from clients; longer keys are ignored.
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 128;
+
+/// Remembers the outcome of recent keyed requests so a client retry gets the original answer
+/// instead of repeating its side effects.
+#[derive(Debug)]
+pub struct IdempotencyCache<T> {
+    entries: HashMap<String, Entry<T>>,
+}
+
+#[derive(Debug)]
+enum Entry<T> {
+    InFlight { started_at: i64 },
+    Done { value: T, expires_at: i64 },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Begin<T> {
+    /// No record of this key; the caller owns it until `complete` or `abandon`.
+    Fresh,
+    Cached(T),
+    InFlight,
+}
+
+impl<T: Clone> IdempotencyCache<T> {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Claims `key` for a new request, or reports what an earlier request with it produced.
+    /// In-flight claims older than `in_flight_timeout_ms` are treated as abandoned.
+    pub fn begin(&mut self, key: &str, now: i64, in_flight_timeout_ms: i64) -> Begin<T> {
+        self.prune(now, in_flight_timeout_ms);
+        match self.entries.get(key) {
+            Some(Entry::Done { value, .. }) => Begin::Cached(value.clone()),
+            Some(Entry::InFlight { .. }) => Begin::InFlight,
+            None => {
+                self.entries
+                    .insert(key.to_string(), Entry::InFlight { started_at: now });
+                Begin::Fresh
+            }
+        }
+    }
+
+    /// Stores the result for `key` until `expires_at`.
+    pub fn complete(&mut self, key: &str, value: T, expires_at: i64) {
+        self.entries
+            .insert(key.to_string(), Entry::Done { value, expires_at });
+    }
+
+    /// Releases a claim whose request failed so a retry can try again.
+    pub fn abandon(&mut self, key: &str) {
+        if matches!(self.entries.get(key), Some(Entry::InFlight { .. })) {
+            self.entries.remove(key);
+        }
+    }
+
+    fn prune(&mut self, now: i64, in_flight_timeout_ms: i64) {
+        self.entries.retain(|_, entry| match entry {
+            Entry::InFlight { started_at } => now - *started_at < in_flight_timeout_ms,
+            Entry::Done { expires_at, .. } => now < *expires_at,
+        });
+    }
+}
+
+/// Trims a client-supplied key, dropping empty or oversized ones.
+pub fn normalize_idempotency_key(raw: Option<&str>) -> Option<String> {
+    raw.map(str::trim)
+        .filter(|key| !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LEN)
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_idempotency_key, Begin, IdempotencyCache, MAX_IDEMPOTENCY_KEY_LEN};
+
+    #[test]
+    fn completed_keys_return_cached_value_until_expiry() {
+        let mut cache = IdempotencyCache::new();
+        assert_eq!(cache.begin("key-1", 0, 1_000), Begin::Fresh);
+        assert_eq!(cache.begin("key-1", 10, 1_000), Begin::InFlight);
+
+        cache.complete("key-1", "room-a".to_string(), 500);
+        assert_eq!(
+            cache.begin("key-1", 100, 1_000),
+            Begin::Cached("room-a".to_string())
+        );
+        assert_eq!(cache.begin("key-1", 500, 1_000), Begin::Fresh);
+    }
+
+    #[test]
+    fn abandoned_and_stuck_claims_free_the_key() {
+        let mut cache = IdempotencyCache::<String>::new();
+        assert_eq!(cache.begin("key-1", 0, 1_000), Begin::Fresh);
+        cache.abandon("key-1");
+        assert_eq!(cache.begin("key-1", 10, 1_000), Begin::Fresh);
+
+        assert_eq!(cache.begin("key-2", 0, 1_000), Begin::Fresh);
+        assert_eq!(cache.begin("key-2", 1_000, 1_000), Begin::Fresh);
+    }
+
+    #[test]
+    fn normalize_rejects_blank_and_oversized_keys() {
+        assert_eq!(
+            normalize_idempotency_key(Some("  abc  ")),
+            Some("abc".to_string())
+        );
+        assert_eq!(normalize_idempotency_key(Some("   ")), None);
+        let oversized = "k".repeat(MAX_IDEMPOTENCY_KEY_LEN + 1);
+        assert_eq!(normalize_idempotency_key(Some(&oversized)), None);
+        assert_eq!(normalize_idempotency_key(None), None);
+    }
+}
@@ -1,35 +1,50 @@
 mod cloud_init;
 mod hetzner;
+mod idempotency;
+mod queue;
 
-use crate::app::room_name::sanitize_room_name;
 use crate::app::time::now_millis;
 use crate::control::cloud_init::{build_room_cloud_init, RoomCloudInitConfig};
 use crate::control::hetzner::{CreateServerParams, HetznerClient};
+use crate::control::idempotency::{normalize_idempotency_key, Begin, IdempotencyCache};
+use crate::control::queue::{MatchmakeQueue, QueueFull, TicketStatus};
+use crate::shared::api_error::{ErrorCode, ErrorResponse};
+use crate::shared::cors::cors_layer_from_env;
+use crate::shared::room_name::sanitize_room_name;
 use crate::shared::room_token::{sign_room_token, RoomTokenClaims};
 use anyhow::{bail, Context};
 use axum::{
     extract::{Query, State},
-    http::{HeaderMap, Method, StatusCode},
-    response::IntoResponse,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::Mutex;
-use tower_http::cors::{Any, CorsLayer};
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
 
 const ROOM_LABEL_SELECTOR: &str = "app=spherical-snake-room,managed_by=snake-control";
+/// A keyed matchmake still unresolved after this long (e.g. the control plane crashed mid-way)
+/// no longer blocks retries; it comfortably covers a server provision.
+const MATCHMAKE_IN_FLIGHT_TIMEOUT_MS: i64 = 10 * 60 * 1000;
 
 #[derive(Clone)]
 pub struct ControlState {
     config: Arc<ControlConfig>,
     registry: Arc<Mutex<RoomRegistry>>,
     hetzner: HetznerClient,
-    provision_lock: Arc<Mutex<()>>,
+    /// Caps concurrent `provision_room` calls at `provision_max_in_flight`.
+    provision_slots: Arc<Semaphore>,
+    /// `provision_room` calls currently running, so warm-room top-ups count servers that are
+    /// already on their way.
+    provisions_in_flight: Arc<AtomicUsize>,
+    matchmake_queue: Arc<Mutex<MatchmakeQueue<RoomRecord>>>,
+    matchmake_idempotency: Arc<Mutex<IdempotencyCache<MatchmakeResponse>>>,
     http: reqwest::Client,
 }
 
@@ -38,24 +53,107 @@ struct ControlConfig {
     capacity: usize,
     min_warm_rooms: usize,
     idle_scale_down_secs: i64,
+    stale_heartbeat_secs: i64,
     token_ttl_secs: i64,
+    matchmake_queue_capacity: usize,
+    provision_max_in_flight: usize,
+    /// Hard ceiling on provisioned rooms, counting ones still being provisioned. `None` means
+    /// unlimited.
+    max_total_rooms: Option<usize>,
+    /// Idle scale-downs wait this long after the last provisioned room, and warm-room top-ups
+    /// wait `scale_up_cooldown_ms` after the last scale-down, so borderline load doesn't churn
+    /// servers. Provisioning for players who have no free seat is never held back.
+    scale_down_cooldown_ms: i64,
+    scale_up_cooldown_ms: i64,
     room_port: u16,
     room_firewall_ids: Vec<i64>,
     room_image: String,
     room_registry_username: Option<String>,
     room_registry_password: Option<String>,
-    hetzner_location: String,
+    regions: Vec<RegionLocation>,
     hetzner_server_type: String,
     hetzner_image: String,
     control_plane_url: String,
     room_heartbeat_token: String,
     room_token_secret: String,
     room_proxy_secret: String,
+    /// Bearer token for the rooms' `/internal/*` endpoints. Consolidation needs it to drain
+    /// rooms and is skipped when unset.
+    room_admin_token: Option<String>,
+    consolidation: ConsolidationPolicy,
+    fill_policy: RoomFillPolicy,
+}
+
+/// Which room a new player lands in when no preferred room has a free seat. Ties go to the
+/// lowest `room_id` so assignment is deterministic.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum RoomFillPolicy {
+    /// Least-populated room first, keeping rooms evenly filled.
+    #[default]
+    Spread,
+    /// Fullest room with a free seat first, so fewer rooms stay warm at low traffic.
+    Pack,
+}
+
+impl RoomFillPolicy {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "spread" => Some(Self::Spread),
+            "pack" => Some(Self::Pack),
+            _ => None,
+        }
+    }
+
+    fn from_env() -> anyhow::Result<Self> {
+        match env::var("ROOM_FILL_POLICY") {
+            Ok(raw) if !raw.trim().is_empty() => Self::parse(&raw).with_context(|| {
+                format!(
+                    "ROOM_FILL_POLICY must be 'spread' or 'pack', got '{}'",
+                    raw.trim()
+                )
+            }),
+            _ => Ok(Self::default()),
+        }
+    }
+}
+
+/// When two sparse rooms in a region get merged. Hysteresis comes from requiring both rooms to
+/// stay under `low_watermark` for `sustain_ms`, keeping the merged room at or below
+/// `max_merged_fill`, and waiting `cooldown_ms` between merges.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ConsolidationPolicy {
+    /// Fraction of capacity a populated room must stay below to be merged.
+    low_watermark: f64,
+    /// Largest fraction of capacity the merged room may reach.
+    max_merged_fill: f64,
+    sustain_ms: i64,
+    cooldown_ms: i64,
+}
+
+impl Default for ConsolidationPolicy {
+    fn default() -> Self {
+        Self {
+            low_watermark: 0.3,
+            max_merged_fill: 0.75,
+            sustain_ms: 120 * 1000,
+            cooldown_ms: 300 * 1000,
+        }
+    }
+}
+
+/// A player-facing region name mapped to the Hetzner location rooms in it are created in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RegionLocation {
+    region: String,
+    location: String,
 }
 
 #[derive(Debug, Default)]
 struct RoomRegistry {
     rooms: HashMap<String, RoomRecord>,
+    last_consolidation_at: Option<i64>,
+    last_scale_up_at: Option<i64>,
+    last_scale_down_at: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -65,21 +163,45 @@ struct RoomRecord {
     #[serde(rename = "serverId")]
     server_id: i64,
     origin: String,
+    region: String,
     #[serde(rename = "playerCount")]
     player_count: usize,
     #[serde(rename = "lastHeartbeatAt")]
     last_heartbeat_at: i64,
+    /// Last time the room sent any heartbeat; `last_heartbeat_at` only moves while it has players.
+    #[serde(rename = "lastContactAt")]
+    last_contact_at: i64,
     #[serde(rename = "lastAssignedAt")]
     last_assigned_at: i64,
+    /// Since when the room has been populated but under the consolidation low watermark.
+    #[serde(skip)]
+    sparse_since: Option<i64>,
+    /// Set once the room is being drained into another; it gets no new assignments.
+    draining: bool,
+    /// Provisioned (or discovered) empty and not yet given a player. Only these count toward
+    /// `min_warm_rooms`, so half-full rooms don't stand in for a pre-warmed one.
+    warm: bool,
+}
+
+impl RoomRecord {
+    fn reserve_seat(&mut self, now: i64) {
+        self.player_count = self.player_count.saturating_add(1);
+        self.last_assigned_at = now;
+        self.warm = false;
+    }
 }
 
 #[derive(Debug, Deserialize, Default)]
 struct MatchmakeRequest {
     #[serde(rename = "preferredRoom")]
     preferred_room: Option<String>,
+    region: Option<String>,
+    /// Retries carrying the same key get the original assignment instead of another seat.
+    #[serde(rename = "idempotencyKey")]
+    idempotency_key: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 struct MatchmakeResponse {
     #[serde(rename = "roomId")]
     room_id: String,
@@ -88,6 +210,19 @@ struct MatchmakeResponse {
     capacity: usize,
     #[serde(rename = "expiresAt")]
     expires_at: i64,
+    region: String,
+}
+
+#[derive(Debug, Serialize)]
+struct MatchmakeQueuedResponse {
+    ticket: String,
+    #[serde(rename = "queuePosition")]
+    queue_position: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct MatchmakeStatusQuery {
+    ticket: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -100,15 +235,24 @@ struct RoomHeartbeatRequest {
     total_sessions: usize,
 }
 
+/// Body of a room's `POST /internal/drain` when draining it into another room.
 #[derive(Debug, Serialize)]
-struct OkResponse {
-    ok: bool,
+struct RoomDrainRequest<'a> {
+    enabled: bool,
+    migrate: RoomMigrateTarget<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct RoomMigrateTarget<'a> {
+    #[serde(rename = "roomId")]
+    room_id: &'a str,
+    #[serde(rename = "roomToken")]
+    room_token: &'a str,
 }
 
 #[derive(Debug, Serialize)]
-struct ErrorResponse {
+struct OkResponse {
     ok: bool,
-    error: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -116,6 +260,50 @@ struct RoomsResponse {
     rooms: Vec<RoomRecord>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RoomSortKey {
+    PlayerCount,
+    LastAssignedAt,
+    LastHeartbeatAt,
+}
+
+/// Optional `sort`, `order` and `minPlayers` query params for `GET /internal/rooms`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RoomListQuery {
+    sort: Option<RoomSortKey>,
+    descending: bool,
+    min_players: usize,
+}
+
+impl RoomListQuery {
+    fn from_params(params: &HashMap<String, String>) -> Result<Self, String> {
+        let sort = match params.get("sort").map(String::as_str) {
+            None => None,
+            Some("playerCount") => Some(RoomSortKey::PlayerCount),
+            Some("lastAssignedAt") => Some(RoomSortKey::LastAssignedAt),
+            Some("lastHeartbeatAt") => Some(RoomSortKey::LastHeartbeatAt),
+            Some(other) => return Err(format!("Unknown sort '{other}'")),
+        };
+        let descending = match params.get("order").map(String::as_str) {
+            None | Some("asc") => false,
+            Some("desc") => true,
+            Some(other) => return Err(format!("Unknown order '{other}'")),
+        };
+        let min_players = match params.get("minPlayers") {
+            None => 0,
+            Some(value) => value
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| "minPlayers must be a non-negative integer".to_string())?,
+        };
+        Ok(Self {
+            sort,
+            descending,
+            min_players,
+        })
+    }
+}
+
 pub async fn run() -> anyhow::Result<()> {
     let config = Arc::new(ControlConfig::from_env()?);
     let state = ControlState {
@@ -124,7 +312,12 @@ pub async fn run() -> anyhow::Result<()> {
         hetzner: HetznerClient::new(
             env::var("HETZNER_API_TOKEN").context("missing HETZNER_API_TOKEN")?,
         ),
-        provision_lock: Arc::new(Mutex::new(())),
+        provision_slots: Arc::new(Semaphore::new(config.provision_max_in_flight)),
+        provisions_in_flight: Arc::new(AtomicUsize::new(0)),
+        matchmake_queue: Arc::new(Mutex::new(MatchmakeQueue::new(
+            config.matchmake_queue_capacity,
+        ))),
+        matchmake_idempotency: Arc::new(Mutex::new(IdempotencyCache::new())),
         http: reqwest::Client::new(),
     };
 
@@ -147,14 +340,12 @@ pub async fn run() -> anyhow::Result<()> {
         }
     });
 
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods([Method::GET, Method::POST])
-        .allow_headers(Any);
+    let cors = cors_layer_from_env()?;
 
     let app = Router::new()
         .route("/api/health", get(health))
         .route("/api/matchmake", post(matchmake))
+        .route("/api/matchmake/status", get(matchmake_status))
         .route("/internal/room-heartbeat", post(room_heartbeat))
         .route("/internal/rooms", get(list_rooms))
         .layer(cors)
@@ -171,6 +362,34 @@ pub async fn run() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// `provision_room` refused because `MAX_TOTAL_ROOMS` rooms already exist or are on their way.
+#[derive(Debug)]
+struct ServerFull;
+
+impl std::fmt::Display for ServerFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("room ceiling reached")
+    }
+}
+
+impl std::error::Error for ServerFull {}
+
+/// Counts a running `provision_room` call, releasing the count however the call ends.
+struct InFlightGuard<'a>(&'a AtomicUsize);
+
+impl<'a> InFlightGuard<'a> {
+    fn enter(counter: &'a AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Self(counter)
+    }
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 impl ControlConfig {
     fn from_env() -> anyhow::Result<Self> {
         Self {
@@ -186,10 +405,29 @@ impl ControlConfig {
                 .ok()
                 .and_then(|value| value.parse::<i64>().ok())
                 .unwrap_or(180),
+            stale_heartbeat_secs: env::var("ROOM_STALE_HEARTBEAT_SECS")
+                .ok()
+                .and_then(|value| value.parse::<i64>().ok())
+                .unwrap_or(180),
             token_ttl_secs: env::var("ROOM_TOKEN_TTL_SECS")
                 .ok()
                 .and_then(|value| value.parse::<i64>().ok())
                 .unwrap_or(90),
+            matchmake_queue_capacity: env::var("MATCHMAKE_QUEUE_CAPACITY")
+                .ok()
+                .and_then(|value| value.parse::<usize>().ok())
+                .unwrap_or(100),
+            provision_max_in_flight: env::var("ROOM_PROVISION_MAX_IN_FLIGHT")
+                .ok()
+                .and_then(|value| value.parse::<usize>().ok())
+                .unwrap_or(1)
+                .max(1),
+            max_total_rooms: env::var("MAX_TOTAL_ROOMS")
+                .ok()
+                .and_then(|value| value.trim().parse::<usize>().ok())
+                .filter(|value| *value > 0),
+            scale_down_cooldown_ms: cooldown_ms_from_env("ROOM_SCALE_DOWN_COOLDOWN_SECS", 180),
+            scale_up_cooldown_ms: cooldown_ms_from_env("ROOM_SCALE_UP_COOLDOWN_SECS", 60),
             room_port: env::var("ROOM_PORT")
                 .ok()
                 .and_then(|value| value.parse::<u16>().ok())
@@ -204,7 +442,9 @@ impl ControlConfig {
                 .ok()
                 .map(|value| value.trim().to_string())
                 .filter(|value| !value.is_empty()),
-            hetzner_location: env::var("HETZNER_LOCATION").unwrap_or_else(|_| "ash".to_string()),
+            regions: parse_regions_env(
+                &env::var("HETZNER_LOCATION").unwrap_or_else(|_| "ash".to_string()),
+            )?,
             hetzner_server_type: env::var("HETZNER_SERVER_TYPE")
                 .unwrap_or_else(|_| "cpx11".to_string()),
             hetzner_image: env::var("HETZNER_IMAGE").unwrap_or_else(|_| "ubuntu-24.04".to_string()),
@@ -216,12 +456,69 @@ impl ControlConfig {
                 .context("missing ROOM_TOKEN_SECRET")?,
             room_proxy_secret: env::var("ROOM_PROXY_SECRET")
                 .context("missing ROOM_PROXY_SECRET")?,
+            room_admin_token: env::var("ROOM_ADMIN_TOKEN")
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty()),
+            consolidation: ConsolidationPolicy::from_env(),
+            fill_policy: RoomFillPolicy::from_env()?,
         }
         .validate_registry_auth()
     }
 }
 
+fn cooldown_ms_from_env(name: &str, default_secs: i64) -> i64 {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.trim().parse::<i64>().ok())
+        .filter(|value| *value >= 0)
+        .unwrap_or(default_secs)
+        * 1000
+}
+
+impl ConsolidationPolicy {
+    fn from_env() -> Self {
+        let defaults = Self::default();
+        let fraction = |name: &str, default: f64| {
+            env::var(name)
+                .ok()
+                .and_then(|value| value.trim().parse::<f64>().ok())
+                .filter(|value| value.is_finite() && (0.0..=1.0).contains(value))
+                .unwrap_or(default)
+        };
+        let secs = |name: &str, default_ms: i64| {
+            env::var(name)
+                .ok()
+                .and_then(|value| value.trim().parse::<i64>().ok())
+                .filter(|value| *value >= 0)
+                .map_or(default_ms, |value| value * 1000)
+        };
+        Self {
+            low_watermark: fraction("ROOM_CONSOLIDATE_LOW_WATERMARK", defaults.low_watermark),
+            max_merged_fill: fraction("ROOM_CONSOLIDATE_MAX_FILL", defaults.max_merged_fill),
+            sustain_ms: secs("ROOM_CONSOLIDATE_SUSTAIN_SECS", defaults.sustain_ms),
+            cooldown_ms: secs("ROOM_CONSOLIDATE_COOLDOWN_SECS", defaults.cooldown_ms),
+        }
+    }
+}
+
 impl ControlConfig {
+    fn default_region(&self) -> &RegionLocation {
+        &self.regions[0]
+    }
+
+    /// Maps a client-requested region to a configured one, falling back to the default region
+    /// for missing or unknown names.
+    fn region_for(&self, requested: Option<&str>) -> &RegionLocation {
+        requested
+            .and_then(|requested| {
+                self.regions
+                    .iter()
+                    .find(|region| region.region.eq_ignore_ascii_case(requested.trim()))
+            })
+            .unwrap_or_else(|| self.default_region())
+    }
+
     fn validate_registry_auth(self) -> anyhow::Result<Self> {
         let has_user = self.room_registry_username.is_some();
         let has_pass = self.room_registry_password.is_some();
@@ -232,6 +529,205 @@ impl ControlConfig {
     }
 }
 
+impl RoomRegistry {
+    /// Rooms that haven't sent a heartbeat since `cutoff`, whatever their recorded player count.
+    fn stale_room_ids(&self, cutoff: i64) -> Vec<String> {
+        let mut room_ids: Vec<String> = self
+            .rooms
+            .values()
+            .filter(|record| record.last_contact_at <= cutoff)
+            .map(|record| record.room_id.clone())
+            .collect();
+        room_ids.sort();
+        room_ids
+    }
+
+    /// Rooms with at least `query.min_players` players, ordered by the requested key. Ties (and
+    /// unsorted listings) fall back to room id so the output is stable between calls.
+    fn list_rooms(&self, query: RoomListQuery) -> Vec<RoomRecord> {
+        let mut rooms: Vec<RoomRecord> = self
+            .rooms
+            .values()
+            .filter(|record| record.player_count >= query.min_players)
+            .cloned()
+            .collect();
+        rooms.sort_by(|a, b| {
+            let ordering = match query.sort {
+                None => std::cmp::Ordering::Equal,
+                Some(RoomSortKey::PlayerCount) => a.player_count.cmp(&b.player_count),
+                Some(RoomSortKey::LastAssignedAt) => a.last_assigned_at.cmp(&b.last_assigned_at),
+                Some(RoomSortKey::LastHeartbeatAt) => a.last_heartbeat_at.cmp(&b.last_heartbeat_at),
+            };
+            let ordering = if query.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            };
+            ordering.then_with(|| a.room_id.cmp(&b.room_id))
+        });
+        rooms
+    }
+
+    /// Reserves a seat, trying `preferred_room` first and then the room `fill_policy` picks.
+    /// When `region` is set, only rooms in that region are considered for the fallback.
+    fn reserve_room(
+        &mut self,
+        preferred_room: Option<&str>,
+        region: Option<&str>,
+        capacity: usize,
+        fill_policy: RoomFillPolicy,
+        now: i64,
+    ) -> Option<RoomRecord> {
+        if let Some(preferred_room) = preferred_room {
+            if let Some(record) = self.rooms.get_mut(preferred_room) {
+                if record.player_count < capacity && !record.draining {
+                    record.reserve_seat(now);
+                    return Some(record.clone());
+                }
+            }
+        }
+
+        let next_room_id = self
+            .rooms
+            .values()
+            .filter(|record| record.player_count < capacity && !record.draining)
+            .filter(|record| region.is_none_or(|region| record.region == region))
+            .min_by(|a, b| {
+                let by_fill = match fill_policy {
+                    RoomFillPolicy::Spread => a.player_count.cmp(&b.player_count),
+                    RoomFillPolicy::Pack => b.player_count.cmp(&a.player_count),
+                };
+                by_fill.then_with(|| a.room_id.cmp(&b.room_id))
+            })
+            .map(|record| record.room_id.clone())?;
+
+        let record = self.rooms.get_mut(&next_room_id)?;
+        record.reserve_seat(now);
+        Some(record.clone())
+    }
+
+    /// Gives back a seat `reserve_room` handed to a player who never claimed it. A heartbeat
+    /// since then may already have replaced the count, hence the saturating decrement.
+    fn release_seat(&mut self, room_id: &str) {
+        if let Some(record) = self.rooms.get_mut(room_id) {
+            record.player_count = record.player_count.saturating_sub(1);
+        }
+    }
+
+    /// Starts or clears each room's `sparse_since` timer. Empty rooms are left to idle
+    /// scale-down rather than merged.
+    fn track_sparse_rooms(&mut self, policy: &ConsolidationPolicy, capacity: usize, now: i64) {
+        let low_watermark = capacity as f64 * policy.low_watermark;
+        for record in self.rooms.values_mut() {
+            let sparse = !record.draining
+                && record.player_count > 0
+                && (record.player_count as f64) < low_watermark;
+            record.sparse_since = if sparse {
+                Some(record.sparse_since.unwrap_or(now))
+            } else {
+                None
+            };
+        }
+    }
+
+    /// Picks a `(source, target)` pair to merge: two rooms in the same region that have both
+    /// been sparse for `sustain_ms` and fit together within `max_merged_fill`. The smallest
+    /// room is drained into the fullest one it fits in. Returns `None` during the cooldown.
+    fn consolidation_pair(
+        &self,
+        policy: &ConsolidationPolicy,
+        capacity: usize,
+        now: i64,
+    ) -> Option<(String, String)> {
+        if self
+            .last_consolidation_at
+            .is_some_and(|at| now - at < policy.cooldown_ms)
+        {
+            return None;
+        }
+        let max_merged = (capacity as f64 * policy.max_merged_fill).floor() as usize;
+        let mut sparse: Vec<&RoomRecord> = self
+            .rooms
+            .values()
+            .filter(|record| !record.draining)
+            .filter(|record| {
+                record
+                    .sparse_since
+                    .is_some_and(|since| now - since >= policy.sustain_ms)
+            })
+            .collect();
+        sparse.sort_by(|a, b| {
+            a.player_count
+                .cmp(&b.player_count)
+                .then_with(|| a.room_id.cmp(&b.room_id))
+        });
+        sparse.iter().find_map(|source| {
+            sparse
+                .iter()
+                .filter(|target| target.room_id != source.room_id)
+                .filter(|target| target.region == source.region)
+                .filter(|target| target.player_count >= source.player_count)
+                .filter(|target| target.player_count + source.player_count <= max_merged)
+                .max_by(|a, b| {
+                    a.player_count
+                        .cmp(&b.player_count)
+                        .then_with(|| b.room_id.cmp(&a.room_id))
+                })
+                .map(|target| (source.room_id.clone(), target.room_id.clone()))
+        })
+    }
+
+    /// Marks `source` as draining and reserves its players' seats in `target`. Returns both
+    /// records as they were before the reservation.
+    fn begin_consolidation(
+        &mut self,
+        source_id: &str,
+        target_id: &str,
+        now: i64,
+    ) -> Option<(RoomRecord, RoomRecord)> {
+        let source = self.rooms.get(source_id)?.clone();
+        let target = self.rooms.get_mut(target_id)?;
+        let target_before = target.clone();
+        target.player_count = target.player_count.saturating_add(source.player_count);
+        target.last_assigned_at = now;
+        target.sparse_since = None;
+        target.warm = false;
+        let source_record = self.rooms.get_mut(source_id)?;
+        source_record.draining = true;
+        source_record.sparse_since = None;
+        self.last_consolidation_at = Some(now);
+        Some((source, target_before))
+    }
+
+    fn warm_room_count(&self) -> usize {
+        self.rooms
+            .values()
+            .filter(|record| record.warm && !record.draining)
+            .count()
+    }
+
+    fn scale_down_cooling(&self, cooldown_ms: i64, now: i64) -> bool {
+        self.last_scale_up_at
+            .is_some_and(|at| now - at < cooldown_ms)
+    }
+
+    fn scale_up_cooling(&self, cooldown_ms: i64, now: i64) -> bool {
+        self.last_scale_down_at
+            .is_some_and(|at| now - at < cooldown_ms)
+    }
+
+    /// Undoes `begin_consolidation` after the drain request failed. The cooldown still applies
+    /// so an unreachable room isn't retried every pass.
+    fn abort_consolidation(&mut self, source_id: &str, target_id: &str, moved: usize) {
+        if let Some(source) = self.rooms.get_mut(source_id) {
+            source.draining = false;
+        }
+        if let Some(target) = self.rooms.get_mut(target_id) {
+            target.player_count = target.player_count.saturating_sub(moved);
+        }
+    }
+}
+
 impl ControlState {
     async fn seed_registry_from_hetzner(&self) -> anyhow::Result<()> {
         let servers = self
@@ -266,64 +762,137 @@ impl ControlState {
                     room_id,
                     server_id: server.id,
                     origin: format!("http://{}:{}", ip, self.config.room_port),
+                    region: server
+                        .labels
+                        .get("region")
+                        .cloned()
+                        .unwrap_or_else(|| self.config.default_region().region.clone()),
                     player_count: 0,
                     last_heartbeat_at: now,
+                    last_contact_at: now,
                     last_assigned_at: now,
+                    sparse_since: None,
+                    draining: false,
+                    // Confirmed or corrected by the room's first heartbeat.
+                    warm: true,
                 },
             );
         }
         Ok(())
     }
 
-    async fn assign_room(&self, preferred_room: Option<String>) -> anyhow::Result<RoomRecord> {
-        if let Some(room) = self.reserve_ready_room(preferred_room.as_deref()).await {
+    async fn assign_room(
+        &self,
+        preferred_room: Option<String>,
+        region: &RegionLocation,
+    ) -> anyhow::Result<RoomRecord> {
+        let preferred_room = preferred_room.as_deref();
+        let region_name = Some(region.region.as_str());
+        if let Some(room) = self.reserve_ready_room(preferred_room, region_name).await {
             return Ok(room);
         }
 
-        let _guard = self.provision_lock.lock().await;
-        if let Some(room) = self.reserve_ready_room(preferred_room.as_deref()).await {
+        let _permit = self.acquire_provision_slot().await?;
+        // Another assignment may have provisioned or freed a room while this one waited.
+        if let Some(room) = self.reserve_ready_room(preferred_room, region_name).await {
             return Ok(room);
         }
 
-        let room = self.provision_room().await?;
+        let room = match self.provision_room(region).await {
+            Ok(room) => room,
+            Err(error) => {
+                // A seat in another region beats no game while this region can't provision.
+                if let Some(room) = self.reserve_ready_room(preferred_room, None).await {
+                    tracing::warn!(
+                        ?error,
+                        region = region.region,
+                        room_id = room.room_id,
+                        "provisioning failed, assigned cross-region room"
+                    );
+                    return Ok(room);
+                }
+                return Err(error);
+            }
+        };
         let mut registry = self.registry.lock().await;
         let record = registry
             .rooms
             .get_mut(&room.room_id)
             .context("provisioned room missing from registry")?;
-        record.player_count = record.player_count.saturating_add(1);
-        record.last_assigned_at = now_millis();
+        record.reserve_seat(now_millis());
         Ok(record.clone())
     }
 
-    async fn reserve_ready_room(&self, preferred_room: Option<&str>) -> Option<RoomRecord> {
+    async fn reserve_ready_room(
+        &self,
+        preferred_room: Option<&str>,
+        region: Option<&str>,
+    ) -> Option<RoomRecord> {
         let now = now_millis();
-        let mut registry = self.registry.lock().await;
+        self.registry.lock().await.reserve_room(
+            preferred_room,
+            region,
+            self.config.capacity,
+            self.config.fill_policy,
+            now,
+        )
+    }
 
-        if let Some(preferred_room) = preferred_room {
-            if let Some(record) = registry.rooms.get_mut(preferred_room) {
-                if record.player_count < self.config.capacity {
-                    record.player_count = record.player_count.saturating_add(1);
-                    record.last_assigned_at = now;
-                    return Some(record.clone());
-                }
-            }
+    /// Hands rooms with free seats to queued matchmake tickets, oldest first. Queued players
+    /// already waited out a provisioning failure, so any region will do.
+    async fn drain_matchmake_queue(&self) {
+        let now = now_millis();
+        let mut registry = self.registry.lock().await;
+        let mut queue = self.matchmake_queue.lock().await;
+        let expired = queue.prune(now);
+        if !expired.is_empty() {
+            tracing::info!(
+                released = expired.len(),
+                "released seats of abandoned matchmake tickets"
+            );
         }
+        for room in expired {
+            registry.release_seat(&room.room_id);
+        }
+        let assigned = queue.drain(now, |preferred_room| {
+            registry.reserve_room(
+                preferred_room,
+                None,
+                self.config.capacity,
+                self.config.fill_policy,
+                now,
+            )
+        });
+        if assigned > 0 {
+            tracing::info!(assigned, waiting = queue.len(), "drained matchmake queue");
+        }
+    }
 
-        let next_room_id = registry
-            .rooms
-            .values()
-            .filter(|record| record.player_count < self.config.capacity)
-            .min_by_key(|record| record.player_count)
-            .map(|record| record.room_id.clone())?;
-
-        let record = registry.rooms.get_mut(&next_room_id)?;
-        record.player_count = record.player_count.saturating_add(1);
-        record.last_assigned_at = now;
-        Some(record.clone())
+    async fn acquire_provision_slot(&self) -> anyhow::Result<OwnedSemaphorePermit> {
+        Arc::clone(&self.provision_slots)
+            .acquire_owned()
+            .await
+            .context("provisioning semaphore closed")
     }
 
-    async fn provision_room(&self) -> anyhow::Result<RoomRecord> {
+    async fn provision_room(&self, region: &RegionLocation) -> anyhow::Result<RoomRecord> {
+        let _in_flight = {
+            // Checked and counted under the registry lock so concurrent provisions can't
+            // overshoot the ceiling together.
+            let registry = self.registry.lock().await;
+            if let Some(max_total_rooms) = self.config.max_total_rooms {
+                let total = registry.rooms.len() + self.provisions_in_flight.load(Ordering::SeqCst);
+                if total >= max_total_rooms {
+                    tracing::warn!(
+                        total,
+                        max_total_rooms,
+                        "room ceiling reached, not provisioning"
+                    );
+                    return Err(ServerFull.into());
+                }
+            }
+            InFlightGuard::enter(&self.provisions_in_flight)
+        };
         let room_id = format!("room-{}", uuid::Uuid::new_v4().simple());
         let server_name = format!("snake-{room_id}");
         let user_data = build_room_cloud_init(&RoomCloudInitConfig {
@@ -334,6 +903,7 @@ impl ControlState {
             control_plane_url: &self.config.control_plane_url,
             heartbeat_token: &self.config.room_heartbeat_token,
             room_proxy_secret: &self.config.room_proxy_secret,
+            room_admin_token: self.config.room_admin_token.as_deref(),
             max_human_players: self.config.capacity,
             port: self.config.room_port,
         });
@@ -341,15 +911,16 @@ impl ControlState {
         labels.insert("app".to_string(), "spherical-snake-room".to_string());
         labels.insert("managed_by".to_string(), "snake-control".to_string());
         labels.insert("room_id".to_string(), room_id.clone());
+        labels.insert("region".to_string(), region.region.clone());
 
-        tracing::info!(room_id, "provisioning room server");
+        tracing::info!(room_id, region = region.region, "provisioning room server");
         let created = self
             .hetzner
             .create_server(&CreateServerParams {
                 name: &server_name,
                 server_type: &self.config.hetzner_server_type,
                 image: &self.config.hetzner_image,
-                location: &self.config.hetzner_location,
+                location: &region.location,
                 firewall_ids: &self.config.room_firewall_ids,
                 labels,
                 user_data: &user_data,
@@ -382,15 +953,20 @@ impl ControlState {
             room_id: room_id.clone(),
             server_id: created.server_id,
             origin,
+            region: region.region.clone(),
             player_count: 0,
             last_heartbeat_at: now,
+            last_contact_at: now,
             last_assigned_at: now,
+            sparse_since: None,
+            draining: false,
+            warm: true,
         };
-        self.registry
-            .lock()
-            .await
-            .rooms
-            .insert(room_id.clone(), record.clone());
+        {
+            let mut registry = self.registry.lock().await;
+            registry.rooms.insert(room_id.clone(), record.clone());
+            registry.last_scale_up_at = Some(now);
+        }
         tracing::info!(room_id, server_id = created.server_id, "room server ready");
         Ok(record)
     }
@@ -432,58 +1008,153 @@ impl ControlState {
     }
 
     async fn reconcile(&self) -> anyhow::Result<()> {
+        // Drain before provisioning too, so queued players still get seats freed by departures
+        // while the provider is failing.
+        self.drain_matchmake_queue().await;
         self.ensure_min_warm_rooms().await?;
+        self.drain_matchmake_queue().await;
+        self.consolidate_sparse_rooms().await;
         self.scale_down_idle_room().await?;
+        self.reap_stale_rooms().await;
+        Ok(())
+    }
+
+    /// Drains one sparse room into another in the same region, handing its clients a token
+    /// for the target room so they reconnect there. The drained room then empties and is
+    /// removed by idle scale-down.
+    async fn consolidate_sparse_rooms(&self) {
+        let Some(admin_token) = self.config.room_admin_token.as_deref() else {
+            return;
+        };
+        let now = now_millis();
+        let (source, target) = {
+            let mut registry = self.registry.lock().await;
+            let policy = &self.config.consolidation;
+            registry.track_sparse_rooms(policy, self.config.capacity, now);
+            let Some((source_id, target_id)) =
+                registry.consolidation_pair(policy, self.config.capacity, now)
+            else {
+                return;
+            };
+            let Some(pair) = registry.begin_consolidation(&source_id, &target_id, now) else {
+                return;
+            };
+            pair
+        };
+
+        tracing::info!(
+            source_room_id = source.room_id,
+            target_room_id = target.room_id,
+            source_players = source.player_count,
+            target_players = target.player_count,
+            "consolidating sparse rooms"
+        );
+        let result = match sign_room_token_for(&self.config, &target) {
+            Ok((room_token, _)) => {
+                self.request_room_drain(&source.origin, admin_token, &target.room_id, &room_token)
+                    .await
+            }
+            Err(error) => Err(error),
+        };
+        if let Err(error) = result {
+            tracing::warn!(
+                source_room_id = source.room_id,
+                target_room_id = target.room_id,
+                ?error,
+                "room consolidation failed, keeping both rooms"
+            );
+            self.registry.lock().await.abort_consolidation(
+                &source.room_id,
+                &target.room_id,
+                source.player_count,
+            );
+        }
+    }
+
+    async fn request_room_drain(
+        &self,
+        origin: &str,
+        admin_token: &str,
+        target_room_id: &str,
+        target_room_token: &str,
+    ) -> anyhow::Result<()> {
+        let endpoint = format!("{}/internal/drain", origin.trim_end_matches('/'));
+        let payload = RoomDrainRequest {
+            enabled: true,
+            migrate: RoomMigrateTarget {
+                room_id: target_room_id,
+                room_token: target_room_token,
+            },
+        };
+        self.http
+            .post(endpoint)
+            .header("Authorization", format!("Bearer {admin_token}"))
+            .timeout(Duration::from_secs(5))
+            .json(&payload)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context("room drain request failed")?;
         Ok(())
     }
 
     async fn ensure_min_warm_rooms(&self) -> anyhow::Result<()> {
         loop {
-            let available = self
+            if self.warm_room_count().await >= self.config.min_warm_rooms {
+                return Ok(());
+            }
+            let cooling = self
                 .registry
                 .lock()
                 .await
-                .rooms
-                .values()
-                .filter(|record| record.player_count < self.config.capacity)
-                .count();
-            if available >= self.config.min_warm_rooms {
+                .scale_up_cooling(self.config.scale_up_cooldown_ms, now_millis());
+            if cooling {
+                tracing::debug!("warm room top-up held back by scale-up cooldown");
                 return Ok(());
             }
 
-            let _guard = self.provision_lock.lock().await;
-            let available_after_lock = self
-                .registry
-                .lock()
-                .await
-                .rooms
-                .values()
-                .filter(|record| record.player_count < self.config.capacity)
-                .count();
-            if available_after_lock >= self.config.min_warm_rooms {
+            let _permit = self.acquire_provision_slot().await?;
+            // Servers still being provisioned will be warm shortly; counting them keeps a
+            // concurrent assignment from making this top-up provision a redundant room.
+            let pending = self.provisions_in_flight.load(Ordering::SeqCst);
+            if self.warm_room_count().await + pending >= self.config.min_warm_rooms {
                 return Ok(());
             }
-            self.provision_room().await?;
+            self.provision_room(self.config.default_region()).await?;
         }
     }
 
+    async fn warm_room_count(&self) -> usize {
+        self.registry.lock().await.warm_room_count()
+    }
+
     async fn scale_down_idle_room(&self) -> anyhow::Result<()> {
         let now = now_millis();
         let idle_cutoff = now - self.config.idle_scale_down_secs * 1000;
         let candidate = {
             let mut registry = self.registry.lock().await;
-            if registry.rooms.len() <= self.config.min_warm_rooms {
+            if registry.rooms.len() <= self.config.min_warm_rooms
+                || registry.scale_down_cooling(self.config.scale_down_cooldown_ms, now)
+            {
                 return Ok(());
             }
+            // Warm rooms idle by design; only trim ones beyond the buffer.
+            let keep_warm = registry.warm_room_count() <= self.config.min_warm_rooms;
             let room_id = registry
                 .rooms
                 .values()
                 .filter(|record| record.player_count == 0)
+                .filter(|record| !(keep_warm && record.warm))
                 .filter(|record| record.last_heartbeat_at <= idle_cutoff)
                 .filter(|record| record.last_assigned_at <= idle_cutoff)
                 .min_by_key(|record| record.last_assigned_at)
                 .map(|record| record.room_id.clone());
-            room_id.and_then(|room_id| registry.rooms.remove(&room_id))
+            let removed = room_id.and_then(|room_id| registry.rooms.remove(&room_id));
+            if removed.is_some() {
+                // Kept even if the delete fails, so an undeletable room isn't retried every pass.
+                registry.last_scale_down_at = Some(now);
+            }
+            removed
         };
 
         let Some(record) = candidate else {
@@ -508,6 +1179,47 @@ impl ControlState {
         Ok(())
     }
 
+    /// Deletes servers that stopped heart-beating and no longer answer health checks. Their
+    /// recorded players are already gone, so `player_count` doesn't protect them.
+    async fn reap_stale_rooms(&self) {
+        let cutoff = now_millis() - self.config.stale_heartbeat_secs * 1000;
+        let stale_rooms: Vec<(String, String)> = {
+            let registry = self.registry.lock().await;
+            registry
+                .stale_room_ids(cutoff)
+                .into_iter()
+                .filter_map(|room_id| {
+                    let origin = registry.rooms.get(&room_id)?.origin.clone();
+                    Some((room_id, origin))
+                })
+                .collect()
+        };
+
+        for (room_id, origin) in stale_rooms {
+            if self.is_room_healthy(&origin).await {
+                continue;
+            }
+            let Some(record) = self.registry.lock().await.rooms.remove(&room_id) else {
+                continue;
+            };
+            tracing::warn!(
+                room_id,
+                server_id = record.server_id,
+                player_count = record.player_count,
+                "reaping room whose heartbeats stopped"
+            );
+            if let Err(error) = self.safe_delete_server(record.server_id).await {
+                tracing::warn!(
+                    room_id,
+                    server_id = record.server_id,
+                    ?error,
+                    "failed to delete stale room server, reinserting room"
+                );
+                self.registry.lock().await.rooms.insert(room_id, record);
+            }
+        }
+    }
+
     async fn safe_delete_server(&self, server_id: i64) -> anyhow::Result<()> {
         let action_id = self.hetzner.delete_server(server_id).await?;
         if let Some(action_id) = action_id {
@@ -523,6 +1235,10 @@ impl ControlState {
         let mut registry = self.registry.lock().await;
         if let Some(record) = registry.rooms.get_mut(&payload.room_id) {
             record.player_count = payload.player_count;
+            record.last_contact_at = now;
+            if payload.player_count > 0 {
+                record.warm = false;
+            }
             if payload.player_count > 0 || payload.total_sessions > 0 {
                 record.last_heartbeat_at = now;
             }
@@ -549,10 +1265,7 @@ async fn matchmake(
         Err(_) => {
             return (
                 StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    ok: false,
-                    error: "Invalid JSON".to_string(),
-                }),
+                Json(ErrorResponse::new(ErrorCode::InvalidJson, "Invalid JSON")),
             )
                 .into_response();
         }
@@ -564,54 +1277,172 @@ async fn matchmake(
         .map(sanitize_room_name)
         .filter(|value| !value.is_empty());
 
-    let room = match state.assign_room(preferred_room).await {
-        Ok(room) => room,
+    let idempotency_key = normalize_idempotency_key(payload.idempotency_key.as_deref());
+    if let Some(key) = &idempotency_key {
+        let begin = state.matchmake_idempotency.lock().await.begin(
+            key,
+            now_millis(),
+            MATCHMAKE_IN_FLIGHT_TIMEOUT_MS,
+        );
+        match begin {
+            Begin::Cached(response) => return (StatusCode::OK, Json(response)).into_response(),
+            Begin::InFlight => {
+                return (
+                    StatusCode::CONFLICT,
+                    Json(ErrorResponse::new(
+                        ErrorCode::MatchmakeInProgress,
+                        "Matchmake already in progress",
+                    )),
+                )
+                    .into_response();
+            }
+            Begin::Fresh => {}
+        }
+    }
+
+    let region = state.config.region_for(payload.region.as_deref());
+    let assigned = match state.assign_room(preferred_room.clone(), region).await {
+        Ok(room) => issue_matchmake_response(&state, room),
         Err(error) => {
+            if let Some(key) = &idempotency_key {
+                state.matchmake_idempotency.lock().await.abandon(key);
+            }
+            // The ceiling is deliberate, so queueing would only park players behind it.
+            if error.is::<ServerFull>() {
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    Json(ErrorResponse::new(
+                        ErrorCode::ServerFull,
+                        "All rooms are full",
+                    )),
+                )
+                    .into_response();
+            }
             tracing::error!(?error, "matchmake failed");
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    ok: false,
-                    error: "Failed to assign room".to_string(),
-                }),
-            )
-                .into_response();
+            return enqueue_matchmake(&state, preferred_room).await;
         }
     };
 
-    let expires_at = now_millis() + state.config.token_ttl_secs * 1000;
-    let claims = RoomTokenClaims {
-        room_id: room.room_id.clone(),
-        origin: room.origin,
-        expires_at_ms: expires_at,
-    };
-    let room_token = match sign_room_token(&claims, &state.config.room_token_secret) {
-        Ok(token) => token,
-        Err(error) => {
-            tracing::error!(?error, "failed to sign room token");
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    ok: false,
-                    error: "Failed to issue room token".to_string(),
-                }),
-            )
-                .into_response();
+    if let Some(key) = &idempotency_key {
+        let mut cache = state.matchmake_idempotency.lock().await;
+        match &assigned {
+            Ok(response) => cache.complete(key, response.clone(), response.expires_at),
+            Err(_) => cache.abandon(key),
         }
-    };
+    }
+    match assigned {
+        Ok(response) => (StatusCode::OK, Json(response)).into_response(),
+        Err(error) => room_token_error_response(error),
+    }
+}
+
+async fn enqueue_matchmake(state: &ControlState, preferred_room: Option<String>) -> Response {
+    let mut queue = state.matchmake_queue.lock().await;
+    if !queue.is_enabled() {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(
+                ErrorCode::RoomAssignmentFailed,
+                "Failed to assign room",
+            )),
+        )
+            .into_response();
+    }
+    match queue.enqueue(preferred_room, now_millis()) {
+        Ok((ticket, queue_position)) => (
+            StatusCode::ACCEPTED,
+            Json(MatchmakeQueuedResponse {
+                ticket,
+                queue_position,
+            }),
+        )
+            .into_response(),
+        Err(QueueFull) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::new(
+                ErrorCode::MatchmakeQueueFull,
+                "Matchmaking queue is full",
+            )),
+        )
+            .into_response(),
+    }
+}
+
+async fn matchmake_status(
+    State(state): State<Arc<ControlState>>,
+    Query(query): Query<MatchmakeStatusQuery>,
+) -> impl IntoResponse {
+    let status = state
+        .matchmake_queue
+        .lock()
+        .await
+        .poll(&query.ticket, now_millis());
+    match status {
+        TicketStatus::Ready(room) => matchmake_success_response(&state, room),
+        TicketStatus::Queued { position } => (
+            StatusCode::ACCEPTED,
+            Json(MatchmakeQueuedResponse {
+                ticket: query.ticket,
+                queue_position: position,
+            }),
+        )
+            .into_response(),
+        TicketStatus::Unknown => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(
+                ErrorCode::UnknownTicket,
+                "Unknown matchmake ticket",
+            )),
+        )
+            .into_response(),
+    }
+}
+
+fn matchmake_success_response(state: &ControlState, room: RoomRecord) -> Response {
+    match issue_matchmake_response(state, room) {
+        Ok(response) => (StatusCode::OK, Json(response)).into_response(),
+        Err(error) => room_token_error_response(error),
+    }
+}
 
+fn room_token_error_response(error: anyhow::Error) -> Response {
+    tracing::error!(?error, "failed to sign room token");
     (
-        StatusCode::OK,
-        Json(MatchmakeResponse {
-            room_id: claims.room_id,
-            room_token,
-            capacity: state.config.capacity,
-            expires_at,
-        }),
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse::new(
+            ErrorCode::TokenIssueFailed,
+            "Failed to issue room token",
+        )),
     )
         .into_response()
 }
 
+/// Signs a token for joining `room`, returning it with its expiry.
+fn sign_room_token_for(config: &ControlConfig, room: &RoomRecord) -> anyhow::Result<(String, i64)> {
+    let expires_at = now_millis() + config.token_ttl_secs * 1000;
+    let claims = RoomTokenClaims {
+        room_id: room.room_id.clone(),
+        origin: room.origin.clone(),
+        expires_at_ms: expires_at,
+    };
+    let room_token = sign_room_token(&claims, &config.room_token_secret)?;
+    Ok((room_token, expires_at))
+}
+
+fn issue_matchmake_response(
+    state: &ControlState,
+    room: RoomRecord,
+) -> anyhow::Result<MatchmakeResponse> {
+    let (room_token, expires_at) = sign_room_token_for(&state.config, &room)?;
+    Ok(MatchmakeResponse {
+        room_id: room.room_id,
+        room_token,
+        capacity: state.config.capacity,
+        expires_at,
+        region: room.region,
+    })
+}
+
 async fn room_heartbeat(
     State(state): State<Arc<ControlState>>,
     headers: HeaderMap,
@@ -620,10 +1451,7 @@ async fn room_heartbeat(
     if bearer_token(&headers) != Some(state.config.room_heartbeat_token.as_str()) {
         return (
             StatusCode::UNAUTHORIZED,
-            Json(ErrorResponse {
-                ok: false,
-                error: "Unauthorized".to_string(),
-            }),
+            Json(ErrorResponse::new(ErrorCode::Unauthorized, "Unauthorized")),
         )
             .into_response();
     }
@@ -633,10 +1461,7 @@ async fn room_heartbeat(
         Err(_) => {
             return (
                 StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    ok: false,
-                    error: "Invalid JSON".to_string(),
-                }),
+                Json(ErrorResponse::new(ErrorCode::InvalidJson, "Invalid JSON")),
             )
                 .into_response();
         }
@@ -654,32 +1479,29 @@ async fn list_rooms(
         if expected_token != &state.config.room_heartbeat_token {
             return (
                 StatusCode::UNAUTHORIZED,
-                Json(ErrorResponse {
-                    ok: false,
-                    error: "Unauthorized".to_string(),
-                }),
+                Json(ErrorResponse::new(ErrorCode::Unauthorized, "Unauthorized")),
             )
                 .into_response();
         }
     } else {
         return (
             StatusCode::UNAUTHORIZED,
-            Json(ErrorResponse {
-                ok: false,
-                error: "Unauthorized".to_string(),
-            }),
+            Json(ErrorResponse::new(ErrorCode::Unauthorized, "Unauthorized")),
         )
             .into_response();
     }
 
-    let rooms = state
-        .registry
-        .lock()
-        .await
-        .rooms
-        .values()
-        .cloned()
-        .collect::<Vec<_>>();
+    let query = match RoomListQuery::from_params(&params) {
+        Ok(query) => query,
+        Err(error) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new(ErrorCode::InvalidQuery, error)),
+            )
+                .into_response();
+        }
+    };
+    let rooms = state.registry.lock().await.list_rooms(query);
     (StatusCode::OK, Json(RoomsResponse { rooms })).into_response()
 }
 
@@ -694,6 +1516,49 @@ fn parse_required_id_list_env(var_name: &str) -> anyhow::Result<Vec<i64>> {
     parse_id_list(var_name, &raw)
 }
 
+/// Reads `HETZNER_REGIONS` (`region=location` pairs, comma separated; the first is the
+/// default). Without it, rooms use a single region named after `default_location`.
+fn parse_regions_env(default_location: &str) -> anyhow::Result<Vec<RegionLocation>> {
+    match env::var("HETZNER_REGIONS") {
+        Ok(raw) if !raw.trim().is_empty() => parse_region_list("HETZNER_REGIONS", &raw),
+        _ => Ok(vec![RegionLocation {
+            region: default_location.to_string(),
+            location: default_location.to_string(),
+        }]),
+    }
+}
+
+fn parse_region_list(var_name: &str, raw: &str) -> anyhow::Result<Vec<RegionLocation>> {
+    let mut regions: Vec<RegionLocation> = Vec::new();
+    for token in raw.split(',') {
+        let Some((region, location)) = token.split_once('=') else {
+            bail!(
+                "{var_name} entry '{}' must be region=location",
+                token.trim()
+            );
+        };
+        let region = region.trim();
+        let location = location.trim();
+        if region.is_empty() || location.is_empty() {
+            bail!(
+                "{var_name} entry '{}' must be region=location",
+                token.trim()
+            );
+        }
+        if regions
+            .iter()
+            .any(|existing| existing.region.eq_ignore_ascii_case(region))
+        {
+            bail!("{var_name} lists region '{region}' more than once");
+        }
+        regions.push(RegionLocation {
+            region: region.to_string(),
+            location: location.to_string(),
+        });
+    }
+    Ok(regions)
+}
+
 fn parse_id_list(var_name: &str, raw: &str) -> anyhow::Result<Vec<i64>> {
     let mut ids = Vec::new();
     for token in raw.split(',') {
@@ -719,22 +1584,484 @@ fn parse_id_list(var_name: &str, raw: &str) -> anyhow::Result<Vec<i64>> {
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_id_list, sanitize_room_name, sign_room_token, RoomTokenClaims};
+    use super::{
+        list_rooms, matchmake, now_millis, parse_id_list, parse_region_list, sign_room_token,
+        ConsolidationPolicy, ControlConfig, ControlState, HetznerClient, IdempotencyCache,
+        MatchmakeQueue, MatchmakeRequest, RegionLocation, RoomFillPolicy, RoomRecord, RoomRegistry,
+        RoomTokenClaims, Semaphore,
+    };
+    use axum::{
+        extract::{Query, State},
+        http::StatusCode,
+        response::IntoResponse,
+        Json,
+    };
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+
+    fn test_control_state(registry: RoomRegistry) -> Arc<ControlState> {
+        control_state_with(
+            test_control_config(),
+            HetznerClient::new("unused".to_string()),
+            registry,
+        )
+    }
+
+    fn test_control_config() -> ControlConfig {
+        ControlConfig {
+            capacity: 25,
+            min_warm_rooms: 1,
+            idle_scale_down_secs: 180,
+            stale_heartbeat_secs: 180,
+            token_ttl_secs: 90,
+            matchmake_queue_capacity: 0,
+            provision_max_in_flight: 1,
+            max_total_rooms: None,
+            scale_down_cooldown_ms: 180_000,
+            scale_up_cooldown_ms: 60_000,
+            room_port: 8787,
+            room_firewall_ids: Vec::new(),
+            room_image: "room:test".to_string(),
+            room_registry_username: None,
+            room_registry_password: None,
+            regions: vec![RegionLocation {
+                region: "us-east".to_string(),
+                location: "ash".to_string(),
+            }],
+            hetzner_server_type: "cpx11".to_string(),
+            hetzner_image: "ubuntu-24.04".to_string(),
+            control_plane_url: "http://127.0.0.1:8787".to_string(),
+            room_heartbeat_token: "heartbeat".to_string(),
+            room_token_secret: "secret".to_string(),
+            room_proxy_secret: "proxy".to_string(),
+            room_admin_token: None,
+            consolidation: ConsolidationPolicy::default(),
+            fill_policy: RoomFillPolicy::Spread,
+        }
+    }
+
+    fn control_state_with(
+        config: ControlConfig,
+        hetzner: HetznerClient,
+        registry: RoomRegistry,
+    ) -> Arc<ControlState> {
+        Arc::new(ControlState {
+            provision_slots: Arc::new(Semaphore::new(config.provision_max_in_flight)),
+            config: Arc::new(config),
+            registry: Arc::new(Mutex::new(registry)),
+            hetzner,
+            provisions_in_flight: Arc::new(AtomicUsize::new(0)),
+            matchmake_queue: Arc::new(Mutex::new(MatchmakeQueue::new(0))),
+            matchmake_idempotency: Arc::new(Mutex::new(IdempotencyCache::new())),
+            http: reqwest::Client::new(),
+        })
+    }
+
+    async fn matchmake_json(
+        state: &Arc<ControlState>,
+        idempotency_key: Option<&str>,
+    ) -> (StatusCode, serde_json::Value) {
+        let request = MatchmakeRequest {
+            idempotency_key: idempotency_key.map(str::to_string),
+            ..MatchmakeRequest::default()
+        };
+        let response = matchmake(State(Arc::clone(state)), Ok(Json(request)))
+            .await
+            .into_response();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body");
+        (status, serde_json::from_slice(&body).expect("json body"))
+    }
+
+    fn room_record(room_id: &str, region: &str, player_count: usize) -> RoomRecord {
+        RoomRecord {
+            room_id: room_id.to_string(),
+            server_id: 1,
+            origin: "http://127.0.0.1:8787".to_string(),
+            region: region.to_string(),
+            player_count,
+            last_heartbeat_at: 0,
+            last_contact_at: 0,
+            last_assigned_at: 0,
+            sparse_since: None,
+            draining: false,
+            warm: false,
+        }
+    }
+
+    fn registry_with(records: Vec<RoomRecord>) -> RoomRegistry {
+        let mut registry = RoomRegistry::default();
+        for record in records {
+            registry.rooms.insert(record.room_id.clone(), record);
+        }
+        registry
+    }
 
     #[test]
-    fn sanitize_room_name_preserves_generated_room_ids() {
-        let room_id = "room-e0d805ef307540a0b0315c6a8f787d47";
-        assert_eq!(sanitize_room_name(room_id), room_id);
+    fn reserve_room_prefers_same_region_over_emptier_cross_region_room() {
+        let mut registry = registry_with(vec![
+            room_record("room-eu", "eu-central", 0),
+            room_record("room-us", "us-east", 10),
+        ]);
+        let room = registry
+            .reserve_room(None, Some("us-east"), 25, RoomFillPolicy::Spread, 1)
+            .expect("same-region room");
+        assert_eq!(room.room_id, "room-us");
+        assert_eq!(room.player_count, 11);
+    }
+
+    const POLICY: ConsolidationPolicy = ConsolidationPolicy {
+        low_watermark: 0.3,
+        max_merged_fill: 0.75,
+        sustain_ms: 1_000,
+        cooldown_ms: 10_000,
+    };
+
+    /// Runs one reconcile-style pass: update sparse timers, then pick a pair.
+    fn consolidation_pass(registry: &mut RoomRegistry, now: i64) -> Option<(String, String)> {
+        registry.track_sparse_rooms(&POLICY, 25, now);
+        registry.consolidation_pair(&POLICY, 25, now)
+    }
+
+    #[test]
+    fn consolidation_drains_smallest_sparse_room_into_fullest_fit_after_sustain() {
+        let mut registry = registry_with(vec![
+            room_record("room-a", "us-east", 2),
+            room_record("room-b", "us-east", 6),
+            room_record("room-c", "us-east", 4),
+            room_record("room-busy", "us-east", 20),
+            room_record("room-empty", "us-east", 0),
+            room_record("room-eu", "eu-central", 1),
+        ]);
+
+        assert_eq!(consolidation_pass(&mut registry, 0), None);
+        assert_eq!(consolidation_pass(&mut registry, 500), None);
+        assert_eq!(
+            consolidation_pass(&mut registry, 1_000),
+            Some(("room-a".to_string(), "room-b".to_string()))
+        );
+        assert!(registry.rooms["room-busy"].sparse_since.is_none());
+        assert!(registry.rooms["room-empty"].sparse_since.is_none());
+
+        let (source, target) = registry
+            .begin_consolidation("room-a", "room-b", 1_000)
+            .expect("pair exists");
+        assert_eq!((source.player_count, target.player_count), (2, 6));
+        assert!(registry.rooms["room-a"].draining);
+        assert_eq!(registry.rooms["room-b"].player_count, 8);
+        // A draining room takes no new players, even when asked for by name.
+        let reserved = registry
+            .reserve_room(
+                Some("room-a"),
+                Some("us-east"),
+                25,
+                RoomFillPolicy::Spread,
+                1_000,
+            )
+            .expect("another room");
+        assert_ne!(reserved.room_id, "room-a");
+    }
+
+    #[test]
+    fn consolidation_hysteresis_resets_timers_and_waits_out_cooldown() {
+        let mut registry = registry_with(vec![
+            room_record("room-a", "us-east", 3),
+            room_record("room-b", "us-east", 5),
+        ]);
+        consolidation_pass(&mut registry, 0);
+
+        // A brief bump above the watermark restarts the sustain timer.
+        registry.rooms.get_mut("room-b").unwrap().player_count = 9;
+        assert_eq!(consolidation_pass(&mut registry, 600), None);
+        registry.rooms.get_mut("room-b").unwrap().player_count = 5;
+        assert_eq!(consolidation_pass(&mut registry, 1_200), None);
+        assert_eq!(
+            consolidation_pass(&mut registry, 2_200),
+            Some(("room-a".to_string(), "room-b".to_string()))
+        );
+
+        // A failed drain is rolled back but still counts against the cooldown.
+        registry.begin_consolidation("room-a", "room-b", 2_200);
+        registry.abort_consolidation("room-a", "room-b", 3);
+        assert!(!registry.rooms["room-a"].draining);
+        assert_eq!(registry.rooms["room-b"].player_count, 5);
+        assert_eq!(consolidation_pass(&mut registry, 5_000), None);
+        assert_eq!(consolidation_pass(&mut registry, 11_000), None);
+        assert_eq!(
+            consolidation_pass(&mut registry, 12_200),
+            Some(("room-a".to_string(), "room-b".to_string()))
+        );
+    }
+
+    #[test]
+    fn consolidation_skips_pairs_that_would_overfill_or_cross_regions() {
+        // Merged cap is floor(25 * 0.75) = 18 players.
+        let mut registry = registry_with(vec![
+            room_record("room-us", "us-east", 7),
+            room_record("room-eu", "eu-central", 7),
+            room_record("room-ap", "ap-south", 7),
+        ]);
+        consolidation_pass(&mut registry, 0);
+        assert_eq!(consolidation_pass(&mut registry, 1_000), None);
+
+        let policy = ConsolidationPolicy {
+            max_merged_fill: 0.5,
+            ..POLICY
+        };
+        let mut registry = registry_with(vec![
+            room_record("room-a", "us-east", 7),
+            room_record("room-b", "us-east", 7),
+        ]);
+        registry.track_sparse_rooms(&policy, 25, 0);
+        assert_eq!(registry.consolidation_pair(&policy, 25, 1_000), None);
+        assert_eq!(
+            registry.consolidation_pair(&POLICY, 25, 1_000),
+            Some(("room-a".to_string(), "room-b".to_string()))
+        );
+    }
+
+    async fn listed_room_ids(
+        state: &Arc<ControlState>,
+        params: &[(&str, &str)],
+    ) -> (StatusCode, Vec<String>) {
+        let params: HashMap<String, String> = params
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        let response = list_rooms(State(Arc::clone(state)), Query(params))
+            .await
+            .into_response();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body");
+        let json: serde_json::Value = serde_json::from_slice(&body).expect("json body");
+        let room_ids = json["rooms"]
+            .as_array()
+            .map(|rooms| {
+                rooms
+                    .iter()
+                    .map(|room| room["roomId"].as_str().expect("room id").to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        (status, room_ids)
+    }
+
+    #[tokio::test]
+    async fn list_rooms_errors_carry_stable_codes() {
+        let state = test_control_state(registry_with(Vec::new()));
+        for (params, expected) in [
+            (
+                vec![("token", "wrong")],
+                (StatusCode::UNAUTHORIZED, "unauthorized"),
+            ),
+            (
+                vec![("token", "heartbeat"), ("sort", "name")],
+                (StatusCode::BAD_REQUEST, "invalid_query"),
+            ),
+        ] {
+            let params: HashMap<String, String> = params
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect();
+            let response = list_rooms(State(Arc::clone(&state)), Query(params))
+                .await
+                .into_response();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .expect("response body");
+            let json: serde_json::Value = serde_json::from_slice(&body).expect("json body");
+            assert_eq!((status, json["code"].as_str().expect("code")), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn list_rooms_sorts_and_filters_by_query() {
+        let mut busy = room_record("room-busy", "us-east", 20);
+        busy.last_assigned_at = 100;
+        busy.last_heartbeat_at = 3_000;
+        let mut quiet = room_record("room-quiet", "us-east", 2);
+        quiet.last_assigned_at = 300;
+        quiet.last_heartbeat_at = 1_000;
+        let mut empty = room_record("room-empty", "eu-central", 0);
+        empty.last_assigned_at = 200;
+        empty.last_heartbeat_at = 2_000;
+        let mut mid = room_record("room-mid", "eu-central", 8);
+        mid.last_assigned_at = 400;
+        mid.last_heartbeat_at = 4_000;
+        let state = test_control_state(registry_with(vec![busy, quiet, empty, mid]));
+        let token = ("token", "heartbeat");
+
+        let (status, ids) = listed_room_ids(&state, &[token]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ids, ["room-busy", "room-empty", "room-mid", "room-quiet"]);
+
+        let (_, ids) = listed_room_ids(&state, &[token, ("sort", "playerCount")]).await;
+        assert_eq!(ids, ["room-empty", "room-quiet", "room-mid", "room-busy"]);
+
+        let (_, ids) = listed_room_ids(
+            &state,
+            &[token, ("sort", "lastAssignedAt"), ("order", "desc")],
+        )
+        .await;
+        assert_eq!(ids, ["room-mid", "room-quiet", "room-empty", "room-busy"]);
+
+        let (_, ids) = listed_room_ids(
+            &state,
+            &[token, ("sort", "lastHeartbeatAt"), ("minPlayers", "2")],
+        )
+        .await;
+        assert_eq!(ids, ["room-quiet", "room-busy", "room-mid"]);
+
+        let (status, _) = listed_room_ids(&state, &[token, ("sort", "region")]).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = listed_room_ids(&state, &[("sort", "playerCount")]).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn stale_room_ids_ignore_player_count_and_fresh_rooms() {
+        let mut crashed = room_record("room-crashed", "us-east", 12);
+        crashed.last_contact_at = 1_000;
+        let mut warm = room_record("room-warm", "us-east", 0);
+        warm.last_contact_at = 9_000;
+        let mut idle = room_record("room-idle", "us-east", 0);
+        idle.last_contact_at = 2_000;
+        let registry = registry_with(vec![crashed, warm, idle]);
+
+        assert_eq!(
+            registry.stale_room_ids(5_000),
+            vec!["room-crashed".to_string(), "room-idle".to_string()]
+        );
+        assert!(registry.stale_room_ids(500).is_empty());
+    }
+
+    #[tokio::test]
+    async fn matchmake_retry_with_same_idempotency_key_reserves_once() {
+        let state = test_control_state(registry_with(vec![room_record("room-a", "us-east", 0)]));
+
+        let (first_status, first) = matchmake_json(&state, Some("retry-key")).await;
+        let (second_status, second) = matchmake_json(&state, Some("retry-key")).await;
+        assert_eq!(first_status, StatusCode::OK);
+        assert_eq!(second_status, StatusCode::OK);
+        assert_eq!(first["roomToken"], second["roomToken"]);
+        assert_eq!(first["roomId"], "room-a");
+        assert_eq!(state.registry.lock().await.rooms["room-a"].player_count, 1);
+
+        let (_, other) = matchmake_json(&state, Some("other-key")).await;
+        assert_eq!(other["roomId"], "room-a");
+        assert_eq!(state.registry.lock().await.rooms["room-a"].player_count, 2);
+    }
+
+    fn partially_full_registry() -> RoomRegistry {
+        let mut draining = room_record("room-draining", "us-east", 20);
+        draining.draining = true;
+        registry_with(vec![
+            room_record("room-c", "us-east", 12),
+            room_record("room-a", "us-east", 4),
+            room_record("room-d", "us-east", 25),
+            room_record("room-b", "us-east", 4),
+            room_record("room-e", "us-east", 12),
+            draining,
+        ])
+    }
+
+    #[test]
+    fn released_seat_returns_to_the_room_it_was_reserved_in() {
+        let mut registry = registry_with(vec![room_record("room-a", "nbg1", 3)]);
+        let reserved = registry
+            .reserve_room(None, None, 4, RoomFillPolicy::Spread, 0)
+            .expect("seat");
+        assert_eq!(registry.rooms["room-a"].player_count, 4);
+
+        registry.release_seat(&reserved.room_id);
+        assert_eq!(registry.rooms["room-a"].player_count, 3);
+        registry.release_seat("missing-room");
     }
 
     #[test]
-    fn sanitize_room_name_strips_invalid_chars_and_bounds_length() {
-        let source = "room-abc!@#$%^&*()_+[]{}<>?/|`~xyz123456789012345678901234567890";
-        let cleaned = sanitize_room_name(source);
-        assert!(cleaned
-            .chars()
-            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_'));
-        assert!(cleaned.len() <= 64);
+    fn spread_policy_fills_the_emptiest_room_breaking_ties_by_id() {
+        let mut registry = partially_full_registry();
+        let picks: Vec<String> = (0..4)
+            .map(|_| {
+                registry
+                    .reserve_room(None, None, 25, RoomFillPolicy::Spread, 1)
+                    .expect("room with a seat")
+                    .room_id
+            })
+            .collect();
+        assert_eq!(picks, ["room-a", "room-b", "room-a", "room-b"]);
+    }
+
+    #[test]
+    fn pack_policy_fills_the_fullest_open_room_before_others() {
+        let mut registry = partially_full_registry();
+        let picks: Vec<String> = (0..14)
+            .map(|_| {
+                registry
+                    .reserve_room(None, None, 25, RoomFillPolicy::Pack, 1)
+                    .expect("room with a seat")
+                    .room_id
+            })
+            .collect();
+        // room-c wins the tie with room-e and fills up, then room-e takes over. Full and
+        // draining rooms are never picked.
+        assert!(picks[..13].iter().all(|id| id == "room-c"));
+        assert_eq!(picks[13], "room-e");
+        assert_eq!(registry.rooms["room-c"].player_count, 25);
+        assert_eq!(registry.rooms["room-draining"].player_count, 20);
+    }
+
+    #[test]
+    fn room_fill_policy_parses_known_names_only() {
+        assert_eq!(
+            RoomFillPolicy::parse("spread"),
+            Some(RoomFillPolicy::Spread)
+        );
+        assert_eq!(RoomFillPolicy::parse(" PACK "), Some(RoomFillPolicy::Pack));
+        assert_eq!(RoomFillPolicy::parse("random"), None);
+    }
+
+    #[test]
+    fn reserve_room_skips_cross_region_rooms_so_caller_can_provision() {
+        let mut registry = registry_with(vec![
+            room_record("room-eu", "eu-central", 0),
+            room_record("room-us", "us-east", 25),
+        ]);
+        assert!(registry
+            .reserve_room(None, Some("us-east"), 25, RoomFillPolicy::Spread, 1)
+            .is_none());
+        let fallback = registry
+            .reserve_room(None, None, 25, RoomFillPolicy::Spread, 1)
+            .expect("any-region fallback");
+        assert_eq!(fallback.room_id, "room-eu");
+    }
+
+    #[test]
+    fn parse_region_list_maps_regions_to_locations() {
+        let regions = parse_region_list("HETZNER_REGIONS", "us-east=ash, eu-central = fsn1")
+            .expect("regions");
+        assert_eq!(
+            regions,
+            vec![
+                RegionLocation {
+                    region: "us-east".to_string(),
+                    location: "ash".to_string(),
+                },
+                RegionLocation {
+                    region: "eu-central".to_string(),
+                    location: "fsn1".to_string(),
+                },
+            ]
+        );
+        assert!(parse_region_list("HETZNER_REGIONS", "us-east").is_err());
+        assert!(parse_region_list("HETZNER_REGIONS", "us-east=ash,US-EAST=hil").is_err());
     }
 
     #[test]
@@ -770,4 +2097,228 @@ mod tests {
         assert!(parts.next().is_some());
         assert!(parts.next().is_none());
     }
+
+    struct FakeProvider {
+        base_url: String,
+        room_port: u16,
+        created: Arc<AtomicUsize>,
+        max_overlap: Arc<AtomicUsize>,
+    }
+
+    /// Serves the Hetzner endpoints `provision_room` touches plus the room health check,
+    /// recording how many `create_server` calls arrive and how many overlap.
+    async fn spawn_fake_provider() -> FakeProvider {
+        use axum::{extract::Path, routing::get, routing::post, Router};
+
+        let created = Arc::new(AtomicUsize::new(0));
+        let max_overlap = Arc::new(AtomicUsize::new(0));
+        let overlap = Arc::new(AtomicUsize::new(0));
+        let app = Router::new()
+            .route(
+                "/v1/servers",
+                post({
+                    let created = Arc::clone(&created);
+                    let max_overlap = Arc::clone(&max_overlap);
+                    move || async move {
+                        let current = overlap.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_overlap.fetch_max(current, Ordering::SeqCst);
+                        tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+                        overlap.fetch_sub(1, Ordering::SeqCst);
+                        let id = created.fetch_add(1, Ordering::SeqCst) + 1;
+                        Json(serde_json::json!({ "server": { "id": id }, "action": null }))
+                    }
+                }),
+            )
+            .route(
+                "/v1/servers/:id",
+                get(|Path(id): Path<i64>| async move {
+                    Json(serde_json::json!({
+                        "server": { "id": id, "public_net": { "ipv4": { "ip": "127.0.0.1" } } }
+                    }))
+                })
+                .delete(|| async { Json(serde_json::json!({ "action": null })) }),
+            )
+            .route("/api/health", get(|| async { "ok" }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind fake provider");
+        let room_port = listener.local_addr().expect("local addr").port();
+        tokio::spawn(async move {
+            axum::serve(listener, app)
+                .await
+                .expect("serve fake provider");
+        });
+        FakeProvider {
+            base_url: format!("http://127.0.0.1:{room_port}/v1"),
+            room_port,
+            created,
+            max_overlap,
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_assignments_provision_in_parallel_up_to_the_limit() {
+        let provider = spawn_fake_provider().await;
+        let config = ControlConfig {
+            capacity: 1,
+            provision_max_in_flight: 2,
+            room_port: provider.room_port,
+            ..test_control_config()
+        };
+        let region = config.default_region().clone();
+        let state = control_state_with(
+            config,
+            HetznerClient::with_base_url("token".to_string(), provider.base_url),
+            RoomRegistry::default(),
+        );
+
+        let assignments = (0..4).map(|_| {
+            let state = Arc::clone(&state);
+            let region = region.clone();
+            tokio::spawn(async move { state.assign_room(None, &region).await })
+        });
+        let mut room_ids = Vec::new();
+        for assignment in assignments.collect::<Vec<_>>() {
+            let room = assignment.await.expect("join").expect("assigned room");
+            room_ids.push(room.room_id);
+        }
+        room_ids.sort();
+        room_ids.dedup();
+
+        // Single-seat rooms force one server per player; two run at once, never three.
+        assert_eq!(room_ids.len(), 4);
+        assert_eq!(provider.max_overlap.load(Ordering::SeqCst), 2);
+        assert_eq!(state.registry.lock().await.rooms.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn scale_cooldowns_keep_reconcile_from_flapping() {
+        let provider = spawn_fake_provider().await;
+        let config = ControlConfig {
+            capacity: 2,
+            room_port: provider.room_port,
+            ..test_control_config()
+        };
+        let scale_down_cooldown_ms = config.scale_down_cooldown_ms;
+        let state = control_state_with(
+            config,
+            HetznerClient::with_base_url("token".to_string(), provider.base_url),
+            registry_with(vec![
+                room_record("room-idle", "us-east", 0),
+                room_record("room-full", "us-east", 2),
+            ]),
+        );
+
+        // No warm room: the top-up provisions one, and the long-idle room must not be scaled
+        // down right behind it.
+        state.ensure_min_warm_rooms().await.expect("top-up");
+        assert_eq!(provider.created.load(Ordering::SeqCst), 1);
+        state.scale_down_idle_room().await.expect("scale-down");
+        assert!(state.registry.lock().await.rooms.contains_key("room-idle"));
+
+        // Once the cooldown has passed, the idle room goes.
+        state.registry.lock().await.last_scale_up_at =
+            Some(now_millis() - scale_down_cooldown_ms - 1);
+        state.scale_down_idle_room().await.expect("scale-down");
+        assert!(!state.registry.lock().await.rooms.contains_key("room-idle"));
+
+        // A player takes the warm room, but the top-up waits out its own cooldown.
+        state
+            .registry
+            .lock()
+            .await
+            .reserve_room(None, None, 2, RoomFillPolicy::Spread, now_millis())
+            .expect("seat");
+        assert_eq!(state.warm_room_count().await, 0);
+        state.ensure_min_warm_rooms().await.expect("top-up");
+        assert_eq!(provider.created.load(Ordering::SeqCst), 1);
+        assert_eq!(state.registry.lock().await.rooms.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn warm_buffer_is_kept_alongside_half_full_rooms() {
+        let provider = spawn_fake_provider().await;
+        let config = ControlConfig {
+            min_warm_rooms: 2,
+            room_port: provider.room_port,
+            ..test_control_config()
+        };
+        let state = control_state_with(
+            config,
+            HetznerClient::with_base_url("token".to_string(), provider.base_url),
+            registry_with(vec![
+                room_record("room-a", "us-east", 10),
+                room_record("room-b", "us-east", 12),
+                room_record("room-c", "us-east", 3),
+            ]),
+        );
+
+        // Half-full rooms have free seats but aren't warm, so both buffer rooms get provisioned.
+        state.ensure_min_warm_rooms().await.expect("top-up");
+        assert_eq!(provider.created.load(Ordering::SeqCst), 2);
+        assert_eq!(state.warm_room_count().await, 2);
+        state.ensure_min_warm_rooms().await.expect("top-up");
+        assert_eq!(provider.created.load(Ordering::SeqCst), 2);
+
+        // Seating a player in a warm room uses it up, and the next pass replaces it.
+        let seated = state
+            .registry
+            .lock()
+            .await
+            .reserve_room(None, None, 25, RoomFillPolicy::Spread, now_millis())
+            .expect("seat");
+        assert!(!seated.warm);
+        assert_eq!(seated.player_count, 1);
+        assert_eq!(state.warm_room_count().await, 1);
+        state.ensure_min_warm_rooms().await.expect("top-up");
+        assert_eq!(provider.created.load(Ordering::SeqCst), 3);
+        assert_eq!(state.warm_room_count().await, 2);
+
+        // Idle warm rooms within the buffer are never scaled down.
+        state.registry.lock().await.last_scale_up_at = None;
+        for record in state.registry.lock().await.rooms.values_mut() {
+            record.last_heartbeat_at = 0;
+            record.last_assigned_at = 0;
+        }
+        state.scale_down_idle_room().await.expect("scale-down");
+        assert_eq!(state.warm_room_count().await, 2);
+    }
+
+    #[tokio::test]
+    async fn matchmake_at_room_ceiling_with_full_rooms_returns_server_full() {
+        let provider = spawn_fake_provider().await;
+        let config = ControlConfig {
+            capacity: 2,
+            max_total_rooms: Some(2),
+            room_port: provider.room_port,
+            ..test_control_config()
+        };
+        let state = control_state_with(
+            config,
+            HetznerClient::with_base_url("token".to_string(), provider.base_url),
+            registry_with(vec![
+                room_record("room-a", "us-east", 2),
+                room_record("room-b", "us-east", 2),
+            ]),
+        );
+
+        let (status, body) = matchmake_json(&state, Some("full-key")).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["code"], "server_full");
+        assert_eq!(provider.created.load(Ordering::SeqCst), 0);
+        assert_eq!(state.registry.lock().await.rooms.len(), 2);
+
+        // A freed seat is assigned as usual under the ceiling.
+        state
+            .registry
+            .lock()
+            .await
+            .rooms
+            .get_mut("room-b")
+            .expect("room-b")
+            .player_count = 1;
+        let (status, body) = matchmake_json(&state, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["roomId"], "room-b");
+    }
 }
@@ -0,0 +1,241 @@
+use std::collections::{HashMap, VecDeque};
+
+/// Tickets that haven't been polled for this long are dropped, whether still waiting or
+/// already assigned, so abandoned clients don't hold queue slots or reserved seats. Expired
+/// assignments come back from [`MatchmakeQueue::prune`] so the caller can release their seats.
+pub const TICKET_IDLE_TIMEOUT_MS: i64 = 30_000;
+
+#[derive(Debug)]
+pub struct MatchmakeQueue<T> {
+    capacity: usize,
+    waiting: VecDeque<QueuedTicket>,
+    ready: HashMap<String, ReadyTicket<T>>,
+}
+
+#[derive(Debug)]
+struct QueuedTicket {
+    ticket: String,
+    preferred_room: Option<String>,
+    last_polled_at: i64,
+}
+
+#[derive(Debug)]
+struct ReadyTicket<T> {
+    assignment: T,
+    assigned_at: i64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TicketStatus<T> {
+    Queued { position: usize },
+    Ready(T),
+    Unknown,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct QueueFull;
+
+impl<T> MatchmakeQueue<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            waiting: VecDeque::new(),
+            ready: HashMap::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.waiting.len()
+    }
+
+    /// Adds a request to the back of the queue. Returns the ticket and its 1-based position.
+    pub fn enqueue(
+        &mut self,
+        preferred_room: Option<String>,
+        now: i64,
+    ) -> Result<(String, usize), QueueFull> {
+        self.prune_waiting(now);
+        if self.waiting.len() >= self.capacity {
+            return Err(QueueFull);
+        }
+        let ticket = uuid::Uuid::new_v4().simple().to_string();
+        self.waiting.push_back(QueuedTicket {
+            ticket: ticket.clone(),
+            preferred_room,
+            last_polled_at: now,
+        });
+        Ok((ticket, self.waiting.len()))
+    }
+
+    /// Reports a ticket's status. A ready assignment is handed out once and then forgotten.
+    pub fn poll(&mut self, ticket: &str, now: i64) -> TicketStatus<T> {
+        self.prune_waiting(now);
+        if let Some(ready) = self.ready.remove(ticket) {
+            return TicketStatus::Ready(ready.assignment);
+        }
+        match self
+            .waiting
+            .iter_mut()
+            .enumerate()
+            .find(|(_, queued)| queued.ticket == ticket)
+        {
+            Some((index, queued)) => {
+                queued.last_polled_at = now;
+                TicketStatus::Queued {
+                    position: index + 1,
+                }
+            }
+            None => TicketStatus::Unknown,
+        }
+    }
+
+    /// Assigns waiting tickets in FIFO order until `reserve` can't place the front ticket.
+    /// Returns how many tickets were assigned.
+    pub fn drain(&mut self, now: i64, mut reserve: impl FnMut(Option<&str>) -> Option<T>) -> usize {
+        self.prune_waiting(now);
+        let mut assigned = 0;
+        while let Some(front) = self.waiting.front() {
+            let Some(assignment) = reserve(front.preferred_room.as_deref()) else {
+                break;
+            };
+            let Some(queued) = self.waiting.pop_front() else {
+                break;
+            };
+            self.ready.insert(
+                queued.ticket,
+                ReadyTicket {
+                    assignment,
+                    assigned_at: now,
+                },
+            );
+            assigned += 1;
+        }
+        assigned
+    }
+
+    /// Drops abandoned tickets and returns the assignments of those that were already given a
+    /// seat, which the caller still holds in reserve for them.
+    pub fn prune(&mut self, now: i64) -> Vec<T> {
+        self.prune_waiting(now);
+        let cutoff = now - TICKET_IDLE_TIMEOUT_MS;
+        let expired: Vec<String> = self
+            .ready
+            .iter()
+            .filter(|(_, ready)| ready.assigned_at <= cutoff)
+            .map(|(ticket, _)| ticket.clone())
+            .collect();
+        expired
+            .into_iter()
+            .filter_map(|ticket| self.ready.remove(&ticket))
+            .map(|ready| ready.assignment)
+            .collect()
+    }
+
+    fn prune_waiting(&mut self, now: i64) {
+        let cutoff = now - TICKET_IDLE_TIMEOUT_MS;
+        self.waiting.retain(|queued| queued.last_polled_at > cutoff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MatchmakeQueue, QueueFull, TicketStatus, TICKET_IDLE_TIMEOUT_MS};
+
+    #[test]
+    fn enqueue_reports_increasing_positions() {
+        let mut queue = MatchmakeQueue::<String>::new(4);
+        let (first, first_position) = queue.enqueue(None, 0).unwrap();
+        let (second, second_position) = queue.enqueue(Some("room-a".to_string()), 0).unwrap();
+        assert_eq!(first_position, 1);
+        assert_eq!(second_position, 2);
+        assert_eq!(queue.poll(&first, 10), TicketStatus::Queued { position: 1 });
+        assert_eq!(
+            queue.poll(&second, 10),
+            TicketStatus::Queued { position: 2 }
+        );
+        assert_eq!(queue.poll("missing", 10), TicketStatus::Unknown);
+    }
+
+    #[test]
+    fn drain_assigns_in_order_until_rooms_run_out() {
+        let mut queue = MatchmakeQueue::new(4);
+        let (first, _) = queue.enqueue(None, 0).unwrap();
+        let (second, _) = queue.enqueue(Some("room-b".to_string()), 0).unwrap();
+        let (third, _) = queue.enqueue(None, 0).unwrap();
+
+        let mut seats = 2;
+        let assigned = queue.drain(100, |preferred| {
+            if seats == 0 {
+                return None;
+            }
+            seats -= 1;
+            Some(preferred.unwrap_or("room-a").to_string())
+        });
+
+        assert_eq!(assigned, 2);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(
+            queue.poll(&first, 200),
+            TicketStatus::Ready("room-a".to_string())
+        );
+        assert_eq!(
+            queue.poll(&second, 200),
+            TicketStatus::Ready("room-b".to_string())
+        );
+        assert_eq!(
+            queue.poll(&third, 200),
+            TicketStatus::Queued { position: 1 }
+        );
+        // Assignments are handed out once.
+        assert_eq!(queue.poll(&first, 300), TicketStatus::Unknown);
+    }
+
+    #[test]
+    fn enqueue_rejects_when_queue_is_full() {
+        let mut queue = MatchmakeQueue::<String>::new(2);
+        queue.enqueue(None, 0).unwrap();
+        queue.enqueue(None, 0).unwrap();
+        assert_eq!(queue.enqueue(None, 0), Err(QueueFull));
+
+        let disabled = MatchmakeQueue::<String>::new(0);
+        assert!(!disabled.is_enabled());
+    }
+
+    #[test]
+    fn abandoned_tickets_expire_and_free_their_slot() {
+        let mut queue = MatchmakeQueue::<String>::new(1);
+        let (ticket, _) = queue.enqueue(None, 0).unwrap();
+        assert_eq!(queue.enqueue(None, 0), Err(QueueFull));
+
+        let later = TICKET_IDLE_TIMEOUT_MS + 1;
+        assert!(queue.enqueue(None, later).is_ok());
+        assert_eq!(queue.poll(&ticket, later), TicketStatus::Unknown);
+    }
+
+    #[test]
+    fn expired_assignments_are_returned_so_their_seats_can_be_released() {
+        let mut queue = MatchmakeQueue::new(4);
+        let (claimed, _) = queue.enqueue(None, 0).unwrap();
+        let (abandoned, _) = queue.enqueue(None, 0).unwrap();
+        queue.drain(100, |_| Some("room-a".to_string()));
+        assert_eq!(
+            queue.poll(&claimed, 200),
+            TicketStatus::Ready("room-a".to_string())
+        );
+
+        assert!(queue.prune(100 + TICKET_IDLE_TIMEOUT_MS - 1).is_empty());
+        assert_eq!(
+            queue.prune(100 + TICKET_IDLE_TIMEOUT_MS),
+            vec!["room-a".to_string()]
+        );
+        assert_eq!(
+            queue.poll(&abandoned, 100 + TICKET_IDLE_TIMEOUT_MS),
+            TicketStatus::Unknown
+        );
+        assert!(queue.prune(200 + TICKET_IDLE_TIMEOUT_MS).is_empty());
+    }
+}
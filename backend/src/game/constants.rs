@@ -9,13 +9,25 @@ pub const BASE_SPEED: f64 =
 pub const BOOST_MULTIPLIER: f64 = 2.16;
 pub const OXYGEN_MAX: f64 = 1.0;
 pub const OXYGEN_DRAIN_PER_SEC: f64 = 0.1;
+// Time a head can spend in water before oxygen starts draining, so clipping a lake edge is free.
+pub const LAKE_OXYGEN_GRACE_MS: i64 = 300;
+pub const LAKE_OXYGEN_GRACE_MAX_MS: i64 = 10_000;
+pub const DAY_NIGHT_CYCLE_MS: i64 = 10 * 60 * 1000;
+pub const NIGHT_OXYGEN_DRAIN_MULTIPLIER: f64 = 1.75;
 pub const MIN_SURVIVAL_LENGTH: usize = 3;
+// Tail nodes lost per second at zero oxygen; the snake drowns once it would drop below
+// `MIN_SURVIVAL_LENGTH`.
+pub const OXYGEN_DAMAGE_NODES_PER_SEC: f64 = 4.0;
 pub const DIGESTION_TRAVEL_SPEED_MULT: f64 = 3.0;
-pub const TURN_RATE: f64 = 0.115;
+// Base heading change in radians per second (0.115 rad per 50ms tick). `tick()` converts it
+// to a per-tick step from the tick interval, so turning feel doesn't depend on the tick rate.
+pub const TURN_RATE_PER_SEC: f64 = 2.3;
+pub const TURN_RATE_PER_SEC_MIN: f64 = 0.5;
+pub const TURN_RATE_PER_SEC_MAX: f64 = 10.0;
 // Slither-inspired turn envelope:
 // - `scang` falls with body size (longer snake -> lower angular responsiveness).
 // - `spang` falls with boost speed so boost steering has a wider turning radius.
-// We normalize against baseline (spawn length, non-boost speed) so TURN_RATE remains the
+// We normalize against baseline (spawn length, non-boost speed) so the base turn rate remains the
 // default non-boost feel while boost/length apply relative scaling.
 pub const TURN_SCANG_BASE: f64 = 0.13;
 pub const TURN_SCANG_RANGE: f64 = 0.87;
@@ -29,7 +41,6 @@ pub const TURN_RESPONSE_GAIN_BOOST_PER_SEC: f64 = 4.6;
 pub const TURN_RATE_MIN_MULTIPLIER: f64 = 0.22;
 pub const TURN_RATE_MAX_MULTIPLIER: f64 = 4.0;
 pub const TURN_SUBSTEPS_NORMAL: usize = 2;
-pub const TURN_SUBSTEPS_BOOST: usize = 4;
 pub const COLLISION_DISTANCE: f64 = 0.10467191248588766 / WORLD_SCALE;
 #[cfg(not(test))]
 pub const BASE_PELLET_COUNT: usize = 2400;
@@ -46,6 +57,11 @@ pub const SMALL_PELLET_SIZE_MIN: f32 = 0.55;
 pub const SMALL_PELLET_SIZE_MAX: f32 = 0.95;
 pub const DEATH_PELLET_SIZE_MIN: f32 = 1.65;
 pub const DEATH_PELLET_SIZE_MAX: f32 = 2.85;
+// Once boost engages it stays on (and drains) for a minimum window, and a released boost must
+// stay released for a cooldown before re-engaging, so toggling the flag every tick can't buy
+// boost speed at a discount.
+pub const BOOST_MIN_ENGAGE_MS: i64 = 300;
+pub const BOOST_COOLDOWN_MS: i64 = 250;
 pub const BOOST_TRAIL_PELLET_INTERVAL_MS: i64 = 100;
 pub const BOOST_TRAIL_PELLET_TTL_MS: i64 = 30_000;
 pub const BOOST_TRAIL_PELLET_SIZE_MIN: f32 = 0.55;
@@ -90,31 +106,68 @@ pub const EVASIVE_PELLET_EVADE_FULL_RADIUS_RATIO: f64 = 0.72;
 pub const EVASIVE_PELLET_EVADE_FADE_RADIUS_RATIO: f64 = 1.2;
 pub const EVASIVE_PELLET_ZIGZAG_HZ: f64 = 1.35;
 pub const EVASIVE_PELLET_ZIGZAG_STRENGTH: f64 = 0.26;
+/// Evade speed/zigzag multiplier floor for owners who never catch their evasive pellets.
+pub const EVASIVE_PELLET_MIN_DIFFICULTY: f64 = 0.55;
+/// Resolved evasive pellets kept per owner before the attempt/capture counts are halved.
+pub const EVASIVE_PELLET_SKILL_WINDOW: u32 = 8;
 pub const EVASIVE_PELLET_MAX_PER_PLAYER: usize = 1;
 pub const EVASIVE_PELLET_SPAWN_ATTEMPTS: usize = 28;
+/// Size span of the `u8` pellet size on the wire; it must cover every spawned `base_size` so
+/// large pellets keep their variation instead of all clamping to `255`.
 pub const PELLET_SIZE_ENCODE_MIN: f32 = SMALL_PELLET_SIZE_MIN;
 pub const PELLET_SIZE_ENCODE_MAX: f32 = DEATH_PELLET_SIZE_MAX;
+const _: () = assert!(
+    SMALL_PELLET_SIZE_MIN >= PELLET_SIZE_ENCODE_MIN
+        && SMALL_PELLET_SIZE_MAX <= PELLET_SIZE_ENCODE_MAX
+        && BOOST_TRAIL_PELLET_SIZE_MIN >= PELLET_SIZE_ENCODE_MIN
+        && BOOST_TRAIL_PELLET_SIZE_MAX <= PELLET_SIZE_ENCODE_MAX
+        && DEATH_PELLET_SIZE_MIN >= PELLET_SIZE_ENCODE_MIN
+        && DEATH_PELLET_SIZE_MAX <= PELLET_SIZE_ENCODE_MAX
+        && EVASIVE_PELLET_SIZE_MIN >= PELLET_SIZE_ENCODE_MIN
+        && EVASIVE_PELLET_SIZE_MAX <= PELLET_SIZE_ENCODE_MAX
+);
 pub const SMALL_PELLET_SHRINK_MIN_RATIO: f32 = 0.24;
 pub const SMALL_PELLET_ATTRACT_RADIUS: f64 = 0.0825;
+// Longer snakes pull pellets from further away: the radius scales with girth up to this cap.
+pub const SMALL_PELLET_ATTRACT_RADIUS_MAX: f64 = 0.14;
 pub const SMALL_PELLET_LOCK_CONE_ANGLE: f64 = std::f64::consts::FRAC_PI_4;
 pub const SMALL_PELLET_CONSUME_ANGLE: f64 = 0.00266;
 pub const SMALL_PELLET_ATTRACT_SPEED: f64 = 3.2;
 pub const SMALL_PELLET_ATTRACT_STEP_MAX_RATIO: f64 = 0.35;
 pub const SMALL_PELLET_MOUTH_FORWARD: f64 = 0.0;
 pub const SMALL_PELLET_SPAWN_HEAD_EXCLUSION_ANGLE: f64 = 0.08;
-pub const SMALL_PELLET_ZOOM_MIN_CAMERA_DISTANCE: f64 = 4.0;
-pub const SMALL_PELLET_ZOOM_MAX_CAMERA_DISTANCE: f64 = 10.0;
+/// Pellet budget per session, ramped across the full accepted camera distance range.
 pub const SMALL_PELLET_VISIBLE_MIN: usize = 520;
 pub const SMALL_PELLET_VISIBLE_MAX: usize = 2200;
 pub const SMALL_PELLET_VIEW_MARGIN_MIN: f64 = 0.06;
 pub const SMALL_PELLET_VIEW_MARGIN_MAX: f64 = 0.2;
 pub const TICK_MS: u64 = 50;
+pub const BOOST_MIN_ENGAGE_TICKS: u32 =
+    ((BOOST_MIN_ENGAGE_MS + TICK_MS as i64 - 1) / TICK_MS as i64) as u32;
+pub const BOOST_COOLDOWN_TICKS: u32 =
+    ((BOOST_COOLDOWN_MS + TICK_MS as i64 - 1) / TICK_MS as i64) as u32;
 pub const RESPAWN_COOLDOWN_MS: i64 = 5000;
+pub const RESPAWN_COOLDOWN_MAX_MS: i64 = 60_000;
 pub const RESPAWN_RETRY_MS: i64 = 500;
 pub const PLAYER_TIMEOUT_MS: i64 = 15000;
 pub const SPAWN_CONE_ANGLE: f64 = std::f64::consts::PI / 3.0;
 pub const MAX_SPAWN_ATTEMPTS: usize = 32;
 pub const SPAWN_PLAYER_MIN_DISTANCE: f64 = COLLISION_DISTANCE * 2.0;
+// Fraction shaved off the head-to-body contact distance so only clear overlaps kill; grazes
+// lost to quantization and client/server drift are forgiven.
+pub const COLLISION_LENIENCY: f64 = 0.1;
+pub const COLLISION_LENIENCY_MAX: f64 = 0.5;
+// Upper bound for the optional score a snake earns when another head dies on its body.
+pub const KILL_BONUS_MAX: i64 = 10_000;
+// Nodes behind the head skipped by the optional self-collision rule. A full-rate turn closes its
+// loop after roughly 19 nodes, so 12 leaves the neck clear even at maximum girth.
+pub const SELF_COLLISION_MIN_NODE_GAP: usize = 12;
+// Spawn attempts before the head-distance buffer starts relaxing toward the room's floor.
+pub const SPAWN_STRICT_ATTEMPTS: usize = MAX_SPAWN_ATTEMPTS / 2;
+// Fraction of the strict head distance the last spawn attempt still requires. At 0.5 the
+// buffer equals the bare collision distance; bodies may never overlap at any attempt.
+pub const SPAWN_SAFETY_FLOOR: f64 = 0.6;
+pub const SPAWN_SAFETY_FLOOR_MIN: f64 = 0.5;
 pub const DIGESTION_TAIL_SETTLE_STEPS: i64 = 4;
 // Delay digestion travel so bulges start after pellet intake reaches the mouth.
 // This is tuned to match the client consume-ghost duration (~0.34s).
@@ -129,7 +182,15 @@ pub const DIGESTION_TAIL_GROWTH_BASE_PER_STEP: f64 = 0.035;
 pub const DIGESTION_TAIL_GROWTH_BACKLOG_SQRT_MULT: f64 = 0.08;
 pub const DIGESTION_TAIL_GROWTH_MAX_PER_STEP: f64 = 0.28;
 pub const BOT_COUNT: usize = 5;
+pub const BOT_MIN_COUNT: usize = 1;
 pub const BOT_BOOST_DISTANCE: f64 = 0.6 / WORLD_SCALE;
+// Easy bots jitter their steering axis by up to this many radians each tick.
+pub const BOT_EASY_WANDER_ANGLE: f64 = 0.35;
+// Extra chord distance beyond the collision threshold at which hard bots start dodging bodies.
+pub const BOT_HARD_AVOID_MARGIN: f64 = 0.25 / WORLD_SCALE;
+pub const BOT_HARD_CUTOFF_DISTANCE: f64 = 0.8 / WORLD_SCALE;
+pub const BOT_HARD_CUTOFF_LEAD: f64 = 0.3 / WORLD_SCALE;
+pub const BOT_HARD_EVASIVE_CHASE_DISTANCE: f64 = 1.0 / WORLD_SCALE;
 pub const SNAKE_GIRTH_NODES_PER_STEP: usize = 10;
 pub const SNAKE_GIRTH_STEP_PERCENT: f64 = 0.10;
 pub const SNAKE_GIRTH_MAX_SCALE: f64 = 2.0;
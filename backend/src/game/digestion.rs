@@ -96,7 +96,7 @@ pub fn advance_digestions_with_boost(
     steps: i32,
     boost_drain: BoostDrainConfig,
 ) -> bool {
-    let step_count = steps.max(1) as i32;
+    let step_count = steps.max(1);
     let step_count_f64 = step_count as f64;
     let min_length = if boost_drain.min_length > 0 {
         boost_drain.min_length
@@ -272,6 +272,8 @@ mod tests {
             color: "#fff".to_string(),
             skin: None,
             is_bot: false,
+            bot_skill: None,
+            afk_autopilot: false,
             axis: Point {
                 x: 1.0,
                 y: 0.0,
@@ -284,16 +286,25 @@ mod tests {
             },
             boost: false,
             is_boosting: false,
+            boost_engage_ticks: 0,
+            boost_cooldown_ticks: 0,
             oxygen: 1.0,
             oxygen_damage_accumulator: 0.0,
+            submerged_secs: 0.0,
             score: 0,
+            session_best: 0,
             alive: true,
             connected: true,
             last_seen: 0,
+            last_active_at: 0,
             respawn_at: None,
+            respawn_length: None,
             boost_floor_len: 4,
             trail_color_cycle_cursor: 0,
             next_boost_trail_pellet_at_ms: 0,
+            boost_shed_value: 0.0,
+            evasive_attempts: 0,
+            evasive_captures: 0,
             snake: make_snake(4),
             pellet_growth_fraction: 0.0,
             tail_extension: 0.0,
@@ -495,7 +506,7 @@ mod tests {
         let mut player = make_player();
         add_digestion_with_strength(&mut player, 0.7, 0.2);
         assert_eq!(player.digestions.len(), 1);
-        assert!(DIGESTION_INTAKE_DELAY_STEPS > 0);
+        const { assert!(DIGESTION_INTAKE_DELAY_STEPS > 0) };
 
         let mut digestion = player.digestions[0].clone();
         assert!(get_digestion_progress(&digestion) <= 1e-6);
@@ -513,7 +524,7 @@ mod tests {
         let mut player = make_player();
         add_digestion_with_strength(&mut player, 0.7, 0.2);
         assert_eq!(player.digestions.len(), 1);
-        assert!(DIGESTION_INTAKE_DELAY_STEPS > 0);
+        const { assert!(DIGESTION_INTAKE_DELAY_STEPS > 0) };
 
         let mut digestion = player.digestions[0].clone();
         assert!(get_digestion_visual_strength(&digestion) <= 1e-6);
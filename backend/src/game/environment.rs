@@ -54,6 +54,15 @@ pub const MOUNTAIN_OUTLINE_SAMPLES: usize = 64;
 const MOUNTAIN_OUTLINE_SMOOTH_RADIUS: isize = 4;
 const MOUNTAIN_OUTLINE_SMOOTH_PASSES: usize = 2;
 
+// Per-room hazard limits: enough headroom for a "death maze" without packing the sphere so
+// tightly that placement stops finding free spots.
+pub const ENVIRONMENT_LAKE_COUNT_MAX: usize = 6;
+pub const ENVIRONMENT_TREE_COUNT_MAX: usize = 160;
+pub const ENVIRONMENT_CACTUS_COUNT_MAX: usize = 48;
+pub const ENVIRONMENT_MOUNTAIN_COUNT_MAX: usize = 32;
+pub const ENVIRONMENT_SIZE_SCALE_MIN: f64 = 0.5;
+pub const ENVIRONMENT_SIZE_SCALE_MAX: f64 = 2.0;
+
 const LAKE_SEED: u32 = 0x91fcae12;
 const ENV_SEED: u32 = 0x6f35d2a1;
 const MOUNTAIN_VARIANT_SEED: u32 = 0x03f2a9b1;
@@ -99,6 +108,58 @@ pub struct MountainInstance {
     pub outline: Vec<f64>,
 }
 
+/// How many of each hazard a generated layout gets, and how large lakes and mountains are. The
+/// default reproduces the standard planet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnvironmentConfig {
+    pub lake_count: usize,
+    /// Multiplier on lake radius.
+    pub lake_scale: f64,
+    /// Forest trees, placed outside the desert.
+    pub tree_count: usize,
+    /// Cacti, placed inside the desert.
+    pub cactus_count: usize,
+    pub mountain_count: usize,
+    /// Multiplier on mountain footprint and height.
+    pub mountain_scale: f64,
+}
+
+impl Default for EnvironmentConfig {
+    fn default() -> Self {
+        Self {
+            lake_count: LAKE_COUNT,
+            lake_scale: 1.0,
+            tree_count: FOREST_TREE_COUNT,
+            cactus_count: DESERT_CACTUS_COUNT,
+            mountain_count: MOUNTAIN_COUNT,
+            mountain_scale: 1.0,
+        }
+    }
+}
+
+impl EnvironmentConfig {
+    /// Clamps counts to the `ENVIRONMENT_*_COUNT_MAX` limits and scales to
+    /// `ENVIRONMENT_SIZE_SCALE_MIN..=ENVIRONMENT_SIZE_SCALE_MAX`; a non-finite scale falls back to
+    /// `1.0`.
+    pub fn sanitized(self) -> Self {
+        let scale = |value: f64| {
+            if value.is_finite() {
+                value.clamp(ENVIRONMENT_SIZE_SCALE_MIN, ENVIRONMENT_SIZE_SCALE_MAX)
+            } else {
+                1.0
+            }
+        };
+        Self {
+            lake_count: self.lake_count.min(ENVIRONMENT_LAKE_COUNT_MAX),
+            lake_scale: scale(self.lake_scale),
+            tree_count: self.tree_count.min(ENVIRONMENT_TREE_COUNT_MAX),
+            cactus_count: self.cactus_count.min(ENVIRONMENT_CACTUS_COUNT_MAX),
+            mountain_count: self.mountain_count.min(ENVIRONMENT_MOUNTAIN_COUNT_MAX),
+            mountain_scale: scale(self.mountain_scale),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Environment {
     pub lakes: Vec<Lake>,
@@ -111,6 +172,16 @@ pub struct LakeSample {
     pub boundary: f64,
 }
 
+/// Keeps the lake and environment seeds derived from one room seed independent.
+const ENV_SEED_SALT: u64 = 0xd1b5_4a32_d192_ed03;
+
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
 pub fn desert_biome_center() -> Point {
     let mut rng = SeededRng::new(DESERT_BIOME_SEED);
     random_on_sphere(&mut rng)
@@ -118,8 +189,28 @@ pub fn desert_biome_center() -> Point {
 
 impl Environment {
     pub fn generate() -> Self {
-        let lakes = create_lakes(LAKE_SEED, LAKE_COUNT);
-        let mut rng = SeededRng::new(ENV_SEED);
+        Self::generate_from_seeds(LAKE_SEED, ENV_SEED, &EnvironmentConfig::default())
+    }
+
+    /// Generates a reproducible layout for `seed` with hazard counts and sizes from `config`.
+    /// Seed `0` with the default config yields the default layout; the desert biome stays fixed
+    /// so clients can keep placing it without extra data. Other seeds are mixed with splitmix64,
+    /// so seeds that differ only in their high bits still get unrelated layouts.
+    pub fn generate_with_seed(seed: u64, config: &EnvironmentConfig) -> Self {
+        let config = config.sanitized();
+        if seed == 0 {
+            return Self::generate_from_seeds(LAKE_SEED, ENV_SEED, &config);
+        }
+        Self::generate_from_seeds(
+            splitmix64(seed) as u32,
+            splitmix64(seed ^ ENV_SEED_SALT) as u32,
+            &config,
+        )
+    }
+
+    fn generate_from_seeds(lake_seed: u32, env_seed: u32, config: &EnvironmentConfig) -> Self {
+        let lakes = create_lakes(lake_seed, config.lake_count, config.lake_scale);
+        let mut rng = SeededRng::new(env_seed);
         let rand_range =
             |rng: &mut SeededRng, min: f64, max: f64| min + (max - min) * rng.next_f64();
 
@@ -152,13 +243,13 @@ impl Environment {
         let is_in_lake =
             |candidate: Point| sample_lakes(candidate, &lakes).boundary > LAKE_EXCLUSION_THRESHOLD;
 
-        let mut trees = Vec::with_capacity(TREE_INSTANCE_COUNT);
+        let mut trees = Vec::with_capacity(config.tree_count + config.cactus_count);
 
         let tree_min_dot = TREE_MIN_ANGLE.cos();
-        let mut tree_normals: Vec<Point> = Vec::with_capacity(FOREST_TREE_COUNT);
+        let mut tree_normals: Vec<Point> = Vec::with_capacity(config.tree_count);
         let is_invalid_forest_tree =
             |candidate: Point| is_in_lake(candidate) || in_desert(candidate);
-        for _ in 0..FOREST_TREE_COUNT {
+        for _ in 0..config.tree_count {
             let candidate = pick_sparse_normal(
                 &mut rng,
                 tree_min_dot,
@@ -178,9 +269,9 @@ impl Environment {
         }
 
         let cactus_min_dot = DESERT_CACTUS_MIN_ANGLE.cos();
-        let mut cactus_normals: Vec<Point> = Vec::with_capacity(DESERT_CACTUS_COUNT);
+        let mut cactus_normals: Vec<Point> = Vec::with_capacity(config.cactus_count);
         let is_invalid_cactus = |candidate: Point| is_in_lake(candidate) || !in_desert(candidate);
-        for _ in 0..DESERT_CACTUS_COUNT {
+        for _ in 0..config.cactus_count {
             let candidate = pick_sparse_normal(
                 &mut rng,
                 cactus_min_dot,
@@ -201,18 +292,20 @@ impl Environment {
         }
 
         let mountain_min_dot = MOUNTAIN_MIN_ANGLE.cos();
-        let mut mountains = Vec::with_capacity(MOUNTAIN_COUNT);
-        let mut mountain_normals: Vec<Point> = Vec::with_capacity(MOUNTAIN_COUNT);
+        let mut mountains = Vec::with_capacity(config.mountain_count);
+        let mut mountain_normals: Vec<Point> = Vec::with_capacity(config.mountain_count);
         let is_invalid_mountain = |candidate: Point| is_in_lake(candidate) || in_desert(candidate);
-        for _ in 0..MOUNTAIN_COUNT {
+        for _ in 0..config.mountain_count {
             let candidate = pick_sparse_normal(
                 &mut rng,
                 mountain_min_dot,
                 &mountain_normals,
                 &is_invalid_mountain,
             );
-            let radius = rand_range(&mut rng, MOUNTAIN_RADIUS_MIN, MOUNTAIN_RADIUS_MAX);
-            let height = rand_range(&mut rng, MOUNTAIN_HEIGHT_MIN, MOUNTAIN_HEIGHT_MAX);
+            let radius = rand_range(&mut rng, MOUNTAIN_RADIUS_MIN, MOUNTAIN_RADIUS_MAX)
+                * config.mountain_scale;
+            let height = rand_range(&mut rng, MOUNTAIN_HEIGHT_MIN, MOUNTAIN_HEIGHT_MAX)
+                * config.mountain_scale;
             let variant = (rng.next_f64() * MOUNTAIN_VARIANTS as f64).floor() as u8;
             let twist = rand_range(&mut rng, 0.0, PI * 2.0);
             let variant_seed = MOUNTAIN_VARIANT_SEED + (variant as u32) * 57;
@@ -341,10 +434,12 @@ pub fn sample_lakes(normal: Point, lakes: &[Lake]) -> LakeSample {
         }
     }
 
-    LakeSample { boundary: max_boundary }
+    LakeSample {
+        boundary: max_boundary,
+    }
 }
 
-fn create_lakes(seed: u32, count: usize) -> Vec<Lake> {
+fn create_lakes(seed: u32, count: usize, size_scale: f64) -> Vec<Lake> {
     let mut rng = SeededRng::new(seed);
     let mut lakes = Vec::with_capacity(count);
     let rand_range = |rng: &mut SeededRng, min: f64, max: f64| min + (max - min) * rng.next_f64();
@@ -352,7 +447,7 @@ fn create_lakes(seed: u32, count: usize) -> Vec<Lake> {
     let desert_min_dot = DESERT_BIOME_ANGLE.cos();
 
     for _ in 0..count {
-        let radius = rand_range(&mut rng, LAKE_MIN_ANGLE, LAKE_MAX_ANGLE);
+        let radius = rand_range(&mut rng, LAKE_MIN_ANGLE, LAKE_MAX_ANGLE) * size_scale;
         let depth = rand_range(&mut rng, LAKE_MIN_DEPTH, LAKE_MAX_DEPTH);
         let shelf_depth = depth * LAKE_SHELF_DEPTH_RATIO;
         let center = pick_lake_center(radius, &lakes, &mut rng, desert_center, desert_min_dot);
@@ -457,7 +552,7 @@ fn build_mountain_outline(seed: u32, base_angle: f64) -> Vec<f64> {
     let mut rng = SeededRng::new(seed);
     let variance = 0.18 + rng.next_f64() * 0.06;
     let mut outline = vec![0.0; MOUNTAIN_OUTLINE_SAMPLES];
-    for i in 0..MOUNTAIN_OUTLINE_SAMPLES {
+    for (i, sample) in outline.iter_mut().enumerate() {
         let theta = (i as f64 / MOUNTAIN_OUTLINE_SAMPLES as f64) * PI * 2.0;
         let dir = Point {
             x: theta.cos(),
@@ -469,7 +564,7 @@ fn build_mountain_outline(seed: u32, base_angle: f64) -> Vec<f64> {
         let qz = (dir.z * 1024.0).round() as i32;
         let jitter = hash3(seed, qx, qy, qz) * 2.0 - 1.0;
         let scale = 1.0 + jitter * variance;
-        outline[i] = (base_angle * scale).max(base_angle * 0.5);
+        *sample = (base_angle * scale).max(base_angle * 0.5);
     }
 
     // Smooth the outline to avoid sharp discontinuities and produce a rounded collider.
@@ -479,7 +574,7 @@ fn build_mountain_outline(seed: u32, base_angle: f64) -> Vec<f64> {
     let mut smoothed = vec![0.0; MOUNTAIN_OUTLINE_SAMPLES];
     let radius = MOUNTAIN_OUTLINE_SMOOTH_RADIUS.max(1);
     for _ in 0..MOUNTAIN_OUTLINE_SMOOTH_PASSES {
-        for i in 0..MOUNTAIN_OUTLINE_SAMPLES {
+        for (i, sample) in smoothed.iter_mut().enumerate() {
             let mut sum = 0.0;
             let mut weight = 0.0;
             for offset in -radius..=radius {
@@ -489,7 +584,7 @@ fn build_mountain_outline(seed: u32, base_angle: f64) -> Vec<f64> {
                 sum += outline[idx] * w;
                 weight += w;
             }
-            smoothed[i] = sum / weight;
+            *sample = sum / weight;
         }
         std::mem::swap(&mut outline, &mut smoothed);
     }
@@ -537,3 +632,119 @@ impl SeededRng {
         (value as f64) / 4294967296.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(environment: &Environment) -> Vec<u8> {
+        let mut encoder = Encoder::with_capacity(environment.encoded_len());
+        environment.write_to(&mut encoder);
+        encoder.into_vec()
+    }
+
+    #[test]
+    fn same_seed_produces_identical_bytes() {
+        let a = Environment::generate_with_seed(0xdead_beef_1234, &EnvironmentConfig::default());
+        let b = Environment::generate_with_seed(0xdead_beef_1234, &EnvironmentConfig::default());
+        assert_eq!(encode(&a), encode(&b));
+    }
+
+    #[test]
+    fn different_seeds_produce_different_bytes() {
+        let a = Environment::generate_with_seed(1, &EnvironmentConfig::default());
+        let b = Environment::generate_with_seed(2, &EnvironmentConfig::default());
+        assert_ne!(encode(&a), encode(&b));
+    }
+
+    #[test]
+    fn seeds_differing_only_in_high_bits_produce_different_bytes() {
+        let low = Environment::generate_with_seed(0x1234, &EnvironmentConfig::default());
+        for seed in [
+            0x1234 | (1 << 32),
+            0x1234 | (0x1234 << 32),
+            0x1234 | (1 << 63),
+        ] {
+            let high = Environment::generate_with_seed(seed, &EnvironmentConfig::default());
+            assert_ne!(encode(&low), encode(&high), "seed {seed:#x}");
+        }
+    }
+
+    fn cactus_count(environment: &Environment) -> usize {
+        environment
+            .trees
+            .iter()
+            .filter(|tree| tree.width_scale < 0.0)
+            .count()
+    }
+
+    #[test]
+    fn denser_config_produces_proportionally_more_hazards() {
+        let base = EnvironmentConfig::default();
+        let dense = EnvironmentConfig {
+            lake_count: base.lake_count * 2,
+            tree_count: base.tree_count * 3,
+            cactus_count: base.cactus_count * 2,
+            mountain_count: base.mountain_count * 2,
+            ..base
+        };
+        let sparse = Environment::generate_with_seed(7, &base);
+        let maze = Environment::generate_with_seed(7, &dense);
+
+        assert_eq!(maze.lakes.len(), sparse.lakes.len() * 2);
+        assert_eq!(maze.mountains.len(), sparse.mountains.len() * 2);
+        assert_eq!(cactus_count(&maze), cactus_count(&sparse) * 2);
+        assert_eq!(
+            maze.trees.len() - cactus_count(&maze),
+            (sparse.trees.len() - cactus_count(&sparse)) * 3
+        );
+        // Extra lakes are appended after the ones the default layout already has.
+        assert_eq!(maze.lakes[0].center.x, sparse.lakes[0].center.x);
+    }
+
+    #[test]
+    fn open_plains_config_can_drop_every_hazard() {
+        let plains = EnvironmentConfig {
+            lake_count: 0,
+            tree_count: 0,
+            cactus_count: 0,
+            mountain_count: 0,
+            ..EnvironmentConfig::default()
+        };
+        let environment = Environment::generate_with_seed(7, &plains);
+        assert!(environment.lakes.is_empty());
+        assert!(environment.trees.is_empty());
+        assert!(environment.mountains.is_empty());
+        assert_eq!(encode(&environment).len(), environment.encoded_len());
+    }
+
+    #[test]
+    fn environment_config_clamps_counts_and_scales() {
+        let config = EnvironmentConfig {
+            lake_count: 100,
+            lake_scale: f64::NAN,
+            tree_count: 10_000,
+            cactus_count: 10_000,
+            mountain_count: 10_000,
+            mountain_scale: 9.0,
+        }
+        .sanitized();
+        assert_eq!(config.lake_count, ENVIRONMENT_LAKE_COUNT_MAX);
+        assert_eq!(config.lake_scale, 1.0);
+        assert_eq!(config.tree_count, ENVIRONMENT_TREE_COUNT_MAX);
+        assert_eq!(config.cactus_count, ENVIRONMENT_CACTUS_COUNT_MAX);
+        assert_eq!(config.mountain_count, ENVIRONMENT_MOUNTAIN_COUNT_MAX);
+        assert_eq!(config.mountain_scale, ENVIRONMENT_SIZE_SCALE_MAX);
+    }
+
+    #[test]
+    fn zero_seed_matches_default_layout() {
+        assert_eq!(
+            encode(&Environment::generate_with_seed(
+                0,
+                &EnvironmentConfig::default()
+            )),
+            encode(&Environment::generate())
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use super::constants::COLLISION_DISTANCE;
 use super::types::Point;
+use rand::Rng;
 
 pub fn point_from_spherical(theta: f64, phi: f64) -> Point {
     let sin_phi = phi.sin();
@@ -104,8 +105,8 @@ pub fn rotate_toward(current: Point, target: Point, max_angle: f64) -> Point {
     normalize(rotated)
 }
 
-pub fn random_axis() -> Point {
-    let angle = rand::random::<f64>() * std::f64::consts::PI * 2.0;
+pub fn random_axis(rng: &mut impl Rng) -> Point {
+    let angle = rng.gen::<f64>() * std::f64::consts::PI * 2.0;
     Point {
         x: angle.cos(),
         y: angle.sin(),
@@ -9,7 +9,7 @@ const CONTACT_ITERATIONS: usize = 4;
 const STICK_THRESHOLD: f64 = 0.01;
 
 pub fn apply_snake_with_collisions(
-    snake: &mut Vec<SnakeNode>,
+    snake: &mut [SnakeNode],
     axis: &mut Point,
     snake_angular_radius: f64,
     step_velocity: f64,
@@ -1,92 +1,170 @@
 use super::constants::{
-    BASE_PELLET_COUNT, BASE_SPEED, BIG_PELLET_GROWTH_FRACTION, BOOST_MULTIPLIER,
+    BIG_PELLET_GROWTH_FRACTION, BOOST_COOLDOWN_TICKS, BOOST_MIN_ENGAGE_TICKS,
     BOOST_NODE_DRAIN_PER_SEC, BOOST_SCORE_DRAIN_PER_SEC, BOOST_TRAIL_PELLET_GROWTH_FRACTION,
     BOOST_TRAIL_PELLET_INTERVAL_MS, BOOST_TRAIL_PELLET_SIZE_MAX, BOOST_TRAIL_PELLET_SIZE_MIN,
-    BOOST_TRAIL_PELLET_TTL_MS, BOT_BOOST_DISTANCE, BOT_COUNT, COLOR_POOL, DEATH_PELLET_SIZE_MAX,
-    DEATH_PELLET_SIZE_MIN, EVASIVE_PELLET_CHASE_CONE_ANGLE,
-    EVASIVE_PELLET_CHASE_MAX_ANGLE_RATIO, EVASIVE_PELLET_COOLDOWN_JITTER_MS,
-    EVASIVE_PELLET_COOLDOWN_MS, EVASIVE_PELLET_EVADE_FADE_RADIUS_RATIO,
-    EVASIVE_PELLET_EVADE_FULL_RADIUS_RATIO, EVASIVE_PELLET_EVADE_MIN_FACTOR,
-    EVASIVE_PELLET_EVADE_RADIUS, EVASIVE_PELLET_EVADE_SPEED, EVASIVE_PELLET_EVADE_STEP_MAX,
-    EVASIVE_PELLET_LIFETIME_MS, EVASIVE_PELLET_MAX_LEN, EVASIVE_PELLET_MAX_PER_PLAYER,
-    EVASIVE_PELLET_MAX_STEP_PER_TICK, EVASIVE_PELLET_MIN_LEN,
+    BOOST_TRAIL_PELLET_TTL_MS, BOT_BOOST_DISTANCE, BOT_EASY_WANDER_ANGLE, BOT_HARD_AVOID_MARGIN,
+    BOT_HARD_CUTOFF_DISTANCE, BOT_HARD_CUTOFF_LEAD, BOT_HARD_EVASIVE_CHASE_DISTANCE,
+    COLLISION_LENIENCY, COLLISION_LENIENCY_MAX, COLOR_POOL, DEATH_PELLET_SIZE_MAX,
+    DEATH_PELLET_SIZE_MIN, EVASIVE_PELLET_CHASE_CONE_ANGLE, EVASIVE_PELLET_CHASE_MAX_ANGLE_RATIO,
+    EVASIVE_PELLET_COOLDOWN_JITTER_MS, EVASIVE_PELLET_COOLDOWN_MS,
+    EVASIVE_PELLET_EVADE_FADE_RADIUS_RATIO, EVASIVE_PELLET_EVADE_FULL_RADIUS_RATIO,
+    EVASIVE_PELLET_EVADE_MIN_FACTOR, EVASIVE_PELLET_EVADE_RADIUS, EVASIVE_PELLET_EVADE_SPEED,
+    EVASIVE_PELLET_EVADE_STEP_MAX, EVASIVE_PELLET_LIFETIME_MS, EVASIVE_PELLET_MAX_LEN,
+    EVASIVE_PELLET_MAX_PER_PLAYER, EVASIVE_PELLET_MAX_STEP_PER_TICK, EVASIVE_PELLET_MIN_LEN,
     EVASIVE_PELLET_OTHER_HEAD_EXCLUSION_ANGLE, EVASIVE_PELLET_OWNER_NEAR_ANGLE_MAX,
-    EVASIVE_PELLET_OWNER_NEAR_ANGLE_MIN, EVASIVE_PELLET_RETRY_DELAY_MS, EVASIVE_PELLET_SIZE_MAX,
-    EVASIVE_PELLET_SIZE_MIN, EVASIVE_PELLET_SPAWN_ATTEMPTS, EVASIVE_PELLET_SUCTION_RADIUS,
+    EVASIVE_PELLET_OWNER_NEAR_ANGLE_MIN, EVASIVE_PELLET_RETRY_DELAY_MS,
+    EVASIVE_PELLET_SKILL_WINDOW, EVASIVE_PELLET_SPAWN_ATTEMPTS, EVASIVE_PELLET_SUCTION_RADIUS,
     EVASIVE_PELLET_SUCTION_SPEED, EVASIVE_PELLET_SUCTION_STEP_MAX, EVASIVE_PELLET_ZIGZAG_HZ,
-    EVASIVE_PELLET_ZIGZAG_STRENGTH, MAX_PELLETS, MAX_SPAWN_ATTEMPTS, MIN_SURVIVAL_LENGTH,
-    OXYGEN_DRAIN_PER_SEC, OXYGEN_MAX, PELLET_SIZE_ENCODE_MAX, PELLET_SIZE_ENCODE_MIN,
-    PLAYER_TIMEOUT_MS, RESPAWN_COOLDOWN_MS, RESPAWN_RETRY_MS, SMALL_PELLET_ATTRACT_RADIUS,
-    SMALL_PELLET_ATTRACT_SPEED, SMALL_PELLET_ATTRACT_STEP_MAX_RATIO, SMALL_PELLET_CONSUME_ANGLE,
+    EVASIVE_PELLET_ZIGZAG_STRENGTH, KILL_BONUS_MAX, LAKE_OXYGEN_GRACE_MAX_MS, LAKE_OXYGEN_GRACE_MS,
+    MAX_SPAWN_ATTEMPTS, OXYGEN_DAMAGE_NODES_PER_SEC, PELLET_SIZE_ENCODE_MAX,
+    PELLET_SIZE_ENCODE_MIN, RESPAWN_COOLDOWN_MAX_MS, RESPAWN_COOLDOWN_MS, RESPAWN_RETRY_MS,
+    SMALL_PELLET_ATTRACT_RADIUS, SMALL_PELLET_ATTRACT_RADIUS_MAX, SMALL_PELLET_ATTRACT_SPEED,
+    SMALL_PELLET_ATTRACT_STEP_MAX_RATIO, SMALL_PELLET_CONSUME_ANGLE,
     SMALL_PELLET_DIGESTION_STRENGTH, SMALL_PELLET_DIGESTION_STRENGTH_MAX,
-    SMALL_PELLET_COLOR_PALETTE, SMALL_PELLET_GROWTH_FRACTION, SMALL_PELLET_LOCK_CONE_ANGLE,
-    SMALL_PELLET_MOUTH_FORWARD, SMALL_PELLET_SHRINK_MIN_RATIO, SMALL_PELLET_SIZE_MAX,
-    SMALL_PELLET_SIZE_MIN,
+    SMALL_PELLET_LOCK_CONE_ANGLE, SMALL_PELLET_MOUTH_FORWARD, SMALL_PELLET_SHRINK_MIN_RATIO,
     SMALL_PELLET_SPAWN_HEAD_EXCLUSION_ANGLE, SMALL_PELLET_VIEW_MARGIN_MAX,
     SMALL_PELLET_VIEW_MARGIN_MIN, SMALL_PELLET_VISIBLE_MAX, SMALL_PELLET_VISIBLE_MIN,
-    SMALL_PELLET_ZOOM_MAX_CAMERA_DISTANCE, SMALL_PELLET_ZOOM_MIN_CAMERA_DISTANCE,
-    SNAKE_GIRTH_MAX_SCALE, SNAKE_GIRTH_NODES_PER_STEP, SNAKE_GIRTH_STEP_PERCENT, SPAWN_CONE_ANGLE,
-    SPAWN_PLAYER_MIN_DISTANCE, STARTING_LENGTH, TICK_MS, TURN_BOOST_TURN_RATE_MULTIPLIER,
-    TURN_RATE, TURN_RATE_MAX_MULTIPLIER, TURN_RATE_MIN_MULTIPLIER,
-    TURN_RESPONSE_GAIN_BOOST_PER_SEC, TURN_RESPONSE_GAIN_NORMAL_PER_SEC, TURN_SCANG_BASE,
-    TURN_SCANG_RANGE, TURN_SC_LENGTH_DIVISOR, TURN_SC_MAX, TURN_SPEED_BOOST_TURN_PENALTY,
-    TURN_SPEED_MIN_MULTIPLIER, TURN_SUBSTEPS_BOOST, TURN_SUBSTEPS_NORMAL,
+    SPAWN_CONE_ANGLE, SPAWN_PLAYER_MIN_DISTANCE, SPAWN_SAFETY_FLOOR, SPAWN_SAFETY_FLOOR_MIN,
+    SPAWN_STRICT_ATTEMPTS, STARTING_LENGTH, TICK_MS, TURN_BOOST_TURN_RATE_MULTIPLIER,
+    TURN_RATE_MAX_MULTIPLIER, TURN_RATE_MIN_MULTIPLIER, TURN_RATE_PER_SEC, TURN_RATE_PER_SEC_MAX,
+    TURN_RATE_PER_SEC_MIN, TURN_RESPONSE_GAIN_BOOST_PER_SEC, TURN_RESPONSE_GAIN_NORMAL_PER_SEC,
+    TURN_SCANG_BASE, TURN_SCANG_RANGE, TURN_SC_LENGTH_DIVISOR, TURN_SC_MAX,
+    TURN_SPEED_BOOST_TURN_PENALTY, TURN_SPEED_MIN_MULTIPLIER,
 };
 use super::digestion::{
     add_digestion_with_strength, advance_digestions_with_boost, get_digestion_progress,
     get_digestion_visual_strength, BoostDrainConfig,
 };
 use super::environment::{
-    sample_lakes, Environment, LAKE_EXCLUSION_THRESHOLD, LAKE_WATER_MASK_THRESHOLD, PLANET_RADIUS,
-    SNAKE_RADIUS, TREE_TRUNK_RADIUS,
+    sample_lakes, Environment, EnvironmentConfig, LAKE_EXCLUSION_THRESHOLD,
+    LAKE_WATER_MASK_THRESHOLD, PLANET_RADIUS, SNAKE_RADIUS, TREE_TRUNK_RADIUS,
 };
 use super::geometry::{sample_outline_radius, tangent_basis};
 use super::input::parse_axis;
 use super::math::{
     base_collision_angular_radius, clamp, collision_distance_for_angular_radii,
     collision_with_angular_radii, cross, dot, length, normalize, point_from_spherical, random_axis,
-    rotate_toward, rotate_y, rotate_z,
+    rotate_around_axis, rotate_toward, rotate_y, rotate_z,
 };
 use super::physics::apply_snake_with_collisions;
-use super::snake::{compute_extended_tail_point, compute_tail_tip_point, create_snake, rotate_snake};
-use super::types::{Pellet, PelletState, Player, Point, SnakeNode};
+use super::snake::{
+    compute_extended_tail_point, compute_tail_tip_point, create_snake_with_head,
+    create_snake_with_length, rotate_snake,
+};
+use super::types::{BotSkill, Pellet, PelletState, Player, Point, SnakeNode};
 use crate::protocol;
-use crate::shared::names::sanitize_player_name;
-use rand::Rng;
+use crate::shared::names::{name_policy, sanitize_announcement_text, sanitize_chat_text};
+use crate::shared::rate_limiter::RateLimiter;
+use crate::shared::score_receipt::{sign_score_receipt, ScoreReceiptClaims};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde::Deserialize;
 use std::collections::{HashMap, HashSet};
 use std::f64::consts::PI;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
 use std::sync::{Arc, OnceLock};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::sync::{mpsc, Mutex};
 use uuid::Uuid;
 
+mod config;
+mod debug_json;
+mod recording;
+mod scratch;
 mod session;
+mod snapshot;
 #[cfg(test)]
 mod tests;
+mod tick_histogram;
 mod visibility;
 
-pub use session::{LatestFrame, SessionInbound, SessionIo};
+pub use config::{
+    BotConfig, DayNightConfig, DisconnectConfig, GrowthConfig, InputConfig, MovementConfig,
+    OutboundConfig, OutboundFullPolicy, OxygenConfig, PelletConfig, PelletPalette,
+    PelletVisibility, SelfCollisionConfig, ViewConfig,
+};
+use recording::RecordingBuffer;
+use scratch::TickScratch;
+use session::{is_active_input, outbound_queue, OutboundSender};
+pub use session::{LatestFrame, SessionInbound, SessionIo, WireFormat};
+use tick_histogram::TickHistogram;
+pub use tick_histogram::{TickHistogramSnapshot, TICK_HISTOGRAM_BOUNDS_US};
 
 const VIEW_RADIUS_MIN: f64 = 0.2;
 const VIEW_RADIUS_MAX: f64 = 1.4;
-const VIEW_RADIUS_MARGIN: f64 = 0.14;
-const VIEW_NODE_PADDING: usize = 3;
 const VIEW_MIN_WINDOW_POINTS: usize = 2;
-const VIEW_CAMERA_DISTANCE_MIN: f64 = 4.0;
-const VIEW_CAMERA_DISTANCE_MAX: f64 = 10.0;
-
-const OUTBOUND_HI_CAPACITY: usize = 16;
-const OUTBOUND_LO_CAPACITY: usize = 16;
+/// Accepted camera distances, shared with the wire quantization. The pellet zoom ramp spans
+/// exactly this range, so every accepted distance maps onto a distinct pellet budget.
+const VIEW_CAMERA_DISTANCE_MIN: f64 = protocol::VIEW_CAMERA_DISTANCE_MIN as f64;
+const VIEW_CAMERA_DISTANCE_MAX: f64 = protocol::VIEW_CAMERA_DISTANCE_MAX as f64;
+/// Angular head start an already-visible pellet gets under `PelletVisibility::Nearest`.
+const PELLET_VISIBILITY_HYSTERESIS_ANGLE: f64 = 0.03;
+/// Remote snakes sent with node data (window or full) per frame. Farther ones beyond the cap go
+/// out as stubs, bounding frame size however crowded the view gets.
+pub const DEFAULT_MAX_DETAILED_REMOTE_PLAYERS: usize = 32;
+
+/// How long a running tick loop may go without completing a tick before readiness fails.
+const TICK_STALL_THRESHOLD_MS: i64 = 2_000;
+/// How long the tick loop outlives the last session, so a quick rejoin finds the room (and, in
+/// standalone mode, the same room instance) as it was left. The lingering loop is frozen, not
+/// slowed: it polls for sessions without ticking, since simulating an empty room would trim its
+/// bots and respawn pellets. This applies to every room by default; before it, loops stopped on
+/// the first empty tick, which `0` restores.
+pub const DEFAULT_IDLE_LINGER_MS: i64 = 30_000;
+const IDLE_LINGER_MAX_MS: i64 = 600_000;
+/// How often a lingering loop checks for returning sessions.
+const IDLE_LINGER_POLL: Duration = Duration::from_millis(250);
+/// Open sockets allowed per room before new upgrades are refused. Spectators and sockets that
+/// never join count too, so this sits well above any human player cap.
+pub const DEFAULT_MAX_SESSIONS: usize = 512;
+const MAX_SESSIONS_PER_HUMAN_PLAYER: usize = 4;
+/// Largest inbound client message handled; bigger ones end the session before any parsing.
+/// Real client messages (joins with a skin, chat, input) stay well under 1 KiB.
+pub const DEFAULT_MAX_INBOUND_MESSAGE_BYTES: usize = 16 * 1024;
+const MAX_INBOUND_MESSAGE_BYTES_MIN: usize = 1024;
+const MAX_INBOUND_MESSAGE_BYTES_MAX: usize = 1024 * 1024;
 const PELLET_RESET_RETRY_MS: i64 = 250;
-const STATE_DELTA_KEYFRAME_INTERVAL: u32 = 4;
+/// A session resyncing its pellet view this often within the window is logged as a slow client.
+const PELLET_RESYNC_SLOW_CLIENT_THRESHOLD: u32 = 3;
+const PELLET_RESYNC_SLOW_CLIENT_WINDOW_MS: i64 = 10_000;
+/// State frames between scheduled keyframes; joins and client resync requests force one sooner.
+pub const DEFAULT_KEYFRAME_INTERVAL: u32 = 4;
+const INPUT_RATE_LIMIT_PER_TICK: u32 = 8;
+/// A session over its input budget is logged at most once per this interval.
+const INPUT_RATE_LIMIT_WARN_INTERVAL_MS: u64 = 10_000;
+/// Steering changes smaller than this (radians) don't count as activity for AFK autopilot, so
+/// heartbeat inputs repeating the same axis leave a player idle.
+const AFK_INPUT_AXIS_DEADBAND: f64 = 0.02;
+/// Default chat budget per session; rooms can change it with `Room::with_chat_rate_limit`.
+pub const CHAT_RATE_LIMIT_PER_WINDOW: u32 = 3;
+pub const CHAT_RATE_WINDOW_MS: i64 = 5_000;
+/// Only other-snake nodes within this angle of the head count as a threat for hints.
+const THREAT_HINT_MAX_ANGLE: f64 = 0.5;
+const REPLAY_DEFAULT_CAPACITY: usize = 1200;
+const TICK_OVERRUN_SHED_THRESHOLD: u32 = 5;
+const TICK_RECOVERY_HEALTHY_TICKS: u32 = 200;
+const PELLET_LOAD_FACTOR_MIN: f64 = 0.4;
+const PELLET_LOAD_SHED_STEP: f64 = 0.8;
+const PELLET_LOAD_RECOVER_STEP: f64 = 0.1;
+/// Most surplus pellets removed per tick while shedding load, so clients see a gradual thin-out.
+const PELLET_LOAD_TRIM_PER_TICK: usize = 32;
+const DEBUG_MAX_SNAKE_LENGTH: usize = 4096;
 const BOT_COUNT_ENV_KEY: &str = "SNAKE_BOT_COUNT";
 const BOT_SUPPRESS_ROOM_PREFIX_ENV_KEY: &str = "SNAKE_NO_BOTS_ROOM_PREFIX";
 const OXYGEN_DISABLED_ENV_KEY: &str = "SNAKE_DISABLE_OXYGEN";
 const ROCK_PELLET_FREQ_MULT_ENV_KEY: &str = "SNAKE_DEBUG_ROCK_PELLET_FREQ_MULT";
+const TICK_PROFILING_ENV_KEY: &str = "SNAKE_TICK_PROFILING";
+
+/// How far behind a state frame's `now` clients are meant to render, so there is normally a
+/// newer frame to interpolate toward when the current one arrives.
+const STATE_RENDER_DELAY_TICKS: u64 = 2;
 
 const DELTA_FRAME_KEYFRAME: u8 = 1 << 0;
+/// A var-int session best for the session's own player trails the player list.
+const DELTA_FRAME_SESSION_BEST: u8 = 1 << 1;
+/// The session's own player is dead; the server time (ms) it may respawn at trails the player
+/// list, after the session best.
+const DELTA_FRAME_RESPAWN_AT: u8 = 1 << 2;
 
 const DELTA_FIELD_FLAGS: u16 = 1 << 0;
 const DELTA_FIELD_SCORE: u16 = 1 << 1;
@@ -105,13 +183,40 @@ const DELTA_SNAKE_SHIFT_HEAD: u8 = 1;
 pub struct Room {
     state: Mutex<RoomState>,
     running: AtomicBool,
+    last_tick_at_ms: AtomicI64,
+    heartbeat_failures: AtomicU64,
+    tick_durations: TickHistogram,
     max_human_players: Option<usize>,
+    max_sessions: usize,
+    max_inbound_message_bytes: usize,
+    outbound_config: OutboundConfig,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct RoomStats {
     pub human_players: usize,
     pub total_sessions: usize,
+    pub over_budget_inputs: u64,
+    pub tick_overruns: u64,
+    pub heartbeat_failures: u64,
+    /// Pellet deltas dropped on a full `outbound_lo`, each forcing that session to resync.
+    pub pellet_resyncs: u64,
+    /// Highest pellet resync count among the sessions currently connected.
+    pub max_session_pellet_resyncs: u64,
+    pub tick_durations: TickHistogramSnapshot,
+}
+
+/// One player's live standing for overlays. `rank` is 1-based by score among every player in
+/// the room, bots included; tied scores share a rank.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerSnapshot {
+    pub net_id: u16,
+    pub name: String,
+    pub score: i64,
+    pub rank: usize,
+    pub total_players: usize,
+    pub length: usize,
+    pub alive: bool,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -124,7 +229,7 @@ pub enum DebugKillTarget {
 #[derive(Debug)]
 struct SessionEntry {
     outbound_state: Arc<LatestFrame>,
-    outbound_hi: mpsc::Sender<Vec<u8>>,
+    outbound_hi: OutboundSender,
     outbound_lo: mpsc::Sender<Vec<u8>>,
     inbound: Arc<SessionInbound>,
     player_id: Option<String>,
@@ -137,6 +242,13 @@ struct SessionEntry {
     delta_player_cache: HashMap<u16, DeltaPlayerCache>,
     force_next_keyframe: bool,
     latest_applied_input_seq: u16,
+    wire_format: WireFormat,
+    protocol_version: Option<u8>,
+    pellet_resyncs: u64,
+    pellet_resync_window_started_at: i64,
+    pellet_resyncs_in_window: u32,
+    threat_hints: bool,
+    threat_hint_active: bool,
 }
 
 #[derive(Debug)]
@@ -147,10 +259,62 @@ struct RoomState {
     pellets: Vec<Pellet>,
     next_pellet_id: u32,
     next_state_seq: u32,
+    /// `(state_seq, now)` of the last broadcast state frame. Lets each frame carry the interval
+    /// back to its predecessor, which stretches when the tick loop skips late ticks.
+    last_state_frame: Option<(u32, i64)>,
     next_player_net_id: u16,
     next_evasive_spawn_at: HashMap<String, i64>,
     pending_pellet_consumes: Vec<(u32, String)>,
     environment: Environment,
+    environment_seed: u64,
+    environment_config: EnvironmentConfig,
+    over_budget_inputs: u64,
+    pellet_resyncs: u64,
+    bot_config: BotConfig,
+    /// False for pure PvP rooms: no bot ever spawns, whatever `bot_config` says.
+    bots_enabled: bool,
+    pellet_config: PelletConfig,
+    pellet_palette: PelletPalette,
+    growth_config: GrowthConfig,
+    disconnect_config: DisconnectConfig,
+    oxygen_config: OxygenConfig,
+    movement_config: MovementConfig,
+    day_night: Option<DayNightConfig>,
+    day_phase: f64,
+    day_phase_q: Option<u8>,
+    recording: RecordingBuffer,
+    scratch: TickScratch,
+    tick_overruns: u64,
+    overrun_streak: u32,
+    /// Overrun ticks in the current unbroken run. Unlike `overrun_streak` it isn't reset when
+    /// load is shed, so the run is logged once when it starts and once when it ends.
+    overrun_run_ticks: u32,
+    healthy_tick_streak: u32,
+    pellet_load_factor: f64,
+    respawn_length_retention: f64,
+    oxygen_grace_ms: i64,
+    turn_rate_per_sec: f64,
+    spawn_safety_floor: f64,
+    spawn_terrain_protection: bool,
+    collision_leniency: f64,
+    kill_bonus: i64,
+    score_receipt_secret: Option<String>,
+    self_collision: SelfCollisionConfig,
+    input_config: InputConfig,
+    view_config: ViewConfig,
+    chat_limiter: RateLimiter<String>,
+    input_warn_limiter: RateLimiter<String>,
+    boost_drain_shedding: bool,
+    idle_linger_ms: i64,
+    afk_autopilot_ms: Option<i64>,
+    respawn_cooldown_ms: i64,
+    max_detailed_remote_players: usize,
+    keyframe_interval: u32,
+    pellet_visibility: PelletVisibility,
+    draining: bool,
+    /// Source for every random choice the simulation makes (spawns, pellets, bot wander).
+    /// Seeded from entropy; tests pin it with `new_with_rng_seed` so those paths are reproducible.
+    rng: StdRng,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -160,6 +324,15 @@ enum SnakeDetail {
     Stub,
 }
 
+/// Header timing carried by every state frame; see `RoomState::state_frame_timing`.
+#[derive(Clone, Copy, Debug)]
+struct StateFrameTiming {
+    tick_ms: u16,
+    render_delay_ms: u16,
+    prev_state_seq: u32,
+    prev_interval_ms: u16,
+}
+
 #[derive(Clone, Copy, Debug)]
 struct SnakeWindow {
     detail: SnakeDetail,
@@ -244,6 +417,23 @@ struct HeadAttractor {
     head: Point,
     forward: Point,
     mouth: Point,
+    attract_radius: f64,
+}
+
+#[derive(Debug)]
+struct BotObstacle {
+    id: String,
+    is_bot: bool,
+    head: Point,
+    forward: Point,
+    nodes: Vec<Point>,
+    body_angular_radius: f64,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct BotSteering {
+    axis: Point,
+    boost: bool,
 }
 
 #[derive(Debug)]
@@ -251,6 +441,9 @@ struct PlayerCollisionSnapshot {
     id: String,
     alive: bool,
     snake: Vec<Point>,
+    /// Virtual point past the last node while the tail is growing. It is body-only: other heads
+    /// collide with it, but it never acts as a head and doesn't count toward `snake.len()`.
+    extended_tail: Option<Point>,
     contact_angular_radius: f64,
     body_angular_radius: f64,
 }
@@ -272,9 +465,22 @@ enum JsonClientMessage {
         player_id: Option<String>,
         #[serde(rename = "deferSpawn")]
         defer_spawn: Option<bool>,
+        #[serde(default, deserialize_with = "deserialize_json_skin")]
+        skin: Option<Vec<[u8; 3]>>,
     },
     #[serde(rename = "respawn")]
     Respawn,
+    #[serde(rename = "resync")]
+    Resync,
+    #[serde(rename = "chat")]
+    Chat { text: String },
+    #[serde(rename = "hints")]
+    Hints { threat: Option<bool> },
+    #[serde(rename = "ping")]
+    Ping {
+        #[serde(rename = "clientTime")]
+        client_time: u32,
+    },
     #[serde(rename = "input")]
     Input {
         axis: Option<Point>,
@@ -283,11 +489,85 @@ enum JsonClientMessage {
         view_center: Option<Point>,
         #[serde(rename = "viewRadius")]
         view_radius: Option<f32>,
-        #[serde(rename = "cameraDistance")]
+        #[serde(
+            rename = "cameraDistance",
+            default,
+            deserialize_with = "deserialize_json_camera_distance"
+        )]
         camera_distance: Option<f32>,
     },
 }
 
+/// Reads a JSON camera distance, rejecting the whole message when it is not finite. JSON
+/// numbers too large for `f32` arrive as infinity, which would poison the pellet zoom math.
+fn deserialize_json_camera_distance<'de, D>(deserializer: D) -> Result<Option<f32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<f32>::deserialize(deserializer)?;
+    match value {
+        Some(distance) if !protocol::is_valid_camera_distance(distance) => {
+            Err(serde::de::Error::custom("camera distance must be finite"))
+        }
+        _ => Ok(value),
+    }
+}
+
+/// Reads a JSON skin (`["#rrggbb", ...]`), failing as soon as it exceeds
+/// `protocol::MAX_SKIN_COLORS` so oversized payloads are rejected without being collected.
+fn deserialize_json_skin<'de, D>(deserializer: D) -> Result<Option<Vec<[u8; 3]>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct SkinVisitor;
+
+    impl<'de> serde::de::Visitor<'de> for SkinVisitor {
+        type Value = Option<Vec<[u8; 3]>>;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(
+                formatter,
+                "null or up to {} \"#rrggbb\" colors",
+                protocol::MAX_SKIN_COLORS
+            )
+        }
+
+        fn visit_none<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: serde::Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_seq(self)
+        }
+
+        fn visit_seq<A: serde::de::SeqAccess<'de>>(
+            self,
+            mut seq: A,
+        ) -> Result<Self::Value, A::Error> {
+            let mut colors = Vec::with_capacity(protocol::MAX_SKIN_COLORS);
+            while let Some(value) = seq.next_element::<String>()? {
+                if colors.len() >= protocol::MAX_SKIN_COLORS {
+                    return Err(serde::de::Error::invalid_length(colors.len() + 1, &self));
+                }
+                let rgb = protocol::parse_skin_hex(&value).ok_or_else(|| {
+                    serde::de::Error::invalid_value(serde::de::Unexpected::Str(&value), &self)
+                })?;
+                colors.push(rgb);
+            }
+            Ok(Some(colors))
+        }
+    }
+
+    deserializer.deserialize_option(SkinVisitor)
+}
+
 impl Room {
     pub fn new() -> Self {
         Self::with_room_id_and_max_human_players("main".to_string(), None)
@@ -306,30 +586,277 @@ impl Room {
         max_human_players: Option<usize>,
     ) -> Self {
         Self {
-            state: Mutex::new(RoomState {
-                room_id,
-                sessions: HashMap::new(),
-                players: HashMap::new(),
-                pellets: Vec::new(),
-                next_pellet_id: 0,
-                next_state_seq: 1,
-                next_player_net_id: 1,
-                next_evasive_spawn_at: HashMap::new(),
-                pending_pellet_consumes: Vec::new(),
-                environment: Environment::generate(),
-            }),
+            state: Mutex::new(RoomState::new(room_id)),
             running: AtomicBool::new(false),
+            last_tick_at_ms: AtomicI64::new(0),
+            heartbeat_failures: AtomicU64::new(0),
+            tick_durations: TickHistogram::default(),
             max_human_players,
+            max_sessions: max_human_players.map_or(DEFAULT_MAX_SESSIONS, |max_players| {
+                DEFAULT_MAX_SESSIONS.max(max_players.saturating_mul(MAX_SESSIONS_PER_HUMAN_PLAYER))
+            }),
+            max_inbound_message_bytes: DEFAULT_MAX_INBOUND_MESSAGE_BYTES,
+            outbound_config: OutboundConfig::default(),
         }
     }
 
-    pub async fn add_session(&self) -> SessionIo {
+    pub fn with_max_sessions(mut self, max_sessions: usize) -> Self {
+        self.max_sessions = max_sessions.max(1);
+        self
+    }
+
+    /// Largest inbound WebSocket message a session may send, clamped to
+    /// `MAX_INBOUND_MESSAGE_BYTES_MIN..=MAX_INBOUND_MESSAGE_BYTES_MAX`.
+    pub fn with_max_inbound_message_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_inbound_message_bytes =
+            max_bytes.clamp(MAX_INBOUND_MESSAGE_BYTES_MIN, MAX_INBOUND_MESSAGE_BYTES_MAX);
+        self
+    }
+
+    pub fn max_inbound_message_bytes(&self) -> usize {
+        self.max_inbound_message_bytes
+    }
+
+    pub fn with_outbound_config(mut self, outbound_config: OutboundConfig) -> Self {
+        self.outbound_config = outbound_config.sanitized();
+        self
+    }
+
+    pub fn with_bot_config(mut self, bot_config: BotConfig) -> Self {
+        self.state.get_mut().bot_config = bot_config;
+        self
+    }
+
+    /// Disabling bots makes a pure PvP room; any bots already present are removed on the next
+    /// tick.
+    pub fn with_bots_enabled(mut self, enabled: bool) -> Self {
+        self.state.get_mut().bots_enabled = enabled;
+        self
+    }
+
+    pub fn with_pellet_config(mut self, pellet_config: PelletConfig) -> Self {
+        self.state.get_mut().pellet_config = pellet_config.sanitized();
+        self
+    }
+
+    pub fn with_pellet_palette(mut self, pellet_palette: PelletPalette) -> Self {
+        self.state.get_mut().pellet_palette = pellet_palette;
+        self
+    }
+
+    pub fn with_growth_config(mut self, growth_config: GrowthConfig) -> Self {
+        self.state.get_mut().growth_config = growth_config.sanitized();
+        self
+    }
+
+    pub fn with_disconnect_config(mut self, disconnect_config: DisconnectConfig) -> Self {
+        self.state.get_mut().disconnect_config = disconnect_config.sanitized();
+        self
+    }
+
+    pub fn with_oxygen_config(mut self, oxygen_config: OxygenConfig) -> Self {
+        self.state.get_mut().oxygen_config = oxygen_config.sanitized();
+        self
+    }
+
+    pub fn with_movement_config(mut self, movement_config: MovementConfig) -> Self {
+        self.state.get_mut().movement_config = movement_config.sanitized();
+        self
+    }
+
+    pub fn with_pellet_visibility(mut self, visibility: PelletVisibility) -> Self {
+        self.state.get_mut().pellet_visibility = visibility;
+        self
+    }
+
+    pub fn with_day_night_cycle(mut self, config: DayNightConfig) -> Self {
+        self.state.get_mut().day_night = Some(config);
+        self
+    }
+
+    /// How long a dead player waits before a `Respawn` is honored, clamped to
+    /// `0..=RESPAWN_COOLDOWN_MAX_MS`.
+    pub fn with_respawn_cooldown_ms(mut self, cooldown_ms: i64) -> Self {
+        self.state.get_mut().respawn_cooldown_ms = cooldown_ms.clamp(0, RESPAWN_COOLDOWN_MAX_MS);
+        self
+    }
+
+    /// How long a head may stay in water before oxygen starts draining, clamped to
+    /// `0..=LAKE_OXYGEN_GRACE_MAX_MS`.
+    pub fn with_oxygen_grace_ms(mut self, grace_ms: i64) -> Self {
+        self.state.get_mut().oxygen_grace_ms = grace_ms.clamp(0, LAKE_OXYGEN_GRACE_MAX_MS);
+        self
+    }
+
+    /// Fraction of the strict spawn head distance that the last spawn attempt still demands once
+    /// crowding has defeated the strict attempts. Non-finite values are ignored; others are
+    /// clamped to `SPAWN_SAFETY_FLOOR_MIN..=1.0`, where 1.0 never relaxes.
+    pub fn with_spawn_safety_floor(mut self, floor: f64) -> Self {
+        if floor.is_finite() {
+            self.state.get_mut().spawn_safety_floor = floor.clamp(SPAWN_SAFETY_FLOOR_MIN, 1.0);
+        }
+        self
+    }
+
+    /// Rejects human spawn placements with any node inside a lake or a tree, cactus or mountain
+    /// collider, retrying elsewhere like a crowded placement. On by default; bots always spawn
+    /// without the check.
+    pub fn with_spawn_terrain_protection(mut self, enabled: bool) -> Self {
+        self.state.get_mut().spawn_terrain_protection = enabled;
+        self
+    }
+
+    /// Fraction of the head-to-body contact distance forgiven before a head counts as hitting
+    /// another snake's body. Terrain and self contact are unaffected. Non-finite values are
+    /// ignored; others are clamped to `0..=COLLISION_LENIENCY_MAX`, where 0 is exact contact.
+    pub fn with_collision_leniency(mut self, leniency: f64) -> Self {
+        if leniency.is_finite() {
+            self.state.get_mut().collision_leniency = leniency.clamp(0.0, COLLISION_LENIENCY_MAX);
+        }
+        self
+    }
+
+    /// Score awarded to the snake whose body another head dies on, clamped to
+    /// `0..=KILL_BONUS_MAX`. 0 (the default) leaves kills worth only the pellets they drop.
+    pub fn with_kill_bonus(mut self, bonus: i64) -> Self {
+        self.state.get_mut().kill_bonus = bonus.clamp(0, KILL_BONUS_MAX);
+        self
+    }
+
+    /// Signs each human's score at death with `secret` and sends the receipt to their
+    /// sessions as `TYPE_SCORE_RECEIPT`, for leaderboards that only accept room-issued scores.
+    pub fn with_score_receipt_secret(mut self, secret: String) -> Self {
+        self.state.get_mut().score_receipt_secret = Some(secret);
+        self
+    }
+
+    /// Makes a head touching its own body lethal, using the same contact radii and leniency as
+    /// other snakes' bodies. Off by default.
+    pub fn with_self_collision(mut self, self_collision: SelfCollisionConfig) -> Self {
+        self.state.get_mut().self_collision = self_collision.sanitized();
+        self
+    }
+
+    /// Dead zone and smoothing for human steering input; see `InputConfig`.
+    pub fn with_input_config(mut self, input_config: InputConfig) -> Self {
+        self.state.get_mut().input_config = input_config.sanitized();
+        self
+    }
+
+    /// How far past each session's view remote snakes are still sent; see `ViewConfig`.
+    pub fn with_view_config(mut self, view_config: ViewConfig) -> Self {
+        self.state.get_mut().view_config = view_config.sanitized();
+        self
+    }
+
+    /// Chat lines each session may send per `window_ms`, replacing the default of
+    /// `CHAT_RATE_LIMIT_PER_WINDOW` per `CHAT_RATE_WINDOW_MS`. A window under 1 ms counts as 1.
+    pub fn with_chat_rate_limit(mut self, per_window: u32, window_ms: i64) -> Self {
+        let window = Duration::from_millis(window_ms.max(1) as u64);
+        self.state
+            .get_mut()
+            .chat_limiter
+            .configure(per_window, window);
+        self
+    }
+
+    /// How long the frozen tick loop keeps polling after the last session leaves before it
+    /// stops, clamped to `0..=IDLE_LINGER_MAX_MS`. `0` stops it on the first empty tick.
+    pub fn with_idle_linger_ms(mut self, linger_ms: i64) -> Self {
+        self.state.get_mut().idle_linger_ms = linger_ms.clamp(0, IDLE_LINGER_MAX_MS);
+        self
+    }
+
+    /// Hands connected humans whose input hasn't changed for `afk_ms` to the bot planner until
+    /// they steer or toggle boost again, so idle snakes keep moving instead of sitting as
+    /// obstacles. Client heartbeats that repeat the same input don't count. `0` or less
+    /// disables it, which is the default.
+    pub fn with_afk_autopilot_ms(mut self, afk_ms: i64) -> Self {
+        self.state.get_mut().afk_autopilot_ms = (afk_ms > 0).then_some(afk_ms);
+        self
+    }
+
+    /// Sheds the length boosting drains as trail pellets at the tail, so chasers can eat back
+    /// what a boosting snake burns. Off by default, where boost trails are a fixed-rate effect
+    /// unrelated to the drain.
+    pub fn with_boost_drain_shedding(mut self, enabled: bool) -> Self {
+        self.state.get_mut().boost_drain_shedding = enabled;
+        self
+    }
+
+    /// Base snake turn rate in radians per second, shared by humans and bots. Non-finite
+    /// values are ignored; others are clamped to `TURN_RATE_PER_SEC_MIN..=TURN_RATE_PER_SEC_MAX`.
+    pub fn with_turn_rate_per_sec(mut self, turn_rate_per_sec: f64) -> Self {
+        if turn_rate_per_sec.is_finite() {
+            self.state.get_mut().turn_rate_per_sec =
+                turn_rate_per_sec.clamp(TURN_RATE_PER_SEC_MIN, TURN_RATE_PER_SEC_MAX);
+        }
+        self
+    }
+
+    /// Fraction of a snake's length at death that it keeps when respawning. Defaults to `0.0`,
+    /// i.e. every respawn starts from the configured starting length. When a snake keeps length,
+    /// its death pellets are worth only the remaining `1 - fraction` of their usual growth.
+    pub fn with_respawn_length_retention(mut self, fraction: f64) -> Self {
+        let fraction = if fraction.is_finite() {
+            clamp(fraction, 0.0, 1.0)
+        } else {
+            0.0
+        };
+        self.state.get_mut().respawn_length_retention = fraction;
+        self
+    }
+
+    pub fn with_max_detailed_remote_players(mut self, max_players: usize) -> Self {
+        self.state.get_mut().max_detailed_remote_players = max_players;
+        self
+    }
+
+    pub fn with_keyframe_interval(mut self, interval: u32) -> Self {
+        self.state.get_mut().keyframe_interval = interval.max(1);
+        self
+    }
+
+    pub fn with_replay_capacity(mut self, capacity: usize) -> Self {
+        self.state.get_mut().recording = RecordingBuffer::new(capacity);
+        self
+    }
+
+    pub fn with_environment_seed(mut self, seed: u64) -> Self {
+        let state = self.state.get_mut();
+        state.environment_seed = seed;
+        state.regenerate_environment();
+        self
+    }
+
+    /// Hazard counts and sizes for this room's terrain, e.g. sparse "open plains" or a crowded
+    /// "death maze". Combines with `with_environment_seed` in either order.
+    pub fn with_environment_config(mut self, config: EnvironmentConfig) -> Self {
+        let state = self.state.get_mut();
+        state.environment_config = config.sanitized();
+        state.regenerate_environment();
+        self
+    }
+
+    /// Whether another socket can be accepted. Checked before upgrading so a full room can
+    /// answer with a plain 503; `add_session` enforces the same bound.
+    pub async fn has_session_capacity(&self) -> bool {
+        self.state.lock().await.sessions.len() < self.max_sessions
+    }
+
+    /// Registers a new socket, or returns `None` when the room already holds `max_sessions`.
+    pub async fn add_session(&self, wire_format: WireFormat) -> Option<SessionIo> {
+        let mut state = self.state.lock().await;
+        if state.sessions.len() >= self.max_sessions {
+            return None;
+        }
         let session_id = Uuid::new_v4().to_string();
         let inbound = Arc::new(SessionInbound::new());
         let outbound_state = Arc::new(LatestFrame::new());
-        let (outbound_hi, outbound_hi_rx) = mpsc::channel::<Vec<u8>>(OUTBOUND_HI_CAPACITY);
-        let (outbound_lo, outbound_lo_rx) = mpsc::channel::<Vec<u8>>(OUTBOUND_LO_CAPACITY);
-        let mut state = self.state.lock().await;
+        let outbound = self.outbound_config;
+        let (outbound_hi, outbound_hi_rx) =
+            outbound_queue(outbound.hi_capacity, outbound.hi_full_policy);
+        let (outbound_lo, outbound_lo_rx) = mpsc::channel::<Vec<u8>>(outbound.lo_capacity);
         state.sessions.insert(
             session_id.clone(),
             SessionEntry {
@@ -347,15 +874,56 @@ impl Room {
                 delta_player_cache: HashMap::new(),
                 force_next_keyframe: true,
                 latest_applied_input_seq: 0,
+                wire_format,
+                protocol_version: None,
+                pellet_resyncs: 0,
+                pellet_resync_window_started_at: 0,
+                pellet_resyncs_in_window: 0,
+                threat_hints: false,
+                threat_hint_active: false,
             },
         );
-        SessionIo {
+        Some(SessionIo {
             session_id,
+            wire_format,
             inbound,
             outbound_state,
             outbound_hi_rx,
             outbound_lo_rx,
-        }
+        })
+    }
+
+    /// Negotiates the protocol version announced by a session's `Hello` frame and records it
+    /// on the session. Must succeed before the session's `Join` is processed.
+    pub async fn handle_hello(
+        &self,
+        session_id: &str,
+        client_version: u8,
+    ) -> Result<u8, protocol::ServerError> {
+        let mut state = self.state.lock().await;
+        state.negotiate_session_version(session_id, client_version)
+    }
+
+    /// While draining, joins from new players are refused with `ServerError::Draining`;
+    /// players already in the room can still reconnect.
+    pub async fn set_draining(&self, draining: bool) {
+        self.state.lock().await.draining = draining;
+    }
+
+    /// Starts draining and tells every connected session to move to `room_id` using
+    /// `room_token`. Returns how many sessions were told.
+    pub async fn migrate_sessions(&self, room_id: &str, room_token: &str) -> usize {
+        let mut state = self.state.lock().await;
+        state.draining = true;
+        state.send_migrate(room_id, room_token)
+    }
+
+    /// Pushes an operator announcement to every session. Returns how many sessions got it, or
+    /// `None` when nothing printable is left after sanitizing.
+    pub async fn announce(&self, text: &str) -> Option<usize> {
+        let text = sanitize_announcement_text(text)?;
+        let mut state = self.state.lock().await;
+        Some(state.broadcast_announcement(&text))
     }
 
     pub async fn remove_session(&self, session_id: &str) {
@@ -369,6 +937,9 @@ impl Room {
         inbound: &Arc<SessionInbound>,
         text: &str,
     ) -> bool {
+        if !self.accept_inbound_size(session_id, text.len()).await {
+            return false;
+        }
         let Ok(message) = serde_json::from_str::<JsonClientMessage>(text) else {
             return true;
         };
@@ -377,6 +948,7 @@ impl Room {
                 name,
                 player_id,
                 defer_spawn,
+                skin,
             } => {
                 let player_id = player_id.and_then(|value| Uuid::parse_str(&value).ok());
                 self.handle_client_message(
@@ -385,7 +957,7 @@ impl Room {
                         name,
                         player_id,
                         defer_spawn: defer_spawn.unwrap_or(false),
-                        skin: None,
+                        skin,
                     },
                 )
                 .await
@@ -394,6 +966,30 @@ impl Room {
                 self.handle_client_message(session_id, protocol::ClientMessage::Respawn)
                     .await
             }
+            JsonClientMessage::Resync => {
+                self.handle_client_message(session_id, protocol::ClientMessage::Resync)
+                    .await
+            }
+            JsonClientMessage::Chat { text } => {
+                self.handle_client_message(session_id, protocol::ClientMessage::Chat { text })
+                    .await
+            }
+            JsonClientMessage::Hints { threat } => {
+                self.handle_client_message(
+                    session_id,
+                    protocol::ClientMessage::Hints {
+                        threat: threat.unwrap_or(false),
+                    },
+                )
+                .await
+            }
+            JsonClientMessage::Ping { client_time } => {
+                self.handle_client_message(
+                    session_id,
+                    protocol::ClientMessage::Ping { client_time },
+                )
+                .await
+            }
             JsonClientMessage::Input {
                 axis,
                 boost,
@@ -414,6 +1010,9 @@ impl Room {
         inbound: &Arc<SessionInbound>,
         data: &[u8],
     ) -> bool {
+        if !self.accept_inbound_size(session_id, data.len()).await {
+            return false;
+        }
         let Some(message) = protocol::decode_client_message(data) else {
             return true;
         };
@@ -438,6 +1037,23 @@ impl Room {
         }
     }
 
+    /// Rejects a message over `max_inbound_message_bytes` before it is decoded, queueing a
+    /// `TYPE_ERROR` so the client learns why the socket is closing.
+    async fn accept_inbound_size(&self, session_id: &str, len: usize) -> bool {
+        if len <= self.max_inbound_message_bytes {
+            return true;
+        }
+        let state = self.state.lock().await;
+        tracing::warn!(
+            room_id = state.room_id,
+            len,
+            max = self.max_inbound_message_bytes,
+            "session_inbound_message_too_large"
+        );
+        state.send_session_error(session_id, protocol::ServerError::MessageTooLarge);
+        false
+    }
+
     async fn handle_client_message(
         self: &Arc<Self>,
         session_id: &str,
@@ -470,6 +1086,22 @@ impl Room {
                 state.handle_respawn(session_id);
                 true
             }
+            protocol::ClientMessage::Resync => {
+                state.handle_resync(session_id);
+                true
+            }
+            protocol::ClientMessage::Chat { text } => {
+                state.handle_chat(session_id, &text, RoomState::now_millis());
+                true
+            }
+            protocol::ClientMessage::Hints { threat } => {
+                state.handle_hints(session_id, threat);
+                true
+            }
+            protocol::ClientMessage::Ping { client_time } => {
+                state.handle_ping(session_id, client_time, RoomState::now_millis());
+                true
+            }
             protocol::ClientMessage::Input {
                 axis,
                 boost,
@@ -494,12 +1126,106 @@ impl Room {
         state.debug_kill(target)
     }
 
+    /// Rebuilds the target's snake at `length` nodes, keeping its head where it is.
+    pub async fn debug_set_length(&self, target: DebugKillTarget, length: usize) -> Option<String> {
+        let mut state = self.state.lock().await;
+        state.debug_set_length(target, length)
+    }
+
+    /// Rebuilds the target's snake, at its current length, with its head at `head`.
+    pub async fn debug_teleport(&self, target: DebugKillTarget, head: Point) -> Option<String> {
+        let mut state = self.state.lock().await;
+        state.debug_teleport(target, head)
+    }
+
+    pub async fn set_recording(&self, enabled: bool) {
+        let mut state = self.state.lock().await;
+        state.recording.set_enabled(enabled);
+        tracing::info!(room_id = state.room_id, enabled, "room_recording_toggled");
+    }
+
+    /// Serializes the room's simulation state for hot migration to another process.
+    pub async fn snapshot(&self) -> Vec<u8> {
+        let state = self.state.lock().await;
+        let bytes = state.serialize();
+        tracing::info!(
+            room_id = state.room_id,
+            players = state.players.len(),
+            bytes = bytes.len(),
+            "room_snapshot_taken"
+        );
+        bytes
+    }
+
+    /// Replaces the room's simulation state with a snapshot from [`Room::snapshot`].
+    pub async fn restore_snapshot(&self, bytes: &[u8]) -> anyhow::Result<()> {
+        let mut state = self.state.lock().await;
+        state.restore(bytes)?;
+        tracing::info!(
+            room_id = state.room_id,
+            players = state.players.len(),
+            "room_snapshot_restored"
+        );
+        Ok(())
+    }
+
+    pub async fn export_recording(&self) -> Vec<u8> {
+        let state = self.state.lock().await;
+        tracing::debug!(
+            room_id = state.room_id,
+            frames = state.recording.len(),
+            "room_recording_exported"
+        );
+        state.recording.export()
+    }
+
+    /// The room's terrain in the `TYPE_INIT` environment encoding, for map previews.
+    pub async fn environment_bytes(&self) -> Vec<u8> {
+        let state = self.state.lock().await;
+        state.environment_bytes()
+    }
+
+    pub async fn player_snapshot(&self, net_id: u16) -> Option<PlayerSnapshot> {
+        let state = self.state.lock().await;
+        state.player_snapshot(net_id)
+    }
+
     pub async fn stats(&self) -> RoomStats {
         let state = self.state.lock().await;
         RoomStats {
             human_players: state.human_count(),
             total_sessions: state.sessions.len(),
+            over_budget_inputs: state.over_budget_inputs,
+            tick_overruns: state.tick_overruns,
+            heartbeat_failures: self.heartbeat_failures.load(Ordering::Relaxed),
+            pellet_resyncs: state.pellet_resyncs,
+            max_session_pellet_resyncs: state
+                .sessions
+                .values()
+                .map(|session| session.pellet_resyncs)
+                .max()
+                .unwrap_or(0),
+            tick_durations: self.tick_durations.snapshot(),
+        }
+    }
+
+    /// Consecutive failed control-plane heartbeats, reported by the room runtime.
+    pub fn set_heartbeat_failures(&self, failures: u64) {
+        self.heartbeat_failures.store(failures, Ordering::Relaxed);
+    }
+
+    /// False once the tick loop is running but hasn't completed a tick for
+    /// `TICK_STALL_THRESHOLD_MS`. An idle room with no loop is healthy. Reads only atomics, so
+    /// it still answers while a stuck tick holds the state lock.
+    pub fn is_tick_loop_healthy(&self) -> bool {
+        self.is_tick_loop_healthy_at(RoomState::now_millis())
+    }
+
+    fn is_tick_loop_healthy_at(&self, now_ms: i64) -> bool {
+        if !self.running.load(Ordering::SeqCst) {
+            return true;
         }
+        now_ms - self.last_tick_at_ms.load(Ordering::Relaxed) <= TICK_STALL_THRESHOLD_MS
     }
 
     fn ensure_loop(self: &Arc<Self>) {
@@ -510,25 +1236,132 @@ impl Room {
         {
             return;
         }
+        self.last_tick_at_ms
+            .store(RoomState::now_millis(), Ordering::Relaxed);
 
         let room = Arc::clone(self);
         tokio::spawn(async move {
             let mut interval = tokio::time::interval(std::time::Duration::from_millis(TICK_MS));
             interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            let mut empty_since: Option<Instant> = None;
             loop {
                 interval.tick().await;
                 let mut state = room.state.lock().await;
                 if state.sessions.is_empty() {
-                    room.running.store(false, Ordering::SeqCst);
-                    break;
+                    let linger_ms = state.idle_linger_ms.max(0) as u128;
+                    let empty_since = *empty_since.get_or_insert_with(Instant::now);
+                    // `running` is cleared under the state lock, so a join either lands before
+                    // this check or sees the flag down and starts a fresh loop.
+                    if empty_since.elapsed().as_millis() >= linger_ms {
+                        room.running.store(false, Ordering::SeqCst);
+                        break;
+                    }
+                    drop(state);
+                    // Lingering is not a stall.
+                    room.last_tick_at_ms
+                        .store(RoomState::now_millis(), Ordering::Relaxed);
+                    tokio::time::sleep(IDLE_LINGER_POLL).await;
+                    continue;
                 }
+                empty_since = None;
+                let started = Instant::now();
                 state.tick();
+                let elapsed = started.elapsed();
+                state.record_tick_duration(elapsed);
+                room.tick_durations.record(elapsed);
+                room.last_tick_at_ms
+                    .store(RoomState::now_millis(), Ordering::Relaxed);
             }
         });
     }
+
+    /// Runs exactly one simulation tick in place of the loop, so tests can step a room.
+    #[cfg(test)]
+    async fn tick_once(&self) {
+        self.state.lock().await.tick();
+    }
 }
 
 impl RoomState {
+    #[cfg(test)]
+    fn new_with_rng_seed(room_id: String, seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            ..Self::new(room_id)
+        }
+    }
+
+    fn new(room_id: String) -> Self {
+        Self {
+            room_id,
+            sessions: HashMap::new(),
+            players: HashMap::new(),
+            pellets: Vec::new(),
+            next_pellet_id: 0,
+            next_state_seq: 1,
+            last_state_frame: None,
+            next_player_net_id: 1,
+            next_evasive_spawn_at: HashMap::new(),
+            pending_pellet_consumes: Vec::new(),
+            environment: Environment::generate(),
+            environment_seed: 0,
+            environment_config: EnvironmentConfig::default(),
+            over_budget_inputs: 0,
+            pellet_resyncs: 0,
+            bot_config: BotConfig::default(),
+            bots_enabled: true,
+            pellet_config: PelletConfig::default(),
+            pellet_palette: PelletPalette::default(),
+            growth_config: GrowthConfig::default(),
+            disconnect_config: DisconnectConfig::default(),
+            oxygen_config: OxygenConfig::default(),
+            movement_config: MovementConfig::default(),
+            day_night: None,
+            day_phase: 0.0,
+            day_phase_q: None,
+            recording: RecordingBuffer::new(REPLAY_DEFAULT_CAPACITY),
+            scratch: TickScratch::default(),
+            tick_overruns: 0,
+            overrun_streak: 0,
+            overrun_run_ticks: 0,
+            healthy_tick_streak: 0,
+            pellet_load_factor: 1.0,
+            respawn_length_retention: 0.0,
+            oxygen_grace_ms: LAKE_OXYGEN_GRACE_MS,
+            turn_rate_per_sec: TURN_RATE_PER_SEC,
+            spawn_safety_floor: SPAWN_SAFETY_FLOOR,
+            spawn_terrain_protection: true,
+            collision_leniency: COLLISION_LENIENCY,
+            kill_bonus: 0,
+            score_receipt_secret: None,
+            self_collision: SelfCollisionConfig::default(),
+            input_config: InputConfig::default(),
+            view_config: ViewConfig::default(),
+            chat_limiter: RateLimiter::new(
+                CHAT_RATE_LIMIT_PER_WINDOW,
+                Duration::from_millis(CHAT_RATE_WINDOW_MS as u64),
+            ),
+            input_warn_limiter: RateLimiter::new(
+                1,
+                Duration::from_millis(INPUT_RATE_LIMIT_WARN_INTERVAL_MS),
+            ),
+            boost_drain_shedding: false,
+            idle_linger_ms: DEFAULT_IDLE_LINGER_MS,
+            afk_autopilot_ms: None,
+            respawn_cooldown_ms: RESPAWN_COOLDOWN_MS,
+            max_detailed_remote_players: DEFAULT_MAX_DETAILED_REMOTE_PLAYERS,
+            keyframe_interval: DEFAULT_KEYFRAME_INTERVAL,
+            pellet_visibility: PelletVisibility::default(),
+            draining: false,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    fn regenerate_environment(&mut self) {
+        self.environment =
+            Environment::generate_with_seed(self.environment_seed, &self.environment_config);
+    }
+
     fn now_millis() -> i64 {
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -536,14 +1369,71 @@ impl RoomState {
         now.as_millis() as i64
     }
 
+    fn negotiate_session_version(
+        &mut self,
+        session_id: &str,
+        client_version: u8,
+    ) -> Result<u8, protocol::ServerError> {
+        let version = protocol::negotiate_version(client_version)?;
+        if let Some(session) = self.sessions.get_mut(session_id) {
+            session.protocol_version = Some(version);
+        }
+        Ok(version)
+    }
+
+    fn reject_join_without_net_id(&self, session_id: &str) -> bool {
+        tracing::warn!(
+            room_id = self.room_id,
+            "room_join_rejected_net_ids_exhausted"
+        );
+        self.send_session_error(session_id, protocol::ServerError::RoomFull);
+        false
+    }
+
+    /// Queues a `TYPE_ERROR` frame ahead of the socket closing so the client can tell why.
+    fn send_session_error(&self, session_id: &str, error: protocol::ServerError) {
+        if let Some(session) = self.sessions.get(session_id) {
+            let _ = session.outbound_hi.try_send(protocol::encode_error(error));
+        }
+    }
+
+    fn send_migrate(&self, room_id: &str, room_token: &str) -> usize {
+        let payload = protocol::encode_migrate(room_id, room_token);
+        self.sessions
+            .values()
+            .filter(|session| session.outbound_hi.try_send(payload.clone()).is_ok())
+            .count()
+    }
+
+    fn broadcast_announcement(&mut self, text: &str) -> usize {
+        let payload = protocol::encode_announce(text);
+        let mut stale = Vec::new();
+        for (session_id, session) in &self.sessions {
+            if session.outbound_hi.try_send(payload.clone()).is_err() {
+                stale.push(session_id.clone());
+            }
+        }
+        let delivered = self.sessions.len() - stale.len();
+        for session_id in stale {
+            self.disconnect_session(&session_id);
+        }
+        delivered
+    }
+
     fn disconnect_session(&mut self, session_id: &str) {
         let Some(entry) = self.sessions.remove(session_id) else {
             return;
         };
+        self.chat_limiter.remove(session_id);
+        self.input_warn_limiter.remove(session_id);
         if let Some(player_id) = entry.player_id {
             if let Some(player) = self.players.get_mut(&player_id) {
                 player.connected = false;
                 player.last_seen = Self::now_millis();
+                if self.disconnect_config.freeze_on_disconnect {
+                    player.boost = false;
+                    player.is_boosting = false;
+                }
             }
         }
         if self.human_count() == 0 {
@@ -561,7 +1451,7 @@ impl RoomState {
         max_human_players: Option<usize>,
     ) -> bool {
         let raw_name = name.unwrap_or_else(|| "Player".to_string());
-        let sanitized_name = sanitize_player_name(&raw_name, "Player");
+        let sanitized_name = name_policy().apply(&raw_name, "Player");
 
         let existing_player_id = player_id.map(|id| id.to_string());
         let is_existing_human = existing_player_id
@@ -569,6 +1459,11 @@ impl RoomState {
             .and_then(|id| self.players.get(id))
             .map(|player| !player.is_bot)
             .unwrap_or(false);
+        if self.draining && !is_existing_human {
+            tracing::info!("room_join_rejected_draining");
+            self.send_session_error(session_id, protocol::ServerError::Draining);
+            return false;
+        }
         if let Some(max_players) = max_human_players {
             if self.human_count() >= max_players && !is_existing_human {
                 tracing::warn!(
@@ -576,56 +1471,56 @@ impl RoomState {
                     current_humans = self.human_count(),
                     "room_join_rejected_capacity_reached"
                 );
+                self.send_session_error(session_id, protocol::ServerError::RoomFull);
                 return false;
             }
         }
 
-        let player_id = if let Some(id) = player_id {
-            let id_string = id.to_string();
+        let starting_length = self.growth_config.starting_length;
+        let oxygen_max = self.oxygen_config.max;
+        let resumed_player_id = self.find_player_for_reconnect(player_id);
+        let resumed = resumed_player_id.is_some();
+        let player_id = if let Some(id_string) = resumed_player_id {
             if let Some(player) = self.players.get_mut(&id_string) {
                 player.name = sanitized_name.clone();
                 player.connected = true;
                 player.last_seen = Self::now_millis();
-                if defer_spawn && !player.is_bot {
-                    Self::prepare_player_for_manual_spawn(player);
-                }
-                id_string
-            } else {
-                let mut new_player = self.create_player(id, sanitized_name.clone(), false);
+                player.last_active_at = player.last_seen;
                 if defer_spawn {
-                    Self::prepare_player_for_manual_spawn(&mut new_player);
+                    Self::prepare_player_for_manual_spawn(player, starting_length, oxygen_max);
                 }
-                self.players.insert(id_string.clone(), new_player);
-                id_string
             }
+            id_string
         } else {
-            let id = Uuid::new_v4();
+            // A supplied id that isn't free (it belongs to a bot) gets a fresh one instead.
+            let id = player_id
+                .filter(|id| !self.players.contains_key(&id.to_string()))
+                .unwrap_or_else(Uuid::new_v4);
             let id_string = id.to_string();
-            let mut new_player = self.create_player(id, sanitized_name.clone(), false);
+            let Some(mut new_player) = self.create_player(id, sanitized_name.clone(), false) else {
+                return self.reject_join_without_net_id(session_id);
+            };
             if defer_spawn {
-                Self::prepare_player_for_manual_spawn(&mut new_player);
+                Self::prepare_player_for_manual_spawn(&mut new_player, starting_length, oxygen_max);
             }
             self.players.insert(id_string.clone(), new_player);
             id_string
         };
 
         if let Some(pattern) = skin {
-            let clamped_len = pattern.len().min(8);
-            let stored = if clamped_len > 0 {
-                Some(
-                    pattern
-                        .into_iter()
-                        .take(clamped_len)
-                        .collect::<Vec<[u8; 3]>>(),
-                )
-            } else {
-                None
-            };
+            // Decoders already reject skins over `MAX_SKIN_COLORS`; the bound here only guards
+            // direct callers. An empty skin clears any previous one.
+            let stored = (!pattern.is_empty()).then(|| {
+                pattern
+                    .into_iter()
+                    .take(protocol::MAX_SKIN_COLORS)
+                    .collect::<Vec<[u8; 3]>>()
+            });
             if let Some(player) = self.players.get_mut(&player_id) {
-                player.skin = stored.clone();
-                if let Some(first) = stored.as_ref().and_then(|v| v.first()) {
-                    player.color = format!("#{:02x}{:02x}{:02x}", first[0], first[1], first[2]);
+                if let Some(first) = stored.as_ref().and_then(|colors| colors.first()) {
+                    player.color = protocol::skin_color_hex(*first);
                 }
+                player.skin = stored;
                 player.trail_color_cycle_cursor = 0;
                 player.next_boost_trail_pellet_at_ms = 0;
             }
@@ -643,28 +1538,56 @@ impl RoomState {
             None
         };
         if let Some(outbound_hi) = outbound_hi {
-            let payload = self.build_init_payload_for_session(session_id, &player_id);
+            let payload = self.build_init_payload_for_session(session_id, &player_id, resumed);
             if outbound_hi.try_send(payload).is_err() {
                 self.disconnect_session(session_id);
                 return false;
             }
+            if self.day_night.is_some() {
+                let phase_q = Self::quantize_day_phase(self.day_phase);
+                if outbound_hi
+                    .try_send(self.build_world_payload(phase_q))
+                    .is_err()
+                {
+                    self.disconnect_session(session_id);
+                    return false;
+                }
+            }
         }
         self.maybe_send_pellet_reset_for_session(session_id);
         self.broadcast_player_meta(&[player_id]);
         true
     }
 
-    fn prepare_player_for_manual_spawn(player: &mut Player) {
+    /// The human player a join with `player_id` resumes, keeping its net id and snake. Bots and
+    /// unknown ids never match, so those joins create a new player.
+    fn find_player_for_reconnect(&self, player_id: Option<Uuid>) -> Option<String> {
+        let id_string = player_id?.to_string();
+        self.players
+            .get(&id_string)
+            .filter(|player| !player.is_bot)
+            .map(|_| id_string)
+    }
+
+    fn prepare_player_for_manual_spawn(
+        player: &mut Player,
+        starting_length: usize,
+        oxygen_max: f64,
+    ) {
         player.boost = false;
         player.is_boosting = false;
-        player.oxygen = OXYGEN_MAX;
+        player.boost_engage_ticks = 0;
+        player.boost_cooldown_ticks = 0;
+        player.oxygen = oxygen_max;
         player.oxygen_damage_accumulator = 0.0;
+        player.submerged_secs = 0.0;
         player.score = 0;
         player.alive = false;
         player.respawn_at = None;
-        player.boost_floor_len = STARTING_LENGTH;
+        player.boost_floor_len = starting_length;
         player.trail_color_cycle_cursor = 0;
         player.next_boost_trail_pellet_at_ms = 0;
+        player.boost_shed_value = 0.0;
         player.snake.clear();
         player.pellet_growth_fraction = 0.0;
         player.tail_extension = 0.0;
@@ -672,25 +1595,186 @@ impl RoomState {
         player.digestions.clear();
     }
 
+    fn handle_resync(&mut self, session_id: &str) {
+        if let Some(session) = self.sessions.get_mut(session_id) {
+            session.force_next_keyframe = true;
+        }
+    }
+
+    /// Relays a chat line from a live player to every session whose view cone contains the
+    /// sender's head, the sender included. Lines over the per-session rate limit, empty after
+    /// sanitizing or rejected by the name policy are dropped. Returns how many sessions got it.
+    fn handle_chat(&mut self, session_id: &str, text: &str, now: i64) -> usize {
+        let Some(player_id) = self.session_player_id(session_id) else {
+            return 0;
+        };
+        let Some((net_id, head)) = self.players.get(&player_id).and_then(|player| {
+            let node = player.snake.first().filter(|_| player.alive)?;
+            Some((
+                player.net_id,
+                Point {
+                    x: node.x,
+                    y: node.y,
+                    z: node.z,
+                },
+            ))
+        }) else {
+            return 0;
+        };
+        if !self.chat_limiter.check(session_id, now) {
+            tracing::warn!(session_id, "session_chat_rate_limited");
+            return 0;
+        }
+
+        let Some(text) = sanitize_chat_text(text) else {
+            return 0;
+        };
+        if !name_policy().allows_text(&text) {
+            return 0;
+        }
+        let payload = Self::build_chat_payload(net_id, &text);
+        let mut delivered = 0;
+        for (recipient_id, session) in &self.sessions {
+            let in_view = self
+                .session_view_params(recipient_id)
+                .is_some_and(|(view_center, view_cos)| dot(view_center, head) >= view_cos);
+            // Chat is best-effort: a full queue skips the line rather than dropping the socket.
+            if in_view && session.outbound_hi.try_send(payload.clone()).is_ok() {
+                delivered += 1;
+            }
+        }
+        delivered
+    }
+
+    fn handle_hints(&mut self, session_id: &str, threat: bool) {
+        if let Some(session) = self.sessions.get_mut(session_id) {
+            session.threat_hints = threat;
+            session.threat_hint_active = false;
+        }
+    }
+
+    /// Echoes a latency probe straight back on the reliable queue, outside the tick loop so the
+    /// round trip doesn't include a wait for the next tick.
+    fn handle_ping(&self, session_id: &str, client_time: u32, now: i64) {
+        if let Some(session) = self.sessions.get(session_id) {
+            let _ = session
+                .outbound_hi
+                .try_send(protocol::encode_pong(client_time, now));
+        }
+    }
+
+    /// Sends each opted-in session the direction and angular distance from its head to the
+    /// closest living other-snake node within `THREAT_HINT_MAX_ANGLE`, or a clear frame once
+    /// the threat is gone. Sessions without hints enabled cost nothing.
+    fn send_threat_hints(&mut self, snapshots: &[PlayerCollisionSnapshot]) {
+        let session_ids: Vec<String> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| session.threat_hints)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for session_id in session_ids {
+            let threat = self.session_player_id(&session_id).and_then(|player_id| {
+                let player = self.players.get(&player_id).filter(|player| player.alive)?;
+                let node = player.snake.first()?;
+                let head = Point {
+                    x: node.x,
+                    y: node.y,
+                    z: node.z,
+                };
+                self.nearest_threat(&player_id, head, snapshots)
+            });
+            let Some(session) = self.sessions.get_mut(&session_id) else {
+                continue;
+            };
+            if threat.is_none() && !session.threat_hint_active {
+                continue;
+            }
+            let payload = Self::build_threat_hint_payload(threat);
+            // Hints are advisory: a stalled client just misses this tick's hint.
+            if session.outbound_hi.try_send(payload).is_ok() {
+                session.threat_hint_active = threat.is_some();
+            }
+        }
+    }
+
+    /// Tangent direction at `head` toward the nearest living other-snake node within
+    /// `THREAT_HINT_MAX_ANGLE`, and the angle to that node.
+    fn nearest_threat(
+        &self,
+        player_id: &str,
+        head: Point,
+        snapshots: &[PlayerCollisionSnapshot],
+    ) -> Option<(Point, f64)> {
+        let min_cos = THREAT_HINT_MAX_ANGLE.cos();
+        let mut nearest: Option<(Point, f64)> = None;
+        for snapshot in snapshots {
+            if snapshot.id == player_id
+                || !self
+                    .players
+                    .get(&snapshot.id)
+                    .is_some_and(|player| player.alive)
+            {
+                continue;
+            }
+            for node in snapshot.snake.iter().chain(snapshot.extended_tail.iter()) {
+                let cos = dot(head, *node);
+                if cos >= min_cos && nearest.is_none_or(|(_, best)| cos > best) {
+                    nearest = Some((*node, cos));
+                }
+            }
+        }
+        let (node, cos) = nearest?;
+        let tangent = Point {
+            x: node.x - head.x * cos,
+            y: node.y - head.y * cos,
+            z: node.z - head.z * cos,
+        };
+        let direction = if length(tangent) > 1e-9 {
+            normalize(tangent)
+        } else {
+            node
+        };
+        Some((direction, clamp(cos, -1.0, 1.0).acos()))
+    }
+
+    fn build_threat_hint_payload(threat: Option<(Point, f64)>) -> Vec<u8> {
+        let mut encoder = protocol::Encoder::with_capacity(12);
+        let Some((direction, angle)) = threat else {
+            encoder.write_header(protocol::TYPE_HINT, 0);
+            return encoder.into_vec();
+        };
+        encoder.write_header(protocol::TYPE_HINT, protocol::FLAG_HINT_THREAT);
+        let (ox, oy) = Self::encode_unit_vec_oct_i16(direction);
+        encoder.write_i16(ox);
+        encoder.write_i16(oy);
+        encoder.write_f32(angle as f32);
+        encoder.into_vec()
+    }
+
+    /// Respawns the session's dead player once its cooldown has passed. An early request is
+    /// answered with a `RespawnCooldown` error carrying the milliseconds left.
     fn handle_respawn(&mut self, session_id: &str) {
         let Some(player_id) = self.session_player_id(session_id) else {
             return;
         };
-        let should_respawn = match self.players.get(&player_id) {
-            Some(player) => {
-                if player.alive {
-                    false
-                } else if let Some(respawn_at) = player.respawn_at {
-                    Self::now_millis() >= respawn_at
-                } else {
-                    true
+        let Some(player) = self.players.get(&player_id) else {
+            return;
+        };
+        if player.alive {
+            return;
+        }
+        if let Some(respawn_at) = player.respawn_at {
+            let remaining_ms = respawn_at - Self::now_millis();
+            if remaining_ms > 0 {
+                if let Some(session) = self.sessions.get(session_id) {
+                    let payload = protocol::encode_respawn_cooldown(remaining_ms as u32);
+                    let _ = session.outbound_hi.try_send(payload);
                 }
+                return;
             }
-            None => false,
-        };
-        if should_respawn {
-            self.respawn_player(&player_id);
         }
+        self.respawn_player(&player_id);
     }
 
     fn handle_input(
@@ -707,12 +1791,17 @@ impl RoomState {
             return;
         };
 
-        if let Some(axis) = axis.and_then(parse_axis) {
-            player.target_axis = axis;
+        let axis = axis.and_then(parse_axis);
+        let now = Self::now_millis();
+        if is_active_input(Some(player.target_axis), player.boost, axis, boost) {
+            player.last_active_at = now;
+        }
+        if let Some(axis) = axis {
+            player.target_axis = self.input_config.steer(player.target_axis, axis);
         }
 
         player.boost = boost;
-        player.last_seen = Self::now_millis();
+        player.last_seen = now;
         if let Some(seq) = input_seq {
             if let Some(session) = self.sessions.get_mut(session_id) {
                 session.latest_applied_input_seq = seq;
@@ -741,9 +1830,8 @@ impl RoomState {
         self.maybe_send_pellet_reset_for_session(session_id);
     }
 
-    fn debug_kill(&mut self, target: DebugKillTarget) -> Option<String> {
-        let target_id = self
-            .players
+    fn debug_target_id(&self, target: DebugKillTarget) -> Option<String> {
+        self.players
             .iter()
             .filter(|(_, player)| player.alive)
             .filter(|(_, player)| match target {
@@ -752,9 +1840,52 @@ impl RoomState {
                 DebugKillTarget::Human => !player.is_bot && player.connected,
             })
             .map(|(id, _)| id.clone())
-            .next();
+            .next()
+    }
+
+    fn debug_set_length(&mut self, target: DebugKillTarget, length: usize) -> Option<String> {
+        let id = self.debug_target_id(target)?;
+        let starting_length = self.growth_config.starting_length;
+        let min_survival_length = self.growth_config.min_survival_length;
+        let player = self.players.get_mut(&id)?;
+        let head = player.snake.first()?;
+        let head = Point {
+            x: head.x,
+            y: head.y,
+            z: head.z,
+        };
+        let length = length.clamp(min_survival_length, DEBUG_MAX_SNAKE_LENGTH);
+        Self::rebuild_debug_snake(player, head, length);
+        player.score = length as i64;
+        player.boost_floor_len = length.max(starting_length);
+        Some(id)
+    }
+
+    fn debug_teleport(&mut self, target: DebugKillTarget, head: Point) -> Option<String> {
+        if length(head) <= 1e-9 || !(head.x.is_finite() && head.y.is_finite() && head.z.is_finite())
+        {
+            return None;
+        }
+        let id = self.debug_target_id(target)?;
+        let min_survival_length = self.growth_config.min_survival_length;
+        let player = self.players.get_mut(&id)?;
+        let snake_len = player.snake.len().max(min_survival_length);
+        Self::rebuild_debug_snake(player, head, snake_len);
+        Some(id)
+    }
+
+    fn rebuild_debug_snake(player: &mut Player, head: Point, length: usize) {
+        let (snake, axis) = create_snake_with_head(player.axis, head, length);
+        player.snake = snake;
+        player.axis = axis;
+        player.target_axis = axis;
+        player.tail_extension = 0.0;
+        player.pellet_growth_fraction = 0.0;
+        player.digestions.clear();
+    }
 
-        if let Some(id) = target_id {
+    fn debug_kill(&mut self, target: DebugKillTarget) -> Option<String> {
+        if let Some(id) = self.debug_target_id(target) {
             self.handle_death(&id);
             return Some(id);
         }
@@ -813,7 +1944,14 @@ impl RoomState {
                 .ok()
                 .and_then(|value| value.trim().parse::<usize>().ok())
         });
-        override_count.unwrap_or(BOT_COUNT)
+        let bot_config = match override_count {
+            Some(max_bots) => BotConfig {
+                max_bots: *max_bots,
+                ..self.bot_config
+            },
+            None => self.bot_config,
+        };
+        bot_config.desired_bots(self.human_count())
     }
 
     fn oxygen_disabled() -> bool {
@@ -827,6 +1965,37 @@ impl RoomState {
         })
     }
 
+    fn tick_profiling_enabled() -> bool {
+        static TICK_PROFILING: OnceLock<bool> = OnceLock::new();
+        *TICK_PROFILING.get_or_init(|| {
+            std::env::var(TICK_PROFILING_ENV_KEY)
+                .ok()
+                .map(|value| value.trim().to_ascii_lowercase())
+                .map(|value| value == "1" || value == "true" || value == "yes" || value == "on")
+                .unwrap_or(false)
+        })
+    }
+
+    /// Runs one tick phase inside a debug span that records its duration when
+    /// `SNAKE_TICK_PROFILING` is on; otherwise calls `phase` directly.
+    fn profile_tick_phase<T>(name: &'static str, phase: impl FnOnce() -> T) -> T {
+        if !Self::tick_profiling_enabled() {
+            return phase();
+        }
+        let span = tracing::debug_span!(
+            "tick_phase",
+            phase = name,
+            elapsed_us = tracing::field::Empty
+        );
+        let _entered = span.enter();
+        let started = Instant::now();
+        let result = phase();
+        let elapsed_us = started.elapsed().as_micros() as u64;
+        span.record("elapsed_us", elapsed_us);
+        tracing::debug!(phase = name, elapsed_us, "tick_phase_timing");
+        result
+    }
+
     fn rock_pellet_frequency_multiplier() -> f64 {
         static ROCK_PELLET_FREQ_MULT: OnceLock<f64> = OnceLock::new();
         *ROCK_PELLET_FREQ_MULT.get_or_init(|| {
@@ -880,8 +2049,30 @@ impl RoomState {
             + 1
     }
 
+    fn trim_bots(&mut self, excess: usize) {
+        let mut bots: Vec<(usize, String)> = self
+            .players
+            .values()
+            .filter(|player| player.is_bot)
+            .map(|player| {
+                let index = player
+                    .name
+                    .strip_prefix("Bot-")
+                    .and_then(|suffix| suffix.parse::<usize>().ok())
+                    .unwrap_or(0);
+                (index, player.id.clone())
+            })
+            .collect();
+        // Shed the most recently added bots first so long-lived bots keep their length.
+        bots.sort_by_key(|(index, _)| std::cmp::Reverse(*index));
+        for (_, id) in bots.into_iter().take(excess) {
+            self.players.remove(&id);
+        }
+        self.prune_evasive_spawn_timers();
+    }
+
     fn ensure_bots(&mut self) {
-        if self.human_count() == 0 {
+        if !self.bots_enabled || self.human_count() == 0 {
             self.remove_bots();
             return;
         }
@@ -893,7 +2084,11 @@ impl RoomState {
         }
 
         let mut current = self.bot_count();
-        if current >= desired_bot_count {
+        if current > desired_bot_count {
+            self.trim_bots(current - desired_bot_count);
+            return;
+        }
+        if current == desired_bot_count {
             return;
         }
 
@@ -903,7 +2098,10 @@ impl RoomState {
             let id = Uuid::new_v4();
             let id_string = id.to_string();
             let name = format!("Bot-{}", index);
-            let bot = self.create_player(id, name, true);
+            let Some(mut bot) = self.create_player(id, name, true) else {
+                break;
+            };
+            bot.bot_skill = Some(Self::bot_skill_for_index(index));
             self.players.insert(id_string.clone(), bot);
             new_bot_ids.push(id_string);
             current += 1;
@@ -914,16 +2112,110 @@ impl RoomState {
         }
     }
 
+    fn bot_skill_for_index(index: usize) -> BotSkill {
+        match index % 3 {
+            0 => BotSkill::Hard,
+            1 => BotSkill::Medium,
+            _ => BotSkill::Easy,
+        }
+    }
+
+    fn bot_forward(axis: Point, head: Point) -> Point {
+        let forward = cross(axis, head);
+        if length(forward) < 1e-6 {
+            return Point {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            };
+        }
+        normalize(forward)
+    }
+
+    fn steer_axis_toward(head: Point, target: Point) -> Option<Point> {
+        let axis_raw = cross(head, target);
+        if length(axis_raw) < 1e-6 {
+            None
+        } else {
+            Some(normalize(axis_raw))
+        }
+    }
+
+    /// Flags connected humans idle for at least `afk_autopilot_ms` for bot steering and
+    /// unflags them once a changed input refreshes `last_active_at`.
+    fn update_afk_autopilot(&mut self, now: i64) {
+        let afk_ms = self.afk_autopilot_ms;
+        for player in self.players.values_mut() {
+            if player.is_bot {
+                continue;
+            }
+            let idle =
+                afk_ms.is_some_and(|ms| player.connected && now - player.last_active_at >= ms);
+            if idle != player.afk_autopilot {
+                tracing::debug!(player_id = player.id, idle, "player_afk_autopilot");
+                player.afk_autopilot = idle;
+            }
+        }
+    }
+
+    fn is_bot_steered(&self, player: &Player) -> bool {
+        (self.bots_enabled && player.is_bot) || player.afk_autopilot
+    }
+
     fn update_bots(&mut self) {
-        if self.bot_count() == 0 {
+        if !self
+            .players
+            .values()
+            .any(|player| self.is_bot_steered(player))
+        {
             return;
         }
-        let pellets: Vec<Point> = self.pellets.iter().map(|pellet| pellet.normal).collect();
+        let pellets: Vec<(Point, bool)> = self
+            .pellets
+            .iter()
+            .map(|pellet| {
+                let evasive = matches!(pellet.state, PelletState::Evasive { .. });
+                (pellet.normal, evasive)
+            })
+            .collect();
+        let growth = self.growth_config;
+        let obstacles: Vec<BotObstacle> = self
+            .players
+            .values()
+            .filter(|player| player.alive)
+            .filter_map(|player| {
+                let head = player.snake.first()?;
+                let head = Point {
+                    x: head.x,
+                    y: head.y,
+                    z: head.z,
+                };
+                Some(BotObstacle {
+                    id: player.id.clone(),
+                    is_bot: player.is_bot,
+                    head,
+                    forward: Self::bot_forward(player.axis, head),
+                    nodes: player
+                        .snake
+                        .iter()
+                        .map(|node| Point {
+                            x: node.x,
+                            y: node.y,
+                            z: node.z,
+                        })
+                        .collect(),
+                    body_angular_radius: Self::snake_body_angular_radius_for_len(
+                        &growth,
+                        player.snake.len(),
+                    ),
+                })
+            })
+            .collect();
         let bot_ids: Vec<String> = self
             .players
             .iter()
             .filter_map(|(id, player)| {
-                if player.is_bot {
+                if self.is_bot_steered(player) {
                     Some(id.clone())
                 } else {
                     None
@@ -931,51 +2223,196 @@ impl RoomState {
             })
             .collect();
 
-        for bot_id in bot_ids {
-            let Some(player) = self.players.get_mut(&bot_id) else {
+        let mut rng = self.fork_rng();
+        for bot_id in bot_ids {
+            let Some(player) = self.players.get_mut(&bot_id) else {
+                continue;
+            };
+            if !player.alive {
+                continue;
+            }
+
+            let Some(steering) =
+                Self::plan_bot_steering(player, &growth, &pellets, &obstacles, &mut rng)
+            else {
+                continue;
+            };
+            player.target_axis = steering.axis;
+            player.boost =
+                steering.boost && Self::can_player_boost(player, growth.min_survival_length);
+        }
+    }
+
+    fn plan_bot_steering(
+        player: &Player,
+        growth: &GrowthConfig,
+        pellets: &[(Point, bool)],
+        obstacles: &[BotObstacle],
+        rng: &mut impl Rng,
+    ) -> Option<BotSteering> {
+        let head = player.snake.first()?;
+        let head = Point {
+            x: head.x,
+            y: head.y,
+            z: head.z,
+        };
+        let forward = Self::bot_forward(player.axis, head);
+        let snake_len = player.snake.len();
+        let skill = player.bot_skill.unwrap_or(BotSkill::Medium);
+
+        if skill == BotSkill::Hard {
+            let contact_radius = Self::snake_contact_angular_radius_for_len(growth, snake_len);
+            if let Some(to_threat) =
+                Self::find_bot_threat(&player.id, head, forward, contact_radius, obstacles)
+            {
+                // Turn perpendicular to the current heading, away from the side the threat is on.
+                let side = normalize(cross(head, forward));
+                let escape = if dot(to_threat, side) > 0.0 {
+                    Point {
+                        x: -side.x,
+                        y: -side.y,
+                        z: -side.z,
+                    }
+                } else {
+                    side
+                };
+                if let Some(axis) = Self::steer_axis_toward(head, escape) {
+                    return Some(BotSteering { axis, boost: false });
+                }
+            }
+
+            if let Some(target) = Self::find_bot_cutoff_target(head, snake_len, obstacles) {
+                if let Some(axis) = Self::steer_axis_toward(head, target) {
+                    return Some(BotSteering { axis, boost: true });
+                }
+            }
+
+            let nearest_evasive = Self::nearest_bot_pellet(head, pellets, true)
+                .filter(|(_, dist)| *dist <= BOT_HARD_EVASIVE_CHASE_DISTANCE);
+            if let Some((target, dist)) = nearest_evasive {
+                if let Some(axis) = Self::steer_axis_toward(head, target) {
+                    return Some(BotSteering {
+                        axis,
+                        boost: dist > BOT_BOOST_DISTANCE * 0.5,
+                    });
+                }
+            }
+        }
+
+        let Some((target, dist)) = Self::nearest_bot_pellet(head, pellets, false) else {
+            return Some(BotSteering {
+                axis: random_axis(rng),
+                boost: false,
+            });
+        };
+        let mut axis = Self::steer_axis_toward(head, target).unwrap_or_else(|| random_axis(rng));
+        if skill == BotSkill::Easy {
+            let wander = rng.gen_range(-BOT_EASY_WANDER_ANGLE..=BOT_EASY_WANDER_ANGLE);
+            rotate_around_axis(&mut axis, normalize(head), wander);
+            return Some(BotSteering { axis, boost: false });
+        }
+        Some(BotSteering {
+            axis,
+            boost: dist > BOT_BOOST_DISTANCE,
+        })
+    }
+
+    fn nearest_bot_pellet(
+        head: Point,
+        pellets: &[(Point, bool)],
+        evasive_only: bool,
+    ) -> Option<(Point, f64)> {
+        let mut nearest: Option<(Point, f64)> = None;
+        for (pellet, evasive) in pellets {
+            if evasive_only && !evasive {
                 continue;
+            }
+            let delta = Point {
+                x: pellet.x - head.x,
+                y: pellet.y - head.y,
+                z: pellet.z - head.z,
             };
-            if !player.alive {
-                continue;
+            let dist = length(delta);
+            match nearest {
+                Some((_, best)) if dist >= best => {}
+                _ => nearest = Some((*pellet, dist)),
             }
+        }
+        nearest
+    }
 
-            let Some(head) = player.snake.first() else {
+    /// Returns the offset from `head` to the closest body node ahead of the bot that is within
+    /// its collision threshold plus a dodge margin.
+    fn find_bot_threat(
+        bot_id: &str,
+        head: Point,
+        forward: Point,
+        contact_radius: f64,
+        obstacles: &[BotObstacle],
+    ) -> Option<Point> {
+        let mut closest: Option<(Point, f64)> = None;
+        for obstacle in obstacles {
+            if obstacle.id == bot_id {
                 continue;
-            };
-            let head_point = Point {
-                x: head.x,
-                y: head.y,
-                z: head.z,
-            };
-
-            let mut nearest: Option<(Point, f64)> = None;
-            for pellet in &pellets {
-                let delta = Point {
-                    x: pellet.x - head_point.x,
-                    y: pellet.y - head_point.y,
-                    z: pellet.z - head_point.z,
+            }
+            let threshold =
+                collision_distance_for_angular_radii(contact_radius, obstacle.body_angular_radius)
+                    + BOT_HARD_AVOID_MARGIN;
+            for node in &obstacle.nodes {
+                let to_node = Point {
+                    x: node.x - head.x,
+                    y: node.y - head.y,
+                    z: node.z - head.z,
                 };
-                let dist = length(delta);
-                match nearest {
+                if dot(to_node, forward) <= 0.0 {
+                    continue;
+                }
+                let dist = length(to_node);
+                if dist >= threshold {
+                    continue;
+                }
+                match closest {
                     Some((_, best)) if dist >= best => {}
-                    _ => nearest = Some((*pellet, dist)),
+                    _ => closest = Some((to_node, dist)),
                 }
             }
+        }
+        closest.map(|(to_node, _)| to_node)
+    }
 
-            if let Some((target_pellet, dist)) = nearest {
-                let axis_raw = cross(head_point, target_pellet);
-                let axis = if length(axis_raw) < 1e-6 {
-                    random_axis()
-                } else {
-                    normalize(axis_raw)
-                };
-                player.target_axis = axis;
-                player.boost = dist > BOT_BOOST_DISTANCE && Self::can_player_boost(player);
-            } else {
-                player.target_axis = random_axis();
-                player.boost = false;
+    /// Picks a point just ahead of the nearest shorter human so a hard bot can cut across it.
+    fn find_bot_cutoff_target(
+        head: Point,
+        snake_len: usize,
+        obstacles: &[BotObstacle],
+    ) -> Option<Point> {
+        let mut best: Option<(Point, f64)> = None;
+        for obstacle in obstacles {
+            if obstacle.is_bot || obstacle.nodes.len() > snake_len {
+                continue;
+            }
+            let delta = Point {
+                x: obstacle.head.x - head.x,
+                y: obstacle.head.y - head.y,
+                z: obstacle.head.z - head.z,
+            };
+            let dist = length(delta);
+            if dist > BOT_HARD_CUTOFF_DISTANCE {
+                continue;
+            }
+            match best {
+                Some((_, best_dist)) if dist >= best_dist => {}
+                _ => {
+                    let lead = normalize(Point {
+                        x: obstacle.head.x + obstacle.forward.x * BOT_HARD_CUTOFF_LEAD,
+                        y: obstacle.head.y + obstacle.forward.y * BOT_HARD_CUTOFF_LEAD,
+                        z: obstacle.head.z + obstacle.forward.z * BOT_HARD_CUTOFF_LEAD,
+                    });
+                    best = Some((lead, dist));
+                }
             }
         }
+        best.map(|(target, _)| target)
     }
 
     fn auto_respawn_players(&mut self, now: i64) {
@@ -1001,31 +2438,65 @@ impl RoomState {
         }
     }
 
-    fn allocate_player_net_id(&mut self) -> u16 {
-        let mut candidate = self.next_player_net_id.max(1);
-        for _ in 0..=u16::MAX {
-            if candidate == 0 {
-                candidate = 1;
+    /// Net id `0` means "no player" on the wire, so it is never handed out. When every id is
+    /// taken, the longest-disconnected player is evicted early to reclaim its id; `None` only
+    /// if all ids belong to connected players.
+    fn allocate_player_net_id(&mut self) -> Option<u16> {
+        let used: HashSet<u16> = self.players.values().map(|player| player.net_id).collect();
+        if let Some(net_id) = Self::next_free_net_id(&used, self.next_player_net_id, u16::MAX) {
+            self.next_player_net_id = if net_id == u16::MAX { 1 } else { net_id + 1 };
+            return Some(net_id);
+        }
+
+        let (stale_id, net_id) = self
+            .players
+            .values()
+            .filter(|player| !player.connected)
+            .min_by_key(|player| player.last_seen)
+            .map(|player| (player.id.clone(), player.net_id))?;
+        tracing::warn!(
+            room_id = self.room_id,
+            net_id,
+            "room_net_id_reclaimed_early"
+        );
+        self.players.remove(&stale_id);
+        Some(net_id)
+    }
+
+    /// First id in `1..=max` not in `used`, scanning from `start` and wrapping around.
+    fn next_free_net_id(used: &HashSet<u16>, start: u16, max: u16) -> Option<u16> {
+        let start = start.clamp(1, max.max(1));
+        (start..=max)
+            .chain(1..start)
+            .find(|candidate| !used.contains(candidate))
+    }
+
+    /// The `COLOR_POOL` entry worn by the fewest players in the room, lowest index first, so
+    /// colors stay distinct until the pool runs out and then repeat in pool order. Skinned
+    /// players wear their skin's color and don't count.
+    fn next_player_color(&self) -> &'static str {
+        let mut usage = [0usize; COLOR_POOL.len()];
+        for player in self.players.values() {
+            if player.skin.is_some() {
+                continue;
             }
-            if !self
-                .players
-                .values()
-                .any(|player| player.net_id == candidate)
-            {
-                self.next_player_net_id = candidate.wrapping_add(1);
-                if self.next_player_net_id == 0 {
-                    self.next_player_net_id = 1;
-                }
-                return candidate;
+            if let Some(index) = COLOR_POOL.iter().position(|color| *color == player.color) {
+                usage[index] += 1;
             }
-            candidate = candidate.wrapping_add(1);
         }
-        0
+        let index = (0..COLOR_POOL.len())
+            .min_by_key(|index| usage[*index])
+            .unwrap_or(0);
+        COLOR_POOL[index]
     }
 
-    fn create_player(&mut self, id: Uuid, name: String, is_bot: bool) -> Player {
-        let base_axis = random_axis();
-        let spawned = self.spawn_snake(base_axis, None);
+    fn create_player(&mut self, id: Uuid, name: String, is_bot: bool) -> Option<Player> {
+        let net_id = self.allocate_player_net_id()?;
+        let mut rng = self.fork_rng();
+        let base_axis = random_axis(&mut rng);
+        let starting_length = self.growth_config.starting_length;
+        let avoid_terrain = self.spawn_terrain_protection && !is_bot;
+        let spawned = self.spawn_snake(base_axis, None, starting_length, avoid_terrain, &mut rng);
         let (alive, axis, snake, respawn_at) = match spawned {
             Some(spawned) => (true, spawned.axis, spawned.snake, None),
             None => (
@@ -1037,51 +2508,66 @@ impl RoomState {
         };
 
         let id_string = id.to_string();
-        let net_id = self.allocate_player_net_id();
+        let now = Self::now_millis();
 
-        Player {
+        Some(Player {
             id: id_string,
             id_bytes: *id.as_bytes(),
             net_id,
             name,
-            color: COLOR_POOL[self.players.len() % COLOR_POOL.len()].to_string(),
+            color: self.next_player_color().to_string(),
             skin: None,
             is_bot,
+            bot_skill: None,
+            afk_autopilot: false,
             axis,
             target_axis: axis,
             boost: false,
             is_boosting: false,
-            oxygen: OXYGEN_MAX,
+            boost_engage_ticks: 0,
+            boost_cooldown_ticks: 0,
+            oxygen: self.oxygen_config.max,
             oxygen_damage_accumulator: 0.0,
+            submerged_secs: 0.0,
             score: snake.len() as i64,
+            session_best: snake.len() as i64,
             alive,
             connected: true,
-            last_seen: Self::now_millis(),
+            last_seen: now,
+            last_active_at: now,
             respawn_at,
-            boost_floor_len: snake.len().max(STARTING_LENGTH),
+            respawn_length: None,
+            boost_floor_len: snake.len().max(starting_length),
             trail_color_cycle_cursor: 0,
             next_boost_trail_pellet_at_ms: 0,
+            boost_shed_value: 0.0,
+            evasive_attempts: 0,
+            evasive_captures: 0,
             snake,
             pellet_growth_fraction: 0.0,
             tail_extension: 0.0,
             next_digestion_id: 0,
             digestions: Vec::new(),
-        }
+        })
     }
 
+    /// With `avoid_terrain`, placements with a node on invalid terrain are retried too, so
+    /// a planet with no dry land in the spawn cone spawns nothing.
     fn spawn_snake(
         &self,
         base_axis: Point,
         excluded_player_id: Option<&str>,
+        length: usize,
+        avoid_terrain: bool,
+        rng: &mut impl Rng,
     ) -> Option<SpawnedSnake> {
-        let mut rng = rand::thread_rng();
         for attempt in 0..MAX_SPAWN_ATTEMPTS {
             let axis_seed = if attempt == 0 {
                 base_axis
             } else {
-                random_axis()
+                random_axis(rng)
             };
-            let mut snake = create_snake(axis_seed);
+            let mut snake = create_snake_with_length(axis_seed, length);
             let theta = rng.gen::<f64>() * std::f64::consts::PI * 2.0;
             let phi = std::f64::consts::PI - rng.gen::<f64>() * SPAWN_CONE_ANGLE;
             let rotate_y_angle = std::f64::consts::PI - phi;
@@ -1092,7 +2578,11 @@ impl RoomState {
             rotate_z(&mut rotated_axis, theta);
             let axis = normalize(rotated_axis);
 
-            if !self.is_snake_too_close(&snake, excluded_player_id) {
+            if avoid_terrain && self.is_invalid_snake_spawn(&snake) {
+                continue;
+            }
+            let safety_scale = self.spawn_safety_scale(attempt);
+            if !self.is_snake_too_close(&snake, excluded_player_id, safety_scale) {
                 return Some(SpawnedSnake { snake, axis });
             }
         }
@@ -1100,7 +2590,39 @@ impl RoomState {
         None
     }
 
-    fn is_snake_too_close(&self, snake: &[SnakeNode], excluded_player_id: Option<&str>) -> bool {
+    /// Whether any node would start in a lake or inside a tree, cactus or mountain collider,
+    /// using the same colliders as pellet placement.
+    fn is_invalid_snake_spawn(&self, snake: &[SnakeNode]) -> bool {
+        snake.iter().any(|node| {
+            self.is_invalid_pellet_spawn(Point {
+                x: node.x,
+                y: node.y,
+                z: node.z,
+            })
+        })
+    }
+
+    /// Multiplier on the spawn head distance for a given attempt: strict for the first
+    /// `SPAWN_STRICT_ATTEMPTS`, then easing linearly to `spawn_safety_floor` on the last one.
+    fn spawn_safety_scale(&self, attempt: usize) -> f64 {
+        if attempt < SPAWN_STRICT_ATTEMPTS {
+            return 1.0;
+        }
+        let relaxed_attempts = MAX_SPAWN_ATTEMPTS
+            .saturating_sub(SPAWN_STRICT_ATTEMPTS)
+            .max(1);
+        let progress =
+            ((attempt - SPAWN_STRICT_ATTEMPTS + 1) as f64 / relaxed_attempts as f64).min(1.0);
+        1.0 + (self.spawn_safety_floor - 1.0) * progress
+    }
+
+    /// `safety_scale` shrinks only the head-to-head buffer; any body overlap still rejects.
+    fn is_snake_too_close(
+        &self,
+        snake: &[SnakeNode],
+        excluded_player_id: Option<&str>,
+        safety_scale: f64,
+    ) -> bool {
         if snake.is_empty() {
             return false;
         }
@@ -1121,12 +2643,13 @@ impl RoomState {
                 continue;
             };
             let other_body_angular_radius =
-                Self::snake_body_angular_radius_for_len(player.snake.len());
+                Self::snake_body_angular_radius_for_len(&self.growth_config, player.snake.len());
             let dynamic_min_distance = collision_distance_for_angular_radii(
                 candidate_body_angular_radius,
                 other_body_angular_radius,
             ) * 2.0;
-            let min_head_distance = dynamic_min_distance.max(SPAWN_PLAYER_MIN_DISTANCE);
+            let min_head_distance =
+                dynamic_min_distance.max(SPAWN_PLAYER_MIN_DISTANCE) * safety_scale;
             let distance = length(Point {
                 x: candidate_head.x - other_head.x,
                 y: candidate_head.y - other_head.y,
@@ -1151,7 +2674,7 @@ impl RoomState {
                 continue;
             }
             let other_body_angular_radius =
-                Self::snake_body_angular_radius_for_len(player.snake.len());
+                Self::snake_body_angular_radius_for_len(&self.growth_config, player.snake.len());
             for node in &player.snake {
                 let node_point = Point {
                     x: node.x,
@@ -1173,6 +2696,12 @@ impl RoomState {
         false
     }
 
+    /// Independent generator derived from the room's RNG, so a caller can draw from it while
+    /// also borrowing `self`. Deterministic whenever the room's RNG is.
+    fn fork_rng(&mut self) -> StdRng {
+        StdRng::seed_from_u64(self.rng.gen())
+    }
+
     fn next_small_pellet_id(&mut self) -> u32 {
         let id = self.next_pellet_id;
         self.next_pellet_id = self.next_pellet_id.wrapping_add(1);
@@ -1185,20 +2714,9 @@ impl RoomState {
         point_from_spherical(theta, phi)
     }
 
-    fn random_pellet_color_rgb(rng: &mut impl Rng) -> [u8; 3] {
-        let index = rng.gen_range(0..SMALL_PELLET_COLOR_PALETTE.len());
-        SMALL_PELLET_COLOR_PALETTE[index]
-    }
-
-    fn parse_hex_rgb(value: &str) -> Option<[u8; 3]> {
-        let hex = value.strip_prefix('#')?;
-        if hex.len() != 6 {
-            return None;
-        }
-        let r = u8::from_str_radix(&hex[0..2], 16).ok()?;
-        let g = u8::from_str_radix(&hex[2..4], 16).ok()?;
-        let b = u8::from_str_radix(&hex[4..6], 16).ok()?;
-        Some([r, g, b])
+    fn random_pellet_color_rgb(palette: &PelletPalette, rng: &mut impl Rng) -> [u8; 3] {
+        let colors = palette.colors();
+        colors[rng.gen_range(0..colors.len())]
     }
 
     fn player_skin_cycle_color(player: &mut Player) -> [u8; 3] {
@@ -1209,20 +2727,21 @@ impl RoomState {
                 return skin[index];
             }
         }
-        let fallback = Self::parse_hex_rgb(player.color.as_str()).unwrap_or([255, 255, 255]);
+        let fallback = config::parse_hex_rgb(player.color.as_str()).unwrap_or([255, 255, 255]);
         player.trail_color_cycle_cursor = player.trail_color_cycle_cursor.wrapping_add(1);
         fallback
     }
 
     fn make_small_pellet(&mut self, normal: Point, rng: &mut impl Rng) -> Pellet {
-        let size = rng.gen_range(SMALL_PELLET_SIZE_MIN..=SMALL_PELLET_SIZE_MAX);
+        let config = self.pellet_config;
+        let size = rng.gen_range(config.small_size_min..=config.small_size_max);
         Pellet {
             id: self.next_small_pellet_id(),
             normal,
-            color_rgb: Self::random_pellet_color_rgb(rng),
+            color_rgb: Self::random_pellet_color_rgb(&self.pellet_palette, rng),
             base_size: size,
             current_size: size,
-            growth_fraction: SMALL_PELLET_GROWTH_FRACTION,
+            growth_fraction: config.small_growth_fraction,
             expires_at_ms: None,
             state: PelletState::Idle,
         }
@@ -1404,6 +2923,29 @@ impl RoomState {
         None
     }
 
+    /// A valid surface point up to `max_angle` off `origin` toward a random direction, or
+    /// `None` when `origin` is off the surface or every attempt lands in a collider.
+    fn scatter_death_pellet_spawn(
+        &self,
+        origin: Point,
+        max_angle: f64,
+        rng: &mut impl Rng,
+    ) -> Option<Point> {
+        let len = length(origin);
+        if !len.is_finite() || !(0.85..=1.15).contains(&len) {
+            return None;
+        }
+        for _ in 0..PELLET_DEATH_LOCAL_RESPAWN_ATTEMPTS {
+            let target = Self::random_unit_point(rng);
+            let angle = rng.gen_range(0.0..=max_angle);
+            let candidate = rotate_toward(origin, target, angle);
+            if !self.is_invalid_pellet_spawn(candidate) {
+                return Some(candidate);
+            }
+        }
+        None
+    }
+
     fn spawn_small_pellet_with_rng(&mut self, rng: &mut impl Rng) -> Option<Pellet> {
         const SPAWN_ATTEMPTS: usize = 20;
         let rock_spawn_bias_probability = self.rock_pellet_spawn_bias_probability();
@@ -1508,7 +3050,7 @@ impl RoomState {
     }
 
     fn spawn_evasive_pellets(&mut self, now_ms: i64) {
-        let mut rng = rand::thread_rng();
+        let mut rng = self.fork_rng();
         let eligible_player_ids: Vec<String> = self
             .players
             .iter()
@@ -1545,15 +3087,16 @@ impl RoomState {
                 continue;
             };
 
-            let size = rng.gen_range(EVASIVE_PELLET_SIZE_MIN..=EVASIVE_PELLET_SIZE_MAX);
+            let config = self.pellet_config;
+            let size = rng.gen_range(config.evasive_size_min..=config.evasive_size_max);
             let pellet_id = self.next_small_pellet_id();
             self.pellets.push(Pellet {
                 id: pellet_id,
                 normal: spawn_point,
-                color_rgb: Self::random_pellet_color_rgb(&mut rng),
+                color_rgb: Self::random_pellet_color_rgb(&self.pellet_palette, &mut rng),
                 base_size: size,
                 current_size: size,
-                growth_fraction: BIG_PELLET_GROWTH_FRACTION,
+                growth_fraction: config.big_growth_fraction,
                 expires_at_ms: None,
                 state: PelletState::Evasive {
                     owner_player_id: owner_player_id.clone(),
@@ -1566,22 +3109,113 @@ impl RoomState {
             );
         }
 
-        if self.pellets.len() > MAX_PELLETS {
-            let excess = self.pellets.len() - MAX_PELLETS;
-            self.pellets.drain(0..excess);
+        self.trim_pellets_to_max();
+    }
+
+    /// Tracks tick overruns and sheds pellet work while the room keeps falling behind. Called from
+    /// the tick loop with the measured duration; tests feed synthetic durations.
+    fn record_tick_duration(&mut self, elapsed: Duration) {
+        if elapsed <= Duration::from_millis(TICK_MS) {
+            self.overrun_streak = 0;
+            if self.overrun_run_ticks > 0 {
+                tracing::info!(
+                    room_id = self.room_id,
+                    overrun_ticks = self.overrun_run_ticks,
+                    "room_tick_overrun_ended"
+                );
+                self.overrun_run_ticks = 0;
+            }
+            self.healthy_tick_streak = self.healthy_tick_streak.saturating_add(1);
+            if self.healthy_tick_streak >= TICK_RECOVERY_HEALTHY_TICKS
+                && self.pellet_load_factor < 1.0
+            {
+                self.healthy_tick_streak = 0;
+                self.pellet_load_factor =
+                    (self.pellet_load_factor + PELLET_LOAD_RECOVER_STEP).min(1.0);
+                tracing::info!(
+                    room_id = self.room_id,
+                    pellet_load_factor = self.pellet_load_factor,
+                    "room_pellet_load_recovered"
+                );
+            }
+            return;
+        }
+
+        self.tick_overruns = self.tick_overruns.saturating_add(1);
+        self.healthy_tick_streak = 0;
+        self.overrun_streak = self.overrun_streak.saturating_add(1);
+        // Only the start of a run is logged; `tick_overruns` counts every overrun tick.
+        if self.overrun_run_ticks == 0 {
+            tracing::warn!(
+                room_id = self.room_id,
+                elapsed_ms = elapsed.as_millis() as u64,
+                tick_ms = TICK_MS,
+                "room_tick_overrun_started"
+            );
+        }
+        self.overrun_run_ticks = self.overrun_run_ticks.saturating_add(1);
+        if self.overrun_streak >= TICK_OVERRUN_SHED_THRESHOLD {
+            self.overrun_streak = 0;
+            self.pellet_load_factor =
+                (self.pellet_load_factor * PELLET_LOAD_SHED_STEP).max(PELLET_LOAD_FACTOR_MIN);
+            tracing::warn!(
+                room_id = self.room_id,
+                pellet_load_factor = self.pellet_load_factor,
+                "room_pellet_load_shed"
+            );
         }
     }
 
-    fn ensure_pellets(&mut self) {
-        if self.pellets.len() > MAX_PELLETS {
-            let excess = self.pellets.len() - MAX_PELLETS;
+    /// Small pellet target, shrunk by the load factor while the room is shedding load.
+    fn pellet_target_count(&self) -> usize {
+        let scaled = self.pellet_config.base_count as f64 * self.pellet_load_factor;
+        (scaled.round() as usize).clamp(1, self.pellet_config.max_count)
+    }
+
+    /// Drops the oldest pellets beyond the room's `max_count`.
+    fn trim_pellets_to_max(&mut self) {
+        let max_count = self.pellet_config.max_count;
+        if self.pellets.len() > max_count {
+            let excess = self.pellets.len() - max_count;
             self.pellets.drain(0..excess);
         }
-        let target = BASE_PELLET_COUNT.min(MAX_PELLETS);
+    }
+
+    /// While shedding load, removes up to `PELLET_LOAD_TRIM_PER_TICK` ambient small pellets
+    /// beyond `target`, oldest first. Death and boost-trail pellets hold value players left
+    /// behind, so they are kept, as are pellets mid-intake or evading.
+    fn shed_surplus_pellets(&mut self, target: usize) {
+        if self.pellet_load_factor >= 1.0 {
+            return;
+        }
+        let mut budget = self
+            .pellets
+            .len()
+            .saturating_sub(target)
+            .min(PELLET_LOAD_TRIM_PER_TICK);
+        let small_growth_fraction = self.pellet_config.small_growth_fraction;
+        self.pellets.retain(|pellet| {
+            // Ambient pellets never expire and carry exactly the room's small growth fraction;
+            // boost-trail pellets expire and death pellets carry a share of a big pellet.
+            let ambient = matches!(pellet.state, PelletState::Idle)
+                && pellet.expires_at_ms.is_none()
+                && pellet.growth_fraction == small_growth_fraction;
+            if budget > 0 && ambient {
+                budget -= 1;
+                return false;
+            }
+            true
+        });
+    }
+
+    fn ensure_pellets(&mut self) {
+        self.trim_pellets_to_max();
+        let target = self.pellet_target_count();
         if self.pellets.len() >= target {
+            self.shed_surplus_pellets(target);
             return;
         }
-        let mut rng = rand::thread_rng();
+        let mut rng = self.fork_rng();
         let mut attempts = 0usize;
         let max_attempts = (target.saturating_sub(self.pellets.len()) * 24).max(64);
         while self.pellets.len() < target && attempts < max_attempts {
@@ -1592,7 +3226,22 @@ impl RoomState {
         }
     }
 
+    /// Body length plus growth still being digested. Digestion only moves growth into the
+    /// body, so within a tick this drops by exactly what boosting drained.
+    fn boost_sheddable_length(player: &Player) -> f64 {
+        let pending_growth: f64 = player
+            .digestions
+            .iter()
+            .map(|digestion| (digestion.growth_amount - digestion.applied_growth).max(0.0))
+            .sum();
+        player.snake.len() as f64 + player.tail_extension + pending_growth
+    }
+
     fn spawn_boost_trail_pellets(&mut self, now_ms: i64) {
+        if self.boost_drain_shedding {
+            self.spawn_boost_shed_pellets(now_ms);
+            return;
+        }
         if BOOST_TRAIL_PELLET_INTERVAL_MS <= 0 {
             return;
         }
@@ -1635,7 +3284,7 @@ impl RoomState {
             return;
         }
 
-        let mut rng = rand::thread_rng();
+        let mut rng = self.fork_rng();
         for (normal, color_rgb) in pending_spawns {
             let size = rng.gen_range(BOOST_TRAIL_PELLET_SIZE_MIN..=BOOST_TRAIL_PELLET_SIZE_MAX);
             let pellet_id = self.next_small_pellet_id();
@@ -1651,10 +3300,55 @@ impl RoomState {
             });
         }
 
-        if self.pellets.len() > MAX_PELLETS {
-            let excess = self.pellets.len() - MAX_PELLETS;
-            self.pellets.drain(0..excess);
+        self.trim_pellets_to_max();
+    }
+
+    /// Drops one trail pellet at the tail for every `BOOST_TRAIL_PELLET_GROWTH_FRACTION` of
+    /// drained length, so the trail is worth what the boost cost. Leftover drain carries over.
+    fn spawn_boost_shed_pellets(&mut self, now_ms: i64) {
+        let mut pending_spawns: Vec<(Point, [u8; 3])> = Vec::new();
+        for player in self.players.values_mut() {
+            if !player.alive {
+                continue;
+            }
+            let Some(tail_node) = player.snake.last() else {
+                continue;
+            };
+            let tail = Point {
+                x: tail_node.x,
+                y: tail_node.y,
+                z: tail_node.z,
+            };
+            while player.boost_shed_value >= BOOST_TRAIL_PELLET_GROWTH_FRACTION {
+                player.boost_shed_value -= BOOST_TRAIL_PELLET_GROWTH_FRACTION;
+                pending_spawns.push((tail, Self::player_skin_cycle_color(player)));
+            }
+        }
+
+        if pending_spawns.is_empty() {
+            return;
+        }
+
+        let mut rng = self.fork_rng();
+        for (tail, color_rgb) in pending_spawns {
+            let Some(normal) = self.pick_valid_death_pellet_spawn(tail, &mut rng) else {
+                continue;
+            };
+            let size = rng.gen_range(BOOST_TRAIL_PELLET_SIZE_MIN..=BOOST_TRAIL_PELLET_SIZE_MAX);
+            let pellet_id = self.next_small_pellet_id();
+            self.pellets.push(Pellet {
+                id: pellet_id,
+                normal,
+                color_rgb,
+                base_size: size,
+                current_size: size,
+                growth_fraction: BOOST_TRAIL_PELLET_GROWTH_FRACTION,
+                expires_at_ms: Some(now_ms + BOOST_TRAIL_PELLET_TTL_MS),
+                state: PelletState::Idle,
+            });
         }
+
+        self.trim_pellets_to_max();
     }
 
     fn build_head_attractors(&self) -> HashMap<String, HeadAttractor> {
@@ -1742,22 +3436,27 @@ impl RoomState {
                     head,
                     forward,
                     mouth,
+                    attract_radius: self.pellet_attract_radius_for_len(player.snake.len()),
                 },
             );
         }
         attractors
     }
 
+    fn pellet_attract_radius_for_len(&self, snake_len: usize) -> f64 {
+        (SMALL_PELLET_ATTRACT_RADIUS * self.player_girth_scale_from_len(snake_len))
+            .min(SMALL_PELLET_ATTRACT_RADIUS_MAX)
+    }
+
     fn find_pellet_target(
         pellet: Point,
         attractors: &HashMap<String, HeadAttractor>,
     ) -> Option<(String, HeadAttractor)> {
-        let attract_cos = SMALL_PELLET_ATTRACT_RADIUS.cos();
         let lock_cone_cos = SMALL_PELLET_LOCK_CONE_ANGLE.cos();
         let mut best: Option<(String, HeadAttractor, f64)> = None;
         for (id, attractor) in attractors {
             let head_dot = clamp(dot(attractor.head, pellet), -1.0, 1.0);
-            if head_dot < attract_cos {
+            if head_dot < attractor.attract_radius.cos() {
                 continue;
             }
             let toward = Point {
@@ -1823,14 +3522,40 @@ impl RoomState {
         best.map(|(player_id, attractor, _)| (player_id, attractor))
     }
 
-    fn evasive_speed_factor(owner_dot: f64) -> f64 {
+    /// Scales evasive pellet speed and zigzag by the owner's recent catch rate, from the room's
+    /// `evasive_min_difficulty` for owners who never catch them up to `1.0` for owners who
+    /// always do. A prior of one catch in two attempts keeps new players mid-range.
+    fn evasive_difficulty_for_owner(&self, owner: &Player) -> f64 {
+        let min = self.pellet_config.evasive_min_difficulty;
+        let success = (owner.evasive_captures as f64 + 1.0) / (owner.evasive_attempts as f64 + 2.0);
+        min + (1.0 - min) * clamp(success, 0.0, 1.0)
+    }
+
+    fn record_evasive_outcome(&mut self, owner_player_id: &str, captured: bool) {
+        let Some(owner) = self.players.get_mut(owner_player_id) else {
+            return;
+        };
+        owner.evasive_attempts = owner.evasive_attempts.saturating_add(1);
+        if captured {
+            owner.evasive_captures = owner.evasive_captures.saturating_add(1);
+        }
+        if owner.evasive_attempts > EVASIVE_PELLET_SKILL_WINDOW {
+            // Round attempts up so halving never turns a recent miss into a perfect record.
+            owner.evasive_attempts = owner.evasive_attempts.div_ceil(2);
+            owner.evasive_captures /= 2;
+        }
+    }
+
+    fn evasive_speed_factor(owner_dot: f64, difficulty: f64) -> f64 {
         let owner_angle = clamp(owner_dot, -1.0, 1.0).acos();
         let full_angle = EVASIVE_PELLET_EVADE_RADIUS * EVASIVE_PELLET_EVADE_FULL_RADIUS_RATIO;
         let fade_angle = EVASIVE_PELLET_EVADE_RADIUS * EVASIVE_PELLET_EVADE_FADE_RADIUS_RATIO;
         let fade_span = (fade_angle - full_angle).max(1e-6);
         let t = clamp((owner_angle - full_angle) / fade_span, 0.0, 1.0);
         let smooth = t * t * (3.0 - 2.0 * t);
-        EVASIVE_PELLET_EVADE_MIN_FACTOR + (1.0 - EVASIVE_PELLET_EVADE_MIN_FACTOR) * (1.0 - smooth)
+        let factor = EVASIVE_PELLET_EVADE_MIN_FACTOR
+            + (1.0 - EVASIVE_PELLET_EVADE_MIN_FACTOR) * (1.0 - smooth);
+        factor * difficulty
     }
 
     fn is_owner_chasing_evasive(owner_attractor: HeadAttractor, pellet: Point) -> bool {
@@ -1862,6 +3587,7 @@ impl RoomState {
         pellet: &Pellet,
         owner_attractor: HeadAttractor,
         now_ms: i64,
+        difficulty: f64,
     ) -> Option<Point> {
         let owner_dot = clamp(dot(pellet.normal, owner_attractor.head), -1.0, 1.0);
         let toward_owner = Point {
@@ -1906,7 +3632,7 @@ impl RoomState {
 
         let phase = now_ms as f64 * 0.001 * EVASIVE_PELLET_ZIGZAG_HZ * PI * 2.0
             + pellet.id as f64 * 0.754_877_666_246_692_7;
-        let zigzag = phase.sin() * EVASIVE_PELLET_ZIGZAG_STRENGTH;
+        let zigzag = phase.sin() * EVASIVE_PELLET_ZIGZAG_STRENGTH * difficulty;
         let desired = Point {
             x: away_dir.x + strafe.x * zigzag,
             y: away_dir.y + strafe.y * zigzag,
@@ -1940,6 +3666,7 @@ impl RoomState {
                 + (SMALL_PELLET_DIGESTION_STRENGTH_MAX - SMALL_PELLET_DIGESTION_STRENGTH) * burst_t;
             add_digestion_with_strength(player, strength, growth);
 
+            // Score stays in units of a default big pellet regardless of the room's tuning.
             let score_growth = if BIG_PELLET_GROWTH_FRACTION > 0.0 {
                 growth / BIG_PELLET_GROWTH_FRACTION
             } else {
@@ -1962,10 +3689,7 @@ impl RoomState {
         let attractors = self.build_head_attractors();
         let consume_cos = SMALL_PELLET_CONSUME_ANGLE.cos();
         let suction_cos = EVASIVE_PELLET_SUCTION_RADIUS.cos();
-        // Cap angular travel per tick so attracted pellets visibly move/shrink toward the mouth
-        // instead of snapping directly into the consume angle in one update.
-        let attract_step = (SMALL_PELLET_ATTRACT_SPEED * dt_seconds)
-            .min(SMALL_PELLET_ATTRACT_RADIUS * SMALL_PELLET_ATTRACT_STEP_MAX_RATIO);
+        let attract_speed_step = SMALL_PELLET_ATTRACT_SPEED * dt_seconds;
         let evasive_step = (EVASIVE_PELLET_EVADE_SPEED * dt_seconds)
             .min(EVASIVE_PELLET_EVADE_STEP_MAX)
             .min(EVASIVE_PELLET_MAX_STEP_PER_TICK);
@@ -1990,6 +3714,7 @@ impl RoomState {
             } = pellet_state
             {
                 if expires_at_ms <= now_ms {
+                    self.record_evasive_outcome(&owner_player_id, false);
                     self.pellets.swap_remove(i);
                     continue;
                 }
@@ -1997,6 +3722,7 @@ impl RoomState {
                 if let Some(player_id) =
                     Self::find_consuming_player(self.pellets[i].normal, consume_cos, &attractors)
                 {
+                    self.record_evasive_outcome(&owner_player_id, player_id == owner_player_id);
                     let pellet_id = self.pellets[i].id;
                     let growth_fraction = self.pellets[i].growth_fraction;
                     let entry = consumed_by.entry(player_id.clone()).or_insert((0, 0.0));
@@ -2015,6 +3741,10 @@ impl RoomState {
                     Self::is_owner_chasing_evasive(owner_attractor, self.pellets[i].normal);
                 let suction_target =
                     Self::find_suction_target(self.pellets[i].normal, suction_cos, &attractors);
+                let difficulty = self
+                    .players
+                    .get(&owner_player_id)
+                    .map_or(1.0, |owner| self.evasive_difficulty_for_owner(owner));
 
                 {
                     let pellet = &mut self.pellets[i];
@@ -2023,11 +3753,14 @@ impl RoomState {
                             rotate_toward(pellet.normal, suction_attractor.mouth, suction_step);
                     } else if owner_is_chasing {
                         let owner_dot = clamp(dot(pellet.normal, owner_attractor.head), -1.0, 1.0);
-                        let speed_factor = Self::evasive_speed_factor(owner_dot);
+                        let speed_factor = Self::evasive_speed_factor(owner_dot, difficulty);
                         let step = (evasive_step * speed_factor).max(1e-4);
-                        if let Some(tangent_dir) =
-                            Self::evasive_tangent_direction(pellet, owner_attractor, now_ms)
-                        {
+                        if let Some(tangent_dir) = Self::evasive_tangent_direction(
+                            pellet,
+                            owner_attractor,
+                            now_ms,
+                            difficulty,
+                        ) {
                             let target = normalize(Point {
                                 x: pellet.normal.x + tangent_dir.x * step,
                                 y: pellet.normal.y + tangent_dir.y * step,
@@ -2042,6 +3775,7 @@ impl RoomState {
                 if let Some(player_id) =
                     Self::find_consuming_player(self.pellets[i].normal, consume_cos, &attractors)
                 {
+                    self.record_evasive_outcome(&owner_player_id, player_id == owner_player_id);
                     let pellet_id = self.pellets[i].id;
                     let growth_fraction = self.pellets[i].growth_fraction;
                     let entry = consumed_by.entry(player_id.clone()).or_insert((0, 0.0));
@@ -2061,12 +3795,17 @@ impl RoomState {
                     ref target_player_id,
                 } => {
                     // Preserve the current intake target while that target remains valid/alive.
-                    // Only reacquire if the current target is no longer available.
-                    attractors
-                        .get(target_player_id)
-                        .copied()
-                        .map(|attractor| (target_player_id.clone(), attractor))
-                        .or_else(|| Self::find_pellet_target(self.pellets[i].normal, &attractors))
+                    // Otherwise release the pellet first so a failed reacquire leaves it idle at
+                    // full size rather than frozen mid-flight.
+                    match attractors.get(target_player_id) {
+                        Some(attractor) => Some((target_player_id.clone(), *attractor)),
+                        None => {
+                            let pellet = &mut self.pellets[i];
+                            pellet.state = PelletState::Idle;
+                            pellet.current_size = pellet.base_size;
+                            Self::find_pellet_target(pellet.normal, &attractors)
+                        }
+                    }
                 }
                 PelletState::Idle => Self::find_pellet_target(self.pellets[i].normal, &attractors),
                 PelletState::Evasive { .. } => None,
@@ -2090,6 +3829,10 @@ impl RoomState {
                     continue;
                 }
 
+                // Cap angular travel per tick so attracted pellets visibly move/shrink toward the
+                // mouth instead of snapping directly into the consume angle in one update.
+                let attract_step = attract_speed_step
+                    .min(attractor.attract_radius * SMALL_PELLET_ATTRACT_STEP_MAX_RATIO);
                 pellet.normal = rotate_toward(pellet.normal, attractor.mouth, attract_step);
                 let after_dot = clamp(dot(pellet.normal, attractor.mouth), -1.0, 1.0);
                 if after_dot >= consume_cos {
@@ -2103,7 +3846,7 @@ impl RoomState {
                     continue;
                 }
                 let angle = after_dot.acos();
-                let ratio = clamp(angle / SMALL_PELLET_ATTRACT_RADIUS, 0.0, 1.0) as f32;
+                let ratio = clamp(angle / attractor.attract_radius, 0.0, 1.0) as f32;
                 let shrink =
                     SMALL_PELLET_SHRINK_MIN_RATIO + (1.0 - SMALL_PELLET_SHRINK_MIN_RATIO) * ratio;
                 pellet.current_size = pellet.base_size * shrink;
@@ -2121,12 +3864,8 @@ impl RoomState {
         self.consume_small_pellets(consumed_by);
     }
 
-    fn player_girth_scale_from_len(snake_len: usize) -> f64 {
-        let step_nodes = SNAKE_GIRTH_NODES_PER_STEP.max(1);
-        let added_nodes = snake_len.saturating_sub(STARTING_LENGTH);
-        let growth_per_node = SNAKE_GIRTH_STEP_PERCENT / step_nodes as f64;
-        let uncapped = 1.0 + (added_nodes as f64) * growth_per_node;
-        clamp(uncapped, 1.0, SNAKE_GIRTH_MAX_SCALE.max(1.0))
+    fn player_girth_scale_from_len(&self, snake_len: usize) -> f64 {
+        self.growth_config.girth_scale_for_len(snake_len)
     }
 
     fn snake_contact_angular_radius_for_scale(girth_scale: f64) -> f64 {
@@ -2137,21 +3876,29 @@ impl RoomState {
         base_collision_angular_radius() * girth_scale.max(0.0)
     }
 
-    fn snake_contact_angular_radius_for_len(snake_len: usize) -> f64 {
-        let scale = Self::player_girth_scale_from_len(snake_len);
+    fn snake_contact_angular_radius_for_len(growth: &GrowthConfig, snake_len: usize) -> f64 {
+        let scale = growth.girth_scale_for_len(snake_len);
         Self::snake_contact_angular_radius_for_scale(scale)
     }
 
-    fn snake_body_angular_radius_for_len(snake_len: usize) -> f64 {
-        let scale = Self::player_girth_scale_from_len(snake_len);
+    fn snake_body_angular_radius_for_len(growth: &GrowthConfig, snake_len: usize) -> f64 {
+        let scale = growth.girth_scale_for_len(snake_len);
         Self::snake_body_angular_radius_for_scale(scale)
     }
 
+    /// `leniency` shrinks both contact radii by that fraction, so a head must sink clearly into
+    /// a body before it dies. With `self_collision` enabled a head also dies on its own nodes
+    /// past `min_node_gap`. `killers` maps each head that died on another snake to that
+    /// snake's id.
     fn detect_snake_head_body_collisions(
         player_snapshots: &[PlayerCollisionSnapshot],
+        leniency: f64,
+        self_collision: SelfCollisionConfig,
         dead: &mut HashSet<String>,
         death_reasons: &mut HashMap<String, &'static str>,
+        killers: &mut HashMap<String, String>,
     ) {
+        let contact_scale = 1.0 - leniency;
         for snapshot in player_snapshots {
             if dead.contains(&snapshot.id) || !snapshot.alive || snapshot.snake.len() < 3 {
                 continue;
@@ -2161,17 +3908,22 @@ impl RoomState {
                 if !other_snapshot.alive || other_snapshot.id == snapshot.id {
                     continue;
                 }
-                for node in &other_snapshot.snake {
+                for node in other_snapshot
+                    .snake
+                    .iter()
+                    .chain(other_snapshot.extended_tail.iter())
+                {
                     if collision_with_angular_radii(
                         head,
                         *node,
-                        snapshot.body_angular_radius,
-                        other_snapshot.body_angular_radius,
+                        snapshot.body_angular_radius * contact_scale,
+                        other_snapshot.body_angular_radius * contact_scale,
                     ) {
                         dead.insert(snapshot.id.clone());
                         death_reasons
                             .entry(snapshot.id.clone())
                             .or_insert("snake_collision");
+                        killers.insert(snapshot.id.clone(), other_snapshot.id.clone());
                         break;
                     }
                 }
@@ -2179,11 +3931,28 @@ impl RoomState {
                     break;
                 }
             }
+            if !self_collision.enabled
+                || dead.contains(&snapshot.id)
+                || snapshot.snake.len() <= self_collision.min_node_gap
+            {
+                continue;
+            }
+            let own_radius = snapshot.body_angular_radius * contact_scale;
+            let hit_self = snapshot.snake[self_collision.min_node_gap..]
+                .iter()
+                .chain(snapshot.extended_tail.iter())
+                .any(|node| collision_with_angular_radii(head, *node, own_radius, own_radius));
+            if hit_self {
+                dead.insert(snapshot.id.clone());
+                death_reasons
+                    .entry(snapshot.id.clone())
+                    .or_insert("self_collision");
+            }
         }
     }
 
-    fn can_player_continue_boost(player: &Player) -> bool {
-        if player.snake.len() > player.boost_floor_len.max(1) {
+    fn can_player_continue_boost(player: &Player, min_survival_length: usize) -> bool {
+        if player.snake.len() > player.boost_floor_len.max(min_survival_length).max(1) {
             return true;
         }
         if player.tail_extension > 1e-6 {
@@ -2202,13 +3971,39 @@ impl RoomState {
             .min(i64::MAX as usize) as i64
     }
 
-    fn can_player_boost(player: &Player) -> bool {
-        if !Self::can_player_continue_boost(player) {
+    fn can_player_boost(player: &Player, min_survival_length: usize) -> bool {
+        if !Self::can_player_continue_boost(player, min_survival_length) {
             return false;
         }
         player.is_boosting || player.score >= Self::min_boost_start_score(player)
     }
 
+    /// Applies the minimum engage window and re-engage cooldown to the raw boost flag. An
+    /// engaged boost holds through `BOOST_MIN_ENGAGE_TICKS` even if the flag drops, and a
+    /// released boost can't restart until `BOOST_COOLDOWN_TICKS` have passed.
+    fn resolve_boost_intent(player: &mut Player) -> bool {
+        if player.is_boosting {
+            if player.boost_engage_ticks > 0 {
+                player.boost_engage_ticks -= 1;
+                return true;
+            }
+            if player.boost {
+                return true;
+            }
+            player.boost_cooldown_ticks = BOOST_COOLDOWN_TICKS;
+            return false;
+        }
+        if player.boost_cooldown_ticks > 0 {
+            player.boost_cooldown_ticks -= 1;
+            return false;
+        }
+        if player.boost {
+            player.boost_engage_ticks = BOOST_MIN_ENGAGE_TICKS.saturating_sub(1);
+            return true;
+        }
+        false
+    }
+
     fn slither_scang_for_len(snake_len: usize) -> f64 {
         let sc =
             (1.0 + (snake_len.saturating_sub(2) as f64) / TURN_SC_LENGTH_DIVISOR).min(TURN_SC_MAX);
@@ -2228,26 +4023,19 @@ impl RoomState {
         clamp(damped, TURN_SPEED_MIN_MULTIPLIER, 1.0)
     }
 
-    fn steering_gain_for_speed(speed_factor: f64) -> f64 {
+    /// `boost_multiplier` is the room's full boost speed, where the boost gain fully applies.
+    fn steering_gain_for_speed(speed_factor: f64, boost_multiplier: f64) -> f64 {
         let speed = if speed_factor.is_finite() {
             speed_factor.max(0.0)
         } else {
             0.0
         };
-        let boost_window = (BOOST_MULTIPLIER - 1.0).max(1e-6);
+        let boost_window = (boost_multiplier - 1.0).max(1e-6);
         let blend = clamp((speed - 1.0) / boost_window, 0.0, 1.0);
         TURN_RESPONSE_GAIN_NORMAL_PER_SEC
             + (TURN_RESPONSE_GAIN_BOOST_PER_SEC - TURN_RESPONSE_GAIN_NORMAL_PER_SEC) * blend
     }
 
-    fn movement_substep_count(is_boosting: bool) -> usize {
-        if is_boosting {
-            TURN_SUBSTEPS_BOOST.max(1)
-        } else {
-            TURN_SUBSTEPS_NORMAL.max(1)
-        }
-    }
-
     fn steering_turn_step(
         current_axis: Point,
         target_axis: Point,
@@ -2272,7 +4060,15 @@ impl RoomState {
         clamp(proportional_step, 0.0, capped_turn)
     }
 
-    fn turn_rate_for(snake_len: usize, speed_factor: f64) -> f64 {
+    /// Per-tick turn cap for a snake. `base_turn` is the room's per-second turn rate already
+    /// scaled to one tick, so length and boost shaping stay independent of the tick rate. The
+    /// boost turn bonus is fully applied at `boost_multiplier`, the room's boost speed.
+    fn turn_rate_for(
+        base_turn: f64,
+        snake_len: usize,
+        speed_factor: f64,
+        boost_multiplier: f64,
+    ) -> f64 {
         let speed = if speed_factor.is_finite() {
             speed_factor.max(0.0)
         } else {
@@ -2283,15 +4079,15 @@ impl RoomState {
         let baseline_scang = Self::slither_scang_for_len(STARTING_LENGTH);
         let baseline_spang = Self::slither_spang_for_speed(1.0);
         let baseline = (baseline_scang * baseline_spang).max(1e-6);
-        let boost_window = (BOOST_MULTIPLIER - 1.0).max(1e-6);
+        let boost_window = (boost_multiplier - 1.0).max(1e-6);
         let boost_blend = clamp((speed - 1.0) / boost_window, 0.0, 1.0);
         let boost_turn_mult = 1.0 + (TURN_BOOST_TURN_RATE_MULTIPLIER - 1.0).max(0.0) * boost_blend;
         let normalized = (scang * spang) / baseline;
-        let raw_turn_rate = TURN_RATE * normalized * boost_turn_mult;
+        let raw_turn_rate = base_turn * normalized * boost_turn_mult;
         clamp(
             raw_turn_rate,
-            TURN_RATE * TURN_RATE_MIN_MULTIPLIER,
-            TURN_RATE * TURN_RATE_MAX_MULTIPLIER,
+            base_turn * TURN_RATE_MIN_MULTIPLIER,
+            base_turn * TURN_RATE_MAX_MULTIPLIER,
         )
     }
 
@@ -2300,8 +4096,22 @@ impl RoomState {
     }
 
     fn apply_session_inbound(&mut self) {
-        for session in self.sessions.values_mut() {
-            let inbound = session.inbound.snapshot();
+        let now = Self::now_millis();
+        for (session_id, session) in self.sessions.iter_mut() {
+            let inbound = session.inbound.take_tick_snapshot();
+            if inbound.over_budget_inputs > 0 {
+                self.over_budget_inputs = self
+                    .over_budget_inputs
+                    .saturating_add(inbound.over_budget_inputs as u64);
+            }
+            if inbound.over_budget_inputs > 0 && self.input_warn_limiter.check(session_id, now) {
+                tracing::warn!(
+                    session_id,
+                    over_budget_inputs = inbound.over_budget_inputs,
+                    limit = INPUT_RATE_LIMIT_PER_TICK,
+                    "session_input_over_budget"
+                );
+            }
 
             session.view_center = inbound.view_center;
             session.view_radius = inbound.view_radius;
@@ -2312,24 +4122,162 @@ impl RoomState {
             };
             let Some(player) = self.players.get_mut(player_id) else {
                 continue;
-            };
-
-            if let Some(axis) = inbound.input_axis {
-                player.target_axis = axis;
+            };
+
+            if let Some(axis) = inbound.input_axis {
+                player.target_axis = self.input_config.steer(player.target_axis, axis);
+            }
+            player.boost = inbound.boost;
+            if let Some(seq) = inbound.latest_input_seq {
+                session.latest_applied_input_seq = seq;
+            }
+            if inbound.last_input_at > 0 {
+                player.last_seen = inbound.last_input_at;
+            }
+            if inbound.last_active_at > 0 {
+                player.last_active_at = inbound.last_active_at;
+            }
+        }
+    }
+
+    fn oxygen_drain_multiplier(&self) -> f64 {
+        self.day_night
+            .map(|config| config.oxygen_drain_multiplier(self.day_phase))
+            .unwrap_or(1.0)
+    }
+
+    fn update_day_phase(&mut self, now: i64) {
+        let Some(config) = self.day_night else {
+            return;
+        };
+        self.day_phase = config.phase_at(now);
+        let phase_q = Self::quantize_day_phase(self.day_phase);
+        if self.day_phase_q == Some(phase_q) {
+            return;
+        }
+        self.day_phase_q = Some(phase_q);
+        let payload = self.build_world_payload(phase_q);
+        for session in self.sessions.values() {
+            // World updates are cosmetic and re-sent on the next phase step, so a full low-priority
+            // queue is not worth disconnecting over.
+            let _ = session.outbound_lo.try_send(payload.clone());
+        }
+    }
+
+    fn quantize_day_phase(phase: f64) -> u8 {
+        ((phase.rem_euclid(1.0) * 256.0) as u32).min(u8::MAX as u32) as u8
+    }
+
+    fn build_world_payload(&self, phase_q: u8) -> Vec<u8> {
+        let mut encoder = protocol::Encoder::with_capacity(8);
+        encoder.write_header(protocol::TYPE_WORLD, 0);
+        encoder.write_u8(phase_q);
+        encoder.into_vec()
+    }
+
+    fn build_chat_payload(net_id: u16, text: &str) -> Vec<u8> {
+        let mut encoder = protocol::Encoder::with_capacity(8 + text.len());
+        encoder.write_header(protocol::TYPE_CHAT_MESSAGE, 0);
+        encoder.write_u16(net_id);
+        encoder.write_string(text);
+        encoder.into_vec()
+    }
+
+    fn update_oxygen(
+        &mut self,
+        dt_seconds: f64,
+        death_reasons: &mut HashMap<String, &'static str>,
+    ) -> HashSet<String> {
+        let mut oxygen_dead: HashSet<String> = HashSet::new();
+        let oxygen_disabled = Self::oxygen_disabled();
+        let oxygen_max = self.oxygen_config.max;
+        let oxygen_drain_per_sec =
+            self.oxygen_config.drain_per_sec * self.oxygen_drain_multiplier();
+        let oxygen_grace_ms = self.oxygen_grace_ms;
+        let min_survival_length = self.growth_config.min_survival_length;
+        let mut dropped_points: Vec<Point> = Vec::new();
+        let player_ids: Vec<String> = self.players.keys().cloned().collect();
+        for id in &player_ids {
+            let Some(player) = self.players.get_mut(id) else {
+                continue;
+            };
+            if !player.alive {
+                continue;
+            }
+            if oxygen_disabled {
+                player.oxygen = oxygen_max;
+                player.oxygen_damage_accumulator = 0.0;
+                player.submerged_secs = 0.0;
+                continue;
             }
-            player.boost = inbound.boost;
-            if let Some(seq) = inbound.latest_input_seq {
-                session.latest_applied_input_seq = seq;
+            let head = Point {
+                x: player.snake[0].x,
+                y: player.snake[0].y,
+                z: player.snake[0].z,
+            };
+            let sample = sample_lakes(head, &self.environment.lakes);
+            if sample.boundary > LAKE_WATER_MASK_THRESHOLD {
+                player.submerged_secs += dt_seconds;
+                if player.submerged_secs * 1000.0 <= oxygen_grace_ms as f64 {
+                    continue;
+                }
+                player.oxygen = (player.oxygen - oxygen_drain_per_sec * dt_seconds).max(0.0);
+                if player.oxygen <= 0.0
+                    && Self::apply_oxygen_damage(
+                        player,
+                        dt_seconds,
+                        min_survival_length,
+                        &mut dropped_points,
+                    )
+                {
+                    player.oxygen_damage_accumulator = 0.0;
+                    oxygen_dead.insert(player.id.clone());
+                    death_reasons.entry(player.id.clone()).or_insert("oxygen");
+                }
+            } else {
+                player.oxygen = oxygen_max;
+                player.oxygen_damage_accumulator = 0.0;
+                player.submerged_secs = 0.0;
             }
-            if inbound.last_input_at > 0 {
-                player.last_seen = inbound.last_input_at;
+        }
+        self.spawn_death_pellets(dropped_points, 1.0);
+        oxygen_dead
+    }
+
+    /// Shrinks a drowning snake by whole tail nodes as damage accumulates, dropping each lost
+    /// node as a pellet. Returns true once the snake can't lose another node and should die.
+    fn apply_oxygen_damage(
+        player: &mut Player,
+        dt_seconds: f64,
+        min_survival_length: usize,
+        dropped_points: &mut Vec<Point>,
+    ) -> bool {
+        if player.snake.len() <= min_survival_length {
+            return true;
+        }
+        player.oxygen_damage_accumulator += OXYGEN_DAMAGE_NODES_PER_SEC * dt_seconds.max(0.0);
+        while player.oxygen_damage_accumulator >= 1.0 {
+            if player.snake.len() <= min_survival_length {
+                return true;
             }
+            let Some(tail) = player.snake.pop() else {
+                return true;
+            };
+            dropped_points.push(Point {
+                x: tail.x,
+                y: tail.y,
+                z: tail.z,
+            });
+            player.score = player.score.saturating_sub(1).max(0);
+            player.oxygen_damage_accumulator -= 1.0;
         }
+        false
     }
 
     fn tick(&mut self) {
         let now = Self::now_millis();
         self.apply_session_inbound();
+        self.update_day_phase(now);
 
         // If we had to drop pellet deltas due to backpressure, resync via reset as soon as the
         // session can accept reliable frames again.
@@ -2348,21 +4296,27 @@ impl RoomState {
         let dt_seconds = TICK_MS as f64 / 1000.0;
         let mut move_steps: HashMap<String, i32> = HashMap::new();
 
+        let disconnect_config = self.disconnect_config;
         self.players.retain(|_, player| {
             if player.connected {
                 true
             } else {
-                now - player.last_seen <= PLAYER_TIMEOUT_MS
+                now - player.last_seen <= disconnect_config.timeout_for_len(player.snake.len())
             }
         });
         self.prune_evasive_spawn_timers();
 
         self.ensure_bots();
         self.ensure_pellets();
+        self.update_afk_autopilot(now);
         self.update_bots();
         self.auto_respawn_players(now);
         self.spawn_evasive_pellets(now);
 
+        let growth = self.growth_config;
+        let base_turn = self.turn_rate_per_sec * dt_seconds;
+        let movement = self.movement_config;
+        let freeze_disconnected = disconnect_config.freeze_on_disconnect;
         let player_ids: Vec<String> = self.players.keys().cloned().collect();
         for id in &player_ids {
             let Some(player) = self.players.get_mut(id) else {
@@ -2371,19 +4325,29 @@ impl RoomState {
             if !player.alive {
                 continue;
             }
-            let wants_boost = player.boost;
-            let is_boosting = wants_boost && Self::can_player_boost(player);
+            if freeze_disconnected && !player.connected && !player.is_bot {
+                continue;
+            }
+            let wants_boost = Self::resolve_boost_intent(player);
+            let is_boosting =
+                wants_boost && Self::can_player_boost(player, growth.min_survival_length);
             player.is_boosting = is_boosting;
-            let speed_factor = if is_boosting { BOOST_MULTIPLIER } else { 1.0 };
-            let step_count = Self::movement_substep_count(is_boosting);
-            let step_velocity = (BASE_SPEED * speed_factor) / step_count as f64;
-            let turn_per_tick = Self::turn_rate_for(player.snake.len(), speed_factor);
+            let speed_factor = movement.speed_factor(is_boosting);
+            let step_count = movement.substep_count(speed_factor);
+            let step_velocity = (movement.base_speed * speed_factor) / step_count as f64;
+            let turn_per_tick = Self::turn_rate_for(
+                base_turn,
+                player.snake.len(),
+                speed_factor,
+                movement.boost_multiplier,
+            );
             let turn_per_substep_cap = turn_per_tick / step_count as f64;
-            let steering_gain_per_sec = Self::steering_gain_for_speed(speed_factor);
+            let steering_gain_per_sec =
+                Self::steering_gain_for_speed(speed_factor, movement.boost_multiplier);
             let substep_dt_seconds = dt_seconds / step_count as f64;
             let target_axis = normalize(player.target_axis);
             let snake_angular_radius =
-                Self::snake_contact_angular_radius_for_len(player.snake.len());
+                Self::snake_contact_angular_radius_for_len(&growth, player.snake.len());
             for _ in 0..step_count {
                 let turn_step = Self::steering_turn_step(
                     player.axis,
@@ -2404,72 +4368,12 @@ impl RoomState {
             }
             move_steps.insert(player.id.clone(), step_count as i32);
         }
+        self.recover_non_finite_players();
 
         let mut death_reasons: HashMap<String, &'static str> = HashMap::new();
-        let mut oxygen_dead: HashSet<String> = HashSet::new();
-        let oxygen_disabled = Self::oxygen_disabled();
-        let player_ids: Vec<String> = self.players.keys().cloned().collect();
-        for id in &player_ids {
-            let Some(player) = self.players.get_mut(id) else {
-                continue;
-            };
-            if !player.alive {
-                continue;
-            }
-            if oxygen_disabled {
-                player.oxygen = OXYGEN_MAX;
-                player.oxygen_damage_accumulator = 0.0;
-                continue;
-            }
-            let head = Point {
-                x: player.snake[0].x,
-                y: player.snake[0].y,
-                z: player.snake[0].z,
-            };
-            let sample = sample_lakes(head, &self.environment.lakes);
-            if sample.boundary > LAKE_WATER_MASK_THRESHOLD {
-                player.oxygen = (player.oxygen - OXYGEN_DRAIN_PER_SEC * dt_seconds).max(0.0);
-                if player.oxygen <= 0.0 {
-                    player.oxygen_damage_accumulator = 0.0;
-                    oxygen_dead.insert(player.id.clone());
-                    death_reasons.entry(player.id.clone()).or_insert("oxygen");
-                }
-            } else {
-                player.oxygen = OXYGEN_MAX;
-                player.oxygen_damage_accumulator = 0.0;
-            }
-        }
+        let oxygen_dead = self.update_oxygen(dt_seconds, &mut death_reasons);
 
-        let player_snapshots: Vec<PlayerCollisionSnapshot> = self
-            .players
-            .values()
-            .map(|player| {
-                let girth_scale = Self::player_girth_scale_from_len(player.snake.len());
-                let mut snake_points = player
-                    .snake
-                    .iter()
-                    .map(|node| Point {
-                        x: node.x,
-                        y: node.y,
-                        z: node.z,
-                    })
-                    .collect::<Vec<_>>();
-                if let Some(extended_tail) =
-                    compute_extended_tail_point(&player.snake, player.tail_extension)
-                {
-                    snake_points.push(extended_tail);
-                }
-                PlayerCollisionSnapshot {
-                    id: player.id.clone(),
-                    alive: player.alive,
-                    snake: snake_points,
-                    contact_angular_radius: Self::snake_contact_angular_radius_for_scale(
-                        girth_scale,
-                    ),
-                    body_angular_radius: Self::snake_body_angular_radius_for_scale(girth_scale),
-                }
-            })
-            .collect();
+        let player_snapshots = self.player_collision_snapshots();
 
         let mut dead: HashSet<String> = HashSet::new();
         for snapshot in &player_snapshots {
@@ -2497,15 +4401,34 @@ impl RoomState {
             }
         }
 
-        Self::detect_snake_head_body_collisions(&player_snapshots, &mut dead, &mut death_reasons);
+        let collision_leniency = self.collision_leniency;
+        let self_collision = self.self_collision;
+        let mut killers: HashMap<String, String> = HashMap::new();
+        Self::profile_tick_phase("detect_snake_head_body_collisions", || {
+            Self::detect_snake_head_body_collisions(
+                &player_snapshots,
+                collision_leniency,
+                self_collision,
+                &mut dead,
+                &mut death_reasons,
+                &mut killers,
+            )
+        });
 
         dead.extend(oxygen_dead);
+        let mut kills: Vec<String> = Vec::new();
         for id in dead {
             let reason = death_reasons.get(&id).copied().unwrap_or("collision");
             tracing::debug!(player_id = %id, reason, "death_reason");
             self.handle_death(&id);
+            if reason == "snake_collision" {
+                kills.extend(killers.remove(&id));
+            }
         }
+        self.award_kill_bonuses(&kills);
+        self.send_threat_hints(&player_snapshots);
 
+        let min_survival_length = self.growth_config.min_survival_length;
         let player_ids: Vec<String> = self.players.keys().cloned().collect();
         for id in &player_ids {
             let Some(player) = self.players.get_mut(id) else {
@@ -2519,50 +4442,111 @@ impl RoomState {
             let boost_drain = if player.is_boosting {
                 BoostDrainConfig {
                     active: true,
-                    min_length: player.boost_floor_len.max(MIN_SURVIVAL_LENGTH),
+                    min_length: player.boost_floor_len.max(min_survival_length),
                     score_per_step: (BOOST_SCORE_DRAIN_PER_SEC * dt_seconds) / step_count,
                     node_per_step: (BOOST_NODE_DRAIN_PER_SEC * dt_seconds) / step_count,
                 }
             } else {
                 BoostDrainConfig::default()
             };
+            let shed_from = (self.boost_drain_shedding && player.is_boosting)
+                .then(|| Self::boost_sheddable_length(player));
             let boost_active_after = advance_digestions_with_boost(player, steps, boost_drain);
-            player.is_boosting = player.boost && boost_active_after;
+            if let Some(before) = shed_from {
+                let drained = before - Self::boost_sheddable_length(player);
+                player.boost_shed_value += drained.max(0.0);
+            }
+            player.is_boosting = player.is_boosting && boost_active_after;
         }
 
         self.spawn_boost_trail_pellets(now);
 
         // Advance existing digestions before applying newly swallowed pellets so new bulges
         // always begin from the same head-relative start regardless of boost step count.
-        self.update_small_pellets(dt_seconds);
+        Self::profile_tick_phase("update_small_pellets", || {
+            self.update_small_pellets(dt_seconds)
+        });
         self.ensure_pellets();
+        self.update_session_bests();
 
         let now = Self::now_millis();
         let state_seq = self.next_state_seq;
         self.broadcast_pellet_consumes(now, state_seq);
-        self.broadcast_state_delta(now, state_seq);
-        self.broadcast_pellet_delta(now, state_seq);
+        Self::profile_tick_phase("broadcast_state_delta", || {
+            self.broadcast_state_delta(now, state_seq)
+        });
+        Self::profile_tick_phase("broadcast_pellet_delta", || {
+            self.broadcast_pellet_delta(now, state_seq)
+        });
         self.next_state_seq = self.next_state_seq.wrapping_add(1);
     }
 
+    fn player_collision_snapshots(&self) -> Vec<PlayerCollisionSnapshot> {
+        self.players
+            .values()
+            .map(|player| {
+                let girth_scale = self.player_girth_scale_from_len(player.snake.len());
+                let snake_points = player
+                    .snake
+                    .iter()
+                    .map(|node| Point {
+                        x: node.x,
+                        y: node.y,
+                        z: node.z,
+                    })
+                    .collect::<Vec<_>>();
+                PlayerCollisionSnapshot {
+                    id: player.id.clone(),
+                    alive: player.alive,
+                    snake: snake_points,
+                    extended_tail: compute_extended_tail_point(
+                        &player.snake,
+                        player.tail_extension,
+                    ),
+                    contact_angular_radius: Self::snake_contact_angular_radius_for_scale(
+                        girth_scale,
+                    ),
+                    body_angular_radius: Self::snake_body_angular_radius_for_scale(girth_scale),
+                }
+            })
+            .collect()
+    }
+
+    fn update_session_bests(&mut self) {
+        for player in self.players.values_mut() {
+            player.session_best = player.session_best.max(player.score);
+        }
+    }
+
     fn handle_death(&mut self, player_id: &str) {
-        let (is_bot, dropped_points) = {
+        let starting_length = self.growth_config.starting_length;
+        let (is_bot, final_score, dropped_points, death_pellet_value) = {
             let Some(player) = self.players.get_mut(player_id) else {
                 return;
             };
             if !player.alive {
                 return;
             }
+            let final_score = player.score;
             player.alive = false;
-            player.respawn_at = Some(Self::now_millis() + RESPAWN_COOLDOWN_MS);
+            player.respawn_at = Some(Self::now_millis() + self.respawn_cooldown_ms);
             player.is_boosting = false;
+            player.boost_engage_ticks = 0;
+            player.boost_cooldown_ticks = 0;
             player.digestions.clear();
             player.next_digestion_id = 0;
             player.pellet_growth_fraction = 0.0;
             player.tail_extension = 0.0;
             player.oxygen_damage_accumulator = 0.0;
+            player.submerged_secs = 0.0;
             player.score = 0;
             player.next_boost_trail_pellet_at_ms = 0;
+            player.boost_shed_value = 0.0;
+            player.respawn_length = Self::retained_respawn_length(
+                player.snake.len(),
+                self.respawn_length_retention,
+                starting_length,
+            );
             let dropped_points = player
                 .snake
                 .iter()
@@ -2573,13 +4557,98 @@ impl RoomState {
                     z: node.z,
                 })
                 .collect::<Vec<_>>();
-            (player.is_bot, dropped_points)
+            // Length carried into the respawn isn't dropped a second time as pellets.
+            let death_pellet_value = if player.respawn_length.is_some() {
+                1.0 - self.respawn_length_retention
+            } else {
+                1.0
+            };
+            (
+                player.is_bot,
+                final_score,
+                dropped_points,
+                death_pellet_value,
+            )
         };
         tracing::debug!(player_id, is_bot, "player died");
+        if !is_bot {
+            self.send_score_receipt(player_id, final_score);
+        }
+        self.release_pellets_targeting(player_id);
+        self.spawn_death_pellets(dropped_points, death_pellet_value);
+    }
+
+    fn send_score_receipt(&self, player_id: &str, score: i64) {
+        let Some(secret) = self.score_receipt_secret.as_deref() else {
+            return;
+        };
+        let claims = ScoreReceiptClaims {
+            player_id: player_id.to_string(),
+            score,
+            issued_at_ms: Self::now_millis(),
+        };
+        let receipt = match sign_score_receipt(&claims, secret) {
+            Ok(receipt) => receipt,
+            Err(error) => {
+                tracing::warn!(player_id, %error, "score_receipt_sign_failed");
+                return;
+            }
+        };
+        let payload = protocol::encode_score_receipt(score, &receipt);
+        for session in self.sessions.values() {
+            if session.player_id.as_deref() == Some(player_id) {
+                let _ = session.outbound_hi.try_send(payload.clone());
+            }
+        }
+    }
+
+    /// Credits `kill_bonus` once per kill to each killer still alive after this tick's deaths.
+    fn award_kill_bonuses(&mut self, killer_ids: &[String]) {
+        if self.kill_bonus <= 0 {
+            return;
+        }
+        for killer_id in killer_ids {
+            if let Some(killer) = self.players.get_mut(killer_id) {
+                if killer.alive {
+                    killer.score = killer.score.saturating_add(self.kill_bonus);
+                }
+            }
+        }
+    }
+
+    /// Drops pellets mid-intake back to idle at full size, so they don't hang shrunken on
+    /// their way to a mouth that is gone.
+    fn release_pellets_targeting(&mut self, player_id: &str) {
+        for pellet in &mut self.pellets {
+            let is_target = matches!(
+                &pellet.state,
+                PelletState::Attracting { target_player_id } if target_player_id == player_id
+            );
+            if is_target {
+                pellet.state = PelletState::Idle;
+                pellet.current_size = pellet.base_size;
+            }
+        }
+    }
 
-        let mut rng = rand::thread_rng();
-        for point in dropped_points {
-            let Some(spawn_point) = self.pick_valid_death_pellet_spawn(point, &mut rng) else {
+    /// Spawns one death pellet per point, each worth `value_scale` of a full death pellet's
+    /// growth. A scale of zero or less spawns nothing.
+    fn spawn_death_pellets(&mut self, points: Vec<Point>, value_scale: f64) {
+        if points.is_empty() || value_scale <= 0.0 {
+            return;
+        }
+        let growth_fraction = BIG_PELLET_GROWTH_FRACTION * value_scale.min(1.0);
+        let mut rng = self.fork_rng();
+        let scatter_angle = self.pellet_config.death_scatter_angle;
+        for point in points {
+            let scattered = if scatter_angle > 0.0 {
+                self.scatter_death_pellet_spawn(point, scatter_angle, &mut rng)
+            } else {
+                None
+            };
+            let Some(spawn_point) =
+                scattered.or_else(|| self.pick_valid_death_pellet_spawn(point, &mut rng))
+            else {
                 continue;
             };
             let size = rng.gen_range(DEATH_PELLET_SIZE_MIN..=DEATH_PELLET_SIZE_MAX);
@@ -2587,24 +4656,77 @@ impl RoomState {
             self.pellets.push(Pellet {
                 id: pellet_id,
                 normal: spawn_point,
-                color_rgb: Self::random_pellet_color_rgb(&mut rng),
+                color_rgb: Self::random_pellet_color_rgb(&self.pellet_palette, &mut rng),
                 base_size: size,
                 current_size: size,
-                growth_fraction: BIG_PELLET_GROWTH_FRACTION,
+                growth_fraction,
                 expires_at_ms: None,
                 state: PelletState::Idle,
             });
         }
 
-        if self.pellets.len() > MAX_PELLETS {
-            let excess = self.pellets.len() - MAX_PELLETS;
-            self.pellets.drain(0..excess);
+        self.trim_pellets_to_max();
+    }
+
+    fn retained_respawn_length(
+        death_len: usize,
+        retention: f64,
+        starting_length: usize,
+    ) -> Option<usize> {
+        let retained = (death_len as f64 * retention).floor() as usize;
+        (retained > starting_length).then_some(retained)
+    }
+
+    /// Safety net for math that slipped a NaN or infinity into a live snake: respawns any player
+    /// whose head or axis is non-finite, keeping its length, before the value can reach
+    /// collision checks or get quantized onto the wire.
+    fn recover_non_finite_players(&mut self) {
+        let corrupted: Vec<String> = self
+            .players
+            .values()
+            .filter(|player| player.alive && !Self::player_head_and_axis_finite(player))
+            .map(|player| player.id.clone())
+            .collect();
+        let starting_length = self.growth_config.starting_length;
+        for player_id in corrupted {
+            tracing::warn!(player_id, "non-finite player state; respawning player");
+            self.release_pellets_targeting(&player_id);
+            if let Some(player) = self.players.get_mut(&player_id) {
+                let len = player.snake.len();
+                player.respawn_length = (len > starting_length).then_some(len);
+                player.alive = false;
+                player.snake.clear();
+            }
+            self.respawn_player(&player_id);
         }
     }
 
+    fn player_head_and_axis_finite(player: &Player) -> bool {
+        let axis = player.axis;
+        let axis_finite = axis.x.is_finite() && axis.y.is_finite() && axis.z.is_finite();
+        let head_finite = player
+            .snake
+            .first()
+            .is_none_or(|head| head.x.is_finite() && head.y.is_finite() && head.z.is_finite());
+        axis_finite && head_finite
+    }
+
     fn respawn_player(&mut self, player_id: &str) {
-        let base_axis = random_axis();
-        let spawned = self.spawn_snake(base_axis, Some(player_id));
+        let mut rng = self.fork_rng();
+        let base_axis = random_axis(&mut rng);
+        let starting_length = self.growth_config.starting_length;
+        let length = self
+            .players
+            .get(player_id)
+            .and_then(|player| player.respawn_length)
+            .unwrap_or(starting_length);
+        let avoid_terrain = self.spawn_terrain_protection
+            && self
+                .players
+                .get(player_id)
+                .is_some_and(|player| !player.is_bot);
+        let spawned = self.spawn_snake(base_axis, Some(player_id), length, avoid_terrain, &mut rng);
+        let oxygen_max = self.oxygen_config.max;
         let Some(player) = self.players.get_mut(player_id) else {
             return;
         };
@@ -2617,12 +4739,16 @@ impl RoomState {
         player.alive = true;
         player.boost = false;
         player.is_boosting = false;
-        player.oxygen = OXYGEN_MAX;
+        player.boost_engage_ticks = 0;
+        player.boost_cooldown_ticks = 0;
+        player.oxygen = oxygen_max;
         player.oxygen_damage_accumulator = 0.0;
+        player.submerged_secs = 0.0;
         player.respawn_at = None;
+        player.respawn_length = None;
         player.snake = spawned.snake;
         player.score = player.snake.len() as i64;
-        player.boost_floor_len = player.snake.len().max(STARTING_LENGTH);
+        player.boost_floor_len = player.snake.len().max(starting_length);
         player.trail_color_cycle_cursor = 0;
         player.next_boost_trail_pellet_at_ms = 0;
         player.pellet_growth_fraction = 0.0;
@@ -2632,7 +4758,39 @@ impl RoomState {
         tracing::debug!(player_id, is_bot = player.is_bot, "player respawned");
     }
 
-    fn build_init_payload_for_session(&self, session_id: &str, player_id: &str) -> Vec<u8> {
+    fn player_snapshot(&self, net_id: u16) -> Option<PlayerSnapshot> {
+        let player = self
+            .players
+            .values()
+            .find(|player| player.net_id == net_id)?;
+        let ahead = self
+            .players
+            .values()
+            .filter(|other| other.score > player.score)
+            .count();
+        Some(PlayerSnapshot {
+            net_id,
+            name: player.name.clone(),
+            score: player.score,
+            rank: ahead + 1,
+            total_players: self.players.len(),
+            length: player.snake.len(),
+            alive: player.alive,
+        })
+    }
+
+    fn environment_bytes(&self) -> Vec<u8> {
+        let mut encoder = protocol::Encoder::with_capacity(1024);
+        self.environment.write_to(&mut encoder);
+        encoder.into_vec()
+    }
+
+    fn build_init_payload_for_session(
+        &self,
+        session_id: &str,
+        player_id: &str,
+        resumed: bool,
+    ) -> Vec<u8> {
         let player_bytes = self
             .players
             .get(player_id)
@@ -2677,9 +4835,18 @@ impl RoomState {
             }
         }
         capacity += self.environment.encoded_len();
+        capacity += 4; // girth max scale
+        capacity += 1 + self.pellet_palette.colors().len() * 3;
+        capacity += 4 + 4; // base speed + boost multiplier
+        capacity += 2; // min survival length
 
         let mut encoder = protocol::Encoder::with_capacity(capacity);
-        encoder.write_header(protocol::TYPE_INIT, 0);
+        let flags = if resumed {
+            protocol::FLAG_INIT_RESUMED
+        } else {
+            0
+        };
+        encoder.write_header(protocol::TYPE_INIT, flags);
         encoder.write_uuid(&player_bytes);
         encoder.write_i64(now);
         encoder.write_u32(state_seq);
@@ -2710,6 +4877,21 @@ impl RoomState {
         }
 
         self.environment.write_to(&mut encoder);
+        encoder.write_f32(self.growth_config.girth_max_scale as f32);
+        let palette = self.pellet_palette.colors();
+        encoder.write_u8(palette.len() as u8);
+        for rgb in palette {
+            encoder.write_u8(rgb[0]);
+            encoder.write_u8(rgb[1]);
+            encoder.write_u8(rgb[2]);
+        }
+        encoder.write_f32(self.movement_config.base_speed as f32);
+        encoder.write_f32(self.movement_config.boost_multiplier as f32);
+        encoder.write_u16(
+            self.growth_config
+                .min_survival_length
+                .min(u16::MAX as usize) as u16,
+        );
 
         encoder.into_vec()
     }
@@ -2723,6 +4905,19 @@ impl RoomState {
         let visible_players = self.visible_players_for_session(session_id);
         let total_players = self.players.len().min(u16::MAX as usize);
         let visible_player_count = visible_players.len().min(u16::MAX as usize);
+        let session_best = self
+            .sessions
+            .get(session_id)
+            .and_then(|session| session.player_id.as_ref())
+            .and_then(|player_id| self.players.get(player_id))
+            .map(|player| player.session_best.clamp(i32::MIN as i64, i32::MAX as i64) as i32);
+        let respawn_at = self
+            .sessions
+            .get(session_id)
+            .and_then(|session| session.player_id.as_ref())
+            .and_then(|player_id| self.players.get(player_id))
+            .filter(|player| !player.alive)
+            .and_then(|player| player.respawn_at);
 
         let mut current_players: Vec<(u16, DeltaPlayerCache)> =
             Vec::with_capacity(visible_player_count);
@@ -2731,24 +4926,36 @@ impl RoomState {
             current_players.push((visible.player.net_id, encoded));
         }
 
+        let timing = self.state_frame_timing(now, state_seq);
         let session = self.sessions.get_mut(session_id)?;
-        let keyframe = session.force_next_keyframe
-            || state_seq % STATE_DELTA_KEYFRAME_INTERVAL == 0
-            || session.delta_player_cache.is_empty();
+        let keyframe = Self::is_state_keyframe(session, state_seq, self.keyframe_interval);
         session.force_next_keyframe = false;
 
-        let mut encoder =
-            protocol::Encoder::with_capacity(128 + visible_player_count.saturating_mul(64));
+        let mut encoder = protocol::Encoder::from_buffer(self.scratch.take_bytes());
         encoder.write_header(protocol::TYPE_STATE_DELTA, 0);
         encoder.write_i64(now);
         encoder.write_u32(state_seq);
+        encoder.write_u16(timing.tick_ms);
+        encoder.write_u16(timing.render_delay_ms);
+        encoder.write_u32(timing.prev_state_seq);
+        encoder.write_u16(timing.prev_interval_ms);
         encoder.write_u16(total_players as u16);
         encoder.write_u16(session.latest_applied_input_seq);
-        encoder.write_u8(if keyframe { DELTA_FRAME_KEYFRAME } else { 0 });
+        let mut frame_flags = 0;
+        if keyframe {
+            frame_flags |= DELTA_FRAME_KEYFRAME;
+        }
+        if session_best.is_some() {
+            frame_flags |= DELTA_FRAME_SESSION_BEST;
+        }
+        if respawn_at.is_some() {
+            frame_flags |= DELTA_FRAME_RESPAWN_AT;
+        }
+        encoder.write_u8(frame_flags);
         encoder.write_u16(visible_player_count as u16);
 
-        let mut next_cache: HashMap<u16, DeltaPlayerCache> =
-            HashMap::with_capacity(visible_player_count);
+        let mut next_cache = self.scratch.take_delta_cache();
+        next_cache.reserve(visible_player_count);
         for (net_id, current) in current_players {
             let previous = if keyframe {
                 None
@@ -2894,9 +5101,18 @@ impl RoomState {
 
             next_cache.insert(net_id, current);
         }
+        if let Some(session_best) = session_best {
+            encoder.write_var_i32(session_best);
+        }
+        if let Some(respawn_at) = respawn_at {
+            encoder.write_i64(respawn_at);
+        }
 
-        session.delta_player_cache = next_cache;
-        Some(encoder.into_vec())
+        let previous_cache = std::mem::replace(&mut session.delta_player_cache, next_cache);
+        self.scratch.put_delta_cache(previous_cache);
+        let payload = encoder.as_bytes().to_vec();
+        self.scratch.put_bytes(encoder.into_vec());
+        Some(payload)
     }
 
     fn build_player_meta_payload(&self, player_ids: &[String]) -> Option<Vec<u8>> {
@@ -3024,10 +5240,9 @@ impl RoomState {
             flags,
             score: player.score.clamp(i32::MIN as i64, i32::MAX as i64) as i32,
             score_fraction_q: Self::quantize_unit_u8(Self::player_score_fraction(player)),
-            oxygen_q: Self::quantize_unit_u8(clamp(player.oxygen, 0.0, 1.0)),
-            girth_q: Self::quantize_girth_scale_u8(Self::player_girth_scale_from_len(
-                player.snake.len(),
-            )),
+            oxygen_q: Self::quantize_unit_u8(self.oxygen_config.fraction(player.oxygen)),
+            girth_q: self
+                .quantize_girth_scale_u8(self.player_girth_scale_from_len(player.snake.len())),
             tail_ext_q: Self::quantize_unit_u16(clamp(player.tail_extension, 0.0, 1.0)),
             tail_tip_oct: compute_tail_tip_point(&player.snake, player.tail_extension)
                 .map(Self::encode_unit_vec_oct_i16)
@@ -3097,9 +5312,10 @@ impl RoomState {
         (t * u8::MAX as f64).round() as u8
     }
 
-    fn quantize_girth_scale_u8(scale: f64) -> u8 {
-        // `player_girth_scale_from_len` is clamped to `[1..=SNAKE_GIRTH_MAX_SCALE]`.
-        let denom = (SNAKE_GIRTH_MAX_SCALE - 1.0).max(1e-6);
+    fn quantize_girth_scale_u8(&self, scale: f64) -> u8 {
+        // `player_girth_scale_from_len` is clamped to `[1..=girth_max_scale]`; clients learn the
+        // same bound from the `TYPE_INIT` trailer.
+        let denom = (self.growth_config.girth_max_scale - 1.0).max(1e-6);
         Self::quantize_unit_u8((scale - 1.0) / denom)
     }
 
@@ -3124,7 +5340,7 @@ impl RoomState {
             return (0, 0);
         }
         let l1 = normalized.x.abs() + normalized.y.abs() + normalized.z.abs();
-        if !(l1 > 1e-9) {
+        if l1.is_nan() || l1 <= 1e-9 {
             return (0, 0);
         }
         let mut x = normalized.x / l1;
@@ -3156,9 +5372,11 @@ impl RoomState {
         encoder.write_u8(flags);
         encoder.write_i32(player.score as i32);
         encoder.write_u16(Self::quantize_unit_u16(Self::player_score_fraction(player)));
-        encoder.write_u16(Self::quantize_unit_u16(clamp(player.oxygen, 0.0, 1.0)));
-        let girth_scale = Self::player_girth_scale_from_len(player.snake.len());
-        encoder.write_u8(Self::quantize_girth_scale_u8(girth_scale));
+        encoder.write_u16(Self::quantize_unit_u16(
+            self.oxygen_config.fraction(player.oxygen),
+        ));
+        let girth_scale = self.player_girth_scale_from_len(player.snake.len());
+        encoder.write_u8(self.quantize_girth_scale_u8(girth_scale));
         encoder.write_u16(Self::quantize_unit_u16(clamp(
             player.tail_extension,
             0.0,
@@ -3242,17 +5460,86 @@ impl RoomState {
     }
 
     fn broadcast_state_delta(&mut self, now: i64, state_seq: u32) {
+        let recorded_session_id = self.select_recorded_session();
         let session_ids: Vec<String> = self.sessions.keys().cloned().collect();
         for session_id in session_ids {
-            let Some(payload) =
-                self.build_state_delta_payload_for_session(now, state_seq, &session_id)
-            else {
+            let wire_format = self
+                .sessions
+                .get(&session_id)
+                .map(|session| session.wire_format)
+                .unwrap_or_default();
+            let recorded = recorded_session_id.as_deref() == Some(session_id.as_str());
+            // Decided before building, which clears the session's forced-keyframe request.
+            let keyframe = recorded
+                && match wire_format {
+                    WireFormat::Binary => self.sessions.get(&session_id).is_some_and(|session| {
+                        Self::is_state_keyframe(session, state_seq, self.keyframe_interval)
+                    }),
+                    WireFormat::Json => true,
+                };
+            let payload = match wire_format {
+                WireFormat::Binary => {
+                    self.build_state_delta_payload_for_session(now, state_seq, &session_id)
+                }
+                WireFormat::Json => {
+                    self.build_state_json_payload_for_session(now, state_seq, &session_id)
+                }
+            };
+            let Some(payload) = payload else {
                 continue;
             };
+            if recorded {
+                self.recording.push(payload.clone(), keyframe);
+            }
             if let Some(session) = self.sessions.get(&session_id) {
                 session.outbound_state.store(payload);
             }
         }
+        self.last_state_frame = Some((state_seq, now));
+    }
+
+    /// Interpolation timing for a state frame built at `now`. The previous frame is the last one
+    /// broadcast; before any frame has gone out it is reported as the frame itself with a zero
+    /// interval.
+    fn state_frame_timing(&self, now: i64, state_seq: u32) -> StateFrameTiming {
+        let tick_ms = TICK_MS.min(u16::MAX as u64) as u16;
+        let (prev_state_seq, prev_now) = self.last_state_frame.unwrap_or((state_seq, now));
+        StateFrameTiming {
+            tick_ms,
+            render_delay_ms: (TICK_MS * STATE_RENDER_DELAY_TICKS).min(u16::MAX as u64) as u16,
+            prev_state_seq,
+            prev_interval_ms: (now - prev_now).clamp(0, u16::MAX as i64) as u16,
+        }
+    }
+
+    /// Whether the session's next binary state frame carries every visible player in full.
+    fn is_state_keyframe(session: &SessionEntry, state_seq: u32, keyframe_interval: u32) -> bool {
+        session.force_next_keyframe
+            || state_seq.is_multiple_of(keyframe_interval)
+            || session.delta_player_cache.is_empty()
+    }
+
+    /// Keeps recording a single session's stream so the export decodes like one client's view.
+    /// When that session goes away, another is picked and forced onto a keyframe; so is the
+    /// current one once the ring buffer has wrapped past its last keyframe.
+    fn select_recorded_session(&mut self) -> Option<String> {
+        if !self.recording.is_enabled() {
+            return None;
+        }
+        if let Some(session_id) = self.recording.session_id() {
+            if let Some(session) = self.sessions.get_mut(session_id) {
+                if self.recording.needs_keyframe() {
+                    session.force_next_keyframe = true;
+                }
+                return Some(session_id.to_string());
+            }
+        }
+        let session_id = self.sessions.keys().min().cloned()?;
+        if let Some(session) = self.sessions.get_mut(&session_id) {
+            session.force_next_keyframe = true;
+        }
+        self.recording.follow_session(session_id.clone());
+        Some(session_id)
     }
 
     fn pellet_needs_update(pellet: &Pellet) -> bool {
@@ -3414,7 +5701,8 @@ impl RoomState {
         let Some((view_center, view_cos, max_visible)) = self.pellet_view_params(session_id) else {
             return;
         };
-        let indices = self.visible_pellet_indices(view_center, view_cos, max_visible);
+        let indices =
+            self.visible_pellet_indices(view_center, view_cos, max_visible, &HashSet::new());
         let state_seq = self.next_state_seq.wrapping_sub(1);
         let payload = self.build_pellet_reset_payload_for_indices(now, state_seq, &indices);
 
@@ -3459,50 +5747,62 @@ impl RoomState {
             else {
                 continue;
             };
-            let indices = self.visible_pellet_indices(view_center, view_cos, max_visible);
+            let Some(session) = self.sessions.get(&session_id) else {
+                continue;
+            };
+            let indices = self.visible_pellet_indices(
+                view_center,
+                view_cos,
+                max_visible,
+                &session.pellet_view_ids,
+            );
 
-            let mut next_ids: HashSet<u32> = HashSet::with_capacity(indices.len());
-            let mut adds: Vec<usize> = Vec::new();
-            let mut updates: Vec<usize> = Vec::new();
+            let mut next_ids = self.scratch.take_pellet_ids();
+            next_ids.reserve(indices.len());
+            let mut delta = self.scratch.take_pellet_delta();
             if let Some(session) = self.sessions.get(&session_id) {
                 for index in &indices {
                     if let Some(pellet) = self.pellets.get(*index) {
                         next_ids.insert(pellet.id);
                         if !session.pellet_view_ids.contains(&pellet.id) {
-                            adds.push(*index);
+                            delta.adds.push(*index);
                         } else if Self::pellet_needs_update(pellet) {
-                            updates.push(*index);
+                            delta.updates.push(*index);
                         }
                     }
                 }
-            }
-
-            let removes: Vec<u32> = self
-                .sessions
-                .get(&session_id)
-                .map(|session| {
+                delta.removes.extend(
                     session
                         .pellet_view_ids
                         .iter()
                         .filter(|id| !next_ids.contains(id))
-                        .copied()
-                        .collect::<Vec<u32>>()
-                })
-                .unwrap_or_default();
+                        .copied(),
+                );
+            }
 
             // Skip sending empty delta frames to reduce bandwidth (no visible-set changes and no
             // active pellet states that require updates).
-            if adds.is_empty() && updates.is_empty() && removes.is_empty() {
+            if delta.adds.is_empty() && delta.updates.is_empty() && delta.removes.is_empty() {
+                self.scratch.put_pellet_ids(next_ids);
+                self.scratch.put_pellet_delta(delta);
                 continue;
             }
 
-            let payload =
-                self.build_pellet_delta_payload(now, state_seq, &adds, &updates, &removes);
+            let payload = self.build_pellet_delta_payload(
+                now,
+                state_seq,
+                &delta.adds,
+                &delta.updates,
+                &delta.removes,
+            );
+            self.scratch.put_pellet_delta(delta);
 
             if let Some(session) = self.sessions.get_mut(&session_id) {
                 match session.outbound_lo.try_send(payload) {
                     Ok(()) => {
-                        session.pellet_view_ids = next_ids;
+                        let previous_ids =
+                            std::mem::replace(&mut session.pellet_view_ids, next_ids);
+                        self.scratch.put_pellet_ids(previous_ids);
                     }
                     Err(mpsc::error::TrySendError::Full(_)) => {
                         // If a client can't keep up with deltas, force a reset once it catches up
@@ -3510,6 +5810,25 @@ impl RoomState {
                         session.pellet_view_initialized = false;
                         session.pellet_view_ids.clear();
                         session.pellet_reset_retry_at = now;
+                        session.pellet_resyncs = session.pellet_resyncs.saturating_add(1);
+                        if now - session.pellet_resync_window_started_at
+                            >= PELLET_RESYNC_SLOW_CLIENT_WINDOW_MS
+                        {
+                            session.pellet_resync_window_started_at = now;
+                            session.pellet_resyncs_in_window = 0;
+                        }
+                        session.pellet_resyncs_in_window += 1;
+                        if session.pellet_resyncs_in_window == PELLET_RESYNC_SLOW_CLIENT_THRESHOLD {
+                            tracing::debug!(
+                                session_id,
+                                resyncs = session.pellet_resyncs_in_window,
+                                window_ms = PELLET_RESYNC_SLOW_CLIENT_WINDOW_MS,
+                                total_resyncs = session.pellet_resyncs,
+                                "session_pellet_resync_repeated"
+                            );
+                        }
+                        self.pellet_resyncs = self.pellet_resyncs.saturating_add(1);
+                        self.scratch.put_pellet_ids(next_ids);
                     }
                     Err(mpsc::error::TrySendError::Closed(_)) => {
                         stale.push(session_id);
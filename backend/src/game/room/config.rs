@@ -0,0 +1,662 @@
+use crate::game::constants::{
+    BASE_PELLET_COUNT, BASE_SPEED, BIG_PELLET_GROWTH_FRACTION, BOOST_MULTIPLIER, BOT_COUNT,
+    BOT_MIN_COUNT, DAY_NIGHT_CYCLE_MS, EVASIVE_PELLET_MIN_DIFFICULTY, EVASIVE_PELLET_SIZE_MAX,
+    EVASIVE_PELLET_SIZE_MIN, MAX_PELLETS, MIN_SURVIVAL_LENGTH, NIGHT_OXYGEN_DRAIN_MULTIPLIER,
+    OXYGEN_DRAIN_PER_SEC, OXYGEN_MAX, PELLET_SIZE_ENCODE_MAX, PELLET_SIZE_ENCODE_MIN,
+    PLAYER_TIMEOUT_MS, SELF_COLLISION_MIN_NODE_GAP, SMALL_PELLET_COLOR_PALETTE,
+    SMALL_PELLET_GROWTH_FRACTION, SMALL_PELLET_SIZE_MAX, SMALL_PELLET_SIZE_MIN,
+    SNAKE_GIRTH_MAX_SCALE, SNAKE_GIRTH_NODES_PER_STEP, SNAKE_GIRTH_STEP_PERCENT, STARTING_LENGTH,
+    TURN_SUBSTEPS_NORMAL,
+};
+use crate::game::math::{clamp, dot, rotate_toward};
+use crate::game::types::Point;
+use std::f64::consts::PI;
+
+const STARTING_LENGTH_LIMIT: usize = 64;
+const GIRTH_MAX_SCALE_LIMIT: f64 = 4.0;
+const EVASIVE_MIN_DIFFICULTY_LIMIT: f64 = 0.1;
+const DEATH_SCATTER_ANGLE_LIMIT: f64 = 0.5;
+const DISCONNECT_TIMEOUT_MIN_MS: i64 = 1_000;
+const DISCONNECT_TIMEOUT_MAX_MS: i64 = 600_000;
+const OXYGEN_MAX_MIN: f64 = 0.1;
+const OXYGEN_MAX_LIMIT: f64 = 10.0;
+const OXYGEN_DRAIN_PER_SEC_MIN: f64 = 0.01;
+const OXYGEN_DRAIN_PER_SEC_LIMIT: f64 = 5.0;
+const BASE_SPEED_MIN_RATIO: f64 = 0.25;
+const BASE_SPEED_MAX_RATIO: f64 = 4.0;
+const BOOST_MULTIPLIER_LIMIT: f64 = 4.0;
+const SELF_COLLISION_GAP_MIN: usize = 4;
+const SELF_COLLISION_GAP_MAX: usize = 512;
+const INPUT_DEAD_ZONE_LIMIT: f64 = 0.2;
+const INPUT_SMOOTHING_LIMIT: f64 = 0.9;
+const VIEW_RADIUS_MARGIN_LIMIT: f64 = 0.6;
+const VIEW_NODE_PADDING_LIMIT: usize = 32;
+/// Palette entries beyond this are dropped to keep the `TYPE_INIT` trailer small.
+pub const MAX_PELLET_PALETTE_COLORS: usize = 32;
+
+/// Controls how many bots a room keeps alongside its human players.
+///
+/// The target shrinks by one bot per connected human, never dropping below
+/// `min_bots` while humans are present and never exceeding `max_bots`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BotConfig {
+    pub max_bots: usize,
+    pub min_bots: usize,
+}
+
+impl Default for BotConfig {
+    fn default() -> Self {
+        Self {
+            max_bots: BOT_COUNT,
+            min_bots: BOT_MIN_COUNT,
+        }
+    }
+}
+
+impl BotConfig {
+    pub fn desired_bots(&self, human_count: usize) -> usize {
+        if human_count == 0 {
+            return 0;
+        }
+        let min_bots = self.min_bots.min(self.max_bots);
+        self.max_bots
+            .saturating_sub(human_count)
+            .clamp(min_bots, self.max_bots)
+    }
+}
+
+/// Slow global day/night cycle. Phase `0.0` is midday and `0.5` is midnight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DayNightConfig {
+    pub cycle_ms: i64,
+    pub night_oxygen_drain_multiplier: f64,
+}
+
+impl Default for DayNightConfig {
+    fn default() -> Self {
+        Self {
+            cycle_ms: DAY_NIGHT_CYCLE_MS,
+            night_oxygen_drain_multiplier: NIGHT_OXYGEN_DRAIN_MULTIPLIER,
+        }
+    }
+}
+
+impl DayNightConfig {
+    pub fn phase_at(&self, now_ms: i64) -> f64 {
+        let cycle_ms = self.cycle_ms.max(1);
+        now_ms.rem_euclid(cycle_ms) as f64 / cycle_ms as f64
+    }
+
+    /// How dark it is at `phase`, from `0.0` at midday to `1.0` at midnight.
+    pub fn darkness(phase: f64) -> f64 {
+        (1.0 - (phase * 2.0 * PI).cos()) * 0.5
+    }
+
+    pub fn oxygen_drain_multiplier(&self, phase: f64) -> f64 {
+        1.0 + (self.night_oxygen_drain_multiplier - 1.0) * Self::darkness(phase)
+    }
+}
+
+/// Breath budget for lake dives. `Player::oxygen` is kept in these units; clients only ever see
+/// it as a fraction of `max`, so raising `max` or lowering `drain_per_sec` buys more time
+/// underwater without a client change.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OxygenConfig {
+    pub max: f64,
+    pub drain_per_sec: f64,
+}
+
+impl Default for OxygenConfig {
+    fn default() -> Self {
+        Self {
+            max: OXYGEN_MAX,
+            drain_per_sec: OXYGEN_DRAIN_PER_SEC,
+        }
+    }
+}
+
+impl OxygenConfig {
+    /// Clamps `max` to `0.1..=10` and `drain_per_sec` to `0.01..=5`; non-finite values fall
+    /// back to the defaults.
+    pub fn sanitized(self) -> Self {
+        let defaults = Self::default();
+        Self {
+            max: if self.max.is_finite() {
+                self.max.clamp(OXYGEN_MAX_MIN, OXYGEN_MAX_LIMIT)
+            } else {
+                defaults.max
+            },
+            drain_per_sec: if self.drain_per_sec.is_finite() {
+                self.drain_per_sec
+                    .clamp(OXYGEN_DRAIN_PER_SEC_MIN, OXYGEN_DRAIN_PER_SEC_LIMIT)
+            } else {
+                defaults.drain_per_sec
+            },
+        }
+    }
+
+    /// `oxygen` as the `0..=1` fill clients draw.
+    pub fn fraction(&self, oxygen: f64) -> f64 {
+        (oxygen / self.max).clamp(0.0, 1.0)
+    }
+}
+
+/// How far snakes travel each tick. `base_speed` is the head's arc in radians per tick at
+/// normal speed and boosting multiplies it by `boost_multiplier`. Both go out in `TYPE_INIT`
+/// so client prediction follows the room's tuning.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MovementConfig {
+    pub base_speed: f64,
+    pub boost_multiplier: f64,
+}
+
+impl Default for MovementConfig {
+    fn default() -> Self {
+        Self {
+            base_speed: BASE_SPEED,
+            boost_multiplier: BOOST_MULTIPLIER,
+        }
+    }
+}
+
+impl MovementConfig {
+    /// Clamps `base_speed` to a quarter through four times the default and `boost_multiplier`
+    /// to `1..=4`; non-finite values fall back to the defaults.
+    pub fn sanitized(self) -> Self {
+        let defaults = Self::default();
+        Self {
+            base_speed: if self.base_speed.is_finite() {
+                self.base_speed.clamp(
+                    BASE_SPEED * BASE_SPEED_MIN_RATIO,
+                    BASE_SPEED * BASE_SPEED_MAX_RATIO,
+                )
+            } else {
+                defaults.base_speed
+            },
+            boost_multiplier: if self.boost_multiplier.is_finite() {
+                self.boost_multiplier.clamp(1.0, BOOST_MULTIPLIER_LIMIT)
+            } else {
+                defaults.boost_multiplier
+            },
+        }
+    }
+
+    pub fn speed_factor(&self, is_boosting: bool) -> f64 {
+        if is_boosting {
+            self.boost_multiplier
+        } else {
+            1.0
+        }
+    }
+
+    /// Movement substeps for one tick at `speed_factor`. Body nodes trail the head by a fixed
+    /// number of substeps, so the count grows with the distance covered to keep each substep
+    /// (and the node spacing) close to a default normal-speed one. Fractional ratios round to
+    /// the nearest count: the default 2.16x boost takes 4 substeps to normal speed's 2.
+    pub fn substep_count(&self, speed_factor: f64) -> usize {
+        let distance_ratio = self.base_speed * speed_factor / BASE_SPEED;
+        ((TURN_SUBSTEPS_NORMAL as f64 * distance_ratio).round() as usize).max(1)
+    }
+}
+
+/// Hardcore rule: a head touching its own body dies like it would on another snake's body.
+///
+/// The first `min_node_gap` nodes behind the head are never tested, since the neck always
+/// overlaps the head and a tight turn bunches the nodes just behind it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelfCollisionConfig {
+    pub enabled: bool,
+    pub min_node_gap: usize,
+}
+
+impl Default for SelfCollisionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_node_gap: SELF_COLLISION_MIN_NODE_GAP,
+        }
+    }
+}
+
+impl SelfCollisionConfig {
+    /// Clamps `min_node_gap` to `4..=512`.
+    pub fn sanitized(self) -> Self {
+        Self {
+            enabled: self.enabled,
+            min_node_gap: self
+                .min_node_gap
+                .clamp(SELF_COLLISION_GAP_MIN, SELF_COLLISION_GAP_MAX),
+        }
+    }
+}
+
+/// Filtering applied to a human player's requested steering axis, for analog and touch clients
+/// whose stick jitters around a held direction. Both default to `0.0` (off), which keyboard
+/// and mouse clients want.
+///
+/// A requested axis within `dead_zone` radians of the current target is ignored. Otherwise the
+/// target moves toward it, keeping `smoothing` of the angle between them for later inputs.
+/// The dead zone compares against the current target, so a slow sweep moves it in steps of
+/// about `dead_zone`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InputConfig {
+    pub dead_zone: f64,
+    pub smoothing: f64,
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        Self {
+            dead_zone: 0.0,
+            smoothing: 0.0,
+        }
+    }
+}
+
+impl InputConfig {
+    /// Clamps `dead_zone` to `0..=0.2` radians and `smoothing` to `0..=0.9`; non-finite values
+    /// turn the filter off.
+    pub fn sanitized(self) -> Self {
+        let limit = |value: f64, max: f64| {
+            if value.is_finite() {
+                value.clamp(0.0, max)
+            } else {
+                0.0
+            }
+        };
+        Self {
+            dead_zone: limit(self.dead_zone, INPUT_DEAD_ZONE_LIMIT),
+            smoothing: limit(self.smoothing, INPUT_SMOOTHING_LIMIT),
+        }
+    }
+
+    /// The target axis after a request for `requested` while steering toward `current`. Both
+    /// are unit vectors.
+    pub fn steer(&self, current: Point, requested: Point) -> Point {
+        if self.dead_zone <= 0.0 && self.smoothing <= 0.0 {
+            return requested;
+        }
+        let angle = clamp(dot(current, requested), -1.0, 1.0).acos();
+        if angle <= self.dead_zone {
+            return current;
+        }
+        if self.smoothing <= 0.0 {
+            return requested;
+        }
+        rotate_toward(current, requested, angle * (1.0 - self.smoothing))
+    }
+}
+
+/// How far past a session's view radius remote snakes are still sent. `radius_margin` widens
+/// the cull cone in radians and `node_padding` extends each visible run of nodes on both ends,
+/// so a snake crossing the screen edge arrives before it is drawn. Larger values cost
+/// bandwidth.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewConfig {
+    pub radius_margin: f64,
+    pub node_padding: usize,
+}
+
+impl Default for ViewConfig {
+    fn default() -> Self {
+        Self {
+            radius_margin: 0.18,
+            node_padding: 5,
+        }
+    }
+}
+
+impl ViewConfig {
+    /// Clamps `radius_margin` to `0..=0.6` radians (non-finite falls back to the default) and
+    /// `node_padding` to at most 32.
+    pub fn sanitized(self) -> Self {
+        let radius_margin = if self.radius_margin.is_finite() {
+            self.radius_margin.clamp(0.0, VIEW_RADIUS_MARGIN_LIMIT)
+        } else {
+            Self::default().radius_margin
+        };
+        Self {
+            radius_margin,
+            node_padding: self.node_padding.min(VIEW_NODE_PADDING_LIMIT),
+        }
+    }
+}
+
+/// How a session's pellet budget is filled when more pellets are in view than it allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PelletVisibility {
+    /// Lowest ids first. Cheapest, and the set only changes when pellets enter, leave or die.
+    #[default]
+    LowestId,
+    /// Closest to the view center first. Pellets already shown get a small head start so ones
+    /// sitting near the cutoff don't flip in and out every frame.
+    Nearest,
+}
+
+impl PelletVisibility {
+    /// Parses `lowest-id` or `nearest`, as used by `ROOM_PELLET_VISIBILITY`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "lowest-id" | "lowest_id" => Some(Self::LowestId),
+            "nearest" => Some(Self::Nearest),
+            _ => None,
+        }
+    }
+}
+
+/// Colors regular pellets are drawn from.
+///
+/// Each pellet already carries its own RGB on the wire, so the palette only themes newly
+/// spawned pellets; clients learn it from the `TYPE_INIT` trailer for legends and effects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PelletPalette {
+    colors: Vec<[u8; 3]>,
+}
+
+impl Default for PelletPalette {
+    fn default() -> Self {
+        Self {
+            colors: SMALL_PELLET_COLOR_PALETTE.to_vec(),
+        }
+    }
+}
+
+impl PelletPalette {
+    /// Keeps at most `MAX_PELLET_PALETTE_COLORS` colors; an empty list falls back to the
+    /// default palette.
+    pub fn new(mut colors: Vec<[u8; 3]>) -> Self {
+        colors.truncate(MAX_PELLET_PALETTE_COLORS);
+        if colors.is_empty() {
+            return Self::default();
+        }
+        Self { colors }
+    }
+
+    /// Parses a comma-separated `#rrggbb` list. Any malformed entry rejects the whole list.
+    pub fn parse(value: &str) -> Option<Self> {
+        let colors = value
+            .split(',')
+            .map(|entry| parse_hex_rgb(entry.trim()))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self::new(colors))
+    }
+
+    pub fn colors(&self) -> &[[u8; 3]] {
+        &self.colors
+    }
+}
+
+pub(super) fn parse_hex_rgb(value: &str) -> Option<[u8; 3]> {
+    let hex = value.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let r = u8::from_str_radix(&hex[0..2], 16).ok()?;
+    let g = u8::from_str_radix(&hex[2..4], 16).ok()?;
+    let b = u8::from_str_radix(&hex[4..6], 16).ok()?;
+    Some([r, g, b])
+}
+
+const OUTBOUND_CAPACITY_MIN: usize = 4;
+const OUTBOUND_CAPACITY_MAX: usize = 256;
+
+/// What a session's high-priority queue does when a frame arrives and it is already full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutboundFullPolicy {
+    /// Report the queue as full; callers drop the socket for frames the client can't miss.
+    #[default]
+    Disconnect,
+    /// Evict the oldest advisory frame (chat, hints, consume hints, day phase) to make room.
+    /// Init, error, migrate, announce, player-meta and pellet-reset frames are never evicted; if
+    /// only those are queued the queue still reports full.
+    DropOldest,
+}
+
+impl OutboundFullPolicy {
+    /// Parses `disconnect` or `drop-oldest`, as used by `ROOM_OUTBOUND_HI_POLICY`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "disconnect" => Some(Self::Disconnect),
+            "drop-oldest" | "drop_oldest" => Some(Self::DropOldest),
+            _ => None,
+        }
+    }
+}
+
+/// Per-session outbound queue depths. A full `lo_capacity` queue makes the session resync its
+/// pellet view; a full `hi_capacity` queue is handled by `hi_full_policy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutboundConfig {
+    pub hi_capacity: usize,
+    pub lo_capacity: usize,
+    pub hi_full_policy: OutboundFullPolicy,
+}
+
+impl Default for OutboundConfig {
+    fn default() -> Self {
+        Self {
+            hi_capacity: 16,
+            lo_capacity: 16,
+            hi_full_policy: OutboundFullPolicy::Disconnect,
+        }
+    }
+}
+
+impl OutboundConfig {
+    /// Clamps both depths to `4..=256`.
+    pub fn sanitized(self) -> Self {
+        Self {
+            hi_capacity: self
+                .hi_capacity
+                .clamp(OUTBOUND_CAPACITY_MIN, OUTBOUND_CAPACITY_MAX),
+            lo_capacity: self
+                .lo_capacity
+                .clamp(OUTBOUND_CAPACITY_MIN, OUTBOUND_CAPACITY_MAX),
+            hi_full_policy: self.hi_full_policy,
+        }
+    }
+}
+
+/// Pellet density, growth, and size tuning for a room.
+///
+/// Growth fractions are in snake nodes per pellet; score is still counted in default big
+/// pellets, so raising them makes every pellet worth more score as well as length.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PelletConfig {
+    pub base_count: usize,
+    /// Hard cap on live pellets; older pellets are dropped first when a spawn overshoots it.
+    /// Never above the `u16` pellet count in frame headers.
+    pub max_count: usize,
+    pub small_growth_fraction: f64,
+    pub big_growth_fraction: f64,
+    pub small_size_min: f32,
+    pub small_size_max: f32,
+    pub evasive_size_min: f32,
+    pub evasive_size_max: f32,
+    /// Evade speed and zigzag multiplier for an owner who never catches their evasive pellets.
+    /// Owners who always catch them get the full `1.0`; set this to `1.0` to disable scaling.
+    pub evasive_min_difficulty: f64,
+    /// Max angle (radians) each death pellet is pushed off its body node, spreading a long
+    /// snake's remains into a field instead of a line. `0.0` drops them on the nodes.
+    pub death_scatter_angle: f64,
+}
+
+impl Default for PelletConfig {
+    fn default() -> Self {
+        Self {
+            base_count: BASE_PELLET_COUNT,
+            max_count: MAX_PELLETS,
+            small_growth_fraction: SMALL_PELLET_GROWTH_FRACTION,
+            big_growth_fraction: BIG_PELLET_GROWTH_FRACTION,
+            small_size_min: SMALL_PELLET_SIZE_MIN,
+            small_size_max: SMALL_PELLET_SIZE_MAX,
+            evasive_size_min: EVASIVE_PELLET_SIZE_MIN,
+            evasive_size_max: EVASIVE_PELLET_SIZE_MAX,
+            evasive_min_difficulty: EVASIVE_PELLET_MIN_DIFFICULTY,
+            death_scatter_angle: 0.0,
+        }
+    }
+}
+
+impl PelletConfig {
+    /// Clamps values to what the simulation and wire encoding can represent.
+    pub fn sanitized(self) -> Self {
+        let (small_size_min, small_size_max) =
+            Self::size_range(self.small_size_min, self.small_size_max);
+        let (evasive_size_min, evasive_size_max) =
+            Self::size_range(self.evasive_size_min, self.evasive_size_max);
+        Self {
+            base_count: self.base_count.max(1),
+            max_count: self.max_count.clamp(1, MAX_PELLETS),
+            small_growth_fraction: Self::growth_fraction(self.small_growth_fraction),
+            big_growth_fraction: Self::growth_fraction(self.big_growth_fraction),
+            small_size_min,
+            small_size_max,
+            evasive_size_min,
+            evasive_size_max,
+            evasive_min_difficulty: if self.evasive_min_difficulty.is_finite() {
+                self.evasive_min_difficulty
+                    .clamp(EVASIVE_MIN_DIFFICULTY_LIMIT, 1.0)
+            } else {
+                EVASIVE_PELLET_MIN_DIFFICULTY
+            },
+            death_scatter_angle: if self.death_scatter_angle.is_finite() {
+                self.death_scatter_angle
+                    .clamp(0.0, DEATH_SCATTER_ANGLE_LIMIT)
+            } else {
+                0.0
+            },
+        }
+    }
+
+    fn growth_fraction(value: f64) -> f64 {
+        if value.is_finite() {
+            value.max(0.0)
+        } else {
+            0.0
+        }
+    }
+
+    fn size_range(min: f32, max: f32) -> (f32, f32) {
+        let clamp = |value: f32| {
+            if value.is_finite() {
+                value.clamp(PELLET_SIZE_ENCODE_MIN, PELLET_SIZE_ENCODE_MAX)
+            } else {
+                PELLET_SIZE_ENCODE_MIN
+            }
+        };
+        let (min, max) = (clamp(min), clamp(max));
+        (min.min(max), min.max(max))
+    }
+}
+
+/// Snake length at spawn, the floor it can't shrink below, and how body girth grows with
+/// length.
+///
+/// Boost drain and drowning stop removing nodes at `min_survival_length`; it never exceeds
+/// `starting_length`. Girth starts at `1.0` for `starting_length` nodes and adds
+/// `girth_step_percent` for every `girth_nodes_per_step` nodes beyond it, capped at
+/// `girth_max_scale`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GrowthConfig {
+    pub starting_length: usize,
+    pub min_survival_length: usize,
+    pub girth_step_percent: f64,
+    pub girth_nodes_per_step: usize,
+    pub girth_max_scale: f64,
+}
+
+impl Default for GrowthConfig {
+    fn default() -> Self {
+        Self {
+            starting_length: STARTING_LENGTH,
+            min_survival_length: MIN_SURVIVAL_LENGTH,
+            girth_step_percent: SNAKE_GIRTH_STEP_PERCENT,
+            girth_nodes_per_step: SNAKE_GIRTH_NODES_PER_STEP,
+            girth_max_scale: SNAKE_GIRTH_MAX_SCALE,
+        }
+    }
+}
+
+impl GrowthConfig {
+    /// Clamps values to what spawning, collisions and the girth encoding can handle.
+    pub fn sanitized(self) -> Self {
+        let defaults = Self::default();
+        let starting_length = self
+            .starting_length
+            .clamp(MIN_SURVIVAL_LENGTH, STARTING_LENGTH_LIMIT);
+        Self {
+            starting_length,
+            min_survival_length: self
+                .min_survival_length
+                .clamp(MIN_SURVIVAL_LENGTH, starting_length),
+            girth_step_percent: if self.girth_step_percent.is_finite() {
+                self.girth_step_percent.max(0.0)
+            } else {
+                defaults.girth_step_percent
+            },
+            girth_nodes_per_step: self.girth_nodes_per_step.max(1),
+            girth_max_scale: if self.girth_max_scale.is_finite() {
+                self.girth_max_scale.clamp(1.0, GIRTH_MAX_SCALE_LIMIT)
+            } else {
+                defaults.girth_max_scale
+            },
+        }
+    }
+
+    pub fn girth_scale_for_len(&self, snake_len: usize) -> f64 {
+        let added_nodes = snake_len.saturating_sub(self.starting_length);
+        let growth_per_node = self.girth_step_percent / self.girth_nodes_per_step.max(1) as f64;
+        let uncapped = 1.0 + added_nodes as f64 * growth_per_node;
+        uncapped.clamp(1.0, self.girth_max_scale.max(1.0))
+    }
+}
+
+/// How long a disconnected player's snake stays in the world waiting for a reconnect.
+///
+/// Snakes of at least `large_snake_length` nodes use the (usually shorter)
+/// `large_snake_timeout_ms`, since a large absent snake blocks more space and spawns.
+/// With `freeze_on_disconnect`, absent snakes stop moving instead of coasting on their last
+/// heading; they still collide and can be killed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisconnectConfig {
+    pub timeout_ms: i64,
+    pub large_snake_length: Option<usize>,
+    pub large_snake_timeout_ms: i64,
+    pub freeze_on_disconnect: bool,
+}
+
+impl Default for DisconnectConfig {
+    fn default() -> Self {
+        Self {
+            timeout_ms: PLAYER_TIMEOUT_MS,
+            large_snake_length: None,
+            large_snake_timeout_ms: PLAYER_TIMEOUT_MS,
+            freeze_on_disconnect: false,
+        }
+    }
+}
+
+impl DisconnectConfig {
+    /// Clamps timeouts to a sane range; the large-snake timeout never exceeds the base one.
+    pub fn sanitized(self) -> Self {
+        let timeout_ms = self
+            .timeout_ms
+            .clamp(DISCONNECT_TIMEOUT_MIN_MS, DISCONNECT_TIMEOUT_MAX_MS);
+        Self {
+            timeout_ms,
+            large_snake_length: self.large_snake_length.filter(|length| *length > 0),
+            large_snake_timeout_ms: self
+                .large_snake_timeout_ms
+                .clamp(DISCONNECT_TIMEOUT_MIN_MS, timeout_ms),
+            freeze_on_disconnect: self.freeze_on_disconnect,
+        }
+    }
+
+    pub fn timeout_for_len(&self, snake_len: usize) -> i64 {
+        match self.large_snake_length {
+            Some(threshold) if snake_len >= threshold => self.large_snake_timeout_ms,
+            _ => self.timeout_ms,
+        }
+    }
+}
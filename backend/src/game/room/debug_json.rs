@@ -0,0 +1,124 @@
+use super::*;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+struct JsonStateFrame<'a> {
+    #[serde(rename = "type")]
+    message_type: &'static str,
+    now: i64,
+    seq: u32,
+    #[serde(rename = "tickMs")]
+    tick_ms: u16,
+    #[serde(rename = "renderDelayMs")]
+    render_delay_ms: u16,
+    #[serde(rename = "prevSeq")]
+    prev_seq: u32,
+    #[serde(rename = "prevIntervalMs")]
+    prev_interval_ms: u16,
+    #[serde(rename = "totalPlayers")]
+    total_players: usize,
+    #[serde(rename = "ackInputSeq")]
+    ack_input_seq: u16,
+    players: Vec<JsonPlayerState<'a>>,
+    pellets: Vec<JsonPellet>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonPlayerState<'a> {
+    id: &'a str,
+    #[serde(rename = "netId")]
+    net_id: u16,
+    name: &'a str,
+    alive: bool,
+    #[serde(rename = "isBoosting")]
+    is_boosting: bool,
+    score: i64,
+    oxygen: f64,
+    #[serde(rename = "snakeTotalLen")]
+    snake_total_len: usize,
+    #[serde(rename = "snakeStart")]
+    snake_start: usize,
+    snake: Vec<[f64; 3]>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonPellet {
+    id: u32,
+    normal: [f64; 3],
+    size: f32,
+}
+
+impl RoomState {
+    /// Debug-only JSON rendering of what a binary state frame would carry for this session: the
+    /// same view-scoped players plus the pellets currently in view.
+    pub(super) fn build_state_json_payload_for_session(
+        &self,
+        now: i64,
+        state_seq: u32,
+        session_id: &str,
+    ) -> Option<Vec<u8>> {
+        let session = self.sessions.get(session_id)?;
+        let players = self
+            .visible_players_for_session(session_id)
+            .into_iter()
+            .map(|visible| {
+                let player = visible.player;
+                let start = visible.window.start.min(player.snake.len());
+                let end = (start + visible.window.len).min(player.snake.len());
+                JsonPlayerState {
+                    id: &player.id,
+                    net_id: player.net_id,
+                    name: &player.name,
+                    alive: player.alive,
+                    is_boosting: player.is_boosting,
+                    score: player.score,
+                    oxygen: player.oxygen,
+                    snake_total_len: player.snake.len(),
+                    snake_start: start,
+                    snake: player.snake[start..end]
+                        .iter()
+                        .map(|node| [node.x, node.y, node.z])
+                        .collect(),
+                }
+            })
+            .collect();
+        let pellets = self
+            .pellet_view_params(session_id)
+            .map(|(center, view_cos, max_visible)| {
+                let shown = self
+                    .sessions
+                    .get(session_id)
+                    .map(|session| &session.pellet_view_ids);
+                self.visible_pellet_indices(
+                    center,
+                    view_cos,
+                    max_visible,
+                    shown.unwrap_or(&HashSet::new()),
+                )
+            })
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|index| self.pellets.get(index))
+            .map(|pellet| JsonPellet {
+                id: pellet.id,
+                normal: [pellet.normal.x, pellet.normal.y, pellet.normal.z],
+                size: pellet.current_size,
+            })
+            .collect();
+        let timing = self.state_frame_timing(now, state_seq);
+        let frame = JsonStateFrame {
+            message_type: "state",
+            now,
+            seq: state_seq,
+            tick_ms: timing.tick_ms,
+            render_delay_ms: timing.render_delay_ms,
+            prev_seq: timing.prev_state_seq,
+            prev_interval_ms: timing.prev_interval_ms,
+            total_players: self.players.len(),
+            ack_input_seq: session.latest_applied_input_seq,
+            players,
+            pellets,
+        };
+        serde_json::to_vec(&frame).ok()
+    }
+}
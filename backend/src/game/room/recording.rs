@@ -0,0 +1,89 @@
+use std::collections::VecDeque;
+
+/// Ring buffer of raw state frames captured for one session while recording is enabled.
+///
+/// Frames are stored exactly as they were sent so tools can replay them through the normal client
+/// decoder.
+#[derive(Debug)]
+pub(crate) struct RecordingBuffer {
+    capacity: usize,
+    enabled: bool,
+    session_id: Option<String>,
+    frames: VecDeque<RecordedFrame>,
+}
+
+#[derive(Debug)]
+struct RecordedFrame {
+    payload: Vec<u8>,
+    /// Decodes without the frames before it.
+    keyframe: bool,
+}
+
+impl RecordingBuffer {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            enabled: false,
+            session_id: None,
+            frames: VecDeque::new(),
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        if enabled && !self.enabled {
+            self.frames.clear();
+            self.session_id = None;
+        }
+        self.enabled = enabled;
+    }
+
+    pub(crate) fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+
+    pub(crate) fn follow_session(&mut self, session_id: String) {
+        self.session_id = Some(session_id);
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// True when the next push would leave the ring without a keyframe, so the room should force
+    /// one for the recorded session to keep the export replayable.
+    pub(crate) fn needs_keyframe(&self) -> bool {
+        let evicted = (self.frames.len() + 1).saturating_sub(self.capacity);
+        self.enabled && !self.frames.iter().skip(evicted).any(|frame| frame.keyframe)
+    }
+
+    pub(crate) fn push(&mut self, frame: Vec<u8>, keyframe: bool) {
+        if !self.enabled {
+            return;
+        }
+        while self.frames.len() >= self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(RecordedFrame {
+            payload: frame,
+            keyframe,
+        });
+    }
+
+    /// Concatenates buffered frames oldest-first, each prefixed with its byte length as a
+    /// little-endian `u32`. Deltas older than the oldest retained keyframe are left out, since
+    /// a decoder can't apply them once the ring has wrapped past their base.
+    pub(crate) fn export(&self) -> Vec<u8> {
+        let frames = self.frames.iter().skip_while(|frame| !frame.keyframe);
+        let total: usize = frames.clone().map(|frame| 4 + frame.payload.len()).sum();
+        let mut out = Vec::with_capacity(total);
+        for frame in frames {
+            out.extend_from_slice(&(frame.payload.len() as u32).to_le_bytes());
+            out.extend_from_slice(&frame.payload);
+        }
+        out
+    }
+}
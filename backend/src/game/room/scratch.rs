@@ -0,0 +1,93 @@
+use super::DeltaPlayerCache;
+use std::collections::{HashMap, HashSet};
+
+/// Buffers above this many bytes are dropped instead of kept, so one huge keyframe doesn't pin
+/// its allocation for the rest of the room's life.
+const MAX_RETAINED_BYTES: usize = 64 * 1024;
+/// Same cap for the pellet and player working sets, in entries.
+const MAX_RETAINED_ENTRIES: usize = 4096;
+
+/// Working buffers reused across sessions and ticks by the state and pellet broadcasts.
+///
+/// Everything is handed out empty and handed back after use, so steady-state ticks stop
+/// allocating per-session scratch space. Payloads sent to sessions are still owned `Vec`s; the
+/// state encoder writes into `bytes` and copies the finished frame out at its exact size.
+#[derive(Debug, Default)]
+pub(super) struct TickScratch {
+    bytes: Vec<u8>,
+    delta_cache: HashMap<u16, DeltaPlayerCache>,
+    pellet_ids: HashSet<u32>,
+    pellet_adds: Vec<usize>,
+    pellet_updates: Vec<usize>,
+    pellet_removes: Vec<u32>,
+}
+
+/// Index lists a pellet delta is built from, borrowed from [`TickScratch`] for one session.
+#[derive(Debug, Default)]
+pub(super) struct PelletDeltaScratch {
+    pub(super) adds: Vec<usize>,
+    pub(super) updates: Vec<usize>,
+    pub(super) removes: Vec<u32>,
+}
+
+impl TickScratch {
+    pub(super) fn take_bytes(&mut self) -> Vec<u8> {
+        let mut bytes = std::mem::take(&mut self.bytes);
+        bytes.clear();
+        bytes
+    }
+
+    pub(super) fn put_bytes(&mut self, bytes: Vec<u8>) {
+        if bytes.capacity() <= MAX_RETAINED_BYTES {
+            self.bytes = bytes;
+        }
+    }
+
+    pub(super) fn take_delta_cache(&mut self) -> HashMap<u16, DeltaPlayerCache> {
+        let mut cache = std::mem::take(&mut self.delta_cache);
+        cache.clear();
+        cache
+    }
+
+    pub(super) fn put_delta_cache(&mut self, cache: HashMap<u16, DeltaPlayerCache>) {
+        if cache.capacity() <= MAX_RETAINED_ENTRIES {
+            self.delta_cache = cache;
+        }
+    }
+
+    pub(super) fn take_pellet_ids(&mut self) -> HashSet<u32> {
+        let mut ids = std::mem::take(&mut self.pellet_ids);
+        ids.clear();
+        ids
+    }
+
+    pub(super) fn put_pellet_ids(&mut self, ids: HashSet<u32>) {
+        if ids.capacity() <= MAX_RETAINED_ENTRIES {
+            self.pellet_ids = ids;
+        }
+    }
+
+    pub(super) fn take_pellet_delta(&mut self) -> PelletDeltaScratch {
+        let mut delta = PelletDeltaScratch {
+            adds: std::mem::take(&mut self.pellet_adds),
+            updates: std::mem::take(&mut self.pellet_updates),
+            removes: std::mem::take(&mut self.pellet_removes),
+        };
+        delta.adds.clear();
+        delta.updates.clear();
+        delta.removes.clear();
+        delta
+    }
+
+    pub(super) fn put_pellet_delta(&mut self, delta: PelletDeltaScratch) {
+        if delta.adds.capacity() <= MAX_RETAINED_ENTRIES {
+            self.pellet_adds = delta.adds;
+        }
+        if delta.updates.capacity() <= MAX_RETAINED_ENTRIES {
+            self.pellet_updates = delta.updates;
+        }
+        if delta.removes.capacity() <= MAX_RETAINED_ENTRIES {
+            self.pellet_removes = delta.removes;
+        }
+    }
+}
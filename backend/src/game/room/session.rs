@@ -1,11 +1,33 @@
+use super::config::OutboundFullPolicy;
 use crate::app::time::now_millis;
 use crate::game::input::parse_axis;
-use crate::game::math::clamp;
+use crate::game::math::{clamp, dot};
 use crate::game::types::Point;
+use crate::protocol;
+use std::collections::VecDeque;
 use std::sync::Arc;
 use std::sync::Mutex as StdMutex;
+use tokio::sync::mpsc::error::{TryRecvError, TrySendError};
 use tokio::sync::{mpsc, Notify};
 
+/// Encoding used for a session's state stream. `Json` is a debug-only, much higher-bandwidth
+/// mode for reading frames in browser devtools; every other message stays binary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WireFormat {
+    #[default]
+    Binary,
+    Json,
+}
+
+impl WireFormat {
+    pub fn from_query(value: Option<&str>) -> Self {
+        match value.map(str::trim) {
+            Some(value) if value.eq_ignore_ascii_case("json") => WireFormat::Json,
+            _ => WireFormat::Binary,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct LatestFrame {
     frame: StdMutex<Option<Vec<u8>>>,
@@ -34,15 +56,182 @@ impl LatestFrame {
     }
 }
 
+/// Frames a full high-priority queue may evict under `OutboundFullPolicy::DropOldest`; a lost
+/// pong only costs the client one latency sample. Losing anything else (init, error, migrate,
+/// announce, player meta, pellet reset) would leave the client unable to follow the stream.
+fn is_droppable_frame(payload: &[u8]) -> bool {
+    matches!(
+        payload.get(1).copied(),
+        Some(
+            protocol::TYPE_PELLET_CONSUME
+                | protocol::TYPE_WORLD
+                | protocol::TYPE_CHAT_MESSAGE
+                | protocol::TYPE_HINT
+                | protocol::TYPE_PONG
+        )
+    )
+}
+
+#[derive(Debug)]
+struct OutboundShared {
+    queue: StdMutex<OutboundQueueState>,
+    notify: Notify,
+    capacity: usize,
+    policy: OutboundFullPolicy,
+}
+
+#[derive(Debug)]
+struct OutboundQueueState {
+    frames: VecDeque<Vec<u8>>,
+    senders: usize,
+    receiver_closed: bool,
+}
+
+/// Bounded high-priority queue for one session. Unlike an `mpsc` channel, a full queue can
+/// evict an older advisory frame, depending on its `OutboundFullPolicy`.
+pub(crate) fn outbound_queue(
+    capacity: usize,
+    policy: OutboundFullPolicy,
+) -> (OutboundSender, OutboundReceiver) {
+    let shared = Arc::new(OutboundShared {
+        queue: StdMutex::new(OutboundQueueState {
+            frames: VecDeque::with_capacity(capacity),
+            senders: 1,
+            receiver_closed: false,
+        }),
+        notify: Notify::new(),
+        capacity: capacity.max(1),
+        policy,
+    });
+    (
+        OutboundSender {
+            shared: Arc::clone(&shared),
+        },
+        OutboundReceiver { shared },
+    )
+}
+
+#[derive(Debug)]
+pub(crate) struct OutboundSender {
+    shared: Arc<OutboundShared>,
+}
+
+impl OutboundSender {
+    pub(crate) fn try_send(&self, payload: Vec<u8>) -> Result<(), TrySendError<Vec<u8>>> {
+        let mut queue = self.shared.queue.lock().unwrap();
+        if queue.receiver_closed {
+            return Err(TrySendError::Closed(payload));
+        }
+        if queue.frames.len() >= self.shared.capacity {
+            let evict = match self.shared.policy {
+                OutboundFullPolicy::Disconnect => None,
+                OutboundFullPolicy::DropOldest => queue
+                    .frames
+                    .iter()
+                    .position(|frame| is_droppable_frame(frame)),
+            };
+            let Some(index) = evict else {
+                return Err(TrySendError::Full(payload));
+            };
+            queue.frames.remove(index);
+        }
+        queue.frames.push_back(payload);
+        drop(queue);
+        self.shared.notify.notify_one();
+        Ok(())
+    }
+}
+
+impl Clone for OutboundSender {
+    fn clone(&self) -> Self {
+        self.shared.queue.lock().unwrap().senders += 1;
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl Drop for OutboundSender {
+    fn drop(&mut self) {
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.senders -= 1;
+        if queue.senders == 0 {
+            drop(queue);
+            self.shared.notify.notify_one();
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct OutboundReceiver {
+    shared: Arc<OutboundShared>,
+}
+
+impl OutboundReceiver {
+    /// Next queued frame, or `None` once every sender is gone and the queue is drained.
+    pub(crate) async fn recv(&mut self) -> Option<Vec<u8>> {
+        loop {
+            match self.try_recv() {
+                Ok(payload) => return Some(payload),
+                Err(TryRecvError::Disconnected) => return None,
+                Err(TryRecvError::Empty) => self.shared.notify.notified().await,
+            }
+        }
+    }
+
+    pub(crate) fn try_recv(&mut self) -> Result<Vec<u8>, TryRecvError> {
+        let mut queue = self.shared.queue.lock().unwrap();
+        if let Some(payload) = queue.frames.pop_front() {
+            return Ok(payload);
+        }
+        if queue.senders == 0 {
+            Err(TryRecvError::Disconnected)
+        } else {
+            Err(TryRecvError::Empty)
+        }
+    }
+}
+
+impl Drop for OutboundReceiver {
+    fn drop(&mut self) {
+        self.shared.queue.lock().unwrap().receiver_closed = true;
+    }
+}
+
+/// Whether an input differs from the previous one enough to count as player activity: boost
+/// toggled, or the axis turned past `AFK_INPUT_AXIS_DEADBAND`. Inputs without an axis keep
+/// the previous heading, so they only count if boost changed.
+pub(crate) fn is_active_input(
+    previous_axis: Option<Point>,
+    previous_boost: bool,
+    axis: Option<Point>,
+    boost: bool,
+) -> bool {
+    if boost != previous_boost {
+        return true;
+    }
+    match (previous_axis, axis) {
+        (_, None) => false,
+        (None, Some(_)) => true,
+        (Some(previous), Some(axis)) => {
+            clamp(dot(previous, axis), -1.0, 1.0).acos() > super::AFK_INPUT_AXIS_DEADBAND
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default)]
 pub(crate) struct SessionInboundState {
     pub(crate) input_axis: Option<Point>,
     pub(crate) boost: bool,
     pub(crate) latest_input_seq: Option<u16>,
     pub(crate) last_input_at: i64,
+    /// When the input last actually changed; heartbeats only refresh `last_input_at`.
+    pub(crate) last_active_at: i64,
     pub(crate) view_center: Option<Point>,
     pub(crate) view_radius: Option<f64>,
     pub(crate) camera_distance: Option<f64>,
+    pub(crate) inputs_this_tick: u32,
+    pub(crate) over_budget_inputs: u32,
 }
 
 #[derive(Debug)]
@@ -59,14 +248,26 @@ impl SessionInbound {
 
     pub(crate) fn update_input(&self, axis: Option<Point>, boost: bool, input_seq: Option<u16>) {
         let mut state = self.inner.lock().unwrap();
-        if let Some(axis) = axis.and_then(parse_axis) {
+        // Inputs are coalesced to the latest value per tick anyway, so the newest one always
+        // wins; anything past the budget is only counted so spamming clients show up in stats.
+        if state.inputs_this_tick >= super::INPUT_RATE_LIMIT_PER_TICK {
+            state.over_budget_inputs = state.over_budget_inputs.saturating_add(1);
+        } else {
+            state.inputs_this_tick += 1;
+        }
+        let axis = axis.and_then(parse_axis);
+        let now = now_millis();
+        if is_active_input(state.input_axis, state.boost, axis, boost) {
+            state.last_active_at = now;
+        }
+        if let Some(axis) = axis {
             state.input_axis = Some(axis);
         }
         state.boost = boost;
         if let Some(seq) = input_seq {
             state.latest_input_seq = Some(seq);
         }
-        state.last_input_at = now_millis();
+        state.last_input_at = now;
     }
 
     pub(crate) fn update_view(
@@ -93,15 +294,21 @@ impl SessionInbound {
             });
     }
 
-    pub(crate) fn snapshot(&self) -> SessionInboundState {
-        *self.inner.lock().unwrap()
+    /// Returns the latest inbound state and resets the per-tick input budget.
+    pub(crate) fn take_tick_snapshot(&self) -> SessionInboundState {
+        let mut state = self.inner.lock().unwrap();
+        let snapshot = *state;
+        state.inputs_this_tick = 0;
+        state.over_budget_inputs = 0;
+        snapshot
     }
 }
 
 pub struct SessionIo {
     pub session_id: String,
+    pub wire_format: WireFormat,
     pub inbound: Arc<SessionInbound>,
     pub outbound_state: Arc<LatestFrame>,
-    pub outbound_hi_rx: mpsc::Receiver<Vec<u8>>,
+    pub outbound_hi_rx: OutboundReceiver,
     pub outbound_lo_rx: mpsc::Receiver<Vec<u8>>,
 }
@@ -0,0 +1,656 @@
+use super::*;
+use crate::game::environment::{Lake, MountainInstance, TreeInstance};
+use crate::game::types::Digestion;
+use anyhow::{bail, Context};
+use std::collections::VecDeque;
+
+/// Leading bytes of every room snapshot, so a restore can reject unrelated payloads early.
+const SNAPSHOT_MAGIC: [u8; 4] = *b"SWRS";
+/// Bump whenever the snapshot layout changes. Restores only accept the exact same version, so
+/// both ends of a migration must run the same build.
+pub(crate) const SNAPSHOT_VERSION: u16 = 6;
+
+impl RoomState {
+    /// Serializes the simulation state (players, pellets, environment and sequence counters)
+    /// for migrating the room to another process.
+    ///
+    /// Sessions are not included; clients reconnect and rejoin with their player id. Process
+    /// configuration (bots, pellet tuning, day/night, recording) stays with whichever process
+    /// restores the snapshot.
+    pub(super) fn serialize(&self) -> Vec<u8> {
+        let mut writer = SnapshotWriter::default();
+        writer.bytes(&SNAPSHOT_MAGIC);
+        writer.u16(SNAPSHOT_VERSION);
+        writer.string(&self.room_id);
+        writer.u32(self.next_pellet_id);
+        writer.u32(self.next_state_seq);
+        writer.u16(self.next_player_net_id);
+        writer.f64(self.day_phase);
+        writer.option(self.day_phase_q, |writer, value| writer.u8(value));
+        writer.f64(self.pellet_load_factor);
+
+        let mut player_ids: Vec<&String> = self.players.keys().collect();
+        player_ids.sort();
+        writer.len(player_ids.len());
+        for id in player_ids {
+            write_player(&mut writer, &self.players[id]);
+        }
+
+        writer.len(self.pellets.len());
+        for pellet in &self.pellets {
+            write_pellet(&mut writer, pellet);
+        }
+
+        let mut evasive_timers: Vec<(&String, &i64)> = self.next_evasive_spawn_at.iter().collect();
+        evasive_timers.sort();
+        writer.len(evasive_timers.len());
+        for (player_id, at) in evasive_timers {
+            writer.string(player_id);
+            writer.i64(*at);
+        }
+
+        writer.len(self.pending_pellet_consumes.len());
+        for (pellet_id, player_id) in &self.pending_pellet_consumes {
+            writer.u32(*pellet_id);
+            writer.string(player_id);
+        }
+
+        write_environment(&mut writer, &self.environment);
+        writer.into_vec()
+    }
+
+    /// Replaces the simulation state with a snapshot produced by [`RoomState::serialize`].
+    ///
+    /// The snapshot is fully decoded before anything is applied, so a rejected snapshot leaves
+    /// the room untouched. Restored humans start disconnected and keep their slot for the usual
+    /// reconnect window; any sessions already attached here must rejoin.
+    pub(super) fn restore(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
+        let mut reader = SnapshotReader::new(bytes);
+        if reader.bytes(SNAPSHOT_MAGIC.len())? != SNAPSHOT_MAGIC {
+            bail!("not a room snapshot");
+        }
+        let version = reader.u16()?;
+        if version != SNAPSHOT_VERSION {
+            bail!("unsupported snapshot version {version} (expected {SNAPSHOT_VERSION})");
+        }
+        let room_id = reader.string()?;
+        if room_id != self.room_id {
+            bail!("snapshot is for room {room_id}, not {}", self.room_id);
+        }
+        let next_pellet_id = reader.u32()?;
+        let next_state_seq = reader.u32()?;
+        let next_player_net_id = reader.u16()?;
+        let day_phase = reader.f64()?;
+        let day_phase_q = reader.option(|reader| reader.u8())?;
+        let pellet_load_factor = reader.f64()?;
+
+        let player_count = reader.len()?;
+        let mut players = HashMap::with_capacity(player_count.min(reader.remaining()));
+        for _ in 0..player_count {
+            let player = read_player(&mut reader)?;
+            players.insert(player.id.clone(), player);
+        }
+
+        let pellet_count = reader.len()?;
+        let mut pellets = Vec::with_capacity(pellet_count.min(reader.remaining()));
+        for _ in 0..pellet_count {
+            pellets.push(read_pellet(&mut reader)?);
+        }
+
+        let timer_count = reader.len()?;
+        let mut next_evasive_spawn_at = HashMap::with_capacity(timer_count.min(reader.remaining()));
+        for _ in 0..timer_count {
+            let player_id = reader.string()?;
+            next_evasive_spawn_at.insert(player_id, reader.i64()?);
+        }
+
+        let consume_count = reader.len()?;
+        let mut pending_pellet_consumes = Vec::with_capacity(consume_count.min(reader.remaining()));
+        for _ in 0..consume_count {
+            let pellet_id = reader.u32()?;
+            pending_pellet_consumes.push((pellet_id, reader.string()?));
+        }
+
+        let environment = read_environment(&mut reader)?;
+        if !reader.is_empty() {
+            bail!("trailing bytes after room snapshot");
+        }
+
+        let now = Self::now_millis();
+        for player in players.values_mut() {
+            if !player.is_bot {
+                player.connected = false;
+                player.last_seen = now;
+                player.last_active_at = now;
+            }
+        }
+        for session in self.sessions.values_mut() {
+            session.player_id = None;
+            session.pellet_view_ids.clear();
+            session.pellet_view_initialized = false;
+            session.delta_player_cache.clear();
+            session.force_next_keyframe = true;
+        }
+
+        self.players = players;
+        self.pellets = pellets;
+        self.next_pellet_id = next_pellet_id;
+        self.next_state_seq = next_state_seq;
+        self.next_player_net_id = next_player_net_id;
+        self.next_evasive_spawn_at = next_evasive_spawn_at;
+        self.pending_pellet_consumes = pending_pellet_consumes;
+        self.environment = environment;
+        self.day_phase = day_phase;
+        self.day_phase_q = day_phase_q;
+        self.pellet_load_factor = pellet_load_factor;
+        Ok(())
+    }
+}
+
+fn write_player(writer: &mut SnapshotWriter, player: &Player) {
+    writer.string(&player.id);
+    writer.bytes(&player.id_bytes);
+    writer.u16(player.net_id);
+    writer.string(&player.name);
+    writer.string(&player.color);
+    writer.option(player.skin.as_ref(), |writer, skin| {
+        writer.len(skin.len());
+        for rgb in skin {
+            writer.bytes(rgb);
+        }
+    });
+    writer.bool(player.is_bot);
+    writer.u8(match player.bot_skill {
+        None => 0,
+        Some(BotSkill::Easy) => 1,
+        Some(BotSkill::Medium) => 2,
+        Some(BotSkill::Hard) => 3,
+    });
+    writer.point(player.axis);
+    writer.point(player.target_axis);
+    writer.bool(player.boost);
+    writer.bool(player.is_boosting);
+    writer.u32(player.boost_engage_ticks);
+    writer.u32(player.boost_cooldown_ticks);
+    writer.f64(player.oxygen);
+    writer.f64(player.oxygen_damage_accumulator);
+    writer.f64(player.submerged_secs);
+    writer.i64(player.score);
+    writer.i64(player.session_best);
+    writer.bool(player.alive);
+    writer.bool(player.connected);
+    writer.i64(player.last_seen);
+    writer.option(player.respawn_at, |writer, at| writer.i64(at));
+    writer.option(player.respawn_length, |writer, len| writer.len(len));
+    writer.len(player.boost_floor_len);
+    writer.len(player.trail_color_cycle_cursor);
+    writer.i64(player.next_boost_trail_pellet_at_ms);
+    writer.f64(player.boost_shed_value);
+    writer.u32(player.evasive_attempts);
+    writer.u32(player.evasive_captures);
+    writer.len(player.snake.len());
+    for node in &player.snake {
+        writer.f64(node.x);
+        writer.f64(node.y);
+        writer.f64(node.z);
+        writer.len(node.pos_queue.len());
+        for queued in &node.pos_queue {
+            writer.option(*queued, |writer, point| writer.point(point));
+        }
+    }
+    writer.f64(player.pellet_growth_fraction);
+    writer.f64(player.tail_extension);
+    writer.u32(player.next_digestion_id);
+    writer.len(player.digestions.len());
+    for digestion in &player.digestions {
+        writer.u32(digestion.id);
+        writer.i64(digestion.remaining);
+        writer.i64(digestion.total);
+        writer.i64(digestion.settle_steps);
+        writer.f64(digestion.growth_amount);
+        writer.f64(digestion.applied_growth);
+        writer.f32(digestion.strength);
+    }
+}
+
+fn read_player(reader: &mut SnapshotReader) -> anyhow::Result<Player> {
+    let id = reader.string()?;
+    let id_bytes = reader.array::<16>()?;
+    let net_id = reader.u16()?;
+    let name = reader.string()?;
+    let color = reader.string()?;
+    let skin = reader.option(|reader| {
+        let len = reader.len()?;
+        let mut skin = Vec::with_capacity(len.min(reader.remaining()));
+        for _ in 0..len {
+            skin.push(reader.array::<3>()?);
+        }
+        Ok(skin)
+    })?;
+    let is_bot = reader.bool()?;
+    let bot_skill = match reader.u8()? {
+        0 => None,
+        1 => Some(BotSkill::Easy),
+        2 => Some(BotSkill::Medium),
+        3 => Some(BotSkill::Hard),
+        other => bail!("unknown bot skill {other}"),
+    };
+    let axis = reader.point()?;
+    let target_axis = reader.point()?;
+    let boost = reader.bool()?;
+    let is_boosting = reader.bool()?;
+    let boost_engage_ticks = reader.u32()?;
+    let boost_cooldown_ticks = reader.u32()?;
+    let oxygen = reader.f64()?;
+    let oxygen_damage_accumulator = reader.f64()?;
+    let submerged_secs = reader.f64()?;
+    let score = reader.i64()?;
+    let session_best = reader.i64()?;
+    let alive = reader.bool()?;
+    let connected = reader.bool()?;
+    let last_seen = reader.i64()?;
+    let respawn_at = reader.option(|reader| reader.i64())?;
+    let respawn_length = reader.option(|reader| reader.len())?;
+    let boost_floor_len = reader.len()?;
+    let trail_color_cycle_cursor = reader.len()?;
+    let next_boost_trail_pellet_at_ms = reader.i64()?;
+    let boost_shed_value = reader.f64()?;
+    let evasive_attempts = reader.u32()?;
+    let evasive_captures = reader.u32()?;
+
+    let node_count = reader.len()?;
+    let mut snake = Vec::with_capacity(node_count.min(reader.remaining()));
+    for _ in 0..node_count {
+        let x = reader.f64()?;
+        let y = reader.f64()?;
+        let z = reader.f64()?;
+        let queue_len = reader.len()?;
+        let mut pos_queue = VecDeque::with_capacity(queue_len.min(reader.remaining()));
+        for _ in 0..queue_len {
+            pos_queue.push_back(reader.option(|reader| reader.point())?);
+        }
+        snake.push(SnakeNode { x, y, z, pos_queue });
+    }
+
+    let pellet_growth_fraction = reader.f64()?;
+    let tail_extension = reader.f64()?;
+    let next_digestion_id = reader.u32()?;
+    let digestion_count = reader.len()?;
+    let mut digestions = Vec::with_capacity(digestion_count.min(reader.remaining()));
+    for _ in 0..digestion_count {
+        digestions.push(Digestion {
+            id: reader.u32()?,
+            remaining: reader.i64()?,
+            total: reader.i64()?,
+            settle_steps: reader.i64()?,
+            growth_amount: reader.f64()?,
+            applied_growth: reader.f64()?,
+            strength: reader.f32()?,
+        });
+    }
+
+    Ok(Player {
+        id,
+        id_bytes,
+        net_id,
+        name,
+        color,
+        skin,
+        is_bot,
+        bot_skill,
+        // Re-derived from `last_seen` on the next tick.
+        afk_autopilot: false,
+        axis,
+        target_axis,
+        boost,
+        is_boosting,
+        boost_engage_ticks,
+        boost_cooldown_ticks,
+        oxygen,
+        oxygen_damage_accumulator,
+        submerged_secs,
+        score,
+        session_best,
+        alive,
+        connected,
+        last_seen,
+        last_active_at: last_seen,
+        respawn_at,
+        respawn_length,
+        boost_floor_len,
+        trail_color_cycle_cursor,
+        next_boost_trail_pellet_at_ms,
+        boost_shed_value,
+        evasive_attempts,
+        evasive_captures,
+        snake,
+        pellet_growth_fraction,
+        tail_extension,
+        next_digestion_id,
+        digestions,
+    })
+}
+
+fn write_pellet(writer: &mut SnapshotWriter, pellet: &Pellet) {
+    writer.u32(pellet.id);
+    writer.point(pellet.normal);
+    writer.bytes(&pellet.color_rgb);
+    writer.f32(pellet.base_size);
+    writer.f32(pellet.current_size);
+    writer.f64(pellet.growth_fraction);
+    writer.option(pellet.expires_at_ms, |writer, at| writer.i64(at));
+    match &pellet.state {
+        PelletState::Idle => writer.u8(0),
+        PelletState::Attracting { target_player_id } => {
+            writer.u8(1);
+            writer.string(target_player_id);
+        }
+        PelletState::Evasive {
+            owner_player_id,
+            expires_at_ms,
+        } => {
+            writer.u8(2);
+            writer.string(owner_player_id);
+            writer.i64(*expires_at_ms);
+        }
+    }
+}
+
+fn read_pellet(reader: &mut SnapshotReader) -> anyhow::Result<Pellet> {
+    let id = reader.u32()?;
+    let normal = reader.point()?;
+    let color_rgb = reader.array::<3>()?;
+    let base_size = reader.f32()?;
+    let current_size = reader.f32()?;
+    let growth_fraction = reader.f64()?;
+    let expires_at_ms = reader.option(|reader| reader.i64())?;
+    let state = match reader.u8()? {
+        0 => PelletState::Idle,
+        1 => PelletState::Attracting {
+            target_player_id: reader.string()?,
+        },
+        2 => PelletState::Evasive {
+            owner_player_id: reader.string()?,
+            expires_at_ms: reader.i64()?,
+        },
+        other => bail!("unknown pellet state {other}"),
+    };
+    Ok(Pellet {
+        id,
+        normal,
+        color_rgb,
+        base_size,
+        current_size,
+        growth_fraction,
+        expires_at_ms,
+        state,
+    })
+}
+
+fn write_environment(writer: &mut SnapshotWriter, environment: &Environment) {
+    writer.len(environment.lakes.len());
+    for lake in &environment.lakes {
+        writer.point(lake.center);
+        writer.f64(lake.radius);
+        writer.f64(lake.depth);
+        writer.f64(lake.shelf_depth);
+        writer.f64(lake.edge_falloff);
+        writer.f64(lake.noise_amplitude);
+        writer.f64(lake.noise_frequency);
+        writer.f64(lake.noise_frequency_b);
+        writer.f64(lake.noise_frequency_c);
+        writer.f64(lake.noise_phase);
+        writer.f64(lake.noise_phase_b);
+        writer.f64(lake.noise_phase_c);
+        writer.f64(lake.warp_amplitude);
+        writer.f64(lake.surface_inset);
+        writer.point(lake.tangent);
+        writer.point(lake.bitangent);
+    }
+    writer.len(environment.trees.len());
+    for tree in &environment.trees {
+        writer.point(tree.normal);
+        writer.f64(tree.width_scale);
+        writer.f64(tree.height_scale);
+        writer.f64(tree.twist);
+    }
+    writer.len(environment.mountains.len());
+    for mountain in &environment.mountains {
+        writer.point(mountain.normal);
+        writer.f64(mountain.radius);
+        writer.f64(mountain.height);
+        writer.u8(mountain.variant);
+        writer.f64(mountain.twist);
+        writer.len(mountain.outline.len());
+        for radius in &mountain.outline {
+            writer.f64(*radius);
+        }
+    }
+}
+
+fn read_environment(reader: &mut SnapshotReader) -> anyhow::Result<Environment> {
+    let lake_count = reader.len()?;
+    let mut lakes = Vec::with_capacity(lake_count.min(reader.remaining()));
+    for _ in 0..lake_count {
+        lakes.push(Lake {
+            center: reader.point()?,
+            radius: reader.f64()?,
+            depth: reader.f64()?,
+            shelf_depth: reader.f64()?,
+            edge_falloff: reader.f64()?,
+            noise_amplitude: reader.f64()?,
+            noise_frequency: reader.f64()?,
+            noise_frequency_b: reader.f64()?,
+            noise_frequency_c: reader.f64()?,
+            noise_phase: reader.f64()?,
+            noise_phase_b: reader.f64()?,
+            noise_phase_c: reader.f64()?,
+            warp_amplitude: reader.f64()?,
+            surface_inset: reader.f64()?,
+            tangent: reader.point()?,
+            bitangent: reader.point()?,
+        });
+    }
+    let tree_count = reader.len()?;
+    let mut trees = Vec::with_capacity(tree_count.min(reader.remaining()));
+    for _ in 0..tree_count {
+        trees.push(TreeInstance {
+            normal: reader.point()?,
+            width_scale: reader.f64()?,
+            height_scale: reader.f64()?,
+            twist: reader.f64()?,
+        });
+    }
+    let mountain_count = reader.len()?;
+    let mut mountains = Vec::with_capacity(mountain_count.min(reader.remaining()));
+    for _ in 0..mountain_count {
+        let normal = reader.point()?;
+        let radius = reader.f64()?;
+        let height = reader.f64()?;
+        let variant = reader.u8()?;
+        let twist = reader.f64()?;
+        let outline_len = reader.len()?;
+        let mut outline = Vec::with_capacity(outline_len.min(reader.remaining()));
+        for _ in 0..outline_len {
+            outline.push(reader.f64()?);
+        }
+        mountains.push(MountainInstance {
+            normal,
+            radius,
+            height,
+            variant,
+            twist,
+            outline,
+        });
+    }
+    Ok(Environment {
+        lakes,
+        trees,
+        mountains,
+    })
+}
+
+/// Little-endian writer for the snapshot format. Unlike the wire protocol it keeps full `f64`
+/// precision and uses `u32` lengths, since snapshots must restore the simulation exactly.
+#[derive(Default)]
+struct SnapshotWriter {
+    buffer: Vec<u8>,
+}
+
+impl SnapshotWriter {
+    fn into_vec(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    fn bytes(&mut self, value: &[u8]) {
+        self.buffer.extend_from_slice(value);
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.buffer.push(value);
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn i64(&mut self, value: i64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn f64(&mut self, value: f64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn len(&mut self, value: usize) {
+        self.u32(value.min(u32::MAX as usize) as u32);
+    }
+
+    fn string(&mut self, value: &str) {
+        self.len(value.len());
+        self.bytes(value.as_bytes());
+    }
+
+    fn point(&mut self, value: Point) {
+        self.f64(value.x);
+        self.f64(value.y);
+        self.f64(value.z);
+    }
+
+    fn option<T>(&mut self, value: Option<T>, write: impl FnOnce(&mut Self, T)) {
+        match value {
+            Some(value) => {
+                self.bool(true);
+                write(self, value);
+            }
+            None => self.bool(false),
+        }
+    }
+}
+
+struct SnapshotReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> SnapshotReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len() - self.offset
+    }
+
+    fn is_empty(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    fn bytes(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        if len > self.remaining() {
+            bail!("room snapshot truncated at byte {}", self.offset);
+        }
+        let slice = &self.data[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> anyhow::Result<[u8; N]> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.bytes(N)?);
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> anyhow::Result<u8> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn bool(&mut self) -> anyhow::Result<bool> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            other => bail!("invalid bool {other} in room snapshot"),
+        }
+    }
+
+    fn u16(&mut self) -> anyhow::Result<u16> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> anyhow::Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn i64(&mut self) -> anyhow::Result<i64> {
+        Ok(i64::from_le_bytes(self.array()?))
+    }
+
+    fn f32(&mut self) -> anyhow::Result<f32> {
+        Ok(f32::from_le_bytes(self.array()?))
+    }
+
+    fn f64(&mut self) -> anyhow::Result<f64> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    fn len(&mut self) -> anyhow::Result<usize> {
+        Ok(self.u32()? as usize)
+    }
+
+    fn string(&mut self) -> anyhow::Result<String> {
+        let len = self.len()?;
+        let bytes = self.bytes(len)?;
+        String::from_utf8(bytes.to_vec()).context("invalid UTF-8 in room snapshot")
+    }
+
+    fn point(&mut self) -> anyhow::Result<Point> {
+        Ok(Point {
+            x: self.f64()?,
+            y: self.f64()?,
+            z: self.f64()?,
+        })
+    }
+
+    fn option<T>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> anyhow::Result<T>,
+    ) -> anyhow::Result<Option<T>> {
+        if self.bool()? {
+            read(self).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
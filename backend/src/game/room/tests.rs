@@ -1,5 +1,12 @@
-
+use super::config::MAX_PELLET_PALETTE_COLORS;
+use super::session::OutboundReceiver;
 use super::*;
+use crate::game::constants::{
+    BASE_SPEED, BOOST_MULTIPLIER, COLLISION_DISTANCE, EVASIVE_PELLET_SIZE_MIN, MIN_SURVIVAL_LENGTH,
+    NIGHT_OXYGEN_DRAIN_MULTIPLIER, NODE_ANGLE, OXYGEN_DAMAGE_NODES_PER_SEC, OXYGEN_DRAIN_PER_SEC,
+    OXYGEN_MAX, SMALL_PELLET_GROWTH_FRACTION, SMALL_PELLET_SIZE_MAX, SMALL_PELLET_SIZE_MIN,
+    TURN_SUBSTEPS_NORMAL,
+};
 use crate::game::types::Digestion;
 use std::collections::{HashMap, HashSet, VecDeque};
 
@@ -23,6 +30,8 @@ fn make_player(id: &str, snake: Vec<SnakeNode>) -> Player {
         color: "#ffffff".to_string(),
         skin: None,
         is_bot: false,
+        bot_skill: None,
+        afk_autopilot: false,
         axis: Point {
             x: 1.0,
             y: 0.0,
@@ -35,16 +44,25 @@ fn make_player(id: &str, snake: Vec<SnakeNode>) -> Player {
         },
         boost: false,
         is_boosting: false,
+        boost_engage_ticks: 0,
+        boost_cooldown_ticks: 0,
         oxygen: OXYGEN_MAX,
         oxygen_damage_accumulator: 0.0,
+        submerged_secs: 0.0,
         score: 0,
+        session_best: 0,
         alive: true,
         connected: true,
         last_seen: 0,
+        last_active_at: 0,
         respawn_at: None,
+        respawn_length: None,
         boost_floor_len: snake.len().max(STARTING_LENGTH),
         trail_color_cycle_cursor: 0,
         next_boost_trail_pellet_at_ms: 0,
+        boost_shed_value: 0.0,
+        evasive_attempts: 0,
+        evasive_captures: 0,
         snake,
         pellet_growth_fraction: 0.0,
         tail_extension: 0.0,
@@ -53,6 +71,10 @@ fn make_player(id: &str, snake: Vec<SnakeNode>) -> Player {
     }
 }
 
+fn create_snake(axis: Point) -> Vec<SnakeNode> {
+    create_snake_with_length(axis, STARTING_LENGTH)
+}
+
 fn snake_from_xs(xs: &[f64]) -> Vec<SnakeNode> {
     xs.iter()
         .map(|x| SnakeNode {
@@ -64,19 +86,11 @@ fn snake_from_xs(xs: &[f64]) -> Vec<SnakeNode> {
         .collect()
 }
 
+/// Fixed so spawn placement, pellet rolls and bot wander replay identically on every run.
+const TEST_RNG_SEED: u64 = 0x5eed_2024;
+
 fn make_state() -> RoomState {
-    RoomState {
-        room_id: "test-room".to_string(),
-        sessions: HashMap::new(),
-        players: HashMap::new(),
-        pellets: Vec::new(),
-        next_pellet_id: 0,
-        next_state_seq: 1,
-        next_player_net_id: 1,
-        next_evasive_spawn_at: HashMap::new(),
-        pending_pellet_consumes: Vec::new(),
-        environment: Environment::generate(),
-    }
+    RoomState::new_with_rng_seed("test-room".to_string(), TEST_RNG_SEED)
 }
 
 fn make_pellet(id: u32, normal: Point) -> Pellet {
@@ -239,17 +253,68 @@ fn decode_state_counts(payload: &[u8]) -> (u32, u16, u16) {
     let _flags = read_u16(payload, &mut offset);
     offset += 8; // now
     let state_seq = read_u32(payload, &mut offset);
+    offset += 2 + 2 + 4 + 2; // tick_ms, render_delay_ms, prev_state_seq, prev_interval_ms
     let total_players = read_u16(payload, &mut offset);
     let _ack_input_seq = read_u16(payload, &mut offset);
-    let _frame_flags = read_u8(payload, &mut offset);
+    let frame_flags = read_u8(payload, &mut offset);
     let visible_players = read_u16(payload, &mut offset);
     for _ in 0..visible_players {
         skip_player_state(payload, &mut offset);
     }
+    if frame_flags & DELTA_FRAME_SESSION_BEST != 0 {
+        let _session_best = read_var_i32(payload, &mut offset);
+    }
+    if frame_flags & DELTA_FRAME_RESPAWN_AT != 0 {
+        offset += 8; // respawn_at
+    }
     assert_eq!(offset, payload.len());
     (state_seq, total_players, visible_players)
 }
 
+/// The trailing respawn time of a state delta, if the frame carries one.
+fn decode_state_respawn_at(payload: &[u8]) -> Option<i64> {
+    let mut offset = 4 + 8 + 4 + 2 + 2 + 4 + 2 + 2 + 2; // header .. ack_input_seq
+    let frame_flags = read_u8(payload, &mut offset);
+    let visible_players = read_u16(payload, &mut offset);
+    for _ in 0..visible_players {
+        skip_player_state(payload, &mut offset);
+    }
+    if frame_flags & DELTA_FRAME_SESSION_BEST != 0 {
+        let _session_best = read_var_i32(payload, &mut offset);
+    }
+    if frame_flags & DELTA_FRAME_RESPAWN_AT == 0 {
+        return None;
+    }
+    let respawn_at = i64::from_le_bytes(payload[offset..offset + 8].try_into().unwrap());
+    assert_eq!(offset + 8, payload.len());
+    Some(respawn_at)
+}
+
+/// `(now, state_seq, tick_ms, render_delay_ms, prev_state_seq, prev_interval_ms)`.
+fn decode_state_timing(payload: &[u8]) -> (i64, u32, u16, u16, u32, u16) {
+    let mut offset = 0usize;
+    let version = read_u8(payload, &mut offset);
+    assert_eq!(version, protocol::VERSION);
+    let message_type = read_u8(payload, &mut offset);
+    assert_eq!(message_type, protocol::TYPE_STATE_DELTA);
+    let _flags = read_u16(payload, &mut offset);
+    let now = i64::from_le_bytes(payload[offset..offset + 8].try_into().unwrap());
+    offset += 8;
+    let state_seq = read_u32(payload, &mut offset);
+    let tick_ms = read_u16(payload, &mut offset);
+    let render_delay_ms = read_u16(payload, &mut offset);
+    let prev_state_seq = read_u32(payload, &mut offset);
+    let prev_interval_ms = read_u16(payload, &mut offset);
+    (
+        now,
+        state_seq,
+        tick_ms,
+        render_delay_ms,
+        prev_state_seq,
+        prev_interval_ms,
+    )
+}
+
 fn decode_state_ack_input_seq(payload: &[u8]) -> u16 {
     let mut offset = 0usize;
     let version = read_u8(payload, &mut offset);
@@ -259,10 +324,26 @@ fn decode_state_ack_input_seq(payload: &[u8]) -> u16 {
     let _flags = read_u16(payload, &mut offset);
     offset += 8; // now
     let _state_seq = read_u32(payload, &mut offset);
+    offset += 2 + 2 + 4 + 2; // tick_ms, render_delay_ms, prev_state_seq, prev_interval_ms
     let _total_players = read_u16(payload, &mut offset);
     read_u16(payload, &mut offset)
 }
 
+fn decode_state_frame_flags(payload: &[u8]) -> u8 {
+    let mut offset = 0usize;
+    let version = read_u8(payload, &mut offset);
+    assert_eq!(version, protocol::VERSION);
+    let message_type = read_u8(payload, &mut offset);
+    assert_eq!(message_type, protocol::TYPE_STATE_DELTA);
+    let _flags = read_u16(payload, &mut offset);
+    offset += 8; // now
+    let _state_seq = read_u32(payload, &mut offset);
+    offset += 2 + 2 + 4 + 2; // tick_ms, render_delay_ms, prev_state_seq, prev_interval_ms
+    let _total_players = read_u16(payload, &mut offset);
+    let _ack_input_seq = read_u16(payload, &mut offset);
+    read_u8(payload, &mut offset)
+}
+
 fn decode_init_counts(payload: &[u8]) -> (u32, u16, u16) {
     let mut offset = 0usize;
     let version = read_u8(payload, &mut offset);
@@ -297,13 +378,14 @@ fn decode_init_counts(payload: &[u8]) -> (u32, u16, u16) {
 
 #[test]
 fn girth_scale_grows_per_node_and_caps() {
-    assert!((RoomState::player_girth_scale_from_len(STARTING_LENGTH) - 1.0).abs() < 1e-9);
-    assert!((RoomState::player_girth_scale_from_len(STARTING_LENGTH + 1) - 1.01).abs() < 1e-9);
-    assert!((RoomState::player_girth_scale_from_len(STARTING_LENGTH + 9) - 1.09).abs() < 1e-9);
-    assert!((RoomState::player_girth_scale_from_len(STARTING_LENGTH + 10) - 1.1).abs() < 1e-9);
-    assert!((RoomState::player_girth_scale_from_len(STARTING_LENGTH + 15) - 1.15).abs() < 1e-9);
-    assert!((RoomState::player_girth_scale_from_len(STARTING_LENGTH + 20) - 1.2).abs() < 1e-9);
-    assert!((RoomState::player_girth_scale_from_len(STARTING_LENGTH + 500) - 2.0).abs() < 1e-9);
+    let state = make_state();
+    assert!((state.player_girth_scale_from_len(STARTING_LENGTH) - 1.0).abs() < 1e-9);
+    assert!((state.player_girth_scale_from_len(STARTING_LENGTH + 1) - 1.01).abs() < 1e-9);
+    assert!((state.player_girth_scale_from_len(STARTING_LENGTH + 9) - 1.09).abs() < 1e-9);
+    assert!((state.player_girth_scale_from_len(STARTING_LENGTH + 10) - 1.1).abs() < 1e-9);
+    assert!((state.player_girth_scale_from_len(STARTING_LENGTH + 15) - 1.15).abs() < 1e-9);
+    assert!((state.player_girth_scale_from_len(STARTING_LENGTH + 20) - 1.2).abs() < 1e-9);
+    assert!((state.player_girth_scale_from_len(STARTING_LENGTH + 500) - 2.0).abs() < 1e-9);
 }
 
 #[test]
@@ -329,12 +411,20 @@ fn self_overlap_does_not_kill_player() {
                 z: 1.0,
             },
         ],
+        extended_tail: None,
         contact_angular_radius: radius,
         body_angular_radius: radius,
     };
     let mut dead = HashSet::new();
     let mut death_reasons = HashMap::new();
-    RoomState::detect_snake_head_body_collisions(&[snapshot], &mut dead, &mut death_reasons);
+    RoomState::detect_snake_head_body_collisions(
+        &[snapshot],
+        0.0,
+        SelfCollisionConfig::default(),
+        &mut dead,
+        &mut death_reasons,
+        &mut HashMap::new(),
+    );
     assert!(dead.is_empty());
     assert!(death_reasons.is_empty());
 }
@@ -362,6 +452,7 @@ fn snake_collision_still_kills_on_head_body_overlap() {
                 z: 0.0,
             },
         ],
+        extended_tail: None,
         contact_angular_radius: radius,
         body_angular_radius: radius,
     };
@@ -385,17 +476,439 @@ fn snake_collision_still_kills_on_head_body_overlap() {
                 z: 0.0,
             },
         ],
+        extended_tail: None,
+        contact_angular_radius: radius,
+        body_angular_radius: radius,
+    };
+    let mut dead = HashSet::new();
+    let mut death_reasons = HashMap::new();
+    let mut killers = HashMap::new();
+    RoomState::detect_snake_head_body_collisions(
+        &[a, b],
+        0.0,
+        SelfCollisionConfig::default(),
+        &mut dead,
+        &mut death_reasons,
+        &mut killers,
+    );
+    assert!(dead.contains("a"));
+    assert_eq!(death_reasons.get("a"), Some(&"snake_collision"));
+    assert_eq!(killers.get("a").map(String::as_str), Some("b"));
+    assert!(!dead.contains("b"));
+}
+
+/// A lies across the equator and B's head sits on A's middle, so B dies on A's body.
+fn kill_bonus_state(kill_bonus: i64) -> RoomState {
+    let mut state = make_state();
+    state.bots_enabled = false;
+    state.kill_bonus = kill_bonus;
+    state.environment.lakes.clear();
+    state.environment.trees.clear();
+    state.environment.mountains.clear();
+    state.players.insert(
+        "killer".to_string(),
+        make_player("killer", equator_snake(0.6, 24)),
+    );
+    let hit_angle = 0.6 - 12.0 * NODE_ANGLE;
+    let crossing = (0..12)
+        .map(|index| {
+            let tilt = index as f64 * NODE_ANGLE;
+            SnakeNode {
+                x: tilt.cos() * hit_angle.cos(),
+                y: tilt.cos() * hit_angle.sin(),
+                z: tilt.sin(),
+                pos_queue: VecDeque::new(),
+            }
+        })
+        .collect();
+    state
+        .players
+        .insert("victim".to_string(), make_player("victim", crossing));
+    state.players.get_mut("killer").unwrap().score = 40;
+    state
+}
+
+#[test]
+fn kill_bonus_credits_the_snake_whose_body_was_hit() {
+    let mut state = kill_bonus_state(25);
+    state.tick();
+    assert!(!state.players["victim"].alive);
+    assert!(state.players["killer"].alive);
+    assert_eq!(state.players["killer"].score, 65);
+
+    let mut state = kill_bonus_state(0);
+    state.tick();
+    assert!(!state.players["victim"].alive);
+    assert_eq!(state.players["killer"].score, 40);
+}
+
+#[test]
+fn human_death_sends_a_signed_score_receipt() {
+    use crate::shared::score_receipt::verify_score_receipt;
+
+    let mut state = make_state();
+    state.score_receipt_secret = Some("receipts".to_string());
+    let mut rx = insert_pending_session(&mut state, "session-1");
+    state.sessions.get_mut("session-1").unwrap().player_id = Some("human".to_string());
+    let mut human = make_player("human", create_snake_with_length(equator_point(0.0), 8));
+    human.score = 77;
+    state.players.insert("human".to_string(), human);
+    let mut bot = make_player("bot", create_snake_with_length(equator_point(2.0), 8));
+    bot.is_bot = true;
+    state.players.insert("bot".to_string(), bot);
+
+    state.handle_death("bot");
+    assert!(rx.try_recv().is_err());
+
+    let before = RoomState::now_millis();
+    state.handle_death("human");
+    let frame = rx.try_recv().expect("receipt frame");
+    assert_eq!(frame[1], protocol::TYPE_SCORE_RECEIPT);
+    assert_eq!(i64::from_le_bytes(frame[4..12].try_into().unwrap()), 77);
+    let len = u16::from_le_bytes(frame[12..14].try_into().unwrap()) as usize;
+    let receipt = std::str::from_utf8(&frame[14..14 + len]).expect("utf8");
+    let claims = verify_score_receipt(receipt, "receipts", before, 60_000).expect("valid");
+    assert_eq!((claims.player_id.as_str(), claims.score), ("human", 77));
+}
+
+#[test]
+fn terrain_and_oxygen_deaths_credit_no_kill_bonus() {
+    // A cactus on the victim's head claims the death before the body contact is checked.
+    let mut state = kill_bonus_state(25);
+    let victim_head = state.players["victim"].snake[0].clone();
+    state.environment.trees = make_cactus_collision_state(
+        Point {
+            x: victim_head.x,
+            y: victim_head.y,
+            z: victim_head.z,
+        },
+        1.0,
+    )
+    .environment
+    .trees;
+    state.tick();
+    assert!(!state.players["victim"].alive);
+    assert_eq!(state.players["killer"].score, 40);
+
+    // Drowning is recorded first, so the body contact in the same tick is not a kill.
+    let mut state = kill_bonus_state(25);
+    state.environment.lakes = make_full_lake_state().environment.lakes;
+    state.oxygen_grace_ms = 0;
+    let victim = state.players.get_mut("victim").unwrap();
+    victim.snake.truncate(MIN_SURVIVAL_LENGTH);
+    victim.oxygen = 0.0;
+    state.tick();
+    assert!(!state.players["victim"].alive);
+    assert_eq!(state.players["killer"].score, 40);
+}
+
+fn equator_point(angle: f64) -> Point {
+    Point {
+        x: angle.cos(),
+        y: angle.sin(),
+        z: 0.0,
+    }
+}
+
+/// Snake B runs along the equator with its tail at angle 0, growing into positive angles.
+/// Snake A's head sits `head_gap` past B's extended tail tip.
+fn extended_tail_collision_snapshots(
+    head_gap: f64,
+) -> (PlayerCollisionSnapshot, PlayerCollisionSnapshot) {
+    let radius = RoomState::snake_body_angular_radius_for_scale(1.0);
+    let b_nodes: Vec<SnakeNode> = (0..STARTING_LENGTH)
+        .rev()
+        .map(|index| {
+            let point = equator_point(-(index as f64) * NODE_ANGLE);
+            SnakeNode {
+                x: point.x,
+                y: point.y,
+                z: point.z,
+                pos_queue: VecDeque::new(),
+            }
+        })
+        .collect();
+    let extended_tail = compute_extended_tail_point(&b_nodes, 0.9).expect("extended tail");
+    let tip_angle = extended_tail.y.atan2(extended_tail.x);
+    assert!(tip_angle > 0.0);
+
+    let a_head_angle = tip_angle + head_gap;
+    let a = PlayerCollisionSnapshot {
+        id: "a".to_string(),
+        alive: true,
+        snake: (0..STARTING_LENGTH)
+            .map(|index| {
+                let trail = index as f64 * NODE_ANGLE;
+                normalize(Point {
+                    x: a_head_angle.cos(),
+                    y: a_head_angle.sin(),
+                    z: trail.sin(),
+                })
+            })
+            .collect(),
+        extended_tail: None,
+        contact_angular_radius: radius,
+        body_angular_radius: radius,
+    };
+    let b = PlayerCollisionSnapshot {
+        id: "b".to_string(),
+        alive: true,
+        snake: b_nodes
+            .iter()
+            .map(|node| Point {
+                x: node.x,
+                y: node.y,
+                z: node.z,
+            })
+            .collect(),
+        extended_tail: Some(extended_tail),
         contact_angular_radius: radius,
         body_angular_radius: radius,
     };
+    (a, b)
+}
+
+#[test]
+fn head_reaching_into_extended_tail_dies() {
+    let radius = RoomState::snake_body_angular_radius_for_scale(1.0);
+    let reach = 2.0 * radius;
+    let (a, b) = extended_tail_collision_snapshots(reach * 0.95);
+    // Only the extended tail is within reach; the last real node is not.
+    assert!(!collision_with_angular_radii(
+        a.snake[0],
+        *b.snake.last().unwrap(),
+        radius,
+        radius
+    ));
+
     let mut dead = HashSet::new();
     let mut death_reasons = HashMap::new();
-    RoomState::detect_snake_head_body_collisions(&[a, b], &mut dead, &mut death_reasons);
+    RoomState::detect_snake_head_body_collisions(
+        &[a, b],
+        0.0,
+        SelfCollisionConfig::default(),
+        &mut dead,
+        &mut death_reasons,
+        &mut HashMap::new(),
+    );
     assert!(dead.contains("a"));
     assert_eq!(death_reasons.get("a"), Some(&"snake_collision"));
+    // The extended tail is body-only, so touching A's head never kills B.
+    assert!(!dead.contains("b"));
+}
+
+#[test]
+fn head_just_short_of_extended_tail_survives() {
+    let radius = RoomState::snake_body_angular_radius_for_scale(1.0);
+    let reach = 2.0 * radius;
+    let (a, b) = extended_tail_collision_snapshots(reach * 1.05);
+
+    let mut dead = HashSet::new();
+    let mut death_reasons = HashMap::new();
+    RoomState::detect_snake_head_body_collisions(
+        &[a, b],
+        0.0,
+        SelfCollisionConfig::default(),
+        &mut dead,
+        &mut death_reasons,
+        &mut HashMap::new(),
+    );
+    assert!(dead.is_empty());
+    assert!(death_reasons.is_empty());
+}
+
+#[test]
+fn extended_tail_does_not_make_short_snake_an_attacker() {
+    let radius = RoomState::snake_body_angular_radius_for_scale(1.0);
+    let (a, mut b) = extended_tail_collision_snapshots(radius);
+    // A two-node snake with a growing tail still has too few real nodes to act as a head.
+    b.snake = vec![a.snake[1], a.snake[0]];
+
+    let mut dead = HashSet::new();
+    let mut death_reasons = HashMap::new();
+    RoomState::detect_snake_head_body_collisions(
+        &[a, b],
+        0.0,
+        SelfCollisionConfig::default(),
+        &mut dead,
+        &mut death_reasons,
+        &mut HashMap::new(),
+    );
     assert!(!dead.contains("b"));
 }
 
+#[test]
+fn collision_leniency_forgives_a_graze_but_not_a_clear_overlap() {
+    let radius = RoomState::snake_body_angular_radius_for_scale(1.0);
+    let reach = 2.0 * radius;
+
+    // Inside exact contact, but not by more than the leniency shaves off.
+    let graze = reach * (1.0 - COLLISION_LENIENCY * 0.5);
+    let (a, b) = extended_tail_collision_snapshots(graze);
+    let mut dead = HashSet::new();
+    let mut death_reasons = HashMap::new();
+    RoomState::detect_snake_head_body_collisions(
+        &[a, b],
+        0.0,
+        SelfCollisionConfig::default(),
+        &mut dead,
+        &mut death_reasons,
+        &mut HashMap::new(),
+    );
+    assert!(dead.contains("a"));
+    let (a, b) = extended_tail_collision_snapshots(graze);
+    let mut dead = HashSet::new();
+    let mut death_reasons = HashMap::new();
+    RoomState::detect_snake_head_body_collisions(
+        &[a, b],
+        COLLISION_LENIENCY,
+        SelfCollisionConfig::default(),
+        &mut dead,
+        &mut death_reasons,
+        &mut HashMap::new(),
+    );
+    assert!(dead.is_empty());
+    assert!(death_reasons.is_empty());
+
+    let (a, b) = extended_tail_collision_snapshots(reach * (1.0 - COLLISION_LENIENCY * 2.0));
+    let mut dead = HashSet::new();
+    let mut death_reasons = HashMap::new();
+    RoomState::detect_snake_head_body_collisions(
+        &[a, b],
+        COLLISION_LENIENCY,
+        SelfCollisionConfig::default(),
+        &mut dead,
+        &mut death_reasons,
+        &mut HashMap::new(),
+    );
+    assert!(dead.contains("a"));
+    assert_eq!(death_reasons.get("a"), Some(&"snake_collision"));
+}
+
+#[test]
+fn collision_leniency_is_clamped() {
+    let mut room = Room::with_room_id("grazing".to_string()).with_collision_leniency(f64::NAN);
+    assert_eq!(room.state.get_mut().collision_leniency, COLLISION_LENIENCY);
+    let mut room = room.with_collision_leniency(2.0);
+    assert_eq!(
+        room.state.get_mut().collision_leniency,
+        COLLISION_LENIENCY_MAX
+    );
+    let mut room = room.with_collision_leniency(-1.0);
+    assert_eq!(room.state.get_mut().collision_leniency, 0.0);
+}
+
+/// A snake curled around the north pole, one lap every `nodes_per_lap` nodes.
+fn self_loop_snapshot(len: usize, nodes_per_lap: usize) -> PlayerCollisionSnapshot {
+    let radius = RoomState::snake_body_angular_radius_for_scale(1.0);
+    let lap_radius = (nodes_per_lap as f64 * NODE_ANGLE / (2.0 * std::f64::consts::PI)).asin();
+    let snake = (0..len)
+        .map(|index| {
+            let theta = index as f64 * 2.0 * std::f64::consts::PI / nodes_per_lap as f64;
+            point_from_spherical(theta, lap_radius)
+        })
+        .collect();
+    PlayerCollisionSnapshot {
+        id: "looper".to_string(),
+        alive: true,
+        snake,
+        extended_tail: None,
+        contact_angular_radius: radius,
+        body_angular_radius: radius,
+    }
+}
+
+fn hardcore() -> SelfCollisionConfig {
+    SelfCollisionConfig {
+        enabled: true,
+        ..SelfCollisionConfig::default()
+    }
+}
+
+#[test]
+fn tight_self_loop_kills_only_with_self_collision_enabled() {
+    let mut dead = HashSet::new();
+    let mut death_reasons = HashMap::new();
+    RoomState::detect_snake_head_body_collisions(
+        &[self_loop_snapshot(20, 18)],
+        COLLISION_LENIENCY,
+        SelfCollisionConfig::default(),
+        &mut dead,
+        &mut death_reasons,
+        &mut HashMap::new(),
+    );
+    assert!(dead.is_empty());
+
+    RoomState::detect_snake_head_body_collisions(
+        &[self_loop_snapshot(20, 18)],
+        COLLISION_LENIENCY,
+        hardcore(),
+        &mut dead,
+        &mut death_reasons,
+        &mut HashMap::new(),
+    );
+    assert!(dead.contains("looper"));
+    assert_eq!(death_reasons.get("looper"), Some(&"self_collision"));
+}
+
+#[test]
+fn neck_nodes_never_trigger_self_collision() {
+    let gap = SelfCollisionConfig::default().min_node_gap;
+    // Every node within the gap is piled on the head, as in a hard turn.
+    let mut snapshot = self_loop_snapshot(gap, 18);
+    let head = snapshot.snake[0];
+    for node in snapshot.snake.iter_mut() {
+        *node = head;
+    }
+    snapshot.snake.extend((1..8).map(|index| {
+        let mut point = head;
+        rotate_y(&mut point, (index * 4) as f64 * NODE_ANGLE);
+        point
+    }));
+    let mut dead = HashSet::new();
+    let mut death_reasons = HashMap::new();
+    RoomState::detect_snake_head_body_collisions(
+        &[snapshot],
+        0.0,
+        hardcore(),
+        &mut dead,
+        &mut death_reasons,
+        &mut HashMap::new(),
+    );
+    assert!(dead.is_empty());
+
+    // A loop that only closes inside the gap is still the neck.
+    RoomState::detect_snake_head_body_collisions(
+        &[self_loop_snapshot(gap, gap - 2)],
+        0.0,
+        hardcore(),
+        &mut dead,
+        &mut death_reasons,
+        &mut HashMap::new(),
+    );
+    assert!(dead.is_empty());
+    assert!(death_reasons.is_empty());
+}
+
+#[test]
+fn self_collision_gap_is_clamped() {
+    let mut room =
+        Room::with_room_id("hardcore".to_string()).with_self_collision(SelfCollisionConfig {
+            enabled: true,
+            min_node_gap: 0,
+        });
+    let config = room.state.get_mut().self_collision;
+    assert!(config.enabled);
+    assert_eq!(config.min_node_gap, 4);
+    assert_eq!(
+        Room::with_room_id("default".to_string())
+            .state
+            .get_mut()
+            .self_collision,
+        SelfCollisionConfig::default()
+    );
+}
+
 #[test]
 fn boost_start_requires_next_whole_score_above_floor() {
     let mut player = make_player(
@@ -405,10 +918,10 @@ fn boost_start_requires_next_whole_score_above_floor() {
     player.boost_floor_len = STARTING_LENGTH;
     player.score = STARTING_LENGTH as i64;
     player.pellet_growth_fraction = 0.8;
-    assert!(!RoomState::can_player_boost(&player));
+    assert!(!RoomState::can_player_boost(&player, MIN_SURVIVAL_LENGTH));
 
     player.score = STARTING_LENGTH as i64 + 1;
-    assert!(RoomState::can_player_boost(&player));
+    assert!(RoomState::can_player_boost(&player, MIN_SURVIVAL_LENGTH));
 }
 
 #[test]
@@ -420,10 +933,10 @@ fn active_boost_can_continue_below_start_threshold_until_floor() {
     player.boost_floor_len = STARTING_LENGTH;
     player.score = STARTING_LENGTH as i64;
     player.is_boosting = true;
-    assert!(RoomState::can_player_boost(&player));
+    assert!(RoomState::can_player_boost(&player, MIN_SURVIVAL_LENGTH));
 
     player.is_boosting = false;
-    assert!(!RoomState::can_player_boost(&player));
+    assert!(!RoomState::can_player_boost(&player, MIN_SURVIVAL_LENGTH));
 }
 
 #[test]
@@ -442,54 +955,182 @@ fn boost_can_start_at_floor_when_pending_growth_exists() {
         strength: 1.0,
     });
 
-    assert!(RoomState::can_player_boost(&player));
+    assert!(RoomState::can_player_boost(&player, MIN_SURVIVAL_LENGTH));
+}
+
+fn base_turn() -> f64 {
+    TURN_RATE_PER_SEC * TICK_MS as f64 / 1000.0
+}
+
+#[test]
+fn turn_rate_per_tick_follows_tick_interval_at_constant_turn_per_second() {
+    let dt_seconds = TICK_MS as f64 / 1000.0;
+    let half_dt_seconds = dt_seconds / 2.0;
+    for (snake_len, speed_factor) in [
+        (STARTING_LENGTH, 1.0),
+        (STARTING_LENGTH + 220, BOOST_MULTIPLIER),
+    ] {
+        let per_tick = RoomState::turn_rate_for(
+            TURN_RATE_PER_SEC * dt_seconds,
+            snake_len,
+            speed_factor,
+            BOOST_MULTIPLIER,
+        );
+        let per_fast_tick = RoomState::turn_rate_for(
+            TURN_RATE_PER_SEC * half_dt_seconds,
+            snake_len,
+            speed_factor,
+            BOOST_MULTIPLIER,
+        );
+        assert!((per_fast_tick - per_tick / 2.0).abs() < 1e-12);
+        assert!((per_fast_tick / half_dt_seconds - per_tick / dt_seconds).abs() < 1e-9);
+    }
+
+    let mut room = Room::with_room_id("twitchy".to_string()).with_turn_rate_per_sec(f64::NAN);
+    assert_eq!(room.state.get_mut().turn_rate_per_sec, TURN_RATE_PER_SEC);
+    let mut room = room.with_turn_rate_per_sec(100.0);
+    assert_eq!(
+        room.state.get_mut().turn_rate_per_sec,
+        TURN_RATE_PER_SEC_MAX
+    );
 }
 
 #[test]
 fn turn_rate_scales_up_with_boost_speed() {
-    let normal = RoomState::turn_rate_for(STARTING_LENGTH, 1.0);
-    let boost = RoomState::turn_rate_for(STARTING_LENGTH, BOOST_MULTIPLIER);
+    let normal = RoomState::turn_rate_for(base_turn(), STARTING_LENGTH, 1.0, BOOST_MULTIPLIER);
+    let boost = RoomState::turn_rate_for(
+        base_turn(),
+        STARTING_LENGTH,
+        BOOST_MULTIPLIER,
+        BOOST_MULTIPLIER,
+    );
     assert!(boost > normal);
-    assert!(boost <= TURN_RATE * TURN_RATE_MAX_MULTIPLIER + 1e-9);
+    assert!(boost <= base_turn() * TURN_RATE_MAX_MULTIPLIER + 1e-9);
 }
 
 #[test]
 fn turn_rate_scales_down_for_longer_snakes() {
-    let short = RoomState::turn_rate_for(STARTING_LENGTH, 1.0);
-    let long = RoomState::turn_rate_for(STARTING_LENGTH + 220, 1.0);
+    let short = RoomState::turn_rate_for(base_turn(), STARTING_LENGTH, 1.0, BOOST_MULTIPLIER);
+    let long = RoomState::turn_rate_for(base_turn(), STARTING_LENGTH + 220, 1.0, BOOST_MULTIPLIER);
     assert!(long < short);
-    assert!(long >= TURN_RATE * TURN_RATE_MIN_MULTIPLIER - 1e-9);
+    assert!(long >= base_turn() * TURN_RATE_MIN_MULTIPLIER - 1e-9);
 }
 
 #[test]
 fn steering_gain_scales_down_with_boost_speed() {
-    let normal_gain = RoomState::steering_gain_for_speed(1.0);
-    let boost_gain = RoomState::steering_gain_for_speed(BOOST_MULTIPLIER);
+    let normal_gain = RoomState::steering_gain_for_speed(1.0, BOOST_MULTIPLIER);
+    let boost_gain = RoomState::steering_gain_for_speed(BOOST_MULTIPLIER, BOOST_MULTIPLIER);
     assert!((normal_gain - TURN_RESPONSE_GAIN_NORMAL_PER_SEC).abs() < 1e-9);
     assert!((boost_gain - TURN_RESPONSE_GAIN_BOOST_PER_SEC).abs() < 1e-9);
     assert!(boost_gain < normal_gain);
 }
 
 #[test]
-fn movement_substeps_use_fixed_policy_for_boost_state() {
-    assert_eq!(
-        RoomState::movement_substep_count(false),
-        TURN_SUBSTEPS_NORMAL.max(1),
-    );
-    assert_eq!(
-        RoomState::movement_substep_count(true),
-        TURN_SUBSTEPS_BOOST.max(1),
-    );
-    assert!(
-        RoomState::movement_substep_count(true) >= RoomState::movement_substep_count(false)
-    );
+fn movement_substeps_scale_with_distance_per_tick() {
+    let movement = MovementConfig::default();
+    assert_eq!(movement.substep_count(1.0), TURN_SUBSTEPS_NORMAL);
+    assert_eq!(movement.substep_count(BOOST_MULTIPLIER), 4);
+    assert!(movement.substep_count(BOOST_MULTIPLIER) >= movement.substep_count(1.0));
+
+    // Fractional multipliers round to the nearest whole substep count.
+    assert_eq!(movement.substep_count(1.2), 2);
+    assert_eq!(movement.substep_count(1.3), 3);
+    assert_eq!(movement.substep_count(3.0), 6);
+    let slow = MovementConfig {
+        base_speed: BASE_SPEED * 0.25,
+        ..movement
+    };
+    assert_eq!(slow.substep_count(1.0), 1);
+}
+
+fn head_travel_per_tick(movement: MovementConfig, boost: bool) -> f64 {
+    let mut state = make_state();
+    state.bots_enabled = false;
+    state.environment = Environment {
+        lakes: Vec::new(),
+        trees: Vec::new(),
+        mountains: Vec::new(),
+    };
+    state.movement_config = movement.sanitized();
+    let axis = Point {
+        x: 1.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    let mut player = make_player("mover", create_snake_with_length(axis, 40));
+    player.score = 40;
+    player.boost_floor_len = STARTING_LENGTH;
+    player.boost = boost;
+    let head = player.snake[0].clone();
+    state.players.insert("mover".to_string(), player);
+
+    state.tick();
+    let player = &state.players["mover"];
+    assert!(player.alive);
+    assert_eq!(player.is_boosting, boost);
+    let before = Point {
+        x: head.x,
+        y: head.y,
+        z: head.z,
+    };
+    let after = Point {
+        x: player.snake[0].x,
+        y: player.snake[0].y,
+        z: player.snake[0].z,
+    };
+    clamp(dot(normalize(before), normalize(after)), -1.0, 1.0).acos()
+}
+
+#[test]
+fn custom_boost_multiplier_sets_the_boosted_distance_per_tick() {
+    let movement = MovementConfig {
+        base_speed: BASE_SPEED * 1.5,
+        boost_multiplier: 1.7,
+    };
+    let normal = head_travel_per_tick(movement, false);
+    let boosted = head_travel_per_tick(movement, true);
+    assert!((normal - BASE_SPEED * 1.5).abs() < 1e-9);
+    assert!((boosted - BASE_SPEED * 1.5 * 1.7).abs() < 1e-9);
+
+    let default_boosted = head_travel_per_tick(MovementConfig::default(), true);
+    assert!((default_boosted - BASE_SPEED * BOOST_MULTIPLIER).abs() < 1e-9);
+}
+
+#[test]
+fn movement_config_is_sanitized_and_sent_in_init() {
+    let mut room = Room::with_room_id("fast".to_string()).with_movement_config(MovementConfig {
+        base_speed: BASE_SPEED * 2.0,
+        boost_multiplier: 1.5,
+    });
+    let state = room.state.get_mut();
+    let payload = state.build_init_payload_for_session("missing-session", "missing-player", false);
+    let trailer = &payload[payload.len() - INIT_TRAILER_LEN..];
+    let base_speed = f32::from_le_bytes(trailer[..4].try_into().unwrap());
+    let boost_multiplier = f32::from_le_bytes(trailer[4..8].try_into().unwrap());
+    assert_eq!(base_speed, (BASE_SPEED * 2.0) as f32);
+    assert_eq!(boost_multiplier, 1.5);
+
+    let sanitized = MovementConfig {
+        base_speed: f64::NAN,
+        boost_multiplier: 0.5,
+    }
+    .sanitized();
+    assert_eq!(sanitized.base_speed, BASE_SPEED);
+    assert_eq!(sanitized.boost_multiplier, 1.0);
+    let sanitized = MovementConfig {
+        base_speed: BASE_SPEED * 100.0,
+        boost_multiplier: 50.0,
+    }
+    .sanitized();
+    assert_eq!(sanitized.base_speed, BASE_SPEED * 4.0);
+    assert_eq!(sanitized.boost_multiplier, 4.0);
 }
 
 #[test]
 fn steering_turn_step_is_proportional_until_turn_cap() {
-    let turn_cap = RoomState::turn_rate_for(STARTING_LENGTH, 1.0);
+    let turn_cap = RoomState::turn_rate_for(base_turn(), STARTING_LENGTH, 1.0, BOOST_MULTIPLIER);
     let dt_seconds = TICK_MS as f64 / 1000.0;
-    let steering_gain = RoomState::steering_gain_for_speed(1.0);
+    let steering_gain = RoomState::steering_gain_for_speed(1.0, BOOST_MULTIPLIER);
     let current_axis = Point {
         x: 1.0,
         y: 0.0,
@@ -536,7 +1177,7 @@ fn insert_session_with_view(
     view_radius: Option<f64>,
 ) {
     let outbound_state = Arc::new(LatestFrame::new());
-    let (outbound_hi, _outbound_hi_rx) = mpsc::channel::<Vec<u8>>(1);
+    let (outbound_hi, _outbound_hi_rx) = outbound_queue(1, OutboundFullPolicy::Disconnect);
     let (outbound_lo, _outbound_lo_rx) = mpsc::channel::<Vec<u8>>(1);
     let inbound = Arc::new(SessionInbound::new());
     state.sessions.insert(
@@ -556,46 +1197,2857 @@ fn insert_session_with_view(
             delta_player_cache: HashMap::new(),
             force_next_keyframe: true,
             latest_applied_input_seq: 0,
+            wire_format: WireFormat::Binary,
+            protocol_version: Some(protocol::VERSION),
+            pellet_resyncs: 0,
+            pellet_resync_window_started_at: 0,
+            pellet_resyncs_in_window: 0,
+            threat_hints: false,
+            threat_hint_active: false,
         },
     );
 }
 
 #[test]
-fn spawn_rejects_heads_within_min_distance() {
+fn input_burst_applies_last_input_within_tick_budget() {
     let mut state = make_state();
     state.players.insert(
-        "other".to_string(),
-        make_player("other", snake_from_xs(&[0.0, 0.4, 0.8])),
+        "player-1".to_string(),
+        make_player("player-1", make_snake(3, 0.0)),
     );
+    insert_session_with_view(&mut state, "session-1", "player-1", None, None);
+    let inbound = Arc::clone(&state.sessions["session-1"].inbound);
 
-    let candidate = snake_from_xs(&[SPAWN_PLAYER_MIN_DISTANCE * 0.75]);
-    assert!(state.is_snake_too_close(&candidate, None));
+    let budget = INPUT_RATE_LIMIT_PER_TICK as u16;
+    for seq in 1..=budget {
+        let axis = Point {
+            x: 0.0,
+            y: if seq.is_multiple_of(2) { 1.0 } else { -1.0 },
+            z: seq as f64,
+        };
+        inbound.update_input(Some(axis), false, Some(seq));
+    }
+    state.apply_session_inbound();
+
+    let applied = state.players["player-1"].target_axis;
+    let expected = normalize(Point {
+        x: 0.0,
+        y: if budget.is_multiple_of(2) { 1.0 } else { -1.0 },
+        z: budget as f64,
+    });
+    assert!((applied.y - expected.y).abs() < 1e-9);
+    assert!((applied.z - expected.z).abs() < 1e-9);
+    assert_eq!(state.sessions["session-1"].latest_applied_input_seq, budget);
+    assert_eq!(state.over_budget_inputs, 0);
 }
 
 #[test]
-fn spawn_allows_heads_outside_min_distance() {
+fn input_burst_beyond_tick_budget_keeps_newest_input_and_counts_excess() {
     let mut state = make_state();
     state.players.insert(
-        "other".to_string(),
-        make_player("other", snake_from_xs(&[0.0, 0.4, 0.8])),
+        "player-1".to_string(),
+        make_player("player-1", make_snake(3, 0.0)),
     );
+    insert_session_with_view(&mut state, "session-1", "player-1", None, None);
+    let inbound = Arc::clone(&state.sessions["session-1"].inbound);
 
-    let candidate = snake_from_xs(&[SPAWN_PLAYER_MIN_DISTANCE + 0.05]);
-    assert!(!state.is_snake_too_close(&candidate, None));
-}
-
-#[test]
-fn spawn_check_ignores_excluded_player_id() {
-    let mut state = make_state();
+    let budget = INPUT_RATE_LIMIT_PER_TICK as u16;
+    let up = Point {
+        x: 0.0,
+        y: 1.0,
+        z: 0.0,
+    };
+    let down = Point {
+        x: 0.0,
+        y: -1.0,
+        z: 0.0,
+    };
+    for seq in 1..=budget {
+        inbound.update_input(Some(up), false, Some(seq));
+    }
+    for seq in budget + 1..=budget + 5 {
+        inbound.update_input(Some(down), true, Some(seq));
+    }
+    state.apply_session_inbound();
+
+    let player = &state.players["player-1"];
+    assert!((player.target_axis.y + 1.0).abs() < 1e-9);
+    assert!(player.boost);
+    assert_eq!(
+        state.sessions["session-1"].latest_applied_input_seq,
+        budget + 5
+    );
+    assert_eq!(state.over_budget_inputs, 5);
+
+    // The budget resets every tick.
+    inbound.update_input(Some(up), false, Some(budget + 6));
+    state.apply_session_inbound();
+    let player = &state.players["player-1"];
+    assert!((player.target_axis.y - 1.0).abs() < 1e-9);
+    assert!(!player.boost);
+    assert_eq!(state.over_budget_inputs, 5);
+}
+
+fn insert_humans(state: &mut RoomState, count: usize) {
+    for index in 0..count {
+        let id = format!("human-{index}");
+        let mut player = make_player(&id, make_snake(STARTING_LENGTH, 0.0));
+        player.snake.clear();
+        player.alive = false;
+        state.players.insert(id, player);
+    }
+}
+
+#[test]
+fn bot_config_scales_desired_bots_with_humans() {
+    let config = BotConfig {
+        max_bots: 5,
+        min_bots: 1,
+    };
+    assert_eq!(config.desired_bots(0), 0);
+    assert_eq!(config.desired_bots(1), 4);
+    assert_eq!(config.desired_bots(3), 2);
+    assert_eq!(config.desired_bots(20), 1);
+
+    let min_above_max = BotConfig {
+        max_bots: 2,
+        min_bots: 4,
+    };
+    assert_eq!(min_above_max.desired_bots(10), 2);
+}
+
+#[test]
+fn ensure_bots_removes_all_bots_without_humans() {
+    let mut state = make_state();
+    state.bot_config = BotConfig {
+        max_bots: 5,
+        min_bots: 2,
+    };
+    insert_humans(&mut state, 1);
+    state.ensure_bots();
+    assert_eq!(state.bot_count(), 4);
+
+    state.players.retain(|_, player| player.is_bot);
+    state.ensure_bots();
+    assert_eq!(state.bot_count(), 0);
+}
+
+#[test]
+fn no_bots_room_never_spawns_bots_across_ticks() {
+    let mut state = make_state();
+    state.bot_config = BotConfig {
+        max_bots: 5,
+        min_bots: 2,
+    };
+    state.bots_enabled = false;
+    insert_humans(&mut state, 1);
+    for _ in 0..10 {
+        state.tick();
+        assert_eq!(state.bot_count(), 0);
+    }
+
+    // Switching a populated room to PvP drops the bots it already had.
+    state.bots_enabled = true;
+    state.ensure_bots();
+    assert_eq!(state.bot_count(), 4);
+    state.bots_enabled = false;
+    state.tick();
+    assert_eq!(state.bot_count(), 0);
+}
+
+#[tokio::test]
+async fn no_bots_room_stops_its_tick_loop_once_sessions_leave() {
+    let room = Arc::new(
+        Room::with_room_id("pvp".to_string())
+            .with_bots_enabled(false)
+            .with_idle_linger_ms(0),
+    );
+    let _rx = {
+        let mut state = room.state.lock().await;
+        insert_pending_session(&mut state, "session-1")
+    };
+    room.ensure_loop();
+    assert!(room.running.load(Ordering::SeqCst));
+
+    room.remove_session("session-1").await;
+    for _ in 0..40 {
+        if !room.running.load(Ordering::SeqCst) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(TICK_MS)).await;
+    }
+    assert!(!room.running.load(Ordering::SeqCst));
+    assert_eq!(room.state.lock().await.bot_count(), 0);
+}
+
+#[test]
+fn ensure_bots_sheds_bots_as_humans_join() {
+    let mut state = make_state();
+    state.bot_config = BotConfig {
+        max_bots: 5,
+        min_bots: 2,
+    };
+    insert_humans(&mut state, 1);
+    state.ensure_bots();
+    assert_eq!(state.bot_count(), 4);
+
+    insert_humans(&mut state, 12);
+    state.ensure_bots();
+    assert_eq!(state.bot_count(), 2);
+    let mut names: Vec<&str> = state
+        .players
+        .values()
+        .filter(|player| player.is_bot)
+        .map(|player| player.name.as_str())
+        .collect();
+    names.sort_unstable();
+    assert_eq!(names, vec!["Bot-1", "Bot-2"]);
+}
+
+#[test]
+fn room_with_bot_config_applies_to_state() {
+    let config = BotConfig {
+        max_bots: 3,
+        min_bots: 0,
+    };
+    let mut room = Room::with_room_id("bots".to_string()).with_bot_config(config);
+    assert_eq!(room.state.get_mut().bot_config, config);
+}
+
+fn equator_snake(head_angle: f64, len: usize) -> Vec<SnakeNode> {
+    (0..len)
+        .map(|index| {
+            let angle = head_angle - index as f64 * NODE_ANGLE;
+            SnakeNode {
+                x: angle.cos(),
+                y: angle.sin(),
+                z: 0.0,
+                pos_queue: VecDeque::new(),
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn idle_human_is_bot_steered_after_the_afk_threshold_and_reverts_on_input() {
+    let mut room = Room::with_room_id("afk".to_string()).with_afk_autopilot_ms(5_000);
+    let state = room.state.get_mut();
+    let away = Point {
+        x: 0.0,
+        y: 0.0,
+        z: -1.0,
+    };
+    let mut human = make_player("human", equator_snake(0.0, STARTING_LENGTH));
+    human.axis = away;
+    human.target_axis = away;
+    human.last_active_at = 10_000;
+    state.players.insert("human".to_string(), human);
+    insert_session_with_view(state, "session-1", "human", None, None);
+    state.pellets.push(make_pellet(
+        1,
+        Point {
+            x: 0.2f64.cos(),
+            y: 0.2f64.sin(),
+            z: 0.0,
+        },
+    ));
+
+    state.update_afk_autopilot(14_999);
+    state.update_bots();
+    assert!(!state.players["human"].afk_autopilot);
+    assert!(dot(state.players["human"].target_axis, away) > 0.99);
+
+    state.update_afk_autopilot(15_000);
+    state.update_bots();
+    let human = &state.players["human"];
+    assert!(human.afk_autopilot);
+    assert!(!human.is_bot);
+    assert!(
+        dot(
+            human.target_axis,
+            Point {
+                x: 0.0,
+                y: 0.0,
+                z: 1.0
+            }
+        ) > 0.99
+    );
+    assert_eq!(state.human_count(), 1);
+
+    // Steering away from the autopilot's heading counts as activity, handing the snake straight
+    // back to its player.
+    state.handle_input("session-1", Some(away), false, None);
+    state.update_afk_autopilot(15_100);
+    state.update_bots();
+    let human = &state.players["human"];
+    assert!(!human.afk_autopilot);
+    assert!(dot(human.target_axis, away) > 0.99);
+}
+
+#[test]
+fn repeated_heartbeat_inputs_do_not_keep_a_player_out_of_afk_autopilot() {
+    let mut room = Room::with_room_id("afk-heartbeat".to_string()).with_afk_autopilot_ms(5_000);
+    let state = room.state.get_mut();
+    let away = Point {
+        x: 0.0,
+        y: 0.0,
+        z: -1.0,
+    };
+    state.players.insert(
+        "human".to_string(),
+        make_player("human", equator_snake(0.0, STARTING_LENGTH)),
+    );
+    insert_session_with_view(state, "session-1", "human", None, None);
+    let inbound = Arc::clone(&state.sessions["session-1"].inbound);
+
+    inbound.update_input(Some(away), false, Some(1));
+    state.apply_session_inbound();
+    let active_at = state.players["human"].last_active_at;
+    assert!(active_at > 0);
+
+    // The client repeats its last input every 100ms even when nothing changed.
+    for seq in 2..=20 {
+        inbound.update_input(Some(away), false, Some(seq));
+        state.apply_session_inbound();
+    }
+    let human = &state.players["human"];
+    assert_eq!(human.last_active_at, active_at);
+    assert!(human.last_seen >= active_at);
+
+    state.update_afk_autopilot(active_at + 5_000);
+    assert!(state.players["human"].afk_autopilot);
+
+    // Toggling boost is real activity.
+    inbound.update_input(Some(away), true, Some(21));
+    state.apply_session_inbound();
+    let active_at = state.players["human"].last_active_at;
+    state.update_afk_autopilot(active_at + 4_999);
+    assert!(!state.players["human"].afk_autopilot);
+}
+
+#[test]
+fn input_dead_zone_ignores_jitter_but_follows_a_clear_turn() {
+    let up = Point {
+        x: 0.0,
+        y: 0.0,
+        z: 1.0,
+    };
+    let toward = |x: f64, y: f64, angle: f64| rotate_toward(up, Point { x, y, z: 0.0 }, angle);
+    let mut state = make_state();
+    state.input_config = InputConfig {
+        dead_zone: 0.05,
+        smoothing: 0.0,
+    };
+    let mut player = make_player("player-1", equator_snake(0.0, STARTING_LENGTH));
+    player.target_axis = up;
+    state.players.insert("player-1".to_string(), player);
+    insert_session_with_view(&mut state, "session-1", "player-1", None, None);
+
+    for jitter in [
+        toward(1.0, 0.0, 0.01),
+        toward(0.0, 1.0, 0.03),
+        toward(-1.0, 0.0, 0.045),
+    ] {
+        state.handle_input("session-1", Some(jitter), false, None);
+        assert_eq!(dot(state.players["player-1"].target_axis, up), 1.0);
+    }
+
+    let turn = toward(1.0, 0.0, 0.4);
+    state.handle_input("session-1", Some(turn), false, None);
+    assert!(dot(state.players["player-1"].target_axis, turn) > 1.0 - 1e-12);
+
+    // Off by default: the same jitter goes straight through.
+    state.input_config = InputConfig::default();
+    let jitter = toward(0.0, 1.0, 0.01);
+    state.players.get_mut("player-1").unwrap().target_axis = up;
+    state.handle_input("session-1", Some(jitter), false, None);
+    assert!(dot(state.players["player-1"].target_axis, jitter) > 1.0 - 1e-12);
+
+    // Smoothing closes part of the gap per input.
+    state.input_config = InputConfig {
+        dead_zone: 0.0,
+        smoothing: 0.5,
+    };
+    state.players.get_mut("player-1").unwrap().target_axis = up;
+    state.handle_input("session-1", Some(turn), false, None);
+    let angle = dot(state.players["player-1"].target_axis, up).acos();
+    assert!((angle - 0.2).abs() < 1e-9);
+
+    let sanitized = InputConfig {
+        dead_zone: 5.0,
+        smoothing: f64::NAN,
+    }
+    .sanitized();
+    assert_eq!(sanitized.dead_zone, 0.2);
+    assert_eq!(sanitized.smoothing, 0.0);
+}
+
+fn bot_target_forward_toward_crossing_body(skill: BotSkill) -> f64 {
+    let mut state = make_state();
+    let mut bot = make_player("bot", equator_snake(0.0, STARTING_LENGTH));
+    bot.is_bot = true;
+    bot.bot_skill = Some(skill);
+    bot.axis = Point {
+        x: 0.0,
+        y: 0.0,
+        z: 1.0,
+    };
+    state.players.insert("bot".to_string(), bot);
+
+    // A longer snake's body crosses the equator just ahead of the bot's head.
+    let crossing_angle: f64 = 0.03;
+    let blocker_snake: Vec<SnakeNode> = (0..24)
+        .map(|index| {
+            let lat = (index as f64 - 12.0) * NODE_ANGLE;
+            SnakeNode {
+                x: crossing_angle.cos() * lat.cos(),
+                y: crossing_angle.sin() * lat.cos(),
+                z: lat.sin(),
+                pos_queue: VecDeque::new(),
+            }
+        })
+        .collect();
+    let mut blocker = make_player("blocker", blocker_snake);
+    blocker.is_bot = true;
+    blocker.axis = Point {
+        x: -crossing_angle.sin(),
+        y: crossing_angle.cos(),
+        z: 0.0,
+    };
+    state.players.insert("blocker".to_string(), blocker);
+    state.pellets.push(make_pellet(
+        1,
+        Point {
+            x: 0.2f64.cos(),
+            y: 0.2f64.sin(),
+            z: 0.0,
+        },
+    ));
+
+    state.update_bots();
+
+    let bot = state.players.get("bot").expect("bot");
+    let head = Point {
+        x: 1.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    let target_forward = normalize(cross(bot.target_axis, head));
+    let to_body = normalize(Point {
+        x: crossing_angle.cos() - 1.0,
+        y: crossing_angle.sin(),
+        z: 0.0,
+    });
+    dot(target_forward, to_body)
+}
+
+#[test]
+fn hard_bot_steers_away_from_imminent_body_collision() {
+    let toward_body = bot_target_forward_toward_crossing_body(BotSkill::Hard);
+    assert!(
+        toward_body < 0.1,
+        "hard bot should turn off the collision course, got {toward_body}"
+    );
+}
+
+#[test]
+fn easy_bot_ignores_imminent_body_collision() {
+    let toward_body = bot_target_forward_toward_crossing_body(BotSkill::Easy);
+    assert!(
+        toward_body > 0.9,
+        "easy bot should keep heading for the pellet, got {toward_body}"
+    );
+}
+
+#[test]
+fn ensure_bots_assigns_mixed_skills() {
+    let mut state = make_state();
+    state.bot_config = BotConfig {
+        max_bots: 4,
+        min_bots: 3,
+    };
+    insert_humans(&mut state, 1);
+    state.ensure_bots();
+    let skills: HashSet<_> = state
+        .players
+        .values()
+        .filter_map(|player| player.bot_skill)
+        .collect();
+    assert_eq!(skills.len(), 3);
+}
+
+fn underwater_oxygen_after_tick(day_phase: f64) -> f64 {
+    let mut state = make_state();
+    state.day_night = Some(DayNightConfig::default());
+    state.day_phase = day_phase;
+    state.oxygen_grace_ms = 0;
+    let lake_center = state.environment.lakes[0].center;
+    let mut player = make_player("diver", make_snake(1, 0.0));
+    player.snake[0].x = lake_center.x;
+    player.snake[0].y = lake_center.y;
+    player.snake[0].z = lake_center.z;
+    state.players.insert("diver".to_string(), player);
+
+    let mut death_reasons = HashMap::new();
+    state.update_oxygen(TICK_MS as f64 / 1000.0, &mut death_reasons);
+    state.players["diver"].oxygen
+}
+
+#[test]
+fn night_phase_drains_more_oxygen_underwater_than_day() {
+    let day_oxygen = underwater_oxygen_after_tick(0.0);
+    let night_oxygen = underwater_oxygen_after_tick(0.5);
+    assert!(day_oxygen < OXYGEN_MAX);
+    assert!(
+        night_oxygen < day_oxygen,
+        "night {night_oxygen} should drain more than day {day_oxygen}"
+    );
+}
+
+#[test]
+fn day_phase_advances_only_when_cycle_enabled() {
+    let mut state = make_state();
+    state.update_day_phase(1_000);
+    assert_eq!(state.day_phase_q, None);
+
+    state.day_night = Some(DayNightConfig {
+        cycle_ms: 1_000,
+        ..DayNightConfig::default()
+    });
+    state.update_day_phase(1_500);
+    assert_eq!(state.day_phase_q, Some(128));
+    assert!((state.oxygen_drain_multiplier() - NIGHT_OXYGEN_DRAIN_MULTIPLIER).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn player_snapshot_ranks_players_by_score() {
+    let room = Room::with_room_id("overlay".to_string());
+    {
+        let mut state = room.state.lock().await;
+        for (id, net_id, score, len) in [
+            ("leader", 1, 90, 9),
+            ("middle", 2, 40, 6),
+            ("tied", 3, 40, 5),
+            ("last", 4, 10, 4),
+        ] {
+            let mut player = make_player(id, make_snake(len, 0.0));
+            player.name = id.to_string();
+            player.net_id = net_id;
+            player.score = score;
+            state.players.insert(id.to_string(), player);
+        }
+        state.players.get_mut("last").expect("last").alive = false;
+    }
+
+    let leader = room.player_snapshot(1).await.expect("leader");
+    assert_eq!((leader.rank, leader.score, leader.length), (1, 90, 9));
+    assert_eq!(leader.name, "leader");
+    assert_eq!(leader.total_players, 4);
+    assert_eq!(room.player_snapshot(2).await.expect("middle").rank, 2);
+    assert_eq!(room.player_snapshot(3).await.expect("tied").rank, 2);
+    let last = room.player_snapshot(4).await.expect("last");
+    assert_eq!(last.rank, 4);
+    assert!(!last.alive);
+    assert_eq!(room.player_snapshot(99).await, None);
+}
+
+/// `TYPE_INIT` ends with the `f32` base speed and boost multiplier, then the `u16` minimum
+/// survival length.
+const INIT_TRAILER_LEN: usize = 10;
+
+#[test]
+fn environment_preview_matches_init_environment() {
+    let mut room = Room::with_room_id("preview".to_string()).with_environment_seed(7);
+    let state = room.state.get_mut();
+    state.players.insert(
+        "player-1".to_string(),
+        make_player("player-1", make_snake(6, 0.0)),
+    );
+    let environment = state.environment_bytes();
+    assert!(!environment.is_empty());
+
+    let payload = state.build_init_payload_for_session("missing-session", "player-1", false);
+    // Girth max scale, palette, then the trailer follow the environment.
+    let trailer_len = 4 + 1 + 3 * state.pellet_palette.colors().len() + INIT_TRAILER_LEN;
+    let end = payload.len() - trailer_len;
+    assert_eq!(
+        &payload[end - environment.len()..end],
+        environment.as_slice()
+    );
+}
+
+#[test]
+fn pellet_palette_themes_spawned_pellets_and_is_sent_in_init() {
+    let palette = PelletPalette::parse("#ff0000, #00ff00").expect("palette");
+    let mut room = Room::with_room_id("themed".to_string()).with_pellet_palette(palette);
+    let state = room.state.get_mut();
+    state.ensure_pellets();
+    assert!(!state.pellets.is_empty());
+    for pellet in &state.pellets {
+        assert!(pellet.color_rgb == [255, 0, 0] || pellet.color_rgb == [0, 255, 0]);
+    }
+
+    let payload = state.build_init_payload_for_session("missing-session", "missing-player", false);
+    let end = payload.len() - INIT_TRAILER_LEN;
+    assert_eq!(&payload[end - 7..end], &[2, 255, 0, 0, 0, 255, 0]);
+
+    assert_eq!(PelletPalette::parse("#ff0000,red"), None);
+    assert_eq!(PelletPalette::parse(""), None);
+    let oversized = PelletPalette::new(vec![[1, 2, 3]; 100]);
+    assert_eq!(oversized.colors().len(), MAX_PELLET_PALETTE_COLORS);
+    assert_eq!(PelletPalette::new(Vec::new()), PelletPalette::default());
+}
+
+#[test]
+fn custom_starting_length_sets_initial_score_and_boost_floor() {
+    for starting_length in [5, 16] {
+        let mut room = Room::with_room_id("growth".to_string()).with_growth_config(GrowthConfig {
+            starting_length,
+            ..GrowthConfig::default()
+        });
+        let state = room.state.get_mut();
+        state.bot_config = BotConfig {
+            max_bots: 0,
+            min_bots: 0,
+        };
+
+        let player = state
+            .create_player(Uuid::new_v4(), "Grower".to_string(), false)
+            .expect("player");
+        assert!(player.alive);
+        assert_eq!(player.snake.len(), starting_length);
+        assert_eq!(player.score, starting_length as i64);
+        assert_eq!(player.boost_floor_len, starting_length);
+        assert!((state.player_girth_scale_from_len(player.snake.len()) - 1.0).abs() < 1e-9);
+
+        let player_id = player.id.clone();
+        state.players.insert(player_id.clone(), player);
+        state.handle_death(&player_id);
+        state.respawn_player(&player_id);
+        let player = &state.players[&player_id];
+        assert!(player.alive);
+        assert_eq!(player.snake.len(), starting_length);
+        assert_eq!(player.score, starting_length as i64);
+        assert_eq!(player.boost_floor_len, starting_length);
+    }
+}
+
+#[test]
+fn growth_config_max_scale_drives_girth_encoding_and_init_trailer() {
+    let mut room = Room::with_room_id("arcade".to_string()).with_growth_config(GrowthConfig {
+        girth_max_scale: 3.0,
+        ..GrowthConfig::default()
+    });
+    let state = room.state.get_mut();
+    let capped = state.player_girth_scale_from_len(STARTING_LENGTH + 10_000);
+    assert!((capped - 3.0).abs() < 1e-9);
+    assert_eq!(state.quantize_girth_scale_u8(capped), u8::MAX);
+    assert_eq!(state.quantize_girth_scale_u8(2.0), 128);
+
+    let payload = state.build_init_payload_for_session("missing-session", "missing-player", false);
+    let end = payload.len() - INIT_TRAILER_LEN - (1 + state.pellet_palette.colors().len() * 3);
+    let girth_max: [u8; 4] = payload[end - 4..end].try_into().unwrap();
+    assert_eq!(f32::from_le_bytes(girth_max), 3.0);
+
+    let sanitized = GrowthConfig {
+        starting_length: 0,
+        min_survival_length: 0,
+        girth_step_percent: f64::NAN,
+        girth_nodes_per_step: 0,
+        girth_max_scale: 100.0,
+    }
+    .sanitized();
+    assert_eq!(sanitized.starting_length, MIN_SURVIVAL_LENGTH);
+    assert_eq!(sanitized.min_survival_length, MIN_SURVIVAL_LENGTH);
+    assert_eq!(
+        sanitized.girth_step_percent,
+        GrowthConfig::default().girth_step_percent
+    );
+    assert_eq!(sanitized.girth_nodes_per_step, 1);
+    assert!(sanitized.girth_max_scale <= 4.0);
+}
+
+fn split_replay_frames(blob: &[u8]) -> Vec<Vec<u8>> {
+    let mut frames = Vec::new();
+    let mut offset = 0usize;
+    while offset < blob.len() {
+        let len = read_u32(blob, &mut offset) as usize;
+        frames.push(blob[offset..offset + len].to_vec());
+        offset += len;
+    }
+    frames
+}
+
+#[test]
+fn recording_captures_state_frames_in_order() {
+    let mut state = make_state();
+    state.recording = RecordingBuffer::new(16);
+    state.players.insert(
+        "player-1".to_string(),
+        make_player("player-1", make_snake(3, 0.0)),
+    );
+    insert_session_with_view(&mut state, "session-1", "player-1", None, None);
+
+    state.broadcast_state_delta(1, 1);
+    assert_eq!(state.recording.len(), 0);
+
+    state.recording.set_enabled(true);
+    for seq in 10..15u32 {
+        state.broadcast_state_delta(seq as i64, seq);
+    }
+
+    let frames = split_replay_frames(&state.recording.export());
+    assert_eq!(frames.len(), 5);
+    for (index, frame) in frames.iter().enumerate() {
+        let (state_seq, _, _) = decode_state_counts(frame);
+        assert_eq!(state_seq, 10 + index as u32);
+    }
+}
+
+#[test]
+fn recording_buffer_drops_oldest_frames_at_capacity() {
+    let mut buffer = RecordingBuffer::new(3);
+    buffer.set_enabled(true);
+    for value in 0..5u8 {
+        buffer.push(vec![value], true);
+    }
+    assert_eq!(
+        split_replay_frames(&buffer.export()),
+        vec![vec![2], vec![3], vec![4]]
+    );
+}
+
+#[test]
+fn recording_export_starts_at_the_oldest_retained_keyframe() {
+    let mut buffer = RecordingBuffer::new(4);
+    buffer.set_enabled(true);
+    for value in 0..6u8 {
+        buffer.push(vec![value], value % 4 == 0);
+    }
+    // Frames 2 and 3 are deltas whose keyframe (0) was evicted.
+    assert_eq!(
+        split_replay_frames(&buffer.export()),
+        vec![vec![4], vec![5]]
+    );
+    assert!(!buffer.needs_keyframe());
+
+    buffer.push(vec![6], false);
+    buffer.push(vec![7], false);
+    buffer.push(vec![8], false);
+    assert!(buffer.needs_keyframe());
+    assert!(buffer.export().is_empty());
+}
+
+#[test]
+fn wrapped_recording_forces_a_keyframe_so_the_export_stays_decodable() {
+    let mut state = make_state();
+    state.recording = RecordingBuffer::new(2);
+    state.players.insert(
+        "player-1".to_string(),
+        make_player("player-1", make_snake(3, 0.0)),
+    );
+    insert_session_with_view(&mut state, "session-1", "player-1", None, None);
+    state.recording.set_enabled(true);
+
+    for seq in 1..20u32 {
+        state.broadcast_state_delta(seq as i64, seq);
+        let frames = split_replay_frames(&state.recording.export());
+        assert!(!frames.is_empty(), "seq {seq}");
+        assert_ne!(
+            decode_state_frame_flags(&frames[0]) & DELTA_FRAME_KEYFRAME,
+            0,
+            "seq {seq}"
+        );
+    }
+}
+
+#[test]
+fn tick_histogram_buckets_durations_and_reports_quantiles() {
+    let histogram = TickHistogram::default();
+    assert_eq!(histogram.snapshot().quantile_us(0.5), None);
+
+    for micros in [100, 250, 251, 900, 3_000, 3_000, 45_000, 50_001, 250_000] {
+        histogram.record(Duration::from_micros(micros));
+    }
+    histogram.record(Duration::from_secs(u64::MAX));
+
+    let snapshot = histogram.snapshot();
+    assert_eq!(snapshot.counts, [2, 1, 1, 0, 2, 0, 0, 0, 1, 1, 2]);
+    assert_eq!(snapshot.total(), 10);
+    assert_eq!(snapshot.quantile_us(0.0), Some(250));
+    assert_eq!(snapshot.quantile_us(0.5), Some(4_000));
+    assert_eq!(snapshot.quantile_us(0.7), Some(50_000));
+    // The overflow bucket reports the last bound as a floor.
+    assert_eq!(snapshot.quantile_us(0.99), Some(100_000));
+}
+
+#[test]
+fn repeated_tick_overruns_shed_pellets_until_recovery() {
+    let mut state = make_state();
+    let full_target = state.pellet_target_count();
+    let slow = Duration::from_millis(TICK_MS * 3);
+    let fast = Duration::from_millis(TICK_MS / 2);
+
+    for _ in 0..TICK_OVERRUN_SHED_THRESHOLD - 1 {
+        state.record_tick_duration(slow);
+    }
+    assert_eq!(
+        state.tick_overruns,
+        (TICK_OVERRUN_SHED_THRESHOLD - 1) as u64
+    );
+    assert_eq!(state.pellet_target_count(), full_target);
+
+    state.record_tick_duration(slow);
+    assert!(state.pellet_load_factor < 1.0);
+    assert!(state.pellet_target_count() < full_target);
+    // Shedding restarts the shed streak but not the logged overrun run.
+    assert_eq!(state.overrun_streak, 0);
+    assert_eq!(state.overrun_run_ticks, TICK_OVERRUN_SHED_THRESHOLD);
+
+    for _ in 0..TICK_RECOVERY_HEALTHY_TICKS * 10 {
+        state.record_tick_duration(fast);
+    }
+    assert!((state.pellet_load_factor - 1.0).abs() < 1e-9);
+    assert_eq!(state.pellet_target_count(), full_target);
+    assert_eq!(state.tick_overruns, TICK_OVERRUN_SHED_THRESHOLD as u64);
+    assert_eq!(state.overrun_run_ticks, 0);
+}
+
+#[test]
+fn shedding_load_thins_idle_pellets_in_bounded_batches() {
+    let mut state = make_state();
+    state.pellet_load_factor = 0.5;
+    let target = state.pellet_target_count();
+    let surplus = PELLET_LOAD_TRIM_PER_TICK * 2 + 5;
+    let normal = Point {
+        x: 0.0,
+        y: 0.0,
+        z: 1.0,
+    };
+    let mut attracting = make_pellet(0, normal);
+    attracting.state = PelletState::Attracting {
+        target_player_id: "player-1".to_string(),
+    };
+    state.pellets.push(attracting);
+    for id in 1..(target + surplus) as u32 {
+        state.pellets.push(make_pellet(id, normal));
+    }
+
+    state.ensure_pellets();
+    assert_eq!(
+        state.pellets.len(),
+        target + surplus - PELLET_LOAD_TRIM_PER_TICK
+    );
+    // The oldest idle pellets go first.
+    assert_eq!(state.pellets[1].id, PELLET_LOAD_TRIM_PER_TICK as u32 + 1);
+    state.ensure_pellets();
+    state.ensure_pellets();
+    assert_eq!(state.pellets.len(), target);
+    assert!(matches!(
+        state.pellets[0].state,
+        PelletState::Attracting { .. }
+    ));
+
+    // At full load the surplus is left to be eaten.
+    state.pellet_load_factor = 1.0;
+    let full_target = state.pellet_target_count();
+    for id in 0..(full_target as u32 + 10) {
+        state.pellets.push(make_pellet(10_000 + id, normal));
+    }
+    let before = state.pellets.len();
+    state.ensure_pellets();
+    assert_eq!(
+        state.pellets.len(),
+        before.min(state.pellet_config.max_count)
+    );
+}
+
+#[test]
+fn shedding_load_keeps_death_and_boost_trail_pellets() {
+    let mut state = make_state();
+    state.pellet_load_factor = 0.5;
+    let target = state.pellet_target_count();
+    let normal = Point {
+        x: 0.0,
+        y: 0.0,
+        z: 1.0,
+    };
+    let mut death = make_pellet(0, normal);
+    death.growth_fraction = BIG_PELLET_GROWTH_FRACTION;
+    state.pellets.push(death);
+    let mut trail = make_pellet(1, normal);
+    trail.growth_fraction = BOOST_TRAIL_PELLET_GROWTH_FRACTION;
+    trail.expires_at_ms = Some(i64::MAX);
+    state.pellets.push(trail);
+    for id in 2..(target + PELLET_LOAD_TRIM_PER_TICK) as u32 {
+        state.pellets.push(make_pellet(id, normal));
+    }
+
+    state.ensure_pellets();
+    assert_eq!(state.pellets.len(), target);
+    // The two oldest pellets were left by players, so ambient ones went instead.
+    assert_eq!(state.pellets[0].id, 0);
+    assert_eq!(state.pellets[1].id, 1);
+}
+
+#[test]
+fn pellet_load_factor_never_drops_below_floor() {
+    let mut state = make_state();
+    for _ in 0..TICK_OVERRUN_SHED_THRESHOLD * 100 {
+        state.record_tick_duration(Duration::from_millis(TICK_MS * 2));
+    }
+    assert!((state.pellet_load_factor - PELLET_LOAD_FACTOR_MIN).abs() < 1e-9);
+}
+
+#[test]
+fn json_wire_format_emits_readable_state_frames() {
+    let mut state = make_state();
+    let mut player = make_player("player-1", make_snake(3, 0.0));
+    player.snake = equator_snake(0.0, 3);
+    state.players.insert("player-1".to_string(), player);
+    state.pellets.push(make_pellet(
+        7,
+        Point {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        },
+    ));
+    insert_session_with_view(&mut state, "session-1", "player-1", None, None);
+    state.sessions.get_mut("session-1").unwrap().wire_format = WireFormat::Json;
+
+    state.broadcast_state_delta(1234, 9);
+    let payload = state.sessions["session-1"]
+        .outbound_state
+        .take_latest()
+        .expect("state frame");
+    let frame: serde_json::Value = serde_json::from_slice(&payload).expect("json frame");
+    assert_eq!(frame["type"], "state");
+    assert_eq!(frame["seq"], 9);
+    assert_eq!(frame["players"][0]["id"], "player-1");
+    assert_eq!(frame["players"][0]["snake"].as_array().unwrap().len(), 3);
+    assert_eq!(frame["pellets"][0]["id"], 7);
+}
+
+#[test]
+fn wire_format_parses_format_query() {
+    assert_eq!(WireFormat::from_query(Some("json")), WireFormat::Json);
+    assert_eq!(WireFormat::from_query(Some(" JSON ")), WireFormat::Json);
+    assert_eq!(WireFormat::from_query(Some("binary")), WireFormat::Binary);
+    assert_eq!(WireFormat::from_query(None), WireFormat::Binary);
+}
+
+#[test]
+fn hello_records_negotiated_version_on_session() {
+    let mut state = make_state();
+    insert_session_with_view(&mut state, "session-1", "player-1", None, None);
+    state
+        .sessions
+        .get_mut("session-1")
+        .unwrap()
+        .protocol_version = None;
+
+    let negotiated = state.negotiate_session_version("session-1", protocol::MIN_SUPPORTED_VERSION);
+    assert_eq!(negotiated, Ok(protocol::MIN_SUPPORTED_VERSION));
+    assert_eq!(
+        state.sessions["session-1"].protocol_version,
+        Some(protocol::MIN_SUPPORTED_VERSION)
+    );
+}
+
+#[test]
+fn hello_rejects_too_old_and_too_new_versions() {
+    let mut state = make_state();
+    insert_session_with_view(&mut state, "session-1", "player-1", None, None);
+    state
+        .sessions
+        .get_mut("session-1")
+        .unwrap()
+        .protocol_version = None;
+
+    assert_eq!(
+        state.negotiate_session_version("session-1", protocol::MIN_SUPPORTED_VERSION - 1),
+        Err(protocol::ServerError::VersionTooOld)
+    );
+    assert_eq!(
+        state.negotiate_session_version("session-1", protocol::VERSION + 1),
+        Err(protocol::ServerError::VersionTooNew)
+    );
+    assert_eq!(state.sessions["session-1"].protocol_version, None);
+}
+
+fn run_boost_pattern(pattern: impl Fn(usize) -> bool, ticks: usize) -> (f64, usize) {
+    let mut state = make_state();
+    state.bot_config = BotConfig {
+        max_bots: 0,
+        min_bots: 0,
+    };
+    let mut player = make_player(
+        "player-boost",
+        create_snake(Point {
+            x: 0.0,
+            y: 0.0,
+            z: 1.0,
+        }),
+    );
+    player.target_axis = player.axis;
+    player.boost_floor_len = MIN_SURVIVAL_LENGTH;
+    player.score = 200;
+    state.players.insert("player-boost".to_string(), player);
+
+    let head = |player: &Player| Point {
+        x: player.snake[0].x,
+        y: player.snake[0].y,
+        z: player.snake[0].z,
+    };
+    let mut distance = 0.0;
+    let mut boosted_ticks = 0;
+    for tick in 0..ticks {
+        let before = head(&state.players["player-boost"]);
+        state.players.get_mut("player-boost").unwrap().boost = pattern(tick);
+        state.tick();
+        let player = &state.players["player-boost"];
+        let after = head(player);
+        distance += length(cross(before, after)).atan2(dot(before, after));
+        if player.is_boosting {
+            boosted_ticks += 1;
+        }
+    }
+    (distance, boosted_ticks)
+}
+
+#[test]
+fn alternating_boost_flags_gain_no_speed_over_sustained_boost() {
+    let ticks = 40;
+    let (base_distance, _) = run_boost_pattern(|_| false, ticks);
+    let (sustained_distance, sustained_ticks) = run_boost_pattern(|_| true, ticks);
+    let pressed = |tick: usize| tick.is_multiple_of(2);
+    let (alternating_distance, alternating_ticks) = run_boost_pattern(pressed, ticks);
+
+    assert_eq!(sustained_ticks, ticks);
+    assert!(alternating_distance <= sustained_distance);
+
+    // Drain is charged per boosted tick, so toggling must never boost on fewer ticks than the
+    // flag was held, and each boosted tick buys exactly the sustained-boost extra distance.
+    let pressed_ticks = (0..ticks).filter(|tick| pressed(*tick)).count();
+    assert!(alternating_ticks >= pressed_ticks);
+    let sustained_extra_per_tick = (sustained_distance - base_distance) / sustained_ticks as f64;
+    let alternating_extra = alternating_distance - base_distance;
+    assert!(
+        alternating_extra <= sustained_extra_per_tick * alternating_ticks as f64 * (1.0 + 1e-3)
+    );
+}
+
+#[test]
+fn released_boost_waits_for_cooldown_before_reengaging() {
+    let mut player = make_player("player-1", snake_from_xs(&[1.0, 0.99, 0.98, 0.97]));
+    player.boost = true;
+    assert!(RoomState::resolve_boost_intent(&mut player));
+    player.is_boosting = true;
+    player.boost = false;
+    for _ in 1..BOOST_MIN_ENGAGE_TICKS {
+        assert!(RoomState::resolve_boost_intent(&mut player));
+    }
+    assert!(!RoomState::resolve_boost_intent(&mut player));
+    player.is_boosting = false;
+    player.boost = true;
+    for _ in 0..BOOST_COOLDOWN_TICKS {
+        assert!(!RoomState::resolve_boost_intent(&mut player));
+    }
+    assert!(RoomState::resolve_boost_intent(&mut player));
+}
+
+fn respawn_length_after_death(retention: f64, death_len: usize) -> usize {
+    let mut state = make_state();
+    state.respawn_length_retention = retention;
+    let player = make_player(
+        "player-1",
+        create_snake_with_length(
+            Point {
+                x: 0.0,
+                y: 0.0,
+                z: 1.0,
+            },
+            death_len,
+        ),
+    );
+    state.players.insert("player-1".to_string(), player);
+
+    state.handle_death("player-1");
+    state.respawn_player("player-1");
+
+    let player = &state.players["player-1"];
+    assert!(player.alive);
+    assert_eq!(player.score, player.snake.len() as i64);
+    assert_eq!(player.respawn_length, None);
+    player.snake.len()
+}
+
+#[test]
+fn respawn_without_retention_starts_at_default_length() {
+    assert_eq!(respawn_length_after_death(0.0, 40), STARTING_LENGTH);
+}
+
+#[test]
+fn respawn_with_half_retention_keeps_half_the_length() {
+    assert_eq!(respawn_length_after_death(0.5, 40), 20);
+    // Retained lengths below the default spawn never shrink the snake.
+    assert_eq!(respawn_length_after_death(0.5, 10), STARTING_LENGTH);
+}
+
+#[test]
+fn respawn_with_full_retention_keeps_the_whole_length() {
+    assert_eq!(respawn_length_after_death(1.0, 40), 40);
+}
+
+fn death_pellet_growth_after_death(retention: f64, death_len: usize) -> Vec<f64> {
+    let mut state = make_state();
+    state.respawn_length_retention = retention;
+    let player = make_player(
+        "player-1",
+        create_snake_with_length(
+            Point {
+                x: 0.0,
+                y: 0.0,
+                z: 1.0,
+            },
+            death_len,
+        ),
+    );
+    state.players.insert("player-1".to_string(), player);
+
+    state.handle_death("player-1");
+    state
+        .pellets
+        .iter()
+        .map(|pellet| pellet.growth_fraction)
+        .collect()
+}
+
+#[test]
+fn retained_length_is_not_dropped_again_as_death_pellets() {
+    let full = death_pellet_growth_after_death(0.0, 40);
+    assert!(!full.is_empty());
+    assert!(full
+        .iter()
+        .all(|growth| (growth - BIG_PELLET_GROWTH_FRACTION).abs() < 1e-12));
+
+    let half = death_pellet_growth_after_death(0.5, 40);
+    assert_eq!(half.len(), full.len());
+    assert!(half
+        .iter()
+        .all(|growth| (growth - BIG_PELLET_GROWTH_FRACTION * 0.5).abs() < 1e-12));
+
+    assert!(death_pellet_growth_after_death(1.0, 40).is_empty());
+
+    // A snake too short to keep anything past the default spawn drops its full value.
+    let short = death_pellet_growth_after_death(0.5, 10);
+    assert!(!short.is_empty());
+    assert!(short
+        .iter()
+        .all(|growth| (growth - BIG_PELLET_GROWTH_FRACTION).abs() < 1e-12));
+}
+
+#[test]
+fn json_join_rejects_oversized_or_malformed_skin() {
+    let oversized = format!(
+        r#"{{"type":"join","skin":[{}]}}"#,
+        [r##""#ff0000""##; protocol::MAX_SKIN_COLORS + 1].join(",")
+    );
+    assert!(serde_json::from_str::<JsonClientMessage>(&oversized).is_err());
+    let malformed = r##"{"type":"join","skin":["#ff00zz"]}"##;
+    assert!(serde_json::from_str::<JsonClientMessage>(malformed).is_err());
+
+    let valid = r##"{"type":"join","skin":["#12ab34","#ffffff"]}"##;
+    match serde_json::from_str::<JsonClientMessage>(valid).expect("valid join") {
+        JsonClientMessage::Join { skin, .. } => {
+            assert_eq!(skin, Some(vec![[0x12, 0xab, 0x34], [0xff, 0xff, 0xff]]));
+        }
+        other => panic!("unexpected message {other:?}"),
+    }
+}
+
+#[test]
+fn join_with_skin_sets_player_color_from_first_entry() {
+    let mut state = make_state();
+    let player_id = Uuid::new_v4();
+    let accepted = state.handle_join(
+        "session-1",
+        Some("Skinned".to_string()),
+        Some(player_id),
+        true,
+        Some(vec![[0x12, 0xab, 0x34], [0xff, 0xff, 0xff]]),
+        None,
+    );
+    assert!(accepted);
+
+    let player = &state.players[&player_id.to_string()];
+    assert_eq!(player.color, "#12ab34");
+    assert_eq!(player.skin.as_ref().map(Vec::len), Some(2));
+}
+
+fn insert_pending_session(state: &mut RoomState, session_id: &str) -> OutboundReceiver {
+    insert_session_with_view(state, session_id, "", None, None);
+    let (outbound_hi, outbound_hi_rx) = outbound_queue(4, OutboundFullPolicy::Disconnect);
+    let session = state.sessions.get_mut(session_id).expect("session");
+    session.outbound_hi = outbound_hi;
+    session.player_id = None;
+    outbound_hi_rx
+}
+
+#[test]
+fn join_rejected_at_capacity_sends_room_full_error() {
+    let mut state = make_state();
+    insert_humans(&mut state, 2);
+    let mut outbound_hi_rx = insert_pending_session(&mut state, "session-1");
+
+    let accepted = state.handle_join(
+        "session-1",
+        Some("Late".to_string()),
+        Some(Uuid::new_v4()),
+        true,
+        None,
+        Some(2),
+    );
+    assert!(!accepted);
+
+    let frame = outbound_hi_rx.try_recv().expect("error frame");
+    assert_eq!(frame[1], protocol::TYPE_ERROR);
+    assert_eq!(frame[4], protocol::ERROR_ROOM_FULL);
+}
+
+#[test]
+fn join_rejected_while_draining_sends_draining_error() {
+    let mut state = make_state();
+    state.draining = true;
+    let mut outbound_hi_rx = insert_pending_session(&mut state, "session-1");
+
+    let accepted = state.handle_join("session-1", None, None, true, None, None);
+    assert!(!accepted);
+
+    let frame = outbound_hi_rx.try_recv().expect("error frame");
+    assert_eq!(frame[1], protocol::TYPE_ERROR);
+    assert_eq!(frame[4], protocol::ERROR_DRAINING);
+}
+
+fn init_flags(outbound_hi_rx: &mut OutboundReceiver) -> u16 {
+    let frame = outbound_hi_rx.try_recv().expect("init frame");
+    assert_eq!(frame[1], protocol::TYPE_INIT);
+    u16::from_le_bytes([frame[2], frame[3]])
+}
+
+#[test]
+fn rejoin_with_player_id_resumes_the_player_and_flags_init() {
+    let mut state = make_state();
+    let player_uuid = Uuid::new_v4();
+    let player_id = player_uuid.to_string();
+    let mut first_rx = insert_pending_session(&mut state, "session-1");
+    assert!(state.handle_join("session-1", None, Some(player_uuid), true, None, None));
+    assert_eq!(init_flags(&mut first_rx) & protocol::FLAG_INIT_RESUMED, 0);
+    let net_id = state.players[&player_id].net_id;
+
+    state.disconnect_session("session-1");
+    assert_eq!(
+        state.find_player_for_reconnect(Some(player_uuid)),
+        Some(player_id.clone())
+    );
+    let mut second_rx = insert_pending_session(&mut state, "session-2");
+    assert!(state.handle_join("session-2", None, Some(player_uuid), true, None, None));
+
+    assert_ne!(init_flags(&mut second_rx) & protocol::FLAG_INIT_RESUMED, 0);
+    assert_eq!(state.players.len(), 1);
+    assert_eq!(state.players[&player_id].net_id, net_id);
+    assert!(state.players[&player_id].connected);
+}
+
+#[test]
+fn join_without_a_resumable_player_creates_a_fresh_one() {
+    let mut state = make_state();
+    let bot_uuid = Uuid::new_v4();
+    let mut bot = make_player(&bot_uuid.to_string(), Vec::new());
+    bot.is_bot = true;
+    bot.net_id = 7;
+    state.players.insert(bot_uuid.to_string(), bot);
+
+    assert_eq!(state.find_player_for_reconnect(None), None);
+    assert_eq!(state.find_player_for_reconnect(Some(Uuid::new_v4())), None);
+    assert_eq!(state.find_player_for_reconnect(Some(bot_uuid)), None);
+
+    // A bot's id is not handed to a client; the join gets its own player and net id.
+    let mut outbound_hi_rx = insert_pending_session(&mut state, "session-1");
+    assert!(state.handle_join("session-1", None, Some(bot_uuid), true, None, None));
+    assert_eq!(
+        init_flags(&mut outbound_hi_rx) & protocol::FLAG_INIT_RESUMED,
+        0
+    );
+    let joined_id = state.sessions["session-1"]
+        .player_id
+        .clone()
+        .expect("player");
+    assert_ne!(joined_id, bot_uuid.to_string());
+    assert!(!state.players[&joined_id].is_bot);
+    assert_ne!(state.players[&joined_id].net_id, 7);
+    assert!(state.players[&bot_uuid.to_string()].is_bot);
+}
+
+fn outbound_frame(message_type: u8, tag: u8) -> Vec<u8> {
+    vec![protocol::VERSION, message_type, 0, 0, tag]
+}
+
+#[test]
+fn drop_oldest_outbound_queue_evicts_advisory_frames_but_keeps_critical_ones() {
+    let (sender, mut receiver) = outbound_queue(3, OutboundFullPolicy::DropOldest);
+    sender
+        .try_send(protocol::encode_error(protocol::ServerError::RoomFull))
+        .expect("error frame");
+    sender
+        .try_send(outbound_frame(protocol::TYPE_CHAT_MESSAGE, 1))
+        .expect("first chat");
+    sender
+        .try_send(outbound_frame(protocol::TYPE_HINT, 2))
+        .expect("hint");
+
+    sender
+        .try_send(outbound_frame(protocol::TYPE_PLAYER_META, 3))
+        .expect("meta evicts the oldest advisory frame");
+    sender
+        .try_send(outbound_frame(protocol::TYPE_CHAT_MESSAGE, 4))
+        .expect("chat evicts the hint");
+    sender
+        .try_send(outbound_frame(protocol::TYPE_INIT, 5))
+        .expect("init evicts the newer chat");
+
+    let frames: Vec<Vec<u8>> = std::iter::from_fn(|| receiver.try_recv().ok()).collect();
+    assert_eq!(frames.len(), 3);
+    assert_eq!(frames[0][1], protocol::TYPE_ERROR);
+    assert_eq!(frames[1], outbound_frame(protocol::TYPE_PLAYER_META, 3));
+    assert_eq!(frames[2], outbound_frame(protocol::TYPE_INIT, 5));
+}
+
+#[test]
+fn drop_oldest_outbound_queue_reports_full_when_only_critical_frames_are_queued() {
+    let (sender, mut receiver) = outbound_queue(2, OutboundFullPolicy::DropOldest);
+    sender
+        .try_send(outbound_frame(protocol::TYPE_INIT, 1))
+        .expect("init");
+    sender
+        .try_send(outbound_frame(protocol::TYPE_PELLET_RESET, 2))
+        .expect("pellet reset");
+
+    assert!(matches!(
+        sender.try_send(outbound_frame(protocol::TYPE_CHAT_MESSAGE, 3)),
+        Err(mpsc::error::TrySendError::Full(_))
+    ));
+    assert_eq!(
+        receiver.try_recv().expect("init"),
+        outbound_frame(protocol::TYPE_INIT, 1)
+    );
+    assert_eq!(
+        receiver.try_recv().expect("pellet reset"),
+        outbound_frame(protocol::TYPE_PELLET_RESET, 2)
+    );
+}
+
+#[test]
+fn disconnect_outbound_queue_never_evicts_and_closes_with_its_receiver() {
+    let (sender, receiver) = outbound_queue(1, OutboundFullPolicy::Disconnect);
+    sender
+        .try_send(outbound_frame(protocol::TYPE_HINT, 1))
+        .expect("hint");
+    assert!(matches!(
+        sender.try_send(outbound_frame(protocol::TYPE_HINT, 2)),
+        Err(mpsc::error::TrySendError::Full(_))
+    ));
+
+    drop(receiver);
+    assert!(matches!(
+        sender.try_send(outbound_frame(protocol::TYPE_HINT, 3)),
+        Err(mpsc::error::TrySendError::Closed(_))
+    ));
+}
+
+#[tokio::test]
+async fn outbound_queue_receiver_ends_after_the_last_sender_drops() {
+    let (sender, mut receiver) = outbound_queue(4, OutboundFullPolicy::Disconnect);
+    let clone = sender.clone();
+    clone
+        .try_send(outbound_frame(protocol::TYPE_WORLD, 1))
+        .expect("world");
+    drop(sender);
+    drop(clone);
+
+    assert_eq!(
+        receiver.recv().await,
+        Some(outbound_frame(protocol::TYPE_WORLD, 1))
+    );
+    assert_eq!(receiver.recv().await, None);
+}
+
+#[test]
+fn outbound_config_parses_policies_and_clamps_depths() {
+    assert_eq!(
+        OutboundFullPolicy::parse(" Drop-Oldest "),
+        Some(OutboundFullPolicy::DropOldest)
+    );
+    assert_eq!(
+        OutboundFullPolicy::parse("disconnect"),
+        Some(OutboundFullPolicy::Disconnect)
+    );
+    assert_eq!(OutboundFullPolicy::parse("block"), None);
+
+    let config = OutboundConfig {
+        hi_capacity: 0,
+        lo_capacity: 10_000,
+        hi_full_policy: OutboundFullPolicy::DropOldest,
+    }
+    .sanitized();
+    assert_eq!(config.hi_capacity, 4);
+    assert_eq!(config.lo_capacity, 256);
+    assert_eq!(config.hi_full_policy, OutboundFullPolicy::DropOldest);
+}
+
+#[tokio::test]
+async fn migrate_sessions_drains_and_tells_every_session_where_to_go() {
+    let room = Room::with_room_id("sparse".to_string());
+    let (mut first_rx, mut second_rx) = {
+        let mut state = room.state.lock().await;
+        (
+            insert_pending_session(&mut state, "session-1"),
+            insert_pending_session(&mut state, "session-2"),
+        )
+    };
+
+    assert_eq!(room.migrate_sessions("room-dense", "token-123").await, 2);
+    assert!(room.state.lock().await.draining);
+    for rx in [&mut first_rx, &mut second_rx] {
+        let frame = rx.try_recv().expect("migrate frame");
+        assert_eq!(frame, protocol::encode_migrate("room-dense", "token-123"));
+    }
+}
+
+fn decode_pong(frame: &[u8]) -> (u32, i64) {
+    assert_eq!(frame[1], protocol::TYPE_PONG);
+    let client_time = u32::from_le_bytes(frame[4..8].try_into().unwrap());
+    let server_time = i64::from_le_bytes(frame[8..16].try_into().unwrap());
+    (client_time, server_time)
+}
+
+#[tokio::test]
+async fn json_input_with_an_infinite_camera_distance_is_ignored() {
+    let room = Arc::new(Room::with_room_id("camera".to_string()));
+    {
+        let mut state = room.state.lock().await;
+        insert_pending_session(&mut state, "session-1");
+    }
+    let inbound = Arc::new(SessionInbound::new());
+
+    let overflow = r#"{"type":"input","boost":true,"cameraDistance":1e39}"#;
+    assert!(
+        room.handle_text_message("session-1", &inbound, overflow)
+            .await
+    );
+    let snapshot = inbound.take_tick_snapshot();
+    assert!(!snapshot.boost);
+    assert_eq!(snapshot.camera_distance, None);
+
+    let valid = r#"{"type":"input","boost":true,"cameraDistance":6.5}"#;
+    assert!(room.handle_text_message("session-1", &inbound, valid).await);
+    let snapshot = inbound.take_tick_snapshot();
+    assert!(snapshot.boost);
+    assert_eq!(snapshot.camera_distance, Some(6.5));
+}
+
+#[tokio::test]
+async fn ping_is_echoed_as_pong_with_the_server_time() {
+    let room = Arc::new(Room::with_room_id("latency".to_string()));
+    let mut rx = {
+        let mut state = room.state.lock().await;
+        insert_pending_session(&mut state, "session-1")
+    };
+    let inbound = Arc::new(SessionInbound::new());
+    let before = RoomState::now_millis();
+
+    let ping = r#"{"type":"ping","clientTime":4242}"#;
+    assert!(room.handle_text_message("session-1", &inbound, ping).await);
+    let mut encoder = protocol::Encoder::with_capacity(8);
+    encoder.write_header(protocol::TYPE_PING, 0);
+    encoder.write_u32(u32::MAX);
+    assert!(
+        room.handle_binary_message("session-1", &inbound, encoder.as_bytes())
+            .await
+    );
+
+    let after = RoomState::now_millis();
+    for expected in [4242, u32::MAX] {
+        let (client_time, server_time) = decode_pong(&rx.try_recv().expect("pong frame"));
+        assert_eq!(client_time, expected);
+        assert!((before..=after).contains(&server_time));
+    }
+    assert!(room.state.lock().await.players.is_empty());
+}
+
+#[tokio::test]
+async fn oversized_inbound_message_is_rejected_before_parsing() {
+    let room =
+        Arc::new(Room::with_room_id("guarded".to_string()).with_max_inbound_message_bytes(1));
+    let max_bytes = room.max_inbound_message_bytes();
+    let mut rx = {
+        let mut state = room.state.lock().await;
+        insert_pending_session(&mut state, "session-1")
+    };
+    let inbound = Arc::new(SessionInbound::new());
+
+    // A well-formed join padded past the limit would add a player if it were parsed.
+    let padding = " ".repeat(max_bytes);
+    let join = format!(r#"{{"type":"join","name":"Big"{padding}}}"#);
+    assert!(!room.handle_text_message("session-1", &inbound, &join).await);
+    assert_eq!(
+        rx.try_recv().expect("error frame"),
+        protocol::encode_error(protocol::ServerError::MessageTooLarge)
+    );
+    assert!(
+        !room
+            .handle_binary_message("session-1", &inbound, &vec![0; max_bytes + 1])
+            .await
+    );
+    assert!(room.state.lock().await.players.is_empty());
+
+    let join = r#"{"type":"join","name":"Small"}"#;
+    assert!(room.handle_text_message("session-1", &inbound, join).await);
+    assert_eq!(room.state.lock().await.players.len(), 1);
+}
+
+#[tokio::test]
+async fn announcement_reaches_every_session_and_drops_stale_ones() {
+    let room = Room::with_room_id("busy".to_string());
+    let (mut first_rx, mut second_rx) = {
+        let mut state = room.state.lock().await;
+        let first = insert_pending_session(&mut state, "session-1");
+        let second = insert_pending_session(&mut state, "session-2");
+        drop(insert_pending_session(&mut state, "session-stale"));
+        (first, second)
+    };
+
+    assert_eq!(
+        room.announce("  Server restarting\u{7} in   60s ").await,
+        Some(2)
+    );
+    for rx in [&mut first_rx, &mut second_rx] {
+        let frame = rx.try_recv().expect("announce frame");
+        assert_eq!(frame, protocol::encode_announce("Server restarting in 60s"));
+    }
+    let state = room.state.lock().await;
+    assert!(state.sessions.contains_key("session-1"));
+    assert!(state.sessions.contains_key("session-2"));
+    assert!(!state.sessions.contains_key("session-stale"));
+    drop(state);
+
+    assert_eq!(room.announce(" \n\t ").await, None);
+    assert!(first_rx.try_recv().is_err());
+}
+
+/// Adds a live player whose head sits at `head` and a session viewing from it, returning the
+/// session's high-priority receiver so relayed frames can be inspected.
+fn insert_chat_player(
+    state: &mut RoomState,
+    id: &str,
+    net_id: u16,
+    head: Point,
+) -> OutboundReceiver {
+    let trailing = normalize(Point {
+        x: head.x - 0.05,
+        y: head.y + 0.05,
+        z: head.z,
+    });
+    let mut player = make_player(id, make_snake_with_head(head, trailing, 6));
+    player.net_id = net_id;
+    state.players.insert(id.to_string(), player);
+    let session_id = format!("session-{id}");
+    insert_session_with_view(state, &session_id, id, None, None);
+    let (outbound_hi, outbound_hi_rx) = outbound_queue(8, OutboundFullPolicy::Disconnect);
+    state
+        .sessions
+        .get_mut(&session_id)
+        .expect("session")
+        .outbound_hi = outbound_hi;
+    outbound_hi_rx
+}
+
+fn decode_chat_frame(frame: &[u8]) -> (u16, String) {
+    assert_eq!(frame[1], protocol::TYPE_CHAT_MESSAGE);
+    let net_id = u16::from_le_bytes([frame[4], frame[5]]);
+    let len = frame[6] as usize;
+    assert_eq!(frame.len(), 7 + len);
+    (
+        net_id,
+        String::from_utf8(frame[7..].to_vec()).expect("utf8"),
+    )
+}
+
+#[test]
+fn chat_relays_to_sessions_viewing_the_sender_only() {
+    let mut state = make_state();
+    let mut sender_rx = insert_chat_player(
+        &mut state,
+        "sender",
+        7,
+        Point {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        },
+    );
+    let mut near_rx = insert_chat_player(
+        &mut state,
+        "near",
+        8,
+        normalize(Point {
+            x: 0.9,
+            y: 0.3,
+            z: 0.0,
+        }),
+    );
+    let mut far_rx = insert_chat_player(
+        &mut state,
+        "far",
+        9,
+        Point {
+            x: -1.0,
+            y: 0.0,
+            z: 0.0,
+        },
+    );
+
+    let delivered = state.handle_chat("session-sender", "  hello\u{7}   there ", 1_000);
+    assert_eq!(delivered, 2);
+    for rx in [&mut sender_rx, &mut near_rx] {
+        let frame = rx.try_recv().expect("relayed chat");
+        assert_eq!(decode_chat_frame(&frame), (7, "hello there".to_string()));
+    }
+    assert!(far_rx.try_recv().is_err());
+
+    // Once the far session turns its camera toward the sender it starts receiving chat.
+    state
+        .sessions
+        .get_mut("session-far")
+        .expect("session")
+        .view_center = Some(Point {
+        x: 1.0,
+        y: 0.0,
+        z: 0.0,
+    });
+    assert_eq!(state.handle_chat("session-sender", "hi", 1_100), 3);
+    assert_eq!(decode_chat_frame(&far_rx.try_recv().expect("chat")).1, "hi");
+}
+
+#[test]
+fn chat_is_rate_limited_per_session_window() {
+    let mut state = make_state();
+    let origin = Point {
+        x: 1.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    let mut rx = insert_chat_player(&mut state, "sender", 7, origin);
+    let _other_rx = insert_chat_player(&mut state, "other", 8, origin);
+
+    for index in 0..CHAT_RATE_LIMIT_PER_WINDOW {
+        let now = 1_000 + index as i64;
+        assert_eq!(state.handle_chat("session-sender", "spam", now), 2);
+    }
+    assert_eq!(state.handle_chat("session-sender", "spam", 2_000), 0);
+    // The limit is per session; another player can still talk.
+    assert_eq!(state.handle_chat("session-other", "hey", 2_000), 2);
+    // Rejected lines still count against the window.
+    assert_eq!(state.handle_chat("session-sender", "   ", 2_500), 0);
+
+    while rx.try_recv().is_ok() {}
+    assert_eq!(
+        state.handle_chat("session-sender", "back", 1_000 + CHAT_RATE_WINDOW_MS),
+        2
+    );
+    assert_eq!(decode_chat_frame(&rx.try_recv().expect("chat")).1, "back");
+}
+
+#[test]
+fn chat_from_dead_or_unjoined_sessions_is_dropped() {
+    let mut state = make_state();
+    let origin = Point {
+        x: 1.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    let mut rx = insert_chat_player(&mut state, "viewer", 8, origin);
+    let _sender_rx = insert_chat_player(&mut state, "sender", 7, origin);
+    state.players.get_mut("sender").expect("sender").alive = false;
+    let _pending_rx = insert_pending_session(&mut state, "session-pending");
+
+    assert_eq!(state.handle_chat("session-sender", "boo", 1_000), 0);
+    assert_eq!(state.handle_chat("session-pending", "hi", 1_000), 0);
+    assert!(rx.try_recv().is_err());
+}
+
+fn score_after_small_pellets(state: &mut RoomState, pellet_count: usize) -> i64 {
+    state.players.insert(
+        "player-1".to_string(),
+        make_player("player-1", make_snake(STARTING_LENGTH, 0.0)),
+    );
+    let mut rng = state.fork_rng();
+    let growth_total: f64 = (0..pellet_count)
+        .map(|_| state.random_small_pellet(&mut rng).growth_fraction)
+        .sum();
+    let mut consumed = HashMap::new();
+    consumed.insert("player-1".to_string(), (pellet_count, growth_total));
+    state.consume_small_pellets(consumed);
+    state.players["player-1"].score
+}
+
+#[test]
+fn pellet_config_with_doubled_growth_accrues_score_faster() {
+    let default_config = PelletConfig::default();
+    let doubled = PelletConfig {
+        small_growth_fraction: default_config.small_growth_fraction * 2.0,
+        big_growth_fraction: default_config.big_growth_fraction * 2.0,
+        ..default_config
+    };
+    let mut classic = Room::with_room_id("classic".to_string());
+    let mut arcade = Room::with_room_id("arcade".to_string()).with_pellet_config(doubled);
+    assert_eq!(arcade.state.get_mut().pellet_config, doubled);
+
+    let classic_score = score_after_small_pellets(classic.state.get_mut(), 40);
+    let arcade_score = score_after_small_pellets(arcade.state.get_mut(), 40);
+    assert!(classic_score > 0);
+    assert_eq!(arcade_score, classic_score * 2);
+}
+
+#[test]
+fn pellet_config_sanitizes_inverted_sizes_and_negative_growth() {
+    let config = PelletConfig {
+        base_count: 0,
+        small_growth_fraction: -1.0,
+        small_size_min: SMALL_PELLET_SIZE_MAX,
+        small_size_max: SMALL_PELLET_SIZE_MIN,
+        death_scatter_angle: f64::NAN,
+        ..PelletConfig::default()
+    }
+    .sanitized();
+    assert_eq!(config.base_count, 1);
+    assert_eq!(config.death_scatter_angle, 0.0);
+    assert_eq!(config.small_growth_fraction, 0.0);
+    assert!(config.small_size_min <= config.small_size_max);
+}
+
+#[test]
+fn pellet_size_encoding_keeps_death_pellets_distinct_from_small_ones() {
+    let small_max = RoomState::quantize_pellet_size(SMALL_PELLET_SIZE_MAX);
+    let death_min = RoomState::quantize_pellet_size(DEATH_PELLET_SIZE_MIN);
+    let death_mid =
+        RoomState::quantize_pellet_size((DEATH_PELLET_SIZE_MIN + DEATH_PELLET_SIZE_MAX) * 0.5);
+    let death_max = RoomState::quantize_pellet_size(DEATH_PELLET_SIZE_MAX);
+
+    assert_eq!(RoomState::quantize_pellet_size(SMALL_PELLET_SIZE_MIN), 0);
+    assert!(small_max < death_min);
+    assert!(death_min < death_mid && death_mid < death_max);
+    assert_eq!(death_max, u8::MAX);
+}
+
+#[test]
+fn pellet_config_max_count_stays_within_frame_count_cap() {
+    let over = PelletConfig {
+        max_count: u16::MAX as usize + 1_000,
+        ..PelletConfig::default()
+    }
+    .sanitized();
+    assert_eq!(over.max_count, u16::MAX as usize);
+    let zero = PelletConfig {
+        max_count: 0,
+        ..PelletConfig::default()
+    }
+    .sanitized();
+    assert_eq!(zero.max_count, 1);
+}
+
+#[test]
+fn small_pellet_max_count_holds_after_a_large_death_drop() {
+    let mut room = Room::with_room_id("low-cpu".to_string()).with_pellet_config(PelletConfig {
+        max_count: 10,
+        ..PelletConfig::default()
+    });
+    let state = room.state.get_mut();
+    let player = make_player(
+        "player-1",
+        create_snake_with_length(
+            Point {
+                x: 0.0,
+                y: 0.0,
+                z: 1.0,
+            },
+            120,
+        ),
+    );
+    state.players.insert("player-1".to_string(), player);
+
+    state.handle_death("player-1");
+    assert!(!state.players["player-1"].alive);
+    assert_eq!(state.pellets.len(), 10);
+
+    state.ensure_pellets();
+    assert!(state.pellets.len() <= 10);
+}
+
+#[test]
+fn death_scatter_spreads_pellets_off_the_body_without_changing_the_count() {
+    let mut room = Room::with_room_id("scatter".to_string()).with_pellet_config(PelletConfig {
+        death_scatter_angle: 0.2,
+        ..PelletConfig::default()
+    });
+    let state = room.state.get_mut();
+    let axis = Point {
+        x: 0.0,
+        y: 0.0,
+        z: 1.0,
+    };
+    let snake = create_snake_with_length(axis, 60);
+    let nodes: Vec<Point> = snake
+        .iter()
+        .skip(1)
+        .map(|node| Point {
+            x: node.x,
+            y: node.y,
+            z: node.z,
+        })
+        .collect();
+    state
+        .players
+        .insert("player-1".to_string(), make_player("player-1", snake));
+
+    state.handle_death("player-1");
+
+    assert_eq!(state.pellets.len(), nodes.len());
+    for (pellet, node) in state.pellets.iter().zip(&nodes) {
+        let offset = length(Point {
+            x: pellet.normal.x - node.x,
+            y: pellet.normal.y - node.y,
+            z: pellet.normal.z - node.z,
+        });
+        assert!(offset > 1e-9, "pellet {} sits on its node", pellet.id);
+        assert!((length(pellet.normal) - 1.0).abs() < 1e-6);
+        assert!(!state.is_invalid_pellet_spawn(pellet.normal));
+    }
+}
+
+fn insert_player_with_net_id(state: &mut RoomState, id: &str, net_id: u16) {
+    let mut player = make_player(id, Vec::new());
+    player.net_id = net_id;
+    state.players.insert(id.to_string(), player);
+}
+
+#[test]
+fn net_id_allocation_survives_churn_without_zero_or_duplicates() {
+    let mut state = make_state();
+    let mut next_index = 0usize;
+    for round in 0..6 {
+        for _ in 0..1_000 {
+            let net_id = state.allocate_player_net_id().expect("free net id");
+            insert_player_with_net_id(&mut state, &format!("player-{next_index}"), net_id);
+            next_index += 1;
+        }
+        // Drop roughly half the room each round so freed ids get reused.
+        state
+            .players
+            .retain(|_, player| (usize::from(player.net_id) + round) % 2 == 0);
+    }
+
+    let mut seen = HashSet::new();
+    for player in state.players.values() {
+        assert_ne!(player.net_id, 0);
+        assert!(
+            seen.insert(player.net_id),
+            "duplicate net id {}",
+            player.net_id
+        );
+    }
+}
+
+#[test]
+fn net_id_allocation_reclaims_oldest_disconnected_player_when_exhausted() {
+    let mut state = make_state();
+    for net_id in 1..=u16::MAX {
+        insert_player_with_net_id(&mut state, &format!("player-{net_id}"), net_id);
+    }
+    assert_eq!(state.allocate_player_net_id(), None);
+
+    for (id, last_seen) in [("player-10", 500), ("player-20", 100)] {
+        let player = state.players.get_mut(id).expect("player");
+        player.connected = false;
+        player.last_seen = last_seen;
+    }
+    assert_eq!(state.allocate_player_net_id(), Some(20));
+    assert!(!state.players.contains_key("player-20"));
+    assert!(state.players.contains_key("player-10"));
+}
+
+fn insert_disconnected_player(state: &mut RoomState, id: &str, len: usize, last_seen: i64) {
+    let mut player = make_player(id, make_snake(len, 0.0));
+    player.connected = false;
+    player.last_seen = last_seen;
+    state.players.insert(id.to_string(), player);
+}
+
+#[test]
+fn short_disconnect_timeout_prunes_disconnected_players_sooner() {
+    let away_since = RoomState::now_millis() - 5_000;
+    let mut default_state = make_state();
+    insert_disconnected_player(&mut default_state, "player-away", 8, away_since);
+    default_state.tick();
+    assert!(default_state.players.contains_key("player-away"));
+
+    let mut short_state = make_state();
+    short_state.disconnect_config = DisconnectConfig {
+        timeout_ms: 2_000,
+        ..DisconnectConfig::default()
+    }
+    .sanitized();
+    insert_disconnected_player(&mut short_state, "player-away", 8, away_since);
+    short_state.tick();
+    assert!(!short_state.players.contains_key("player-away"));
+}
+
+#[test]
+fn large_disconnected_snakes_use_the_shorter_large_snake_timeout() {
+    let mut state = make_state();
+    state.disconnect_config = DisconnectConfig {
+        timeout_ms: 30_000,
+        large_snake_length: Some(40),
+        large_snake_timeout_ms: 2_000,
+        freeze_on_disconnect: false,
+    }
+    .sanitized();
+    let away_since = RoomState::now_millis() - 5_000;
+    insert_disconnected_player(&mut state, "player-small", 8, away_since);
+    insert_disconnected_player(&mut state, "player-large", 60, away_since);
+
+    state.tick();
+
+    assert!(state.players.contains_key("player-small"));
+    assert!(!state.players.contains_key("player-large"));
+}
+
+#[test]
+fn frozen_disconnected_snake_stays_put_but_connected_snakes_keep_moving() {
+    let mut state = make_state();
+    state.disconnect_config = DisconnectConfig {
+        freeze_on_disconnect: true,
+        ..DisconnectConfig::default()
+    };
+    insert_session_with_view(&mut state, "session-away", "player-away", None, None);
+    let away_snake = make_snake_with_head(
+        Point {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        normalize(Point {
+            x: 1.0,
+            y: -0.05,
+            z: 0.0,
+        }),
+        8,
+    );
+    let mut away = make_player("player-away", away_snake);
+    away.boost = true;
+    state.players.insert("player-away".to_string(), away);
+    let here_snake = make_snake_with_head(
+        Point {
+            x: 0.0,
+            y: 0.0,
+            z: 1.0,
+        },
+        normalize(Point {
+            x: 0.0,
+            y: -0.05,
+            z: 1.0,
+        }),
+        8,
+    );
+    state.players.insert(
+        "player-here".to_string(),
+        make_player("player-here", here_snake),
+    );
+    let head_of = |state: &RoomState, id: &str| {
+        let head = &state.players[id].snake[0];
+        (head.x, head.y, head.z)
+    };
+
+    state.disconnect_session("session-away");
+    let away = &state.players["player-away"];
+    assert!(!away.connected);
+    assert!(!away.boost);
+    let away_head = head_of(&state, "player-away");
+    let here_head = head_of(&state, "player-here");
+
+    for _ in 0..5 {
+        state.tick();
+    }
+
+    assert!(state.players["player-away"].alive);
+    assert_eq!(head_of(&state, "player-away"), away_head);
+    assert_ne!(head_of(&state, "player-here"), here_head);
+}
+
+#[test]
+fn disconnect_config_sanitizes_timeouts() {
+    let config = DisconnectConfig {
+        timeout_ms: 0,
+        large_snake_length: Some(0),
+        large_snake_timeout_ms: 90_000,
+        freeze_on_disconnect: false,
+    }
+    .sanitized();
+    assert_eq!(config.timeout_ms, 1_000);
+    assert_eq!(config.large_snake_length, None);
+    assert_eq!(config.large_snake_timeout_ms, 1_000);
+    assert_eq!(config.timeout_for_len(500), 1_000);
+}
+
+#[test]
+fn next_free_net_id_skips_zero_and_wraps() {
+    let used: HashSet<u16> = [1, 3, 4].into_iter().collect();
+    assert_eq!(RoomState::next_free_net_id(&used, 0, 4), Some(2));
+    assert_eq!(RoomState::next_free_net_id(&used, 3, 4), Some(2));
+    let full: HashSet<u16> = (1..=4).collect();
+    assert_eq!(RoomState::next_free_net_id(&full, 1, 4), None);
+}
+
+fn head_point(player: &Player) -> Point {
+    let head = &player.snake[0];
+    Point {
+        x: head.x,
+        y: head.y,
+        z: head.z,
+    }
+}
+
+#[test]
+fn debug_set_length_updates_score_boost_floor_and_girth() {
+    let mut state = make_state();
+    let axis = Point {
+        x: 1.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    state.players.insert(
+        "player-1".to_string(),
+        make_player("player-1", create_snake(axis)),
+    );
+    let head_before = head_point(&state.players["player-1"]);
+    let girth_before = state.player_girth_scale_from_len(STARTING_LENGTH);
+
+    let affected = state.debug_set_length(DebugKillTarget::Any, 500);
+    assert_eq!(affected.as_deref(), Some("player-1"));
+
+    let player = &state.players["player-1"];
+    assert_eq!(player.snake.len(), 500);
+    assert_eq!(player.score, 500);
+    assert_eq!(player.boost_floor_len, 500);
+    assert!(state.player_girth_scale_from_len(player.snake.len()) > girth_before);
+    let head_after = head_point(player);
+    assert!(length(cross(head_before, head_after)) < 1e-9);
+    assert!(dot(head_before, head_after) > 0.0);
+
+    state.debug_set_length(DebugKillTarget::Any, 1);
+    let player = &state.players["player-1"];
+    assert_eq!(player.snake.len(), MIN_SURVIVAL_LENGTH);
+    assert_eq!(player.boost_floor_len, STARTING_LENGTH);
+}
+
+#[test]
+fn configured_min_survival_length_stops_boost_drain_at_the_floor() {
+    let mut room = Room::with_room_id("floor".to_string()).with_growth_config(GrowthConfig {
+        starting_length: 12,
+        min_survival_length: 10,
+        ..GrowthConfig::default()
+    });
+    let state = room.state.get_mut();
+    state.bots_enabled = false;
+    state.environment = Environment {
+        lakes: Vec::new(),
+        trees: Vec::new(),
+        mountains: Vec::new(),
+    };
+    let axis = Point {
+        x: 1.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    // A boost floor below the room's survival floor, as left by an older snapshot.
+    let mut player = make_player("booster", create_snake_with_length(axis, 11));
+    player.score = 40;
+    player.boost_floor_len = 4;
+    player.boost = true;
+    state.players.insert("booster".to_string(), player);
+
+    let mut boosted = false;
+    for _ in 0..600 {
+        state.tick();
+        let player = &state.players["booster"];
+        boosted |= player.is_boosting;
+        assert!(player.snake.len() >= 10);
+    }
+    let player = &state.players["booster"];
+    assert!(boosted);
+    assert_eq!(player.snake.len(), 10);
+    assert!(!player.is_boosting);
+    assert!(!RoomState::can_player_boost(player, 10));
+    assert!(RoomState::can_player_boost(player, MIN_SURVIVAL_LENGTH));
+
+    let payload = state.build_init_payload_for_session("missing-session", "booster", false);
+    let floor: [u8; 2] = payload[payload.len() - 2..].try_into().unwrap();
+    assert_eq!(u16::from_le_bytes(floor), 10);
+}
+
+#[test]
+fn debug_teleport_moves_head_and_keeps_length() {
+    let mut state = make_state();
+    let axis = Point {
+        x: 1.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    state.players.insert(
+        "player-1".to_string(),
+        make_player("player-1", create_snake(axis)),
+    );
+    let target = normalize(Point {
+        x: 0.3,
+        y: -0.5,
+        z: 0.8,
+    });
+
+    let affected = state.debug_teleport(DebugKillTarget::Any, target);
+    assert_eq!(affected.as_deref(), Some("player-1"));
+
+    let player = &state.players["player-1"];
+    assert_eq!(player.snake.len(), STARTING_LENGTH);
+    let head = head_point(player);
+    assert!(
+        length(Point {
+            x: head.x - target.x,
+            y: head.y - target.y,
+            z: head.z - target.z,
+        }) < 1e-9
+    );
+    // The snake still travels around its (rotated) axis.
+    assert!(dot(player.axis, head).abs() < 1e-9);
+    assert!(state
+        .debug_teleport(
+            DebugKillTarget::Any,
+            Point {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+        )
+        .is_none());
+}
+
+#[tokio::test]
+async fn add_session_rejects_sockets_past_max_sessions() {
+    let room = Room::with_room_id("crowded".to_string()).with_max_sessions(3);
+    let mut sessions = Vec::new();
+    for _ in 0..3 {
+        assert!(room.has_session_capacity().await);
+        sessions.push(room.add_session(WireFormat::Binary).await.expect("session"));
+    }
+
+    assert!(!room.has_session_capacity().await);
+    assert!(room.add_session(WireFormat::Binary).await.is_none());
+
+    room.remove_session(&sessions[0].session_id).await;
+    assert!(room.add_session(WireFormat::Binary).await.is_some());
+}
+
+async fn join_new_session(room: &Arc<Room>) -> String {
+    let session = room.add_session(WireFormat::Binary).await.expect("session");
+    let join = r#"{"type":"join","name":"Regular"}"#;
+    assert!(
+        room.handle_text_message(&session.session_id, &session.inbound, join)
+            .await
+    );
+    session.session_id
+}
+
+#[tokio::test]
+async fn rejoin_within_idle_linger_resumes_the_same_loop_and_room() {
+    let room = Arc::new(Room::with_room_id("linger".to_string()).with_idle_linger_ms(600));
+    let session_id = join_new_session(&room).await;
+    assert!(room.running.load(Ordering::SeqCst));
+    let environment = room.environment_bytes().await;
+
+    room.remove_session(&session_id).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(room.running.load(Ordering::SeqCst));
+    assert!(room.is_tick_loop_healthy());
+
+    // Holding the room Arc, the lingering loop is what keeps it alive in standalone mode.
+    assert!(Arc::strong_count(&room) > 1);
+    let session_id = join_new_session(&room).await;
+    assert_eq!(room.environment_bytes().await, environment);
+    assert_eq!(Arc::strong_count(&room), 2);
+
+    room.remove_session(&session_id).await;
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while Arc::strong_count(&room) > 1 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("loop stops once the linger runs out");
+    assert!(!room.running.load(Ordering::SeqCst));
+}
+
+/// A room with bots off and the tick loop marked as running, so a join never spawns it and
+/// the test steps the simulation itself with `Room::tick_once`.
+fn stepped_room(room_id: &str) -> Arc<Room> {
+    let room = Arc::new(Room::with_room_id(room_id.to_string()).with_bots_enabled(false));
+    room.running.store(true, Ordering::SeqCst);
+    room
+}
+
+#[tokio::test]
+async fn joined_session_gets_init_then_one_state_frame_per_tick() {
+    let room = stepped_room("harness");
+    let mut session = room.add_session(WireFormat::Binary).await.expect("session");
+    let join = r#"{"type":"join","name":"Harness"}"#;
+    assert!(
+        room.handle_text_message(&session.session_id, &session.inbound, join)
+            .await
+    );
+
+    let init = session.outbound_hi_rx.try_recv().expect("init frame");
+    let (init_seq, total_players, visible_players) = decode_init_counts(&init);
+    assert_eq!((total_players, visible_players), (1, 1));
+    assert!(session.outbound_state.take_latest().is_none());
+
+    let mut last_seq = init_seq;
+    for tick in 0..6 {
+        room.tick_once().await;
+        let frame = session.outbound_state.take_latest().expect("state frame");
+        let (state_seq, total_players, visible_players) = decode_state_counts(&frame);
+        assert_eq!(state_seq, last_seq + 1);
+        assert_eq!((total_players, visible_players), (1, 1));
+        if tick == 0 {
+            assert_ne!(decode_state_frame_flags(&frame) & DELTA_FRAME_KEYFRAME, 0);
+        }
+        last_seq = state_seq;
+    }
+    assert_eq!(room.state.lock().await.players.len(), 1);
+}
+
+#[test]
+fn non_finite_head_respawns_the_player_with_its_length() {
+    let mut state = make_state();
+    state.bots_enabled = false;
+    let axis = Point {
+        x: 1.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    let mut player = make_player("corrupt", create_snake_with_length(axis, 20));
+    player.snake[0].x = f64::NAN;
+    state.players.insert("corrupt".to_string(), player);
+    let mut healthy = make_player(
+        "healthy",
+        create_snake(Point {
+            x: 0.0,
+            y: 1.0,
+            z: 0.0,
+        }),
+    );
+    healthy.axis = Point {
+        x: 0.0,
+        y: 1.0,
+        z: 0.0,
+    };
+    state.players.insert("healthy".to_string(), healthy);
+
+    state.tick();
+
+    let player = &state.players["corrupt"];
+    assert!(player.alive);
+    assert_eq!(player.snake.len(), 20);
+    assert!(player
+        .snake
+        .iter()
+        .all(|node| node.x.is_finite() && node.y.is_finite() && node.z.is_finite()));
+    assert!(RoomState::player_head_and_axis_finite(player));
+    assert!(state.players["healthy"].alive);
+}
+
+#[test]
+fn default_max_sessions_stays_above_human_player_cap() {
+    assert_eq!(Room::new().max_sessions, DEFAULT_MAX_SESSIONS);
+    assert_eq!(
+        Room::with_max_human_players(Some(25)).max_sessions,
+        DEFAULT_MAX_SESSIONS
+    );
+    assert!(Room::with_max_human_players(Some(1_000)).max_sessions > 1_000);
+}
+
+fn make_migration_state() -> RoomState {
+    let mut state = make_state();
+    let player_id = "migrating-player".to_string();
+    let mut player = make_player(
+        &player_id,
+        create_snake(Point {
+            x: 0.0,
+            y: 0.0,
+            z: 1.0,
+        }),
+    );
+    player.score = 42;
+    player.tail_extension = 0.4;
+    player.skin = Some(vec![[12, 34, 56], [78, 90, 12]]);
+    player.next_digestion_id = 2;
+    player.digestions.push(Digestion {
+        id: 1,
+        remaining: 5,
+        total: 9,
+        settle_steps: 2,
+        growth_amount: 0.5,
+        applied_growth: 0.1,
+        strength: 0.75,
+    });
+    player.snake[0].pos_queue.push_back(Some(Point {
+        x: 0.1,
+        y: 0.2,
+        z: 0.97,
+    }));
+    player.snake[0].pos_queue.push_back(None);
+    let head = normalize(Point {
+        x: player.snake[0].x,
+        y: player.snake[0].y,
+        z: player.snake[0].z,
+    });
+    state.players.insert(player_id.clone(), player);
+
+    state.pellets.push(make_pellet(3, head));
+    let mut attracting = make_pellet(4, head);
+    attracting.state = PelletState::Attracting {
+        target_player_id: player_id.clone(),
+    };
+    state.pellets.push(attracting);
+    let mut evasive = make_pellet(5, head);
+    evasive.state = PelletState::Evasive {
+        owner_player_id: player_id.clone(),
+        expires_at_ms: 9_000,
+    };
+    evasive.expires_at_ms = Some(9_500);
+    state.pellets.push(evasive);
+    state.next_pellet_id = 6;
+    state.next_state_seq = 41;
+    state.next_evasive_spawn_at.insert(player_id, 7_000);
+    state
+}
+
+#[test]
+fn restored_snapshot_reproduces_state_payload() {
+    let mut original = make_migration_state();
+    let snapshot = original.serialize();
+
+    let mut restored = make_state();
+    restored.restore(&snapshot).expect("restore");
+    assert_eq!(restored.players.len(), 1);
+    assert_eq!(restored.pellets.len(), 3);
+    assert_eq!(restored.next_pellet_id, 6);
+    assert_eq!(restored.next_evasive_spawn_at.len(), 1);
+    assert_eq!(
+        restored.environment.lakes.len(),
+        original.environment.lakes.len()
+    );
+    // Humans wait for their clients to reconnect after a migration.
+    assert!(!restored.players["migrating-player"].connected);
+
+    let view_center = Some(Point {
+        x: 0.0,
+        y: 0.0,
+        z: 1.0,
+    });
+    for state in [&mut original, &mut restored] {
+        insert_session_with_view(state, "viewer", "migrating-player", view_center, Some(1.0));
+    }
+    // A single player keeps the comparison independent of `HashMap` iteration order.
+    let original_payload = original
+        .build_state_delta_payload_for_session(1_234, 41, "viewer")
+        .expect("payload");
+    let restored_payload = restored
+        .build_state_delta_payload_for_session(1_234, 41, "viewer")
+        .expect("payload");
+    assert_eq!(original_payload, restored_payload);
+}
+
+#[test]
+fn rejected_snapshot_leaves_state_untouched() {
+    let snapshot = make_migration_state().serialize();
+    let mut state = make_state();
+    state.next_state_seq = 7;
+
+    assert!(state.restore(&snapshot[..snapshot.len() - 1]).is_err());
+    assert!(state.restore(b"not a snapshot").is_err());
+    let mut other_room = RoomState::new("other-room".to_string());
+    assert!(other_room.restore(&snapshot).is_err());
+
+    assert!(state.players.is_empty());
+    assert_eq!(state.next_state_seq, 7);
+}
+
+#[test]
+fn snapshot_with_an_oversized_count_is_rejected_without_preallocating() {
+    let mut state = make_state();
+    let mut snapshot = state.serialize();
+    // magic, version, room id, pellet id, state seq, net id, world scale, day phase, phase q,
+    // pellet load factor
+    let player_count_at = 4
+        + 2
+        + 4
+        + state.room_id.len()
+        + 4
+        + 4
+        + 2
+        + 8
+        + 8
+        + 1
+        + usize::from(state.day_phase_q.is_some())
+        + 8;
+    assert_eq!(snapshot[player_count_at..player_count_at + 4], [0; 4]);
+    snapshot[player_count_at..player_count_at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    assert!(state.restore(&snapshot).is_err());
+    assert!(state.players.is_empty());
+}
+
+#[test]
+fn tick_loop_readiness_fails_only_when_running_loop_stalls() {
+    let room = Room::new();
+    assert!(room.is_tick_loop_healthy_at(1_000_000));
+
+    room.running.store(true, Ordering::SeqCst);
+    room.last_tick_at_ms.store(1_000, Ordering::Relaxed);
+    assert!(room.is_tick_loop_healthy_at(1_000 + TICK_STALL_THRESHOLD_MS));
+    assert!(!room.is_tick_loop_healthy_at(1_001 + TICK_STALL_THRESHOLD_MS));
+
+    room.running.store(false, Ordering::SeqCst);
+    assert!(room.is_tick_loop_healthy_at(1_000_000));
+}
+
+#[test]
+fn session_best_survives_death_respawn_and_rejoin() {
+    let mut state = make_state();
+    state.bot_config = BotConfig {
+        max_bots: 0,
+        min_bots: 0,
+    };
+    let player_uuid = Uuid::new_v4();
+    let player_id = player_uuid.to_string();
+    assert!(state.handle_join(
+        "session-1",
+        Some("Best".to_string()),
+        Some(player_uuid),
+        false,
+        None,
+        None,
+    ));
+    state.players.get_mut(&player_id).expect("player").score = 42;
+
+    state.tick();
+    let peak = state.players[&player_id].session_best;
+    assert!(peak >= 42);
+
+    state.handle_death(&player_id);
+    let player = &state.players[&player_id];
+    assert_eq!(player.score, 0);
+    assert_eq!(player.session_best, peak);
+
+    state.respawn_player(&player_id);
+    state.tick();
+    let player = &state.players[&player_id];
+    assert!(player.score < peak);
+    assert_eq!(player.session_best, peak);
+
+    assert!(state.handle_join(
+        "session-2",
+        Some("Best".to_string()),
+        Some(player_uuid),
+        true,
+        None,
+        None,
+    ));
+    let player = &state.players[&player_id];
+    assert_eq!(player.score, 0);
+    assert_eq!(player.session_best, peak);
+}
+
+fn dead_player_with_session(state: &mut RoomState, cooldown_ms: i64) -> (String, OutboundReceiver) {
+    state.respawn_cooldown_ms = cooldown_ms;
+    let player_id = "respawner".to_string();
+    state.players.insert(
+        player_id.clone(),
+        make_player(
+            &player_id,
+            create_snake(Point {
+                x: 0.0,
+                y: 0.0,
+                z: 1.0,
+            }),
+        ),
+    );
+    insert_session_with_view(state, "session-1", &player_id, None, None);
+    let (outbound_hi, outbound_hi_rx) = outbound_queue(4, OutboundFullPolicy::Disconnect);
+    state
+        .sessions
+        .get_mut("session-1")
+        .expect("session")
+        .outbound_hi = outbound_hi;
+    state.handle_death(&player_id);
+    (player_id, outbound_hi_rx)
+}
+
+#[test]
+fn early_respawn_is_rejected_with_the_remaining_cooldown() {
+    let mut state = make_state();
+    let (player_id, mut outbound_hi_rx) = dead_player_with_session(&mut state, 2_000);
+    let respawn_at = state.players[&player_id].respawn_at.expect("respawn_at");
+
+    state.handle_respawn("session-1");
+    assert!(!state.players[&player_id].alive);
+
+    let frame = outbound_hi_rx.try_recv().expect("cooldown error");
+    assert_eq!(frame[1], protocol::TYPE_ERROR);
+    assert_eq!(frame[4], protocol::ERROR_RESPAWN_COOLDOWN);
+    let reason_len = frame[7] as usize;
+    let mut offset = 8 + reason_len;
+    let remaining_ms = read_u32(&frame, &mut offset) as i64;
+    assert_eq!(offset, frame.len());
+    let expected_ms = respawn_at - RoomState::now_millis();
+    assert!(remaining_ms > 0 && remaining_ms <= 2_000);
+    assert!((remaining_ms - expected_ms).abs() <= 50);
+
+    state
+        .players
+        .get_mut(&player_id)
+        .expect("player")
+        .respawn_at = Some(RoomState::now_millis() - 1);
+    state.handle_respawn("session-1");
+    assert!(state.players[&player_id].alive);
+    assert!(outbound_hi_rx.try_recv().is_err());
+}
+
+#[test]
+fn respawn_cooldown_is_configurable_and_sent_in_state_frames() {
+    let mut state = make_state();
+    let before = RoomState::now_millis();
+    let (player_id, _outbound_hi_rx) = dead_player_with_session(&mut state, 7_500);
+    let respawn_at = state.players[&player_id].respawn_at.expect("respawn_at");
+    assert!(respawn_at >= before + 7_500);
+    assert!(respawn_at <= RoomState::now_millis() + 7_500);
+
+    let payload = state
+        .build_state_delta_payload_for_session(before, 1, "session-1")
+        .expect("state delta");
+    assert_eq!(decode_state_respawn_at(&payload), Some(respawn_at));
+
+    state.respawn_player(&player_id);
+    let payload = state
+        .build_state_delta_payload_for_session(before, 2, "session-1")
+        .expect("state delta");
+    assert_eq!(decode_state_respawn_at(&payload), None);
+
+    let room = Room::with_room_id("cooldown".to_string()).with_respawn_cooldown_ms(600_000);
+    assert_eq!(
+        room.state.try_lock().expect("state").respawn_cooldown_ms,
+        RESPAWN_COOLDOWN_MAX_MS
+    );
+}
+
+#[test]
+fn pellet_budget_grows_monotonically_across_accepted_camera_range() {
+    let mut state = make_state();
+    let local_id = "local-player".to_string();
+    state.players.insert(
+        local_id.clone(),
+        make_player(&local_id, snake_from_xs(&[1.0, 0.99, 0.98, 0.97])),
+    );
+    insert_session_with_view(&mut state, "session-1", &local_id, None, None);
+
+    let visible_count = |state: &mut RoomState, distance: f32| {
+        state
+            .sessions
+            .get_mut("session-1")
+            .expect("session")
+            .camera_distance = Some(distance as f64);
+        state
+            .pellet_view_params("session-1")
+            .expect("pellet view params")
+            .2
+    };
+
+    let steps = 60;
+    let mut previous = 0usize;
+    // Sweep a little past both ends to cover clamping of out-of-range requests.
+    for step in 0..=steps {
+        let t = step as f32 / steps as f32;
+        let distance = (protocol::VIEW_CAMERA_DISTANCE_MIN - 1.0)
+            + (protocol::VIEW_CAMERA_DISTANCE_MAX - protocol::VIEW_CAMERA_DISTANCE_MIN + 2.0) * t;
+        let count = visible_count(&mut state, distance);
+        assert!(
+            (SMALL_PELLET_VISIBLE_MIN..=SMALL_PELLET_VISIBLE_MAX).contains(&count),
+            "distance {distance} gave {count}"
+        );
+        assert!(
+            count >= previous,
+            "distance {distance} gave {count} < {previous}"
+        );
+        previous = count;
+    }
+    assert_eq!(
+        visible_count(&mut state, protocol::VIEW_CAMERA_DISTANCE_MIN),
+        SMALL_PELLET_VISIBLE_MIN
+    );
+    assert_eq!(
+        visible_count(&mut state, protocol::VIEW_CAMERA_DISTANCE_MAX),
+        SMALL_PELLET_VISIBLE_MAX
+    );
+}
+
+#[test]
+fn nearest_pellet_visibility_prefers_close_high_id_pellets() {
+    let mut state = make_state();
+    let around_center = |angle: f64| Point {
+        x: angle.cos(),
+        y: angle.sin(),
+        z: 0.0,
+    };
+    // Low ids near the edge of the view, high ids right next to the center.
+    state.pellets = (1..=5)
+        .map(|id| make_pellet(id, around_center(0.5 + id as f64 * 0.01)))
+        .chain((100..=104).map(|id| make_pellet(id, around_center((id - 100) as f64 * 0.01))))
+        .collect();
+    let center = around_center(0.0);
+    let view_cos = 0.6f64.cos();
+    let visible_ids = |state: &RoomState, shown: &HashSet<u32>| {
+        state
+            .visible_pellet_indices(center, view_cos, 5, shown)
+            .into_iter()
+            .map(|index| state.pellets[index].id)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(state.pellet_visibility, PelletVisibility::LowestId);
+    assert_eq!(visible_ids(&state, &HashSet::new()), vec![1, 2, 3, 4, 5]);
+
+    state.pellet_visibility = PelletVisibility::Nearest;
+    assert_eq!(
+        visible_ids(&state, &HashSet::new()),
+        vec![100, 101, 102, 103, 104]
+    );
+
+    // A shown pellet keeps its slot against a newcomer that is only marginally closer.
+    state.pellets.push(make_pellet(200, around_center(0.035)));
+    let shown: HashSet<u32> = (100..=104).collect();
+    assert_eq!(visible_ids(&state, &shown), vec![100, 101, 102, 103, 104]);
+    assert_eq!(
+        visible_ids(&state, &HashSet::new()),
+        vec![100, 101, 102, 103, 200]
+    );
+
+    // ...but not against one that is clearly closer.
+    state.pellets.push(make_pellet(201, around_center(-0.005)));
+    assert_eq!(visible_ids(&state, &shown), vec![100, 101, 102, 103, 201]);
+}
+
+#[test]
+fn spawn_rejects_heads_within_min_distance() {
+    let mut state = make_state();
+    state.players.insert(
+        "other".to_string(),
+        make_player("other", snake_from_xs(&[0.0, 0.4, 0.8])),
+    );
+
+    let candidate = snake_from_xs(&[SPAWN_PLAYER_MIN_DISTANCE * 0.75]);
+    assert!(state.is_snake_too_close(&candidate, None, 1.0));
+}
+
+#[test]
+fn spawn_allows_heads_outside_min_distance() {
+    let mut state = make_state();
+    state.players.insert(
+        "other".to_string(),
+        make_player("other", snake_from_xs(&[0.0, 0.4, 0.8])),
+    );
+
+    let candidate = snake_from_xs(&[SPAWN_PLAYER_MIN_DISTANCE + 0.05]);
+    assert!(!state.is_snake_too_close(&candidate, None, 1.0));
+}
+
+#[test]
+fn spawn_check_ignores_excluded_player_id() {
+    let mut state = make_state();
     let player_id = "respawn-player".to_string();
     state.players.insert(
-        player_id.clone(),
-        make_player(&player_id, snake_from_xs(&[0.0, 0.4, 0.8])),
+        player_id.clone(),
+        make_player(&player_id, snake_from_xs(&[0.0, 0.4, 0.8])),
+    );
+
+    let candidate = snake_from_xs(&[SPAWN_PLAYER_MIN_DISTANCE * 0.75]);
+    assert!(state.is_snake_too_close(&candidate, None, 1.0));
+    assert!(!state.is_snake_too_close(&candidate, Some(&player_id), 1.0));
+}
+
+#[test]
+fn spawn_safety_scale_stays_strict_early_then_eases_to_the_floor() {
+    let mut state = make_state();
+    state.spawn_safety_floor = 0.5;
+    for attempt in 0..SPAWN_STRICT_ATTEMPTS {
+        assert_eq!(state.spawn_safety_scale(attempt), 1.0);
+    }
+    let mut previous = 1.0;
+    for attempt in SPAWN_STRICT_ATTEMPTS..MAX_SPAWN_ATTEMPTS {
+        let scale = state.spawn_safety_scale(attempt);
+        assert!(scale < previous);
+        assert!(scale >= 0.5);
+        previous = scale;
+    }
+    assert!((state.spawn_safety_scale(MAX_SPAWN_ATTEMPTS - 1) - 0.5).abs() < 1e-12);
+
+    let room = Room::with_room_id("floor".to_string()).with_spawn_safety_floor(0.1);
+    assert_eq!(
+        room.state.try_lock().expect("state").spawn_safety_floor,
+        SPAWN_SAFETY_FLOOR_MIN
+    );
+}
+
+#[test]
+fn crowded_spawn_candidate_is_rejected_by_strict_attempts_but_accepted_by_relaxed_ones() {
+    let mut state = make_state();
+    state.spawn_safety_floor = 0.5;
+    let candidate = snake_from_xs(&[0.0]);
+    // Heads ring the spot just inside the strict buffer but clear of the candidate's body.
+    let gap = SPAWN_PLAYER_MIN_DISTANCE * 0.75;
+    for (index, (dx, dy)) in [(gap, 0.0), (-gap, 0.0), (0.0, gap), (0.0, -gap)]
+        .into_iter()
+        .enumerate()
+    {
+        let id = format!("crowd-{index}");
+        let head = SnakeNode {
+            x: dx,
+            y: dy,
+            z: 0.0,
+            pos_queue: VecDeque::new(),
+        };
+        state
+            .players
+            .insert(id.clone(), make_player(&id, vec![head]));
+    }
+
+    let first_clear = (0..MAX_SPAWN_ATTEMPTS).find(|&attempt| {
+        !state.is_snake_too_close(&candidate, None, state.spawn_safety_scale(attempt))
+    });
+    let first_clear = first_clear.expect("a relaxed attempt accepts the candidate");
+    assert!(first_clear >= SPAWN_STRICT_ATTEMPTS);
+    assert!(state.is_snake_too_close(&candidate, None, 1.0));
+}
+
+#[test]
+fn relaxed_spawn_attempts_still_reject_overlapping_bodies() {
+    let mut state = make_state();
+    state.players.insert(
+        "other".to_string(),
+        make_player("other", snake_from_xs(&[0.0, 0.4, 0.8])),
     );
 
-    let candidate = snake_from_xs(&[SPAWN_PLAYER_MIN_DISTANCE * 0.75]);
-    assert!(state.is_snake_too_close(&candidate, None));
-    assert!(!state.is_snake_too_close(&candidate, Some(&player_id)));
+    let candidate = snake_from_xs(&[0.4 + COLLISION_DISTANCE * 0.5]);
+    let last_scale = state.spawn_safety_scale(MAX_SPAWN_ATTEMPTS - 1);
+    assert!(state.is_snake_too_close(&candidate, None, last_scale));
+}
+
+#[test]
+fn seeded_rooms_replay_spawns_and_pellets_identically() {
+    let run = |seed: u64| {
+        let mut state = RoomState::new_with_rng_seed("seeded".to_string(), seed);
+        let player = state
+            .create_player(Uuid::from_u128(1), "Seeded".to_string(), false)
+            .expect("player");
+        state.ensure_pellets();
+        let head = player.snake[0].clone();
+        let pellets: Vec<(u32, [u8; 3], f32)> = state
+            .pellets
+            .iter()
+            .map(|pellet| (pellet.id, pellet.color_rgb, pellet.base_size))
+            .collect();
+        ((head.x, head.y, head.z), pellets)
+    };
+
+    assert_eq!(run(7), run(7));
+    assert_ne!(run(7).0, run(8).0);
+}
+
+#[test]
+fn spawn_placement_stays_in_the_spawn_cone_and_spreads_around_it() {
+    let mut state = make_state();
+    state.environment.lakes.clear();
+    state.environment.trees.clear();
+    state.environment.mountains.clear();
+    let mut rng = state.fork_rng();
+    let mut quadrants = HashSet::new();
+    for _ in 0..400 {
+        let base_axis = random_axis(&mut rng);
+        let spawned = state
+            .spawn_snake(base_axis, None, STARTING_LENGTH, true, &mut rng)
+            .expect("empty planet always has room");
+        let head = &spawned.snake[0];
+        // Spawns land in a cap of `SPAWN_CONE_ANGLE` around the south pole.
+        assert!(-head.z >= SPAWN_CONE_ANGLE.cos() - 1e-9);
+        quadrants.insert((head.x >= 0.0, head.y >= 0.0));
+    }
+    assert_eq!(quadrants.len(), 4);
+}
+
+#[test]
+fn spawn_terrain_protection_rejects_a_spawn_cone_full_of_lake() {
+    let mut state = make_full_lake_state();
+    let mut rng = state.fork_rng();
+    let base_axis = random_axis(&mut rng);
+    assert!(state
+        .spawn_snake(base_axis, None, STARTING_LENGTH, true, &mut rng)
+        .is_none());
+    assert!(state
+        .spawn_snake(base_axis, None, STARTING_LENGTH, false, &mut rng)
+        .is_some());
+
+    // Humans wait for dry land; bots still spawn.
+    let mut state = make_full_lake_state();
+    let human = state
+        .create_player(Uuid::new_v4(), "Human".to_string(), false)
+        .expect("net id");
+    assert!(!human.alive);
+    assert!(human.respawn_at.is_some());
+    let bot = state
+        .create_player(Uuid::new_v4(), "Bot".to_string(), true)
+        .expect("net id");
+    assert!(bot.alive);
+}
+
+#[test]
+fn spawn_terrain_protection_places_every_node_on_valid_terrain() {
+    let mut state = make_state();
+    let mut rng = state.fork_rng();
+    for _ in 0..100 {
+        let base_axis = random_axis(&mut rng);
+        let spawned = state
+            .spawn_snake(base_axis, None, STARTING_LENGTH, true, &mut rng)
+            .expect("generated terrain leaves dry land in the spawn cone");
+        assert!(!state.is_invalid_snake_spawn(&spawned.snake));
+    }
 }
 
 #[test]
@@ -640,7 +4092,7 @@ fn death_pellets_clamp_to_u16_max() {
             base_size: SMALL_PELLET_SIZE_MIN,
             current_size: SMALL_PELLET_SIZE_MIN,
             growth_fraction: SMALL_PELLET_GROWTH_FRACTION,
-        expires_at_ms: None,
+            expires_at_ms: None,
             state: PelletState::Idle,
         })
         .collect();
@@ -662,6 +4114,50 @@ fn death_pellets_clamp_to_u16_max() {
     }
 }
 
+fn add_created_player(state: &mut RoomState, index: u128) -> String {
+    let player = state
+        .create_player(Uuid::from_u128(index), format!("Player {index}"), false)
+        .expect("net id available");
+    let id = player.id.clone();
+    state.players.insert(id.clone(), player);
+    id
+}
+
+#[test]
+fn player_colors_stay_distinct_and_freed_colors_are_reused() {
+    let mut state = make_state();
+    let ids: Vec<String> = (1..=COLOR_POOL.len() as u128)
+        .map(|index| add_created_player(&mut state, index))
+        .collect();
+    let colors: HashSet<String> = state
+        .players
+        .values()
+        .map(|player| player.color.clone())
+        .collect();
+    assert_eq!(colors.len(), COLOR_POOL.len());
+
+    let freed = state.players.remove(&ids[2]).unwrap().color;
+    let id = add_created_player(&mut state, 100);
+    assert_eq!(state.players[&id].color, freed);
+
+    // With every color taken, assignment falls back to pool order.
+    let id = add_created_player(&mut state, 101);
+    assert_eq!(state.players[&id].color, COLOR_POOL[0]);
+    let id = add_created_player(&mut state, 102);
+    assert_eq!(state.players[&id].color, COLOR_POOL[1]);
+}
+
+#[test]
+fn skinned_players_do_not_hold_pool_colors() {
+    let mut state = make_state();
+    let id = add_created_player(&mut state, 1);
+    let player = state.players.get_mut(&id).unwrap();
+    assert_eq!(player.color, COLOR_POOL[0]);
+    player.skin = Some(vec![[1, 2, 3]]);
+    let id = add_created_player(&mut state, 2);
+    assert_eq!(state.players[&id].color, COLOR_POOL[0]);
+}
+
 #[test]
 fn boost_trail_pellets_spawn_on_interval_while_boosting() {
     let mut state = make_state();
@@ -694,8 +4190,81 @@ fn boost_trail_pellets_cycle_player_skin_colors() {
     state.spawn_boost_trail_pellets(2_200);
     state.spawn_boost_trail_pellets(2_300);
 
-    let colors: Vec<[u8; 3]> = state.pellets.iter().map(|pellet| pellet.color_rgb).collect();
-    assert_eq!(colors, vec![[10, 20, 30], [40, 50, 60], [70, 80, 90], [10, 20, 30]]);
+    let colors: Vec<[u8; 3]> = state
+        .pellets
+        .iter()
+        .map(|pellet| pellet.color_rgb)
+        .collect();
+    assert_eq!(
+        colors,
+        vec![[10, 20, 30], [40, 50, 60], [70, 80, 90], [10, 20, 30]]
+    );
+}
+
+#[test]
+fn boost_drain_shedding_drops_pellets_worth_the_drained_length() {
+    let mut state = make_state();
+    state.bots_enabled = false;
+    state.environment = Environment {
+        lakes: Vec::new(),
+        trees: Vec::new(),
+        mountains: Vec::new(),
+    };
+    state.pellet_config = PelletConfig {
+        base_count: 1,
+        ..PelletConfig::default()
+    };
+    state.pellets.clear();
+    state.boost_drain_shedding = true;
+    let axis = Point {
+        x: 1.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    let mut player = make_player("shedder", create_snake_with_length(axis, 40));
+    player.score = 40;
+    player.boost_floor_len = STARTING_LENGTH;
+    player.boost = true;
+    state.players.insert("shedder".to_string(), player);
+    let length_before = RoomState::boost_sheddable_length(&state.players["shedder"]);
+
+    let ticks = 40;
+    let mut shed_counts = Vec::new();
+    for _ in 0..ticks {
+        state.tick();
+        assert!(state.players["shedder"].is_boosting);
+        shed_counts.push(
+            state
+                .pellets
+                .iter()
+                .filter(|pellet| pellet.expires_at_ms.is_some())
+                .count(),
+        );
+    }
+
+    // Shedding keeps pace with the drain rather than arriving in one burst.
+    assert!(shed_counts[ticks / 4] > 0);
+    assert!(shed_counts[ticks / 4] < shed_counts[ticks - 1]);
+    let shed_value: f64 = state
+        .pellets
+        .iter()
+        .filter(|pellet| pellet.expires_at_ms.is_some())
+        .map(|pellet| pellet.growth_fraction)
+        .sum();
+    let player = &state.players["shedder"];
+    let drained = length_before - RoomState::boost_sheddable_length(player);
+    let expected_drain = BOOST_NODE_DRAIN_PER_SEC * ticks as f64 * TICK_MS as f64 / 1000.0;
+    assert!((drained - expected_drain).abs() < 1e-6);
+    assert!((shed_value + player.boost_shed_value - drained).abs() < 1e-6);
+    assert!(player.boost_shed_value < BOOST_TRAIL_PELLET_GROWTH_FRACTION);
+}
+
+#[test]
+fn boost_drain_shedding_is_off_by_default() {
+    let mut room = Room::with_room_id("shed".to_string());
+    assert!(!room.state.get_mut().boost_drain_shedding);
+    let mut room = room.with_boost_drain_shedding(true);
+    assert!(room.state.get_mut().boost_drain_shedding);
 }
 
 #[test]
@@ -722,7 +4291,7 @@ fn boost_trail_pellets_set_ttl_and_expire() {
 #[test]
 fn spawn_small_pellet_rejects_lake_zone() {
     let mut state = make_full_lake_state();
-    let mut rng = rand::thread_rng();
+    let mut rng = state.fork_rng();
     assert!(state.spawn_small_pellet_with_rng(&mut rng).is_none());
 }
 
@@ -874,7 +4443,11 @@ fn write_player_state_encodes_digestion_id_and_progress() {
     });
 
     let mut encoder = protocol::Encoder::with_capacity(256);
-    state.write_player_state_with_window(&mut encoder, &player, SnakeWindow::full(player.snake.len()));
+    state.write_player_state_with_window(
+        &mut encoder,
+        &player,
+        SnakeWindow::full(player.snake.len()),
+    );
     let payload = encoder.into_vec();
 
     let mut offset = 0usize;
@@ -945,7 +4518,11 @@ fn write_player_state_encodes_authoritative_is_boosting_flag() {
     player.is_boosting = true;
 
     let mut encoder = protocol::Encoder::with_capacity(256);
-    state.write_player_state_with_window(&mut encoder, &player, SnakeWindow::full(player.snake.len()));
+    state.write_player_state_with_window(
+        &mut encoder,
+        &player,
+        SnakeWindow::full(player.snake.len()),
+    );
     let payload = encoder.into_vec();
 
     let flags = payload[2];
@@ -970,7 +4547,11 @@ fn write_player_state_encodes_most_recent_digestions_when_capped() {
     }
 
     let mut encoder = protocol::Encoder::with_capacity(8192);
-    state.write_player_state_with_window(&mut encoder, &player, SnakeWindow::full(player.snake.len()));
+    state.write_player_state_with_window(
+        &mut encoder,
+        &player,
+        SnakeWindow::full(player.snake.len()),
+    );
     let payload = encoder.into_vec();
 
     let mut offset = 0usize;
@@ -1098,6 +4679,49 @@ fn small_pellet_near_attract_edge_moves_and_shrinks_before_consume() {
     assert!(pellet.current_size < pellet.base_size);
 }
 
+#[test]
+fn long_snakes_attract_small_pellets_from_further_away() {
+    let head = normalize(Point {
+        x: 1.0,
+        y: 0.0,
+        z: 0.0,
+    });
+    let trailing = normalize(Point {
+        x: 1.0,
+        y: -0.05,
+        z: 0.0,
+    });
+    let pellet_start = normalize(Point {
+        x: 1.0,
+        y: SMALL_PELLET_ATTRACT_RADIUS * 1.3,
+        z: 0.0,
+    });
+
+    let mut targeted = Vec::new();
+    for len in [STARTING_LENGTH, STARTING_LENGTH + 200] {
+        let mut state = make_state();
+        let player_id = format!("pellet-reach-{len}");
+        state.players.insert(
+            player_id.clone(),
+            make_player(&player_id, make_snake_with_head(head, trailing, len)),
+        );
+        state.pellets.push(make_pellet(23, pellet_start));
+
+        state.update_small_pellets(TICK_MS as f64 / 1000.0);
+
+        assert_eq!(state.pellets.len(), 1);
+        targeted.push(matches!(
+            state.pellets[0].state,
+            PelletState::Attracting { .. }
+        ));
+    }
+    assert_eq!(targeted, vec![false, true]);
+
+    assert!(
+        make_state().pellet_attract_radius_for_len(usize::MAX) <= SMALL_PELLET_ATTRACT_RADIUS_MAX
+    );
+}
+
 #[test]
 fn small_pellet_targeting_prefers_nearest_mouth() {
     let mut state = make_state();
@@ -1328,6 +4952,60 @@ fn small_pellet_target_retargets_when_target_becomes_invalid() {
     assert!(state.pending_pellet_consumes.is_empty());
 }
 
+#[test]
+fn killing_the_target_mid_attraction_releases_the_pellet_to_idle() {
+    let mut state = make_state();
+    let player_id = "pellet-release-player".to_string();
+    let toward_up = Point {
+        x: 0.0,
+        y: 1.0,
+        z: 0.0,
+    };
+    let toward_down = Point {
+        x: 0.0,
+        y: -1.0,
+        z: 0.0,
+    };
+    let head = Point {
+        x: 1.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    let trailing = rotate_toward(head, toward_down, 0.05);
+    state.players.insert(
+        player_id.clone(),
+        make_player(&player_id, make_snake_with_head(head, trailing, 2)),
+    );
+    let pellet_start = rotate_toward(head, toward_up, SMALL_PELLET_ATTRACT_RADIUS * 0.6);
+    state.pellets.push(make_pellet(905, pellet_start));
+
+    state.update_small_pellets(0.0001);
+    assert!(matches!(
+        &state.pellets[0].state,
+        PelletState::Attracting { target_player_id } if target_player_id == &player_id
+    ));
+    assert!(state.pellets[0].current_size < state.pellets[0].base_size);
+
+    state.handle_death(&player_id);
+    let pellet = state
+        .pellets
+        .iter()
+        .find(|pellet| pellet.id == 905)
+        .expect("pellet is not consumed");
+    assert!(matches!(pellet.state, PelletState::Idle));
+    assert_eq!(pellet.current_size, pellet.base_size);
+
+    state.update_small_pellets(0.0001);
+    let pellet = state
+        .pellets
+        .iter()
+        .find(|pellet| pellet.id == 905)
+        .expect("pellet is not consumed");
+    assert!(matches!(pellet.state, PelletState::Idle));
+    assert_eq!(pellet.current_size, pellet.base_size);
+    assert!(state.pending_pellet_consumes.is_empty());
+}
+
 #[test]
 fn small_pellet_growth_is_fractional_before_full_score_tick() {
     let mut state = make_state();
@@ -2011,7 +5689,11 @@ fn small_pellet_consumes_when_head_moves_more_than_consume_angle_between_ticks()
 
 #[test]
 fn snake_window_uses_partial_window_for_remote_players() {
-    let state = make_state();
+    let mut state = make_state();
+    state.view_config = ViewConfig {
+        radius_margin: 0.14,
+        node_padding: 3,
+    };
     let player = make_player(
         "player-window",
         snake_from_xs(&[
@@ -2019,7 +5701,7 @@ fn snake_window_uses_partial_window_for_remote_players() {
         ]),
     );
 
-    let view_cos = (0.6f64 + VIEW_RADIUS_MARGIN).cos();
+    let view_cos = (0.6f64 + state.view_config.radius_margin).cos();
     let window = state.snake_window_for_player(
         &player,
         false,
@@ -2046,7 +5728,7 @@ fn snake_window_returns_stub_when_remote_snake_is_out_of_view() {
         "player-stub",
         snake_from_xs(&[-0.95, -0.9, -0.88, -0.85, -0.82]),
     );
-    let view_cos = (0.45f64 + VIEW_RADIUS_MARGIN).cos();
+    let view_cos = (0.45f64 + state.view_config.radius_margin).cos();
     let window = state.snake_window_for_player(
         &player,
         false,
@@ -2063,6 +5745,48 @@ fn snake_window_returns_stub_when_remote_snake_is_out_of_view() {
     assert_eq!(window.total_len, 5);
 }
 
+#[test]
+fn wider_view_margin_sends_more_of_a_snake_straddling_the_view_edge() {
+    let mut state = make_state();
+    // The head sits just outside a 0.6 rad view; the next few nodes are inside it.
+    let player = make_player(
+        "player-edge",
+        snake_from_xs(&[
+            0.78, 0.84, 0.88, 0.9, 0.86, 0.8, 0.7, 0.5, 0.2, -0.1, -0.3, -0.5, -0.7, -0.9,
+        ]),
+    );
+    let view_center = Point {
+        x: 1.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    let mut window_with = |view_config: ViewConfig| {
+        state.view_config = view_config;
+        let view_cos = (0.6f64 + view_config.radius_margin).cos();
+        state.snake_window_for_player(&player, false, Some((view_center, view_cos)))
+    };
+
+    let tight = window_with(ViewConfig {
+        radius_margin: 0.0,
+        node_padding: 0,
+    });
+    assert_eq!(tight.detail, SnakeDetail::Window);
+    assert_eq!((tight.start, tight.len), (1, 4));
+
+    let default = window_with(ViewConfig::default());
+    assert_eq!(default.detail, SnakeDetail::Window);
+    assert_eq!(default.start, 0);
+    assert!(default.len > tight.len);
+
+    let sanitized = ViewConfig {
+        radius_margin: f64::NAN,
+        node_padding: 1_000,
+    }
+    .sanitized();
+    assert_eq!(sanitized.radius_margin, ViewConfig::default().radius_margin);
+    assert_eq!(sanitized.node_padding, 32);
+}
+
 #[test]
 fn build_state_delta_payload_for_session_excludes_stub_remote_players() {
     let mut state = make_state();
@@ -2110,6 +5834,92 @@ fn build_state_delta_payload_for_session_excludes_stub_remote_players() {
     assert_eq!(ack_input_seq, 321);
 }
 
+#[test]
+fn crowded_views_cap_detailed_remote_snakes_and_keep_local_full() {
+    let mut state = make_state();
+    state.max_detailed_remote_players = 3;
+    let on_equator = |angle: f64| Point {
+        x: angle.cos(),
+        y: angle.sin(),
+        z: 0.0,
+    };
+    let local_id = "local-player".to_string();
+    state.players.insert(
+        local_id.clone(),
+        make_player(
+            &local_id,
+            make_snake_with_head(on_equator(0.0), on_equator(-0.02), 40),
+        ),
+    );
+    for index in 0..12 {
+        let angle = 0.05 * (index + 1) as f64;
+        let id = format!("remote-{index:02}");
+        state.players.insert(
+            id.clone(),
+            make_player(
+                &id,
+                make_snake_with_head(on_equator(angle), on_equator(angle + 0.02), 8),
+            ),
+        );
+    }
+    insert_session_with_view(
+        &mut state,
+        "session-1",
+        &local_id,
+        Some(on_equator(0.0)),
+        Some(1.0),
+    );
+
+    let visible = state.visible_players_for_session("session-1");
+    assert_eq!(visible.len(), 13);
+    let local = visible
+        .iter()
+        .find(|visible| visible.player.id == local_id)
+        .expect("local player");
+    assert_eq!(local.window.detail, SnakeDetail::Full);
+    let mut detailed: Vec<&str> = visible
+        .iter()
+        .filter(|visible| {
+            visible.player.id != local_id && visible.window.detail != SnakeDetail::Stub
+        })
+        .map(|visible| visible.player.id.as_str())
+        .collect();
+    detailed.sort_unstable();
+    assert_eq!(detailed, vec!["remote-00", "remote-01", "remote-02"]);
+}
+
+#[test]
+fn resync_request_forces_a_keyframe_on_the_next_frame() {
+    let mut state = make_state();
+    state.keyframe_interval = 100;
+    let local_id = "local-player".to_string();
+    state.players.insert(
+        local_id.clone(),
+        make_player(&local_id, snake_from_xs(&[0.2, 0.1, 0.0, -0.1])),
+    );
+    insert_session_with_view(&mut state, "session-1", &local_id, None, None);
+
+    let is_keyframe = |state: &mut RoomState, seq: u32| {
+        let payload = state
+            .build_state_delta_payload_for_session(1000 + seq as i64, seq, "session-1")
+            .expect("state delta payload");
+        decode_state_frame_flags(&payload) & DELTA_FRAME_KEYFRAME != 0
+    };
+    assert!(
+        is_keyframe(&mut state, 1),
+        "new sessions start with a keyframe"
+    );
+    assert!(!is_keyframe(&mut state, 2));
+
+    state.handle_resync("session-1");
+    assert!(is_keyframe(&mut state, 3));
+    assert!(!is_keyframe(&mut state, 4));
+    assert!(
+        is_keyframe(&mut state, 100),
+        "scheduled keyframes still follow the interval"
+    );
+}
+
 #[test]
 fn build_init_payload_for_session_uses_view_scoped_player_count() {
     let mut state = make_state();
@@ -2138,7 +5948,7 @@ fn build_init_payload_for_session_uses_view_scoped_player_count() {
         Some(0.45),
     );
 
-    let payload = state.build_init_payload_for_session("session-2", &local_id);
+    let payload = state.build_init_payload_for_session("session-2", &local_id, false);
     let (state_seq, total_players, visible_players) = decode_init_counts(&payload);
     assert_eq!(state_seq, state.next_state_seq.wrapping_sub(1));
     assert_eq!(total_players, 3);
@@ -2181,7 +5991,7 @@ fn build_state_delta_payload_includes_local_player_when_local_snake_is_empty() {
 fn broadcast_state_delta_increments_state_sequence_once_per_tick() {
     let mut state = make_state();
     let outbound_state = Arc::new(LatestFrame::new());
-    let (outbound_hi, _outbound_hi_rx) = mpsc::channel::<Vec<u8>>(1);
+    let (outbound_hi, _outbound_hi_rx) = outbound_queue(1, OutboundFullPolicy::Disconnect);
     let (outbound_lo, _outbound_lo_rx) = mpsc::channel::<Vec<u8>>(1);
     let inbound = Arc::new(SessionInbound::new());
     state.sessions.insert(
@@ -2201,6 +6011,13 @@ fn broadcast_state_delta_increments_state_sequence_once_per_tick() {
             delta_player_cache: HashMap::new(),
             force_next_keyframe: true,
             latest_applied_input_seq: 0,
+            wire_format: WireFormat::Binary,
+            protocol_version: Some(protocol::VERSION),
+            pellet_resyncs: 0,
+            pellet_resync_window_started_at: 0,
+            pellet_resyncs_in_window: 0,
+            threat_hints: false,
+            threat_hint_active: false,
         },
     );
 
@@ -2216,9 +6033,57 @@ fn broadcast_state_delta_increments_state_sequence_once_per_tick() {
     assert_eq!(second_seq, first_seq.wrapping_add(1));
 }
 
+#[test]
+fn state_frame_timing_tracks_the_previous_frame_across_skipped_ticks() {
+    let mut state = make_state();
+    state.players.insert(
+        "player-1".to_string(),
+        make_player("player-1", make_snake(6, 0.0)),
+    );
+    insert_session_with_view(&mut state, "session-1", "player-1", None, None);
+    let outbound_state = Arc::clone(&state.sessions["session-1"].outbound_state);
+    let tick_ms = TICK_MS as i64;
+
+    // The third frame lands two skipped ticks late; the sequence still advances by one.
+    let mut frames = Vec::new();
+    for now in [1_000, 1_000 + tick_ms, 1_000 + tick_ms * 4] {
+        let state_seq = state.next_state_seq;
+        state.broadcast_state_delta(now, state_seq);
+        state.next_state_seq = state.next_state_seq.wrapping_add(1);
+        let payload = outbound_state.take_latest().expect("state frame");
+        frames.push(decode_state_timing(&payload));
+    }
+
+    for &(_, _, frame_tick_ms, render_delay_ms, _, _) in &frames {
+        assert_eq!(frame_tick_ms as i64, tick_ms);
+        assert_eq!(render_delay_ms as i64, tick_ms * 2);
+    }
+    let (first_now, first_seq, _, _, first_prev_seq, first_interval) = frames[0];
+    assert_eq!(first_prev_seq, first_seq);
+    assert_eq!(first_interval, 0);
+    for pair in frames.windows(2) {
+        let (prev_now, prev_seq, ..) = pair[0];
+        let (now, seq, _, _, reported_prev_seq, interval) = pair[1];
+        assert_eq!(seq, prev_seq.wrapping_add(1));
+        assert!(now > prev_now);
+        assert_eq!(reported_prev_seq, prev_seq);
+        assert_eq!(interval as i64, now - prev_now);
+    }
+    assert_eq!(frames[1].5 as i64, tick_ms);
+    assert_eq!(frames[2].5 as i64, tick_ms * 3);
+    assert_eq!(frames[2].0 - first_now, tick_ms * 4);
+}
+
 fn make_full_lake_state() -> RoomState {
     use crate::game::environment::Lake;
     let mut state = make_state();
+    // These tests measure the drain itself; the entry grace has its own tests.
+    state.oxygen_grace_ms = 0;
+    // Bots would wander into the test snakes and cut their drowning short.
+    state.bot_config = BotConfig {
+        max_bots: 0,
+        min_bots: 0,
+    };
     state.environment.trees.clear();
     state.environment.mountains.clear();
     state.environment.lakes = vec![Lake {
@@ -2367,13 +6232,50 @@ fn forest_tree_collision_is_not_instant_death() {
     state.tick();
 
     let player = state.players.get(&player_id).expect("player");
-    assert!(player.alive);
+    assert!(player.alive);
+}
+
+#[test]
+fn oxygen_depletion_shrinks_snake_before_killing() {
+    let mut state = make_full_lake_state();
+    let player_id = "player-oxygen-drown".to_string();
+    let mut player = make_player(
+        &player_id,
+        create_snake(Point {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        }),
+    );
+    player.oxygen = 0.0;
+    state.players.insert(player_id.clone(), player);
+
+    state.tick();
+    let player = state.players.get(&player_id).expect("player");
+    assert!(player.alive);
+    assert_eq!(player.snake.len(), STARTING_LENGTH);
+
+    let mut shortest = STARTING_LENGTH;
+    for _ in 0..200 {
+        state.tick();
+        let player = state.players.get(&player_id).expect("player");
+        if !player.alive {
+            break;
+        }
+        assert!(player.snake.len() <= shortest);
+        shortest = player.snake.len();
+    }
+
+    let player = state.players.get(&player_id).expect("player");
+    assert!(!player.alive);
+    assert_eq!(shortest, MIN_SURVIVAL_LENGTH);
+    assert_eq!(player.score, 0);
 }
 
 #[test]
-fn oxygen_depletion_kills_immediately_when_empty() {
+fn brief_zero_oxygen_shrinks_but_survives_after_leaving_water() {
     let mut state = make_full_lake_state();
-    let player_id = "player-oxygen-immediate".to_string();
+    let player_id = "player-oxygen-escape".to_string();
     let mut player = make_player(
         &player_id,
         create_snake(Point {
@@ -2382,14 +6284,38 @@ fn oxygen_depletion_kills_immediately_when_empty() {
             z: 0.0,
         }),
     );
+    // Center a smaller lake on the head so the dropped tail node can land on dry ground.
+    state.environment.lakes[0].center = normalize(Point {
+        x: player.snake[0].x,
+        y: player.snake[0].y,
+        z: player.snake[0].z,
+    });
+    state.environment.lakes[0].radius = 1.0;
     player.oxygen = 0.0;
+    player.score = STARTING_LENGTH as i64;
     state.players.insert(player_id.clone(), player);
 
-    state.tick();
+    let dt_seconds = TICK_MS as f64 / 1000.0;
+    let mut death_reasons = HashMap::new();
+    let ticks = (1.5 / (OXYGEN_DAMAGE_NODES_PER_SEC * dt_seconds)).ceil() as usize;
+    for _ in 0..ticks {
+        let dead = state.update_oxygen(dt_seconds, &mut death_reasons);
+        assert!(dead.is_empty());
+    }
+    let player = state.players.get(&player_id).expect("player");
+    assert_eq!(player.snake.len(), STARTING_LENGTH - 1);
+    assert_eq!(player.score, STARTING_LENGTH as i64 - 1);
+    assert_eq!(state.pellets.len(), 1);
+
+    state.environment.lakes.clear();
+    state.update_oxygen(dt_seconds, &mut death_reasons);
 
     let player = state.players.get(&player_id).expect("player");
-    assert!(!player.alive);
-    assert_eq!(player.score, 0);
+    assert!(player.alive);
+    assert_eq!(player.oxygen, OXYGEN_MAX);
+    assert_eq!(player.oxygen_damage_accumulator, 0.0);
+    assert_eq!(player.snake.len(), STARTING_LENGTH - 1);
+    assert!(death_reasons.is_empty());
 }
 
 #[test]
@@ -2413,6 +6339,64 @@ fn oxygen_depletion_kills_at_min_survival_length() {
     assert_eq!(player.score, 0);
 }
 
+#[test]
+fn brief_lake_dip_within_grace_does_not_drain_oxygen() {
+    let mut state = make_full_lake_state();
+    state.oxygen_grace_ms = LAKE_OXYGEN_GRACE_MS;
+    let player_id = "player-oxygen-dip".to_string();
+    let player = make_player(
+        &player_id,
+        create_snake(Point {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        }),
+    );
+    state.players.insert(player_id.clone(), player);
+    let lakes = std::mem::take(&mut state.environment.lakes);
+
+    let dt_seconds = TICK_MS as f64 / 1000.0;
+    let mut death_reasons = HashMap::new();
+    // Repeated one-tick dips never accumulate toward the grace.
+    for _ in 0..20 {
+        state.environment.lakes = lakes.clone();
+        state.update_oxygen(dt_seconds, &mut death_reasons);
+        assert_eq!(state.players[&player_id].oxygen, OXYGEN_MAX);
+        state.environment.lakes.clear();
+        state.update_oxygen(dt_seconds, &mut death_reasons);
+        assert_eq!(state.players[&player_id].submerged_secs, 0.0);
+    }
+}
+
+#[test]
+fn sustained_submersion_drains_oxygen_after_the_grace() {
+    let mut state = make_full_lake_state();
+    state.oxygen_grace_ms = LAKE_OXYGEN_GRACE_MS;
+    let player_id = "player-oxygen-soak".to_string();
+    let player = make_player(
+        &player_id,
+        create_snake(Point {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        }),
+    );
+    state.players.insert(player_id.clone(), player);
+
+    let dt_seconds = TICK_MS as f64 / 1000.0;
+    let mut death_reasons = HashMap::new();
+    let grace_ticks = (LAKE_OXYGEN_GRACE_MS / TICK_MS as i64) as usize;
+    for _ in 0..grace_ticks {
+        state.update_oxygen(dt_seconds, &mut death_reasons);
+    }
+    assert_eq!(state.players[&player_id].oxygen, OXYGEN_MAX);
+
+    for _ in 0..5 {
+        state.update_oxygen(dt_seconds, &mut death_reasons);
+    }
+    assert!(state.players[&player_id].oxygen < OXYGEN_MAX);
+}
+
 #[test]
 fn oxygen_replenishes_when_not_underwater() {
     let mut state = make_full_lake_state();
@@ -2444,3 +6428,449 @@ fn oxygen_replenishes_when_not_underwater() {
     assert_eq!(player.oxygen_damage_accumulator, 0.0);
     assert_eq!(player.snake.len(), 8);
 }
+
+#[test]
+fn oxygen_config_sets_the_tank_size_and_drain_rate() {
+    let mut state = make_full_lake_state();
+    state.oxygen_config = OxygenConfig {
+        max: 2.0,
+        drain_per_sec: OXYGEN_DRAIN_PER_SEC * 2.0,
+    }
+    .sanitized();
+    let spawned = state
+        .create_player(Uuid::new_v4(), "Diver".to_string(), false)
+        .expect("player");
+    assert_eq!(spawned.oxygen, 2.0);
+
+    let player_id = "player-oxygen-config".to_string();
+    let mut player = make_player(
+        &player_id,
+        create_snake(Point {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        }),
+    );
+    player.oxygen = 2.0;
+    state.players.insert(player_id.clone(), player);
+
+    let dt_seconds = 0.5;
+    let mut death_reasons = HashMap::new();
+    state.update_oxygen(dt_seconds, &mut death_reasons);
+    let expected = 2.0 - OXYGEN_DRAIN_PER_SEC * 2.0 * state.oxygen_drain_multiplier() * dt_seconds;
+    assert!((state.players[&player_id].oxygen - expected).abs() < 1e-9);
+
+    state.environment.lakes.clear();
+    state.update_oxygen(dt_seconds, &mut death_reasons);
+    assert_eq!(state.players[&player_id].oxygen, 2.0);
+}
+
+#[test]
+fn larger_oxygen_max_survives_more_ticks_underwater() {
+    fn ticks_until_out_of_air(oxygen_config: OxygenConfig) -> usize {
+        let mut state = make_full_lake_state();
+        state.oxygen_config = oxygen_config.sanitized();
+        let player_id = "player-oxygen-endurance".to_string();
+        let mut player = make_player(
+            &player_id,
+            create_snake(Point {
+                x: 1.0,
+                y: 0.0,
+                z: 0.0,
+            }),
+        );
+        player.oxygen = state.oxygen_config.max;
+        state.players.insert(player_id.clone(), player);
+
+        let dt_seconds = TICK_MS as f64 / 1000.0;
+        let mut death_reasons = HashMap::new();
+        for tick in 1..=10_000 {
+            state.update_oxygen(dt_seconds, &mut death_reasons);
+            let oxygen = state.players[&player_id].oxygen;
+            assert!((0.0..=1.0).contains(&state.oxygen_config.fraction(oxygen)));
+            if oxygen <= 0.0 {
+                return tick;
+            }
+        }
+        panic!("player never ran out of oxygen");
+    }
+
+    let baseline = ticks_until_out_of_air(OxygenConfig::default());
+    let roomy = ticks_until_out_of_air(OxygenConfig {
+        max: OXYGEN_MAX * 2.0,
+        ..OxygenConfig::default()
+    });
+    assert!(roomy > baseline);
+    assert!(roomy >= baseline * 2 - 1);
+}
+
+#[test]
+fn oxygen_config_sanitizes_out_of_range_values() {
+    let config = OxygenConfig {
+        max: f64::NAN,
+        drain_per_sec: 100.0,
+    }
+    .sanitized();
+    assert_eq!(config.max, OXYGEN_MAX);
+    assert!(config.drain_per_sec <= 5.0);
+
+    let config = OxygenConfig {
+        max: 0.0,
+        drain_per_sec: 0.0,
+    }
+    .sanitized();
+    assert!(config.max > 0.0);
+    assert!(config.drain_per_sec > 0.0);
+    assert_eq!(config.fraction(config.max * 3.0), 1.0);
+    assert_eq!(config.fraction(-1.0), 0.0);
+}
+
+#[test]
+fn pellet_delta_dropped_on_full_low_priority_queue_counts_a_resync() {
+    let mut state = make_state();
+    let head = Point {
+        x: 0.0,
+        y: 0.0,
+        z: 1.0,
+    };
+    state.players.insert(
+        "player-1".to_string(),
+        make_player("player-1", make_snake(6, 0.0)),
+    );
+    insert_session_with_view(&mut state, "session-1", "player-1", Some(head), Some(1.0));
+    state.pellets = vec![make_pellet(1, head)];
+    let (outbound_lo, _outbound_lo_rx) = mpsc::channel::<Vec<u8>>(1);
+    outbound_lo
+        .try_send(Vec::new())
+        .expect("fill low-priority queue");
+    let session = state.sessions.get_mut("session-1").expect("session");
+    session.outbound_lo = outbound_lo;
+    session.pellet_view_initialized = true;
+
+    state.broadcast_pellet_delta(1_000, 1);
+
+    let session = &state.sessions["session-1"];
+    assert!(!session.pellet_view_initialized);
+    assert!(session.pellet_view_ids.is_empty());
+    assert_eq!(session.pellet_resyncs, 1);
+    assert_eq!(session.pellet_resyncs_in_window, 1);
+    assert_eq!(state.pellet_resyncs, 1);
+
+    // Once the session is marked uninitialized it waits for a reset instead of more deltas.
+    state.broadcast_pellet_delta(1_050, 2);
+    assert_eq!(state.pellet_resyncs, 1);
+}
+
+#[test]
+fn threat_hint_points_toward_nearby_body_and_clears_when_it_is_gone() {
+    let mut state = make_state();
+    let mut watcher_rx = insert_chat_player(
+        &mut state,
+        "watcher",
+        1,
+        Point {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        },
+    );
+    let mut threat_rx = insert_chat_player(
+        &mut state,
+        "threat",
+        2,
+        normalize(Point {
+            x: 0.95,
+            y: -0.2,
+            z: 0.0,
+        }),
+    );
+    state
+        .sessions
+        .get_mut("session-watcher")
+        .expect("session")
+        .threat_hints = true;
+
+    let snapshots = state.player_collision_snapshots();
+    let head = Point {
+        x: 1.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    let (direction, angle) = state
+        .nearest_threat("watcher", head, &snapshots)
+        .expect("threat nearby");
+    let closest = state.players["threat"]
+        .snake
+        .iter()
+        .map(|node| {
+            dot(
+                head,
+                Point {
+                    x: node.x,
+                    y: node.y,
+                    z: node.z,
+                },
+            )
+            .acos()
+        })
+        .fold(f64::INFINITY, f64::min);
+    assert!((angle - closest).abs() < 1e-9);
+    assert!(
+        direction.y < -0.9,
+        "hint should point toward -y: {direction:?}"
+    );
+    assert!(dot(direction, head).abs() < 1e-9);
+
+    state.send_threat_hints(&snapshots);
+    let frame = watcher_rx.try_recv().expect("threat hint");
+    assert_eq!(frame[1], protocol::TYPE_HINT);
+    assert_eq!(
+        u16::from_le_bytes([frame[2], frame[3]]),
+        protocol::FLAG_HINT_THREAT
+    );
+    let encoded_angle = f32::from_le_bytes([frame[8], frame[9], frame[10], frame[11]]);
+    assert!((encoded_angle as f64 - angle).abs() < 1e-6);
+    assert!(threat_rx.try_recv().is_err());
+
+    state.players.get_mut("threat").expect("threat").alive = false;
+    assert!(state.nearest_threat("watcher", head, &snapshots).is_none());
+    state.send_threat_hints(&snapshots);
+    let frame = watcher_rx.try_recv().expect("clear hint");
+    assert_eq!(frame.len(), 4);
+    assert_eq!(u16::from_le_bytes([frame[2], frame[3]]), 0);
+    state.send_threat_hints(&snapshots);
+    assert!(watcher_rx.try_recv().is_err());
+}
+
+fn evasive_step_for_owner_record(attempts: u32, captures: u32) -> f64 {
+    let mut state = make_state();
+    let owner_id = "evasive-owner-skill".to_string();
+    let snake = make_snake_with_head(
+        Point {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        Point {
+            x: 0.9805806756909201,
+            y: -0.19611613513818402,
+            z: 0.0,
+        },
+        EVASIVE_PELLET_MIN_LEN,
+    );
+    let mut owner = make_player(&owner_id, snake);
+    owner.evasive_attempts = attempts;
+    owner.evasive_captures = captures;
+    state.players.insert(owner_id.clone(), owner);
+    let start = normalize(Point {
+        x: 1.0,
+        y: 0.16,
+        z: 0.0,
+    });
+    state.pellets.push(Pellet {
+        id: 890,
+        normal: start,
+        color_rgb: [255, 255, 255],
+        base_size: EVASIVE_PELLET_SIZE_MIN,
+        current_size: EVASIVE_PELLET_SIZE_MIN,
+        growth_fraction: BIG_PELLET_GROWTH_FRACTION,
+        expires_at_ms: None,
+        state: PelletState::Evasive {
+            owner_player_id: owner_id,
+            expires_at_ms: i64::MAX,
+        },
+    });
+
+    state.update_small_pellets(TICK_MS as f64 / 1000.0);
+    assert_eq!(state.pellets.len(), 1);
+    clamp(dot(start, state.pellets[0].normal), -1.0, 1.0).acos()
+}
+
+#[test]
+fn struggling_owners_face_slower_evasive_pellets_than_skilled_owners() {
+    let struggling = evasive_step_for_owner_record(8, 0);
+    let new_player = evasive_step_for_owner_record(0, 0);
+    let skilled = evasive_step_for_owner_record(8, 8);
+    assert!(struggling > 0.0);
+    assert!(struggling < new_player, "{struggling} vs {new_player}");
+    assert!(new_player < skilled, "{new_player} vs {skilled}");
+}
+
+#[test]
+fn evasive_difficulty_stays_within_configured_floor_and_baseline() {
+    let mut state = make_state();
+    let mut owner = make_player("owner", make_snake(EVASIVE_PELLET_MIN_LEN, 0.0));
+    owner.evasive_attempts = 1_000;
+    let floor = state.evasive_difficulty_for_owner(&owner);
+    assert!(floor >= state.pellet_config.evasive_min_difficulty);
+    owner.evasive_captures = 1_000;
+    assert!(state.evasive_difficulty_for_owner(&owner) <= 1.0);
+
+    state.pellet_config = PelletConfig {
+        evasive_min_difficulty: 1.0,
+        ..PelletConfig::default()
+    }
+    .sanitized();
+    owner.evasive_captures = 0;
+    assert_eq!(state.evasive_difficulty_for_owner(&owner), 1.0);
+}
+
+#[test]
+fn evasive_outcomes_are_recorded_and_windowed_per_owner() {
+    let mut state = make_state();
+    state.players.insert(
+        "owner".to_string(),
+        make_player("owner", make_snake(EVASIVE_PELLET_MIN_LEN, 0.0)),
+    );
+    state.pellets.push(Pellet {
+        id: 891,
+        normal: Point {
+            x: 0.0,
+            y: 0.0,
+            z: 1.0,
+        },
+        color_rgb: [255, 255, 255],
+        base_size: EVASIVE_PELLET_SIZE_MIN,
+        current_size: EVASIVE_PELLET_SIZE_MIN,
+        growth_fraction: BIG_PELLET_GROWTH_FRACTION,
+        expires_at_ms: None,
+        state: PelletState::Evasive {
+            owner_player_id: "owner".to_string(),
+            expires_at_ms: 0,
+        },
+    });
+    state.update_small_pellets(TICK_MS as f64 / 1000.0);
+    let owner = &state.players["owner"];
+    assert_eq!((owner.evasive_attempts, owner.evasive_captures), (1, 0));
+
+    for _ in 0..EVASIVE_PELLET_SKILL_WINDOW {
+        state.record_evasive_outcome("owner", true);
+    }
+    let owner = &state.players["owner"];
+    assert!(owner.evasive_attempts <= EVASIVE_PELLET_SKILL_WINDOW);
+    assert!(owner.evasive_captures < owner.evasive_attempts);
+    assert!(owner.evasive_captures > 0);
+}
+
+fn dirty_tick_scratch(state: &mut RoomState, junk_cache: HashMap<u16, DeltaPlayerCache>) {
+    state.scratch.put_bytes(vec![0xAA; 4096]);
+    state.scratch.put_delta_cache(junk_cache);
+    state
+        .scratch
+        .put_pellet_ids([9_001, 9_002].into_iter().collect());
+    state.scratch.put_pellet_delta(scratch::PelletDeltaScratch {
+        adds: vec![7, 8, 9],
+        updates: vec![3],
+        removes: vec![9_003],
+    });
+}
+
+#[test]
+fn reused_tick_scratch_does_not_change_state_delta_bytes() {
+    let mut state = make_state();
+    let head = Point {
+        x: 1.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    insert_chat_player(&mut state, "viewer", 1, head);
+    insert_chat_player(
+        &mut state,
+        "other",
+        2,
+        normalize(Point {
+            x: 0.95,
+            y: 0.1,
+            z: 0.0,
+        }),
+    );
+
+    let keyframe = state
+        .build_state_delta_payload_for_session(1_000, 1, "session-viewer")
+        .expect("keyframe");
+    let cache_after_keyframe = state.sessions["session-viewer"].delta_player_cache.clone();
+    let mut junk_cache = cache_after_keyframe.clone();
+    for cache in junk_cache.values_mut() {
+        cache.score += 1_000;
+    }
+
+    state
+        .sessions
+        .get_mut("session-viewer")
+        .expect("session")
+        .delta_player_cache = HashMap::new();
+    dirty_tick_scratch(&mut state, junk_cache.clone());
+    let rebuilt_keyframe = state
+        .build_state_delta_payload_for_session(1_000, 1, "session-viewer")
+        .expect("rebuilt keyframe");
+    assert_eq!(rebuilt_keyframe, keyframe);
+
+    state.players.get_mut("other").expect("other").score += 5;
+    state
+        .sessions
+        .get_mut("session-viewer")
+        .expect("session")
+        .delta_player_cache = cache_after_keyframe.clone();
+    let delta = state
+        .build_state_delta_payload_for_session(1_050, 2, "session-viewer")
+        .expect("delta");
+
+    state
+        .sessions
+        .get_mut("session-viewer")
+        .expect("session")
+        .delta_player_cache = cache_after_keyframe;
+    dirty_tick_scratch(&mut state, junk_cache);
+    let rebuilt_delta = state
+        .build_state_delta_payload_for_session(1_050, 2, "session-viewer")
+        .expect("rebuilt delta");
+    assert_eq!(rebuilt_delta, delta);
+}
+
+#[test]
+fn reused_tick_scratch_does_not_change_pellet_delta_bytes() {
+    let mut state = make_state();
+    let head = Point {
+        x: 0.0,
+        y: 0.0,
+        z: 1.0,
+    };
+    state.players.insert(
+        "player-1".to_string(),
+        make_player("player-1", make_snake(6, 0.0)),
+    );
+    insert_session_with_view(&mut state, "session-1", "player-1", Some(head), Some(1.0));
+    state.pellets = vec![
+        make_pellet(1, head),
+        make_pellet(
+            2,
+            normalize(Point {
+                x: 0.1,
+                y: 0.0,
+                z: 1.0,
+            }),
+        ),
+    ];
+    let stale_ids: HashSet<u32> = [2, 40].into_iter().collect();
+    let (outbound_lo, mut outbound_lo_rx) = mpsc::channel::<Vec<u8>>(4);
+    let session = state.sessions.get_mut("session-1").expect("session");
+    session.outbound_lo = outbound_lo;
+    session.pellet_view_initialized = true;
+    session.pellet_view_ids = stale_ids.clone();
+
+    state.broadcast_pellet_delta(1_000, 1);
+    let frame = outbound_lo_rx.try_recv().expect("pellet delta");
+    let view_ids = state.sessions["session-1"].pellet_view_ids.clone();
+
+    state
+        .sessions
+        .get_mut("session-1")
+        .expect("session")
+        .pellet_view_ids = stale_ids;
+    dirty_tick_scratch(&mut state, HashMap::new());
+    state.broadcast_pellet_delta(1_000, 1);
+    let rebuilt = outbound_lo_rx.try_recv().expect("rebuilt pellet delta");
+    assert_eq!(rebuilt, frame);
+    assert_eq!(state.sessions["session-1"].pellet_view_ids, view_ids);
+    assert_eq!(view_ids, [1, 2].into_iter().collect());
+}
@@ -0,0 +1,66 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Inclusive upper bound of each bucket in microseconds. Ticks slower than the last bound land
+/// in one extra overflow bucket. The `50_000` edge is the tick budget, so everything past it is
+/// an overrun.
+pub const TICK_HISTOGRAM_BOUNDS_US: [u64; 10] = [
+    250, 500, 1_000, 2_000, 4_000, 8_000, 16_000, 32_000, 50_000, 100_000,
+];
+const TICK_HISTOGRAM_BUCKETS: usize = TICK_HISTOGRAM_BOUNDS_US.len() + 1;
+
+/// Tick durations since the room started, counted into fixed buckets.
+///
+/// Recording is one relaxed atomic add, so the tick loop can update it without the state lock
+/// and readers never block the tick.
+#[derive(Debug, Default)]
+pub(super) struct TickHistogram {
+    counts: [AtomicU64; TICK_HISTOGRAM_BUCKETS],
+}
+
+impl TickHistogram {
+    pub(super) fn record(&self, elapsed: Duration) {
+        let micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+        let bucket = TICK_HISTOGRAM_BOUNDS_US.partition_point(|bound| *bound < micros);
+        self.counts[bucket].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn snapshot(&self) -> TickHistogramSnapshot {
+        TickHistogramSnapshot {
+            counts: std::array::from_fn(|bucket| self.counts[bucket].load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Point-in-time copy of a [`TickHistogram`]. `counts[i]` pairs with
+/// `TICK_HISTOGRAM_BOUNDS_US[i]`; the final count is the overflow bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickHistogramSnapshot {
+    pub counts: [u64; TICK_HISTOGRAM_BUCKETS],
+}
+
+impl TickHistogramSnapshot {
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Upper bound of the bucket holding the `quantile` tick, in microseconds. A quantile in
+    /// the overflow bucket reports the last bound, i.e. "at least this slow". `None` before
+    /// any tick has been recorded.
+    pub fn quantile_us(&self, quantile: f64) -> Option<u64> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+        let rank = ((quantile.clamp(0.0, 1.0) * total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let bound = bucket.min(TICK_HISTOGRAM_BOUNDS_US.len() - 1);
+                return Some(TICK_HISTOGRAM_BOUNDS_US[bound]);
+            }
+        }
+        TICK_HISTOGRAM_BOUNDS_US.last().copied()
+    }
+}
@@ -1,7 +1,7 @@
 use super::*;
 
 impl RoomState {
-    fn session_view_params(&self, session_id: &str) -> Option<(Point, f64)> {
+    pub(super) fn session_view_params(&self, session_id: &str) -> Option<(Point, f64)> {
         let session = self.sessions.get(session_id)?;
         let player_id = session.player_id.as_ref()?;
         let player = self.players.get(player_id)?;
@@ -19,7 +19,7 @@ impl RoomState {
             .view_radius
             .unwrap_or(1.0)
             .clamp(VIEW_RADIUS_MIN, VIEW_RADIUS_MAX);
-        let view_cos = (view_radius + VIEW_RADIUS_MARGIN).cos();
+        let view_cos = (view_radius + self.view_config.radius_margin).cos();
         Some((view_center, view_cos))
     }
 
@@ -72,8 +72,8 @@ impl RoomState {
             return SnakeWindow::stub(total_len);
         }
 
-        let start = best_start.saturating_sub(VIEW_NODE_PADDING);
-        let end = (best_start + best_len + VIEW_NODE_PADDING).min(total_len);
+        let start = best_start.saturating_sub(self.view_config.node_padding);
+        let end = (best_start + best_len + self.view_config.node_padding).min(total_len);
         let len = end.saturating_sub(start);
         if len < VIEW_MIN_WINDOW_POINTS {
             return SnakeWindow::stub(total_len);
@@ -98,19 +98,60 @@ impl RoomState {
                 visible_players.push(VisiblePlayer { player, window });
             }
         }
+        if let Some((view_center, _)) = view {
+            self.cap_detailed_remote_players(&mut visible_players, local_player_id, view_center);
+        }
         visible_players
     }
 
+    /// Demotes the remote snakes whose heads are farthest from `view_center` to stubs once more
+    /// than `max_detailed_remote_players` would carry node data. The local player is untouched.
+    fn cap_detailed_remote_players(
+        &self,
+        visible_players: &mut [VisiblePlayer<'_>],
+        local_player_id: Option<&str>,
+        view_center: Point,
+    ) {
+        let mut detailed_remotes: Vec<(f64, usize)> = visible_players
+            .iter()
+            .enumerate()
+            .filter(|(_, visible)| {
+                visible.window.detail != SnakeDetail::Stub
+                    && local_player_id != Some(visible.player.id.as_str())
+            })
+            .map(|(index, visible)| {
+                let head_dot = visible.player.snake.first().map_or(-1.0, |node| {
+                    dot(
+                        view_center,
+                        Point {
+                            x: node.x,
+                            y: node.y,
+                            z: node.z,
+                        },
+                    )
+                });
+                (head_dot, index)
+            })
+            .collect();
+        if detailed_remotes.len() <= self.max_detailed_remote_players {
+            return;
+        }
+        detailed_remotes.sort_by(|a, b| b.0.total_cmp(&a.0));
+        for &(_, index) in &detailed_remotes[self.max_detailed_remote_players..] {
+            let total_len = visible_players[index].window.total_len;
+            visible_players[index].window = SnakeWindow::stub(total_len);
+        }
+    }
+
+    /// Maps a camera distance onto `0.0..=1.0` across the accepted camera range; sessions that
+    /// never sent one sit at the midpoint.
     fn pellet_zoom_t(camera_distance: Option<f64>) -> f64 {
         let distance = camera_distance
+            .filter(|value| value.is_finite())
             .unwrap_or((VIEW_CAMERA_DISTANCE_MIN + VIEW_CAMERA_DISTANCE_MAX) * 0.5)
-            .clamp(
-                SMALL_PELLET_ZOOM_MIN_CAMERA_DISTANCE,
-                SMALL_PELLET_ZOOM_MAX_CAMERA_DISTANCE,
-            );
-        let denom = (SMALL_PELLET_ZOOM_MAX_CAMERA_DISTANCE - SMALL_PELLET_ZOOM_MIN_CAMERA_DISTANCE)
-            .max(1e-6);
-        (distance - SMALL_PELLET_ZOOM_MIN_CAMERA_DISTANCE) / denom
+            .clamp(VIEW_CAMERA_DISTANCE_MIN, VIEW_CAMERA_DISTANCE_MAX);
+        let denom = (VIEW_CAMERA_DISTANCE_MAX - VIEW_CAMERA_DISTANCE_MIN).max(1e-6);
+        (distance - VIEW_CAMERA_DISTANCE_MIN) / denom
     }
 
     pub(super) fn pellet_view_params(&self, session_id: &str) -> Option<(Point, f64, usize)> {
@@ -132,27 +173,49 @@ impl RoomState {
             .unwrap_or(1.0)
             .clamp(VIEW_RADIUS_MIN, VIEW_RADIUS_MAX);
         let zoom_t = Self::pellet_zoom_t(session.camera_distance);
-        let visible_count = ((SMALL_PELLET_VISIBLE_MIN as f64)
+        let visible_count = (((SMALL_PELLET_VISIBLE_MIN as f64)
             + ((SMALL_PELLET_VISIBLE_MAX - SMALL_PELLET_VISIBLE_MIN) as f64) * zoom_t)
+            * self.pellet_load_factor)
             .round()
             .max(1.0) as usize;
         let extra_margin = SMALL_PELLET_VIEW_MARGIN_MIN
             + (SMALL_PELLET_VIEW_MARGIN_MAX - SMALL_PELLET_VIEW_MARGIN_MIN) * zoom_t;
-        let visible_cos = (view_radius + VIEW_RADIUS_MARGIN + extra_margin).cos();
+        let visible_cos = (view_radius + self.view_config.radius_margin + extra_margin).cos();
         Some((view_center, visible_cos, visible_count))
     }
 
+    /// Indices of up to `max_visible` pellets inside the view cone, sorted by pellet id.
+    /// `shown` is the set the session currently has, used for `Nearest` hysteresis.
     pub(super) fn visible_pellet_indices(
         &self,
         view_center: Point,
         view_cos: f64,
         max_visible: usize,
+        shown: &HashSet<u32>,
     ) -> Vec<usize> {
         let capped_visible = max_visible.min(u16::MAX as usize);
         if capped_visible == 0 || self.pellets.is_empty() {
             return Vec::new();
         }
 
+        let mut out = match self.pellet_visibility {
+            PelletVisibility::LowestId => {
+                self.lowest_id_pellet_indices(view_center, view_cos, capped_visible)
+            }
+            PelletVisibility::Nearest => {
+                self.nearest_pellet_indices(view_center, view_cos, capped_visible, shown)
+            }
+        };
+        out.sort_unstable_by_key(|&index| self.pellets[index].id);
+        out
+    }
+
+    fn lowest_id_pellet_indices(
+        &self,
+        view_center: Point,
+        view_cos: f64,
+        capped_visible: usize,
+    ) -> Vec<usize> {
         // Choose a stable subset so delta replication does not churn due to Vec order changes.
         // We keep the lowest IDs among visible pellets (IDs are monotonic for practical purposes).
         use std::collections::BinaryHeap;
@@ -166,9 +229,38 @@ impl RoomState {
                 heap.pop();
             }
         }
+        heap.into_iter().map(|(_, index)| index).collect()
+    }
 
-        let mut out: Vec<usize> = heap.into_iter().map(|(_, index)| index).collect();
-        out.sort_unstable_by_key(|&index| self.pellets[index].id);
-        out
+    fn nearest_pellet_indices(
+        &self,
+        view_center: Point,
+        view_cos: f64,
+        capped_visible: usize,
+        shown: &HashSet<u32>,
+    ) -> Vec<usize> {
+        let mut candidates: Vec<(f64, u32, usize)> = self
+            .pellets
+            .iter()
+            .enumerate()
+            .filter_map(|(index, pellet)| {
+                let dot_value = dot(view_center, pellet.normal);
+                if dot_value < view_cos {
+                    return None;
+                }
+                let mut angle = clamp(dot_value, -1.0, 1.0).acos();
+                if shown.contains(&pellet.id) {
+                    angle -= PELLET_VISIBILITY_HYSTERESIS_ANGLE;
+                }
+                Some((angle, pellet.id, index))
+            })
+            .collect();
+        if candidates.len() > capped_visible {
+            candidates.select_nth_unstable_by(capped_visible, |a, b| {
+                a.0.total_cmp(&b.0).then(a.1.cmp(&b.1))
+            });
+            candidates.truncate(capped_visible);
+        }
+        candidates.into_iter().map(|(_, _, index)| index).collect()
     }
 }
@@ -1,4 +1,4 @@
-use super::constants::{NODE_ANGLE, NODE_QUEUE_SIZE, STARTING_LENGTH};
+use super::constants::{NODE_ANGLE, NODE_QUEUE_SIZE};
 use super::math::{clamp, cross, dot, length, normalize, rotate_around_axis, rotate_y, rotate_z};
 use super::types::{Point, SnakeNode};
 use std::collections::VecDeque;
@@ -117,7 +117,11 @@ fn tail_extension_basis(snake: &[SnakeNode]) -> Option<(Point, Point, f64)> {
     ))
 }
 
-fn rotate_tail_along_direction(tail: Point, tail_dir: Point, extend_distance: f64) -> Option<Point> {
+fn rotate_tail_along_direction(
+    tail: Point,
+    tail_dir: Point,
+    extend_distance: f64,
+) -> Option<Point> {
     if extend_distance <= 1e-8 || !extend_distance.is_finite() {
         return None;
     }
@@ -181,11 +185,8 @@ fn resolve_growth_continuity_from_extension(
         return None;
     }
     let (tail, tail_dir, base_length) = tail_extension_basis(snake)?;
-    let pre_tip = rotate_tail_along_direction(
-        tail,
-        tail_dir,
-        base_length * TAIL_EXTENSION_MAX_RATIO,
-    )?;
+    let pre_tip =
+        rotate_tail_along_direction(tail, tail_dir, base_length * TAIL_EXTENSION_MAX_RATIO)?;
     let ratio_after = clamp(tail_extension_after, 0.0, TAIL_EXTENSION_MAX_RATIO);
 
     let axis = cross(tail, tail_dir);
@@ -317,7 +318,7 @@ fn resolve_tail_continuation(
 }
 
 fn apply_growth_history(
-    snake: &mut Vec<SnakeNode>,
+    snake: &mut [SnakeNode],
     snake_node: &mut SnakeNode,
     continuation: TailContinuation,
 ) {
@@ -384,7 +385,11 @@ pub fn add_snake_node(snake: &mut Vec<SnakeNode>, axis: Point) {
     snake.push(snake_node);
 }
 
-pub fn add_snake_node_for_growth(snake: &mut Vec<SnakeNode>, axis: Point, tail_extension_after: f64) {
+pub fn add_snake_node_for_growth(
+    snake: &mut Vec<SnakeNode>,
+    axis: Point,
+    tail_extension_after: f64,
+) {
     let mut snake_node = SnakeNode {
         x: 0.0,
         y: 0.0,
@@ -402,7 +407,8 @@ pub fn add_snake_node_for_growth(snake: &mut Vec<SnakeNode>, axis: Point, tail_e
     // Using history points can disagree with the client-side fractional tail extension (which is a
     // local arc continuation). That mismatch shows up as a visible "pop" right when
     // `tail_extension` crosses 1.0 and a full node is committed.
-    if let Some(continuity) = resolve_growth_continuity_from_extension(snake, tail_extension_after) {
+    if let Some(continuity) = resolve_growth_continuity_from_extension(snake, tail_extension_after)
+    {
         snake_node.x = continuity.point.x;
         snake_node.y = continuity.point.y;
         snake_node.z = continuity.point.z;
@@ -460,14 +466,57 @@ pub fn apply_snake_rotation_step(snake: &mut [SnakeNode], axis: Point, velocity:
     }
 }
 
-pub fn create_snake(axis: Point) -> Vec<SnakeNode> {
-    let mut snake = Vec::with_capacity(STARTING_LENGTH);
-    for _ in 0..STARTING_LENGTH {
+pub fn create_snake_with_length(axis: Point, length: usize) -> Vec<SnakeNode> {
+    let mut snake = Vec::with_capacity(length);
+    for _ in 0..length {
         add_snake_node(&mut snake, axis);
     }
     snake
 }
 
+/// Builds a `len`-node snake travelling around `axis` with its head at `head`. Returns the
+/// snake and the axis rotated along with it.
+pub fn create_snake_with_head(axis: Point, head: Point, len: usize) -> (Vec<SnakeNode>, Point) {
+    let mut snake = create_snake_with_length(axis, len);
+    let Some(first) = snake.first() else {
+        return (snake, axis);
+    };
+    let from = normalize(Point {
+        x: first.x,
+        y: first.y,
+        z: first.z,
+    });
+    let to = normalize(head);
+    let rotation = cross(from, to);
+    let rotation_len = length(rotation);
+    let (rotation_axis, angle) = if rotation_len > 1e-9 {
+        (rotation, rotation_len.atan2(dot(from, to)))
+    } else if dot(from, to) < 0.0 {
+        // Antipodal: the travel axis is perpendicular to the head, so half a turn around it works.
+        (axis, std::f64::consts::PI)
+    } else {
+        return (snake, axis);
+    };
+
+    for node in &mut snake {
+        let mut point = Point {
+            x: node.x,
+            y: node.y,
+            z: node.z,
+        };
+        rotate_around_axis(&mut point, rotation_axis, angle);
+        node.x = point.x;
+        node.y = point.y;
+        node.z = point.z;
+        for queued in node.pos_queue.iter_mut().flatten() {
+            rotate_around_axis(queued, rotation_axis, angle);
+        }
+    }
+    let mut rotated_axis = axis;
+    rotate_around_axis(&mut rotated_axis, rotation_axis, angle);
+    (snake, normalize(rotated_axis))
+}
+
 pub fn rotate_snake(snake: &mut [SnakeNode], z_angle: f64, y_angle: f64) {
     for node in snake {
         let mut point = Point {
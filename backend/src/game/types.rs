@@ -51,6 +51,13 @@ pub struct Pellet {
     pub state: PelletState,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BotSkill {
+    Easy,
+    Medium,
+    Hard,
+}
+
 #[derive(Debug, Clone)]
 pub struct Player {
     pub id: String,
@@ -60,20 +67,39 @@ pub struct Player {
     pub color: String,
     pub skin: Option<Vec<[u8; 3]>>,
     pub is_bot: bool,
+    pub bot_skill: Option<BotSkill>,
+    /// A human steered by the bot planner after going idle; cleared by their next input.
+    pub afk_autopilot: bool,
     pub axis: Point,
     pub target_axis: Point,
     pub boost: bool,
     pub is_boosting: bool,
+    pub boost_engage_ticks: u32,
+    pub boost_cooldown_ticks: u32,
     pub oxygen: f64,
     pub oxygen_damage_accumulator: f64,
+    pub submerged_secs: f64,
     pub score: i64,
+    /// Highest `score` reached since the player joined; survives death and rejoin.
+    pub session_best: i64,
     pub alive: bool,
     pub connected: bool,
     pub last_seen: i64,
+    /// Last time the player's input changed (steering past a small deadband or boost). Unlike
+    /// `last_seen`, repeated heartbeat inputs don't move it, so AFK autopilot keys off this.
+    pub last_active_at: i64,
     pub respawn_at: Option<i64>,
+    pub respawn_length: Option<usize>,
     pub boost_floor_len: usize,
     pub trail_color_cycle_cursor: usize,
     pub next_boost_trail_pellet_at_ms: i64,
+    /// Length drained by boosting that hasn't been shed as trail pellets yet, in nodes. Only
+    /// used when the room sheds boost drain.
+    pub boost_shed_value: f64,
+    /// Recent evasive pellets spawned for this player that have resolved, and how many of
+    /// those the player caught; halved together once the window fills.
+    pub evasive_attempts: u32,
+    pub evasive_captures: u32,
     pub snake: Vec<SnakeNode>,
     pub pellet_growth_fraction: f64,
     pub tail_extension: f64,
@@ -1,12 +1,25 @@
 use crate::game::types::Point;
 use uuid::Uuid;
 
-pub const VERSION: u8 = 21;
+pub const VERSION: u8 = 23;
+/// Oldest client protocol the server accepts. Frame layouts change between versions (v22 added
+/// interpolation timing to state frame headers) and the server only encodes the current ones,
+/// so this stays equal to `VERSION` until a session's layout is chosen by its version.
+pub const MIN_SUPPORTED_VERSION: u8 = VERSION;
 
 pub const TYPE_JOIN: u8 = 0x01;
 pub const TYPE_INPUT: u8 = 0x02;
 pub const TYPE_RESPAWN: u8 = 0x03;
 pub const TYPE_VIEW: u8 = 0x04;
+pub const TYPE_HELLO: u8 = 0x05;
+/// Client asks for a state keyframe after its delta decoder lost sync.
+pub const TYPE_RESYNC: u8 = 0x06;
+/// Short chat line relayed to players whose view includes the sender.
+pub const TYPE_CHAT: u8 = 0x07;
+/// Opts the session in or out of server-computed accessibility hints via header flags.
+pub const TYPE_HINTS: u8 = 0x08;
+/// Latency probe: an opaque `u32` client time the server echoes back in `TYPE_PONG`.
+pub const TYPE_PING: u8 = 0x09;
 
 pub const TYPE_INIT: u8 = 0x10;
 pub const TYPE_PLAYER_META: u8 = 0x12;
@@ -14,12 +27,44 @@ pub const TYPE_PELLET_DELTA: u8 = 0x13;
 pub const TYPE_PELLET_RESET: u8 = 0x14;
 pub const TYPE_STATE_DELTA: u8 = 0x15;
 pub const TYPE_PELLET_CONSUME: u8 = 0x16;
+pub const TYPE_WORLD: u8 = 0x17;
+pub const TYPE_ERROR: u8 = 0x18;
+/// Relayed chat line: `u16` sender net id followed by the text.
+pub const TYPE_CHAT_MESSAGE: u8 = 0x19;
+/// Room is being consolidated into another: string room id, then the room token to join it
+/// with as a `u16`-length string. Clients reconnect there instead of rejoining this room.
+pub const TYPE_MIGRATE: u8 = 0x1A;
+/// Accessibility hint for the local player. With `FLAG_HINT_THREAT` set the body is the
+/// oct-encoded tangent direction from the head toward the nearest other-snake node and the
+/// `f32` angular distance to it; without it the previous threat hint is cleared.
+pub const TYPE_HINT: u8 = 0x1B;
+/// Operator announcement for every session in the room, as a `u16`-length string.
+pub const TYPE_ANNOUNCE: u8 = 0x1C;
+/// Reply to `TYPE_PING`: the echoed `u32` client time, then the server's `i64` wall clock in
+/// milliseconds when the ping was handled, for round-trip and clock offset estimates.
+pub const TYPE_PONG: u8 = 0x1D;
+/// Sent to a player's sessions when they die while the room signs scores: the `i64` score at
+/// death, then the signed receipt as a `u16`-length string to include in the leaderboard POST.
+pub const TYPE_SCORE_RECEIPT: u8 = 0x1E;
+
+pub const ERROR_VERSION_TOO_OLD: u8 = 1;
+pub const ERROR_VERSION_TOO_NEW: u8 = 2;
+pub const ERROR_HELLO_REQUIRED: u8 = 3;
+pub const ERROR_ROOM_FULL: u8 = 4;
+pub const ERROR_DRAINING: u8 = 5;
+pub const ERROR_UNAUTHORIZED: u8 = 6;
+pub const ERROR_MESSAGE_TOO_LARGE: u8 = 7;
+/// Non-fatal: a `Respawn` arrived before the cooldown ended. The socket stays open.
+pub const ERROR_RESPAWN_COOLDOWN: u8 = 8;
 
 pub const FLAG_JOIN_PLAYER_ID: u16 = 1 << 0;
 pub const FLAG_JOIN_NAME: u16 = 1 << 1;
 pub const FLAG_JOIN_DEFER_SPAWN: u16 = 1 << 2;
 pub const FLAG_JOIN_SKIN: u16 = 1 << 3;
 
+/// Hard cap on skin colors per join; longer skins reject the whole join instead of truncating.
+pub const MAX_SKIN_COLORS: usize = 8;
+
 pub const FLAG_INPUT_AXIS: u16 = 1 << 0;
 pub const FLAG_INPUT_BOOST: u16 = 1 << 1;
 
@@ -27,6 +72,14 @@ pub const FLAG_VIEW_CENTER: u16 = 1 << 0;
 pub const FLAG_VIEW_RADIUS: u16 = 1 << 1;
 pub const FLAG_VIEW_CAMERA_DISTANCE: u16 = 1 << 2;
 
+pub const FLAG_HINTS_THREAT: u16 = 1 << 0;
+
+/// Set on `TYPE_INIT` when the join resumed the session's existing player, so its net id and
+/// snake carry over; unset for a freshly created player.
+pub const FLAG_INIT_RESUMED: u16 = 1 << 0;
+
+pub const FLAG_HINT_THREAT: u16 = 1 << 0;
+
 pub const SNAKE_DETAIL_FULL: u8 = 0;
 pub const SNAKE_DETAIL_WINDOW: u8 = 1;
 pub const SNAKE_DETAIL_STUB: u8 = 2;
@@ -36,6 +89,13 @@ pub const VIEW_RADIUS_MAX: f32 = 1.4;
 pub const VIEW_CAMERA_DISTANCE_MIN: f32 = 4.0;
 pub const VIEW_CAMERA_DISTANCE_MAX: f32 = 10.0;
 
+/// Guards JSON input frames, where numbers too large for `f32` decode as infinity; they are
+/// rejected rather than clamped so they never reach the pellet zoom math. Binary view frames
+/// don't need it: their dequantized `u16` always lands inside the camera range.
+pub fn is_valid_camera_distance(value: f32) -> bool {
+    value.is_finite()
+}
+
 fn dequantize_u16_to_range(value: u16, min: f32, max: f32) -> f32 {
     let t = value as f32 / u16::MAX as f32;
     min + (max - min) * t
@@ -65,6 +125,142 @@ fn decode_oct_i16_to_point(xq: i16, yq: i16) -> Point {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Reasons the server refuses or ends a session, sent to the client as a `TYPE_ERROR` frame
+/// right before the socket closes. `RespawnCooldown` is the exception: it only refuses one
+/// request and leaves the session open.
+pub enum ServerError {
+    VersionTooOld,
+    VersionTooNew,
+    HelloRequired,
+    RoomFull,
+    Draining,
+    Unauthorized,
+    MessageTooLarge,
+    RespawnCooldown,
+}
+
+impl ServerError {
+    pub fn code(self) -> u8 {
+        match self {
+            ServerError::VersionTooOld => ERROR_VERSION_TOO_OLD,
+            ServerError::VersionTooNew => ERROR_VERSION_TOO_NEW,
+            ServerError::HelloRequired => ERROR_HELLO_REQUIRED,
+            ServerError::RoomFull => ERROR_ROOM_FULL,
+            ServerError::Draining => ERROR_DRAINING,
+            ServerError::Unauthorized => ERROR_UNAUTHORIZED,
+            ServerError::MessageTooLarge => ERROR_MESSAGE_TOO_LARGE,
+            ServerError::RespawnCooldown => ERROR_RESPAWN_COOLDOWN,
+        }
+    }
+
+    pub fn reason(self) -> &'static str {
+        match self {
+            ServerError::VersionTooOld => "protocol version too old",
+            ServerError::VersionTooNew => "protocol version too new",
+            ServerError::HelloRequired => "hello required before join",
+            ServerError::RoomFull => "room is full",
+            ServerError::Draining => "room is shutting down",
+            ServerError::Unauthorized => "unauthorized",
+            ServerError::MessageTooLarge => "message too large",
+            ServerError::RespawnCooldown => "respawn cooldown active",
+        }
+    }
+}
+
+pub fn negotiate_version(client_version: u8) -> Result<u8, ServerError> {
+    if client_version < MIN_SUPPORTED_VERSION {
+        Err(ServerError::VersionTooOld)
+    } else if client_version > VERSION {
+        Err(ServerError::VersionTooNew)
+    } else {
+        Ok(client_version)
+    }
+}
+
+/// Reads the protocol version from a `Hello` frame. The header version byte is ignored here
+/// since a mismatched client must still be able to announce itself.
+pub fn decode_hello(data: &[u8]) -> Option<u8> {
+    let mut reader = Reader::new(data);
+    let _header_version = reader.read_u8()?;
+    if reader.read_u8()? != TYPE_HELLO {
+        return None;
+    }
+    let _flags = reader.read_u16()?;
+    reader.read_u8()
+}
+
+/// First version whose clients open with a `Hello`. Older clients start with a `Join`.
+pub const HELLO_SINCE_VERSION: u8 = 22;
+
+/// Reads the client's protocol version from its first frame. A non-`Hello` frame from a client
+/// that predates the handshake counts as too old, so it is told to update rather than to send
+/// a `Hello` it doesn't know about.
+pub fn decode_handshake(data: &[u8]) -> Result<u8, ServerError> {
+    if let Some(version) = decode_hello(data) {
+        return Ok(version);
+    }
+    match data.first() {
+        Some(&header_version) if header_version < HELLO_SINCE_VERSION => {
+            Err(ServerError::VersionTooOld)
+        }
+        _ => Err(ServerError::HelloRequired),
+    }
+}
+
+pub fn encode_error(error: ServerError) -> Vec<u8> {
+    error_encoder(error).into_vec()
+}
+
+/// A `RespawnCooldown` error frame followed by the milliseconds left until respawn is allowed.
+pub fn encode_respawn_cooldown(remaining_ms: u32) -> Vec<u8> {
+    let mut encoder = error_encoder(ServerError::RespawnCooldown);
+    encoder.write_u32(remaining_ms);
+    encoder.into_vec()
+}
+
+fn error_encoder(error: ServerError) -> Encoder {
+    let reason = error.reason();
+    let mut encoder = Encoder::with_capacity(12 + reason.len());
+    encoder.write_header(TYPE_ERROR, 0);
+    encoder.write_u8(error.code());
+    encoder.write_u8(MIN_SUPPORTED_VERSION);
+    encoder.write_u8(VERSION);
+    encoder.write_string(reason);
+    encoder
+}
+
+pub fn encode_migrate(room_id: &str, room_token: &str) -> Vec<u8> {
+    let mut encoder = Encoder::with_capacity(8 + room_id.len() + room_token.len());
+    encoder.write_header(TYPE_MIGRATE, 0);
+    encoder.write_string(room_id);
+    encoder.write_long_string(room_token);
+    encoder.into_vec()
+}
+
+pub fn encode_announce(text: &str) -> Vec<u8> {
+    let mut encoder = Encoder::with_capacity(6 + text.len());
+    encoder.write_header(TYPE_ANNOUNCE, 0);
+    encoder.write_long_string(text);
+    encoder.into_vec()
+}
+
+pub fn encode_score_receipt(score: i64, receipt: &str) -> Vec<u8> {
+    let mut encoder = Encoder::with_capacity(14 + receipt.len());
+    encoder.write_header(TYPE_SCORE_RECEIPT, 0);
+    encoder.write_i64(score);
+    encoder.write_long_string(receipt);
+    encoder.into_vec()
+}
+
+pub fn encode_pong(client_time: u32, server_time: i64) -> Vec<u8> {
+    let mut encoder = Encoder::with_capacity(16);
+    encoder.write_header(TYPE_PONG, 0);
+    encoder.write_u32(client_time);
+    encoder.write_i64(server_time);
+    encoder.into_vec()
+}
+
 #[derive(Debug)]
 pub enum ClientMessage {
     Join {
@@ -74,6 +270,16 @@ pub enum ClientMessage {
         skin: Option<Vec<[u8; 3]>>,
     },
     Respawn,
+    Resync,
+    Chat {
+        text: String,
+    },
+    Hints {
+        threat: bool,
+    },
+    Ping {
+        client_time: u32,
+    },
     Input {
         axis: Option<Point>,
         boost: bool,
@@ -86,10 +292,12 @@ pub enum ClientMessage {
     },
 }
 
+/// Decodes every client message after the handshake, including `Join` and `Respawn`, so binary
+/// clients never need the JSON text path.
 pub fn decode_client_message(data: &[u8]) -> Option<ClientMessage> {
     let mut reader = Reader::new(data);
     let version = reader.read_u8()?;
-    if version != VERSION {
+    if !(MIN_SUPPORTED_VERSION..=VERSION).contains(&version) {
         return None;
     }
     let message_type = reader.read_u8()?;
@@ -109,7 +317,9 @@ pub fn decode_client_message(data: &[u8]) -> Option<ClientMessage> {
             let defer_spawn = flags & FLAG_JOIN_DEFER_SPAWN != 0;
             let skin = if flags & FLAG_JOIN_SKIN != 0 {
                 let skin_len = reader.read_u8()? as usize;
-                let skin_len = skin_len.min(8);
+                if skin_len > MAX_SKIN_COLORS {
+                    return None;
+                }
                 if skin_len == 0 {
                     None
                 } else {
@@ -131,6 +341,16 @@ pub fn decode_client_message(data: &[u8]) -> Option<ClientMessage> {
             })
         }
         TYPE_RESPAWN => Some(ClientMessage::Respawn),
+        TYPE_RESYNC => Some(ClientMessage::Resync),
+        TYPE_CHAT => Some(ClientMessage::Chat {
+            text: reader.read_string()?,
+        }),
+        TYPE_HINTS => Some(ClientMessage::Hints {
+            threat: flags & FLAG_HINTS_THREAT != 0,
+        }),
+        TYPE_PING => Some(ClientMessage::Ping {
+            client_time: reader.read_u32()?,
+        }),
         TYPE_INPUT => {
             let axis = if flags & FLAG_INPUT_AXIS != 0 {
                 let ox = reader.read_i16()?;
@@ -181,6 +401,20 @@ pub fn decode_client_message(data: &[u8]) -> Option<ClientMessage> {
     }
 }
 
+/// Parses a `#rrggbb` skin color as sent by JSON clients.
+pub fn parse_skin_hex(value: &str) -> Option<[u8; 3]> {
+    let hex = value.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+pub fn skin_color_hex(rgb: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+}
+
 pub struct Encoder {
     buffer: Vec<u8>,
 }
@@ -192,6 +426,16 @@ impl Encoder {
         }
     }
 
+    /// Encodes into a reused buffer; any previous contents are discarded.
+    pub fn from_buffer(mut buffer: Vec<u8>) -> Self {
+        buffer.clear();
+        Self { buffer }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer
+    }
+
     pub fn into_vec(self) -> Vec<u8> {
         self.buffer
     }
@@ -256,6 +500,18 @@ impl Encoder {
         self.write_u8(end as u8);
         self.buffer.extend_from_slice(&bytes[..end]);
     }
+
+    /// Like `write_string` but with a `u16` length, for values such as room tokens that can
+    /// outgrow 255 bytes.
+    pub fn write_long_string(&mut self, value: &str) {
+        let bytes = value.as_bytes();
+        let mut end = bytes.len().min(u16::MAX as usize);
+        while !value.is_char_boundary(end) {
+            end = end.saturating_sub(1);
+        }
+        self.write_u16(end as u16);
+        self.buffer.extend_from_slice(&bytes[..end]);
+    }
 }
 
 struct Reader<'a> {
@@ -279,6 +535,11 @@ impl<'a> Reader<'a> {
         Some(u16::from_le_bytes(bytes))
     }
 
+    fn read_u32(&mut self) -> Option<u32> {
+        let bytes = self.read_bytes::<4>()?;
+        Some(u32::from_le_bytes(bytes))
+    }
+
     fn read_i16(&mut self) -> Option<i16> {
         let bytes = self.read_bytes::<2>()?;
         Some(i16::from_le_bytes(bytes))
@@ -314,6 +575,76 @@ impl<'a> Reader<'a> {
 mod tests {
     use super::*;
 
+    fn hello_frame(header_version: u8, protocol_version: u8) -> Vec<u8> {
+        let mut encoder = Encoder::with_capacity(8);
+        encoder.write_u8(header_version);
+        encoder.write_u8(TYPE_HELLO);
+        encoder.write_u16(0);
+        encoder.write_u8(protocol_version);
+        encoder.into_vec()
+    }
+
+    #[test]
+    fn hello_handshake_accepts_only_the_current_version() {
+        let current = decode_hello(&hello_frame(VERSION, VERSION)).expect("hello");
+        assert_eq!(negotiate_version(current), Ok(VERSION));
+        // v21 state frames lack the timing fields, so a v21 client can't read v22 frames.
+        let previous = decode_hello(&hello_frame(VERSION - 1, VERSION - 1)).expect("hello");
+        assert_eq!(negotiate_version(previous), Err(ServerError::VersionTooOld));
+    }
+
+    #[test]
+    fn hello_handshake_rejects_too_old_version() {
+        let version = decode_hello(&hello_frame(3, MIN_SUPPORTED_VERSION - 1)).expect("hello");
+        assert_eq!(negotiate_version(version), Err(ServerError::VersionTooOld));
+    }
+
+    #[test]
+    fn hello_handshake_rejects_too_new_version() {
+        let version = decode_hello(&hello_frame(VERSION + 1, VERSION + 1)).expect("hello");
+        assert_eq!(negotiate_version(version), Err(ServerError::VersionTooNew));
+    }
+
+    #[test]
+    fn pre_hello_clients_are_told_they_are_too_old() {
+        let mut legacy_join = Encoder::with_capacity(8);
+        legacy_join.write_u8(HELLO_SINCE_VERSION - 1);
+        legacy_join.write_u8(TYPE_JOIN);
+        legacy_join.write_u16(0);
+        assert_eq!(
+            decode_handshake(&legacy_join.into_vec()),
+            Err(ServerError::VersionTooOld)
+        );
+
+        let mut join = Encoder::with_capacity(8);
+        join.write_header(TYPE_JOIN, 0);
+        assert_eq!(
+            decode_handshake(&join.into_vec()),
+            Err(ServerError::HelloRequired)
+        );
+        assert_eq!(
+            decode_handshake(&hello_frame(VERSION, VERSION)),
+            Ok(VERSION)
+        );
+    }
+
+    #[test]
+    fn decode_hello_ignores_other_message_types() {
+        let mut encoder = Encoder::with_capacity(8);
+        encoder.write_header(TYPE_RESPAWN, 0);
+        assert_eq!(decode_hello(&encoder.into_vec()), None);
+    }
+
+    #[test]
+    fn error_frame_carries_code_and_supported_range() {
+        let data = encode_error(ServerError::VersionTooNew);
+        assert_eq!(data[0], VERSION);
+        assert_eq!(data[1], TYPE_ERROR);
+        assert_eq!(data[4], ERROR_VERSION_TOO_NEW);
+        assert_eq!(data[5], MIN_SUPPORTED_VERSION);
+        assert_eq!(data[6], VERSION);
+    }
+
     #[test]
     fn decode_join_with_name_and_id() {
         let id = Uuid::new_v4();
@@ -341,6 +672,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn decode_join_rejects_oversized_skin() {
+        let mut encoder = Encoder::with_capacity(64);
+        encoder.write_header(TYPE_JOIN, FLAG_JOIN_SKIN);
+        encoder.write_u8((MAX_SKIN_COLORS + 1) as u8);
+        for _ in 0..=MAX_SKIN_COLORS {
+            encoder.write_u8(0xff);
+            encoder.write_u8(0x00);
+            encoder.write_u8(0x80);
+        }
+        assert!(decode_client_message(&encoder.into_vec()).is_none());
+    }
+
+    #[test]
+    fn parse_skin_hex_validates_triples() {
+        assert_eq!(parse_skin_hex("#ff0080"), Some([0xff, 0x00, 0x80]));
+        assert_eq!(parse_skin_hex("#FF0080"), Some([0xff, 0x00, 0x80]));
+        assert_eq!(parse_skin_hex("ff0080"), None);
+        assert_eq!(parse_skin_hex("#ff008"), None);
+        assert_eq!(parse_skin_hex("#gg0080"), None);
+        assert_eq!(skin_color_hex([0xff, 0x00, 0x80]), "#ff0080");
+    }
+
     #[test]
     fn decode_join_with_deferred_spawn_flag() {
         let mut encoder = Encoder::with_capacity(16);
@@ -401,6 +755,192 @@ mod tests {
         }
     }
 
+    fn encode_join(
+        name: Option<&str>,
+        player_id: Option<Uuid>,
+        defer_spawn: bool,
+        skin: Option<&[[u8; 3]]>,
+    ) -> Vec<u8> {
+        let mut flags = 0;
+        if player_id.is_some() {
+            flags |= FLAG_JOIN_PLAYER_ID;
+        }
+        if name.is_some() {
+            flags |= FLAG_JOIN_NAME;
+        }
+        if defer_spawn {
+            flags |= FLAG_JOIN_DEFER_SPAWN;
+        }
+        if skin.is_some() {
+            flags |= FLAG_JOIN_SKIN;
+        }
+        let mut encoder = Encoder::with_capacity(64);
+        encoder.write_header(TYPE_JOIN, flags);
+        if let Some(player_id) = player_id {
+            encoder.write_uuid(player_id.as_bytes());
+        }
+        if let Some(name) = name {
+            encoder.write_string(name);
+        }
+        if let Some(skin) = skin {
+            encoder.write_u8(skin.len() as u8);
+            for rgb in skin {
+                for channel in rgb {
+                    encoder.write_u8(*channel);
+                }
+            }
+        }
+        encoder.into_vec()
+    }
+
+    #[test]
+    fn binary_join_round_trips_every_field() {
+        let id = Uuid::new_v4();
+        let skin = [[0x12, 0x34, 0x56], [0xab, 0xcd, 0xef]];
+        let data = encode_join(Some("Binary"), Some(id), true, Some(&skin));
+
+        match decode_client_message(&data).expect("message") {
+            ClientMessage::Join {
+                name,
+                player_id,
+                defer_spawn,
+                skin: decoded_skin,
+            } => {
+                assert_eq!(name.as_deref(), Some("Binary"));
+                assert_eq!(player_id, Some(id));
+                assert!(defer_spawn);
+                assert_eq!(decoded_skin, Some(skin.to_vec()));
+            }
+            _ => panic!("unexpected message"),
+        }
+    }
+
+    #[test]
+    fn binary_join_with_max_skin_round_trips() {
+        let skin = [[0x01, 0x02, 0x03]; MAX_SKIN_COLORS];
+        let data = encode_join(None, None, false, Some(&skin));
+        match decode_client_message(&data).expect("message") {
+            ClientMessage::Join {
+                skin: decoded_skin, ..
+            } => assert_eq!(decoded_skin, Some(skin.to_vec())),
+            _ => panic!("unexpected message"),
+        }
+    }
+
+    #[test]
+    fn binary_join_truncated_skin_is_rejected() {
+        let mut data = encode_join(None, None, false, Some(&[[0xff, 0x00, 0x00]]));
+        data.pop();
+        assert!(decode_client_message(&data).is_none());
+    }
+
+    #[test]
+    fn decode_respawn_message() {
+        let mut encoder = Encoder::with_capacity(4);
+        encoder.write_header(TYPE_RESPAWN, 0);
+        assert!(matches!(
+            decode_client_message(&encoder.into_vec()),
+            Some(ClientMessage::Respawn)
+        ));
+    }
+
+    #[test]
+    fn max_length_player_names_survive_wire_encoding() {
+        let name = "\u{1d54a}".repeat(crate::shared::names::MAX_PLAYER_NAME_LENGTH);
+        let mut encoder = Encoder::with_capacity(128);
+        encoder.write_string(&name);
+        let data = encoder.into_vec();
+        assert_eq!(
+            Reader::new(&data).read_string().as_deref(),
+            Some(name.as_str())
+        );
+    }
+
+    #[test]
+    fn decode_resync_message() {
+        let mut encoder = Encoder::with_capacity(4);
+        encoder.write_header(TYPE_RESYNC, 0);
+        assert!(matches!(
+            decode_client_message(&encoder.into_vec()),
+            Some(ClientMessage::Resync)
+        ));
+    }
+
+    #[test]
+    fn encode_migrate_carries_room_id_and_long_token() {
+        let token = "t".repeat(300);
+        let data = encode_migrate("room-b", &token);
+        assert_eq!(data[0], VERSION);
+        assert_eq!(data[1], TYPE_MIGRATE);
+        assert_eq!(data[4] as usize, "room-b".len());
+        assert_eq!(&data[5..11], b"room-b");
+        assert_eq!(
+            u16::from_le_bytes([data[11], data[12]]) as usize,
+            token.len()
+        );
+        assert_eq!(&data[13..], token.as_bytes());
+    }
+
+    #[test]
+    fn encode_announce_carries_long_text() {
+        let text = "a".repeat(300);
+        let data = encode_announce(&text);
+        assert_eq!(data[1], TYPE_ANNOUNCE);
+        assert_eq!(u16::from_le_bytes([data[4], data[5]]) as usize, text.len());
+        assert_eq!(&data[6..], text.as_bytes());
+    }
+
+    #[test]
+    fn decode_hints_message() {
+        let mut encoder = Encoder::with_capacity(4);
+        encoder.write_header(TYPE_HINTS, FLAG_HINTS_THREAT);
+        assert!(matches!(
+            decode_client_message(&encoder.into_vec()),
+            Some(ClientMessage::Hints { threat: true })
+        ));
+
+        let mut encoder = Encoder::with_capacity(4);
+        encoder.write_header(TYPE_HINTS, 0);
+        assert!(matches!(
+            decode_client_message(&encoder.into_vec()),
+            Some(ClientMessage::Hints { threat: false })
+        ));
+    }
+
+    #[test]
+    fn decode_ping_message() {
+        let mut encoder = Encoder::with_capacity(8);
+        encoder.write_header(TYPE_PING, 0);
+        encoder.write_u32(0xDEAD_BEEF);
+        assert!(matches!(
+            decode_client_message(&encoder.into_vec()),
+            Some(ClientMessage::Ping {
+                client_time: 0xDEAD_BEEF
+            })
+        ));
+
+        let mut encoder = Encoder::with_capacity(4);
+        encoder.write_header(TYPE_PING, 0);
+        assert!(decode_client_message(&encoder.into_vec()).is_none());
+    }
+
+    #[test]
+    fn decode_chat_message() {
+        let mut encoder = Encoder::with_capacity(16);
+        encoder.write_header(TYPE_CHAT, 0);
+        encoder.write_string("gg all");
+        match decode_client_message(&encoder.into_vec()) {
+            Some(ClientMessage::Chat { text }) => assert_eq!(text, "gg all"),
+            other => panic!("expected chat, got {other:?}"),
+        }
+
+        let mut truncated = Encoder::with_capacity(8);
+        truncated.write_header(TYPE_CHAT, 0);
+        truncated.write_u8(12);
+        truncated.write_u8(b'g');
+        assert!(decode_client_message(&truncated.into_vec()).is_none());
+    }
+
     #[test]
     fn decode_input_axis_and_boost() {
         let mut encoder = Encoder::with_capacity(32);
@@ -1,22 +1,75 @@
-use crate::game::room::Room;
-use crate::transport::ws_session::handle_socket;
+use crate::game::environment::EnvironmentConfig;
+use crate::game::room::{
+    BotConfig, DayNightConfig, DisconnectConfig, GrowthConfig, InputConfig, MovementConfig,
+    OutboundConfig, OutboundFullPolicy, OxygenConfig, PelletConfig, PelletPalette,
+    PelletVisibility, PlayerSnapshot, Room, RoomStats, SelfCollisionConfig, TickHistogramSnapshot,
+    ViewConfig, WireFormat, CHAT_RATE_LIMIT_PER_WINDOW, CHAT_RATE_WINDOW_MS,
+    TICK_HISTOGRAM_BOUNDS_US,
+};
+use crate::protocol::ServerError;
+use crate::shared::api_error::{ErrorCode, ErrorResponse};
+use crate::shared::cors::cors_layer_from_env;
+use crate::shared::room_name::{canonical_room_name, DEFAULT_ROOM_NAME};
+use crate::shared::shutdown::shutdown_signal;
+use crate::shared::tls::{serve, TlsConfig};
+use crate::transport::ws_session::{handle_socket, limit_inbound_size, reject_socket};
 use axum::{
-    extract::{Path, State, WebSocketUpgrade},
-    http::{HeaderMap, Method, StatusCode},
+    body::Bytes,
+    extract::{ws::close_code, DefaultBodyLimit, Path, Query, State, WebSocketUpgrade},
+    http::{header, HeaderMap, StatusCode},
     response::IntoResponse,
-    routing::get,
+    routing::{get, post},
     Json, Router,
 };
-use serde::Serialize;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 use std::sync::Arc;
-use tower_http::cors::{Any, CorsLayer};
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+
+/// Snapshots carry every snake node at full precision, so they outgrow axum's 2 MB default.
+const SNAPSHOT_BODY_LIMIT_BYTES: usize = 64 * 1024 * 1024;
 
 #[derive(Clone)]
 struct RoomModeState {
     room_id: String,
     room: Arc<Room>,
     proxy_secret: Option<String>,
+    admin_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordRequest {
+    enabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct DrainRequest {
+    enabled: bool,
+    /// Where the control plane wants this room's players to go; only used when enabling.
+    #[serde(default)]
+    migrate: Option<MigrateRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnnounceRequest {
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AnnounceResponse {
+    ok: bool,
+    delivered: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct MigrateRequest {
+    #[serde(rename = "roomId")]
+    room_id: String,
+    #[serde(rename = "roomToken")]
+    room_token: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -27,71 +80,411 @@ struct RoomHeartbeatPayload {
     player_count: usize,
     #[serde(rename = "totalSessions")]
     total_sessions: usize,
+    #[serde(rename = "overBudgetInputs")]
+    over_budget_inputs: u64,
+    #[serde(rename = "tickOverruns")]
+    tick_overruns: u64,
+    #[serde(rename = "heartbeatFailures")]
+    heartbeat_failures: u64,
+    #[serde(rename = "pelletResyncs")]
+    pellet_resyncs: u64,
+    #[serde(rename = "maxSessionPelletResyncs")]
+    max_session_pellet_resyncs: u64,
+    #[serde(rename = "tickP50Us")]
+    tick_p50_us: Option<u64>,
+    #[serde(rename = "tickP99Us")]
+    tick_p99_us: Option<u64>,
 }
 
 #[derive(Debug, Serialize)]
-struct OkResponse {
-    ok: bool,
+struct RoomStatsResponse {
+    #[serde(rename = "humanPlayers")]
+    human_players: usize,
+    #[serde(rename = "totalSessions")]
+    total_sessions: usize,
+    #[serde(rename = "overBudgetInputs")]
+    over_budget_inputs: u64,
+    #[serde(rename = "tickOverruns")]
+    tick_overruns: u64,
+    #[serde(rename = "heartbeatFailures")]
+    heartbeat_failures: u64,
+    #[serde(rename = "pelletResyncs")]
+    pellet_resyncs: u64,
+    #[serde(rename = "maxSessionPelletResyncs")]
+    max_session_pellet_resyncs: u64,
+    #[serde(rename = "tickDurations")]
+    tick_durations: TickDurationsResponse,
+}
+
+/// Tick time histogram. `counts[i]` covers ticks up to `boundsUs[i]`; the extra final count
+/// is ticks slower than the last bound. Quantiles are bucket upper bounds.
+#[derive(Debug, Serialize)]
+struct TickDurationsResponse {
+    #[serde(rename = "boundsUs")]
+    bounds_us: &'static [u64],
+    counts: Vec<u64>,
+    #[serde(rename = "p50Us")]
+    p50_us: Option<u64>,
+    #[serde(rename = "p90Us")]
+    p90_us: Option<u64>,
+    #[serde(rename = "p99Us")]
+    p99_us: Option<u64>,
+}
+
+impl From<TickHistogramSnapshot> for TickDurationsResponse {
+    fn from(snapshot: TickHistogramSnapshot) -> Self {
+        Self {
+            bounds_us: &TICK_HISTOGRAM_BOUNDS_US,
+            counts: snapshot.counts.to_vec(),
+            p50_us: snapshot.quantile_us(0.5),
+            p90_us: snapshot.quantile_us(0.9),
+            p99_us: snapshot.quantile_us(0.99),
+        }
+    }
+}
+
+impl From<RoomStats> for RoomStatsResponse {
+    fn from(stats: RoomStats) -> Self {
+        Self {
+            human_players: stats.human_players,
+            total_sessions: stats.total_sessions,
+            over_budget_inputs: stats.over_budget_inputs,
+            tick_overruns: stats.tick_overruns,
+            heartbeat_failures: stats.heartbeat_failures,
+            pellet_resyncs: stats.pellet_resyncs,
+            max_session_pellet_resyncs: stats.max_session_pellet_resyncs,
+            tick_durations: stats.tick_durations.into(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
-struct ErrorResponse {
+struct PlayerSnapshotResponse {
+    #[serde(rename = "netId")]
+    net_id: u16,
+    name: String,
+    score: i64,
+    rank: usize,
+    #[serde(rename = "totalPlayers")]
+    total_players: usize,
+    length: usize,
+    alive: bool,
+}
+
+impl From<PlayerSnapshot> for PlayerSnapshotResponse {
+    fn from(snapshot: PlayerSnapshot) -> Self {
+        Self {
+            net_id: snapshot.net_id,
+            name: snapshot.name,
+            score: snapshot.score,
+            rank: snapshot.rank,
+            total_players: snapshot.total_players,
+            length: snapshot.length,
+            alive: snapshot.alive,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct OkResponse {
     ok: bool,
-    error: String,
 }
 
 pub async fn run_room_mode() -> anyhow::Result<()> {
-    let room_id = env::var("ROOM_ID")
-        .map(|value| value.trim().to_string())
-        .ok()
-        .filter(|value| !value.is_empty())
-        .unwrap_or_else(|| "main".to_string());
+    let room_id = canonical_room_name(&env::var("ROOM_ID").unwrap_or_default());
+    let tls = TlsConfig::from_env()?;
     let max_human_players = env::var("MAX_HUMAN_PLAYERS")
         .ok()
         .and_then(|value| value.parse::<usize>().ok())
         .or(Some(25));
+    let default_bot_config = BotConfig::default();
+    let bot_config = BotConfig {
+        max_bots: env::var("MAX_BOTS")
+            .ok()
+            .and_then(|value| value.trim().parse::<usize>().ok())
+            .unwrap_or(default_bot_config.max_bots),
+        min_bots: env::var("MIN_BOTS")
+            .ok()
+            .and_then(|value| value.trim().parse::<usize>().ok())
+            .unwrap_or(default_bot_config.min_bots),
+    };
+    let environment_seed = env::var("ROOM_ENV_SEED")
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok());
+    let default_environment_config = EnvironmentConfig::default();
+    let environment_config = EnvironmentConfig {
+        lake_count: env_parse("ROOM_ENV_LAKES").unwrap_or(default_environment_config.lake_count),
+        lake_scale: env_parse("ROOM_ENV_LAKE_SCALE")
+            .unwrap_or(default_environment_config.lake_scale),
+        tree_count: env_parse("ROOM_ENV_TREES").unwrap_or(default_environment_config.tree_count),
+        cactus_count: env_parse("ROOM_ENV_CACTI")
+            .unwrap_or(default_environment_config.cactus_count),
+        mountain_count: env_parse("ROOM_ENV_MOUNTAINS")
+            .unwrap_or(default_environment_config.mountain_count),
+        mountain_scale: env_parse("ROOM_ENV_MOUNTAIN_SCALE")
+            .unwrap_or(default_environment_config.mountain_scale),
+    };
+    let bots_enabled = env_flag("ROOM_BOTS_ENABLED", true);
+    let spawn_terrain_protection = env_flag("ROOM_SPAWN_TERRAIN_PROTECTION", true);
+    let boost_drain_shedding = env_flag("ROOM_BOOST_SHED", false);
+    let day_night_enabled = env_flag("ROOM_DAY_NIGHT", false);
+    let day_night_config = day_night_enabled.then(|| {
+        let default_config = DayNightConfig::default();
+        DayNightConfig {
+            cycle_ms: env::var("ROOM_DAY_NIGHT_CYCLE_MS")
+                .ok()
+                .and_then(|value| value.trim().parse::<i64>().ok())
+                .filter(|value| *value > 0)
+                .unwrap_or(default_config.cycle_ms),
+            ..default_config
+        }
+    });
+    let default_pellet_config = PelletConfig::default();
+    let pellet_config = PelletConfig {
+        base_count: env_parse("ROOM_PELLET_BASE_COUNT").unwrap_or(default_pellet_config.base_count),
+        max_count: env_parse("ROOM_PELLET_MAX_COUNT").unwrap_or(default_pellet_config.max_count),
+        small_growth_fraction: env_parse("ROOM_PELLET_SMALL_GROWTH")
+            .unwrap_or(default_pellet_config.small_growth_fraction),
+        big_growth_fraction: env_parse("ROOM_PELLET_BIG_GROWTH")
+            .unwrap_or(default_pellet_config.big_growth_fraction),
+        small_size_min: env_parse("ROOM_PELLET_SMALL_SIZE_MIN")
+            .unwrap_or(default_pellet_config.small_size_min),
+        small_size_max: env_parse("ROOM_PELLET_SMALL_SIZE_MAX")
+            .unwrap_or(default_pellet_config.small_size_max),
+        evasive_size_min: env_parse("ROOM_PELLET_EVASIVE_SIZE_MIN")
+            .unwrap_or(default_pellet_config.evasive_size_min),
+        evasive_size_max: env_parse("ROOM_PELLET_EVASIVE_SIZE_MAX")
+            .unwrap_or(default_pellet_config.evasive_size_max),
+        evasive_min_difficulty: env_parse("ROOM_PELLET_EVASIVE_MIN_DIFFICULTY")
+            .unwrap_or(default_pellet_config.evasive_min_difficulty),
+        death_scatter_angle: env_parse("ROOM_PELLET_DEATH_SCATTER_ANGLE")
+            .unwrap_or(default_pellet_config.death_scatter_angle),
+    };
+    let default_growth_config = GrowthConfig::default();
+    let growth_config = GrowthConfig {
+        starting_length: env_parse("ROOM_STARTING_LENGTH")
+            .unwrap_or(default_growth_config.starting_length),
+        min_survival_length: env_parse("ROOM_MIN_SURVIVAL_LENGTH")
+            .unwrap_or(default_growth_config.min_survival_length),
+        girth_step_percent: env_parse("ROOM_GIRTH_STEP_PERCENT")
+            .unwrap_or(default_growth_config.girth_step_percent),
+        girth_nodes_per_step: env_parse("ROOM_GIRTH_NODES_PER_STEP")
+            .unwrap_or(default_growth_config.girth_nodes_per_step),
+        girth_max_scale: env_parse("ROOM_GIRTH_MAX_SCALE")
+            .unwrap_or(default_growth_config.girth_max_scale),
+    };
+    let default_disconnect_config = DisconnectConfig::default();
+    let disconnect_config = DisconnectConfig {
+        timeout_ms: env_parse("ROOM_PLAYER_TIMEOUT_MS")
+            .unwrap_or(default_disconnect_config.timeout_ms),
+        large_snake_length: env_parse("ROOM_LARGE_SNAKE_LENGTH")
+            .or(default_disconnect_config.large_snake_length),
+        large_snake_timeout_ms: env_parse("ROOM_LARGE_SNAKE_TIMEOUT_MS")
+            .unwrap_or(default_disconnect_config.large_snake_timeout_ms),
+        freeze_on_disconnect: env_flag(
+            "ROOM_FREEZE_ON_DISCONNECT",
+            default_disconnect_config.freeze_on_disconnect,
+        ),
+    };
+    let default_oxygen_config = OxygenConfig::default();
+    let oxygen_config = OxygenConfig {
+        max: env_parse("ROOM_OXYGEN_MAX").unwrap_or(default_oxygen_config.max),
+        drain_per_sec: env_parse("ROOM_OXYGEN_DRAIN_PER_SEC")
+            .unwrap_or(default_oxygen_config.drain_per_sec),
+    };
+    let default_movement_config = MovementConfig::default();
+    let movement_config = MovementConfig {
+        base_speed: env_parse("ROOM_BASE_SPEED").unwrap_or(default_movement_config.base_speed),
+        boost_multiplier: env_parse("ROOM_BOOST_MULTIPLIER")
+            .unwrap_or(default_movement_config.boost_multiplier),
+    };
+    let default_self_collision = SelfCollisionConfig::default();
+    let self_collision = SelfCollisionConfig {
+        enabled: env_flag("ROOM_SELF_COLLISION", default_self_collision.enabled),
+        min_node_gap: env_parse("ROOM_SELF_COLLISION_GAP")
+            .unwrap_or(default_self_collision.min_node_gap),
+    };
+    let default_input_config = InputConfig::default();
+    let input_config = InputConfig {
+        dead_zone: env_parse("ROOM_INPUT_DEAD_ZONE").unwrap_or(default_input_config.dead_zone),
+        smoothing: env_parse("ROOM_INPUT_SMOOTHING").unwrap_or(default_input_config.smoothing),
+    };
+    let default_view_config = ViewConfig::default();
+    let view_config = ViewConfig {
+        radius_margin: env_parse("ROOM_VIEW_MARGIN").unwrap_or(default_view_config.radius_margin),
+        node_padding: env_parse("ROOM_VIEW_NODE_PADDING")
+            .unwrap_or(default_view_config.node_padding),
+    };
+    let default_outbound_config = OutboundConfig::default();
+    let outbound_config = OutboundConfig {
+        hi_capacity: env_parse("ROOM_OUTBOUND_HI_CAPACITY")
+            .unwrap_or(default_outbound_config.hi_capacity),
+        lo_capacity: env_parse("ROOM_OUTBOUND_LO_CAPACITY")
+            .unwrap_or(default_outbound_config.lo_capacity),
+        hi_full_policy: env::var("ROOM_OUTBOUND_HI_POLICY")
+            .ok()
+            .and_then(|value| OutboundFullPolicy::parse(&value))
+            .unwrap_or(default_outbound_config.hi_full_policy),
+    };
+    let respawn_retention = env::var("ROOM_RESPAWN_RETENTION")
+        .ok()
+        .and_then(|value| value.trim().parse::<f64>().ok());
+    let oxygen_grace_ms = env_parse::<i64>("ROOM_OXYGEN_GRACE_MS");
+    let turn_rate_per_sec = env_parse::<f64>("ROOM_TURN_RATE_PER_SEC");
+    let spawn_safety_floor = env_parse::<f64>("ROOM_SPAWN_SAFETY_FLOOR");
+    let max_inbound_message_bytes = env_parse::<usize>("ROOM_MAX_INBOUND_MESSAGE_BYTES");
+    let collision_leniency = env_parse::<f64>("ROOM_COLLISION_LENIENCY");
+    let respawn_cooldown_ms = env_parse::<i64>("ROOM_RESPAWN_COOLDOWN_MS");
+    let kill_bonus = env_parse::<i64>("ROOM_KILL_BONUS");
+    let score_receipt_secret = env::var("SCORE_RECEIPT_SECRET")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+    let chat_rate_limit = env_parse::<u32>("ROOM_CHAT_RATE_LIMIT");
+    let chat_rate_window_ms = env_parse::<i64>("ROOM_CHAT_RATE_WINDOW_MS");
+    let idle_linger_ms = env_parse::<i64>("ROOM_IDLE_LINGER_MS");
+    let afk_autopilot_ms = env_parse::<i64>("ROOM_AFK_AUTOPILOT_MS");
+    let max_sessions = env_parse::<usize>("ROOM_MAX_SESSIONS").filter(|value| *value > 0);
+    let max_detailed_remotes = env_parse::<usize>("ROOM_MAX_DETAILED_REMOTES");
+    let keyframe_interval = env_parse::<u32>("ROOM_KEYFRAME_INTERVAL");
+    let pellet_visibility = env::var("ROOM_PELLET_VISIBILITY")
+        .ok()
+        .and_then(|value| PelletVisibility::parse(&value));
+    let pellet_palette = env::var("ROOM_PELLET_PALETTE")
+        .ok()
+        .and_then(|value| PelletPalette::parse(&value));
+    let replay_capacity = env::var("ROOM_REPLAY_CAPACITY")
+        .ok()
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .filter(|value| *value > 0);
     let proxy_secret = env::var("ROOM_PROXY_SECRET")
         .ok()
         .map(|value| value.trim().to_string())
         .filter(|value| !value.is_empty());
+    let admin_token = env::var("ROOM_ADMIN_TOKEN")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+
+    let mut room = if room_id == DEFAULT_ROOM_NAME {
+        Room::with_max_human_players(max_human_players)
+    } else {
+        Room::with_room_id_and_max_human_players(room_id.clone(), max_human_players)
+    }
+    .with_bot_config(bot_config)
+    .with_bots_enabled(bots_enabled)
+    .with_pellet_config(pellet_config)
+    .with_growth_config(growth_config)
+    .with_disconnect_config(disconnect_config)
+    .with_oxygen_config(oxygen_config)
+    .with_movement_config(movement_config)
+    .with_self_collision(self_collision)
+    .with_input_config(input_config)
+    .with_view_config(view_config)
+    .with_boost_drain_shedding(boost_drain_shedding)
+    .with_spawn_terrain_protection(spawn_terrain_protection)
+    .with_outbound_config(outbound_config);
+    if let Some(seed) = environment_seed {
+        room = room.with_environment_seed(seed);
+    }
+    if environment_config != default_environment_config {
+        room = room.with_environment_config(environment_config);
+    }
+    if let Some(config) = day_night_config {
+        room = room.with_day_night_cycle(config);
+    }
+    if let Some(fraction) = respawn_retention {
+        room = room.with_respawn_length_retention(fraction);
+    }
+    if let Some(grace_ms) = oxygen_grace_ms {
+        room = room.with_oxygen_grace_ms(grace_ms);
+    }
+    if let Some(turn_rate) = turn_rate_per_sec {
+        room = room.with_turn_rate_per_sec(turn_rate);
+    }
+    if let Some(floor) = spawn_safety_floor {
+        room = room.with_spawn_safety_floor(floor);
+    }
+    if let Some(leniency) = collision_leniency {
+        room = room.with_collision_leniency(leniency);
+    }
+    if let Some(cooldown_ms) = respawn_cooldown_ms {
+        room = room.with_respawn_cooldown_ms(cooldown_ms);
+    }
+    if let Some(bonus) = kill_bonus {
+        room = room.with_kill_bonus(bonus);
+    }
+    if let Some(secret) = score_receipt_secret {
+        room = room.with_score_receipt_secret(secret);
+    }
+    if chat_rate_limit.is_some() || chat_rate_window_ms.is_some() {
+        room = room.with_chat_rate_limit(
+            chat_rate_limit.unwrap_or(CHAT_RATE_LIMIT_PER_WINDOW),
+            chat_rate_window_ms.unwrap_or(CHAT_RATE_WINDOW_MS),
+        );
+    }
+    if let Some(linger_ms) = idle_linger_ms {
+        room = room.with_idle_linger_ms(linger_ms);
+    }
+    if let Some(afk_ms) = afk_autopilot_ms {
+        room = room.with_afk_autopilot_ms(afk_ms);
+    }
+    if let Some(capacity) = replay_capacity {
+        room = room.with_replay_capacity(capacity);
+    }
+    if let Some(max_sessions) = max_sessions {
+        room = room.with_max_sessions(max_sessions);
+    }
+    if let Some(max_bytes) = max_inbound_message_bytes {
+        room = room.with_max_inbound_message_bytes(max_bytes);
+    }
+    if let Some(max_players) = max_detailed_remotes {
+        room = room.with_max_detailed_remote_players(max_players);
+    }
+    if let Some(interval) = keyframe_interval {
+        room = room.with_keyframe_interval(interval);
+    }
+    if let Some(visibility) = pellet_visibility {
+        room = room.with_pellet_visibility(visibility);
+    }
+    if let Some(palette) = pellet_palette {
+        room = room.with_pellet_palette(palette);
+    }
 
     let state = Arc::new(RoomModeState {
         room_id: room_id.clone(),
-        room: if room_id == "main" {
-            Arc::new(Room::with_max_human_players(max_human_players))
-        } else {
-            Arc::new(Room::with_room_id_and_max_human_players(
-                room_id.clone(),
-                max_human_players,
-            ))
-        },
+        room: Arc::new(room),
         proxy_secret,
+        admin_token,
     });
 
-    if let (Ok(control_plane_url), Ok(heartbeat_token)) = (
-        env::var("CONTROL_PLANE_URL"),
-        env::var("ROOM_HEARTBEAT_TOKEN"),
-    ) {
+    let heartbeat = HeartbeatTarget::from_env();
+    let heartbeat_task = heartbeat.clone().map(|heartbeat| {
         let heartbeat_room_id = room_id.clone();
         let heartbeat_room = Arc::clone(&state.room);
         tokio::spawn(async move {
-            room_heartbeat_loop(
-                heartbeat_room,
-                heartbeat_room_id,
-                control_plane_url,
-                heartbeat_token,
-            )
-            .await;
-        });
-    }
+            room_heartbeat_loop(heartbeat_room, heartbeat_room_id, heartbeat).await;
+        })
+    });
+    let shutdown_room = Arc::clone(&state.room);
 
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods([Method::GET, Method::POST])
-        .allow_headers(Any);
+    let cors = cors_layer_from_env()?;
     let app: Router = Router::new()
         .route("/api/health", get(health))
+        .route("/api/ready", get(ready))
         .route("/api/room/:room", get(room_mode_ws_handler))
+        .route("/api/room/:room/environment", get(room_environment))
+        .route("/api/room/:room/player/:net_id", get(room_player))
+        .route("/api/room/:room/replay", get(room_replay))
+        .route("/api/room/:room/stats", get(room_stats))
+        .route("/internal/record", post(set_recording))
+        .route("/internal/drain", post(set_draining))
+        .route("/internal/announce", post(announce))
+        .route("/internal/snapshot", get(room_snapshot))
+        .route(
+            "/internal/restore",
+            post(restore_room_snapshot).layer(DefaultBodyLimit::max(SNAPSHOT_BODY_LIMIT_BYTES)),
+        )
         .layer(cors)
         .with_state(state);
 
@@ -100,59 +493,255 @@ pub async fn run_room_mode() -> anyhow::Result<()> {
         .and_then(|value| value.parse().ok())
         .unwrap_or(8787);
     let address = format!("0.0.0.0:{port}");
-    tracing::info!("room-mode listening on {address}");
+    tracing::info!(tls = tls.is_some(), "room-mode listening on {address}");
     let listener = tokio::net::TcpListener::bind(&address).await?;
-    axum::serve(listener, app).await?;
+    let served = serve(listener, app, tls.as_ref(), shutdown_signal()).await;
+
+    // Report the room as empty so the control plane can scale it down without waiting for
+    // heartbeats to go stale.
+    if let Some(task) = heartbeat_task {
+        task.abort();
+    }
+    if let Some(heartbeat) = heartbeat {
+        let stats = shutdown_room.stats().await;
+        let payload = RoomHeartbeatPayload {
+            room_id: room_id.clone(),
+            player_count: 0,
+            total_sessions: 0,
+            over_budget_inputs: stats.over_budget_inputs,
+            tick_overruns: stats.tick_overruns,
+            heartbeat_failures: stats.heartbeat_failures,
+            pellet_resyncs: stats.pellet_resyncs,
+            max_session_pellet_resyncs: stats.max_session_pellet_resyncs,
+            tick_p50_us: stats.tick_durations.quantile_us(0.5),
+            tick_p99_us: stats.tick_durations.quantile_us(0.99),
+        };
+        match heartbeat.send(&payload).await {
+            Ok(()) => tracing::info!(room_id, "sent final room heartbeat"),
+            Err(error) => tracing::warn!(?error, room_id, "final room heartbeat failed"),
+        }
+    }
+    served?;
     Ok(())
 }
 
-async fn room_heartbeat_loop(
-    room: Arc<Room>,
-    room_id: String,
-    control_plane_url: String,
-    heartbeat_token: String,
-) {
-    let endpoint = format!(
-        "{}/internal/room-heartbeat",
-        control_plane_url.trim_end_matches('/')
-    );
-    let client = reqwest::Client::new();
-    let mut interval = tokio::time::interval(std::time::Duration::from_secs(2));
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(2);
+const HEARTBEAT_INTERVAL_MIN: Duration = Duration::from_millis(250);
+const HEARTBEAT_INTERVAL_MAX: Duration = Duration::from_secs(60);
+const HEARTBEAT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// After this many consecutive failures the room is treated as disconnected from the control
+/// plane: it keeps serving players but only retries at `HEARTBEAT_DISCONNECTED_INTERVAL`.
+const HEARTBEAT_DISCONNECTED_AFTER_FAILURES: u64 = 10;
+const HEARTBEAT_DISCONNECTED_INTERVAL: Duration = Duration::from_secs(60);
+
+/// When heartbeats go out. Each room waits a random `0..=jitter` before its first heartbeat so
+/// a fleet that scaled up together doesn't post in lockstep; later beats keep that phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct HeartbeatSchedule {
+    interval: Duration,
+    jitter: Duration,
+}
+
+impl Default for HeartbeatSchedule {
+    fn default() -> Self {
+        Self {
+            interval: HEARTBEAT_INTERVAL,
+            jitter: HEARTBEAT_INTERVAL,
+        }
+    }
+}
+
+impl HeartbeatSchedule {
+    /// `ROOM_HEARTBEAT_INTERVAL_MS` is clamped to `250..=60000`; `ROOM_HEARTBEAT_JITTER_MS`
+    /// defaults to the interval and is capped at it.
+    fn from_env() -> Self {
+        let interval = env_parse::<u64>("ROOM_HEARTBEAT_INTERVAL_MS")
+            .map(Duration::from_millis)
+            .unwrap_or(HEARTBEAT_INTERVAL)
+            .clamp(HEARTBEAT_INTERVAL_MIN, HEARTBEAT_INTERVAL_MAX);
+        let jitter = env_parse::<u64>("ROOM_HEARTBEAT_JITTER_MS")
+            .map(Duration::from_millis)
+            .unwrap_or(interval)
+            .min(interval);
+        Self { interval, jitter }
+    }
+
+    fn first_delay(&self, rng: &mut impl Rng) -> Duration {
+        let jitter_ms = self.jitter.as_millis() as u64;
+        Duration::from_millis(rng.gen_range(0..=jitter_ms))
+    }
+}
+
+#[derive(Debug)]
+struct HeartbeatBackoff {
+    interval: Duration,
+    consecutive_failures: u64,
+}
+
+impl Default for HeartbeatBackoff {
+    fn default() -> Self {
+        Self::new(HEARTBEAT_INTERVAL)
+    }
+}
+
+impl HeartbeatBackoff {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            consecutive_failures: 0,
+        }
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.consecutive_failures >= HEARTBEAT_DISCONNECTED_AFTER_FAILURES
+    }
+
+    fn next_delay(&self) -> Duration {
+        if self.consecutive_failures == 0 {
+            return self.interval;
+        }
+        if self.is_disconnected() {
+            return HEARTBEAT_DISCONNECTED_INTERVAL.max(self.interval);
+        }
+        let shift = self.consecutive_failures.min(16) as u32;
+        self.interval
+            .saturating_mul(1 << shift)
+            .min(HEARTBEAT_MAX_BACKOFF.max(self.interval))
+    }
+
+    /// Returns the failure count that just ended, if any.
+    fn record_success(&mut self) -> Option<u64> {
+        let failures = std::mem::take(&mut self.consecutive_failures);
+        (failures > 0).then_some(failures)
+    }
+
+    fn record_failure(&mut self) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+    }
+}
+
+#[derive(Clone)]
+struct HeartbeatTarget {
+    client: reqwest::Client,
+    endpoint: String,
+    token: String,
+    schedule: HeartbeatSchedule,
+}
+
+impl HeartbeatTarget {
+    /// Heartbeats are only sent when both `CONTROL_PLANE_URL` and `ROOM_HEARTBEAT_TOKEN` are set.
+    fn from_env() -> Option<Self> {
+        let control_plane_url = env::var("CONTROL_PLANE_URL").ok()?;
+        let token = env::var("ROOM_HEARTBEAT_TOKEN").ok()?;
+        Some(Self {
+            client: reqwest::Client::new(),
+            endpoint: format!(
+                "{}/internal/room-heartbeat",
+                control_plane_url.trim_end_matches('/')
+            ),
+            token,
+            schedule: HeartbeatSchedule::from_env(),
+        })
+    }
+
+    async fn send(&self, payload: &RoomHeartbeatPayload) -> reqwest::Result<()> {
+        self.client
+            .post(&self.endpoint)
+            .header("Authorization", format!("Bearer {}", self.token))
+            .json(payload)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map(|_| ())
+    }
+}
+
+fn env_parse<T: std::str::FromStr>(name: &str) -> Option<T> {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.trim().parse::<T>().ok())
+}
+
+/// Reads an on/off env var. Unset or blank means `default`; an unrecognised value also falls
+/// back to `default`, with a warning so a typo doesn't silently flip the setting.
+fn env_flag(name: &str, default: bool) -> bool {
+    let Ok(value) = env::var(name) else {
+        return default;
+    };
+    if value.trim().is_empty() {
+        return default;
+    }
+    parse_flag(&value).unwrap_or_else(|| {
+        tracing::warn!(name, value, default, "unrecognised boolean env value");
+        default
+    })
+}
+
+/// `1`/`true`/`yes`/`on` and `0`/`false`/`no`/`off`, trimmed and case-insensitive.
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+async fn room_heartbeat_loop(room: Arc<Room>, room_id: String, heartbeat: HeartbeatTarget) {
+    let mut backoff = HeartbeatBackoff::new(heartbeat.schedule.interval);
+    let first_delay = heartbeat.schedule.first_delay(&mut rand::thread_rng());
+    tokio::time::sleep(first_delay).await;
     loop {
-        interval.tick().await;
         let stats = room.stats().await;
         let payload = RoomHeartbeatPayload {
             room_id: room_id.clone(),
             player_count: stats.human_players,
             total_sessions: stats.total_sessions,
+            over_budget_inputs: stats.over_budget_inputs,
+            tick_overruns: stats.tick_overruns,
+            heartbeat_failures: stats.heartbeat_failures,
+            pellet_resyncs: stats.pellet_resyncs,
+            max_session_pellet_resyncs: stats.max_session_pellet_resyncs,
+            tick_p50_us: stats.tick_durations.quantile_us(0.5),
+            tick_p99_us: stats.tick_durations.quantile_us(0.99),
         };
-        if let Err(error) = client
-            .post(&endpoint)
-            .header("Authorization", format!("Bearer {heartbeat_token}"))
-            .json(&payload)
-            .send()
-            .await
-        {
-            tracing::warn!(?error, room_id, "room heartbeat failed");
+        let result = heartbeat.send(&payload).await;
+        match result {
+            Ok(_) => {
+                if let Some(failures) = backoff.record_success() {
+                    tracing::info!(room_id, failures, "room heartbeat recovered");
+                }
+            }
+            Err(error) => {
+                backoff.record_failure();
+                let failures = backoff.consecutive_failures;
+                if failures == HEARTBEAT_DISCONNECTED_AFTER_FAILURES {
+                    tracing::error!(
+                        ?error,
+                        room_id,
+                        failures,
+                        "room heartbeat disconnected from control plane; slowing retries"
+                    );
+                } else {
+                    tracing::warn!(?error, room_id, failures, "room heartbeat failed");
+                }
+            }
         }
+        room.set_heartbeat_failures(backoff.consecutive_failures);
+        tokio::time::sleep(backoff.next_delay()).await;
     }
 }
 
 async fn room_mode_ws_handler(
     ws: WebSocketUpgrade,
     Path(room): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
     State(state): State<Arc<RoomModeState>>,
     headers: HeaderMap,
 ) -> impl IntoResponse {
-    let trimmed = room.trim();
-    let requested_room = if trimmed.is_empty() { "main" } else { trimmed };
-    if requested_room != state.room_id {
+    if canonical_room_name(&room) != state.room_id {
         return (
             StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                ok: false,
-                error: "Unknown room".to_string(),
-            }),
+            Json(ErrorResponse::new(ErrorCode::UnknownRoom, "Unknown room")),
         )
             .into_response();
     }
@@ -161,21 +750,411 @@ async fn room_mode_ws_handler(
             .get("x-room-proxy-secret")
             .and_then(|value| value.to_str().ok());
         if supplied_secret != Some(proxy_secret.as_str()) {
+            // Upgrade anyway so the client sees why it was turned away instead of a bare 401.
+            return ws
+                .on_upgrade(|socket| {
+                    reject_socket(socket, ServerError::Unauthorized, close_code::POLICY)
+                })
+                .into_response();
+        }
+    }
+    let room = Arc::clone(&state.room);
+    if !room.has_session_capacity().await {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::new(
+                ErrorCode::RoomFull,
+                "Room is at session capacity",
+            )),
+        )
+            .into_response();
+    }
+    let wire_format = WireFormat::from_query(params.get("format").map(String::as_str));
+    limit_inbound_size(ws, &room)
+        .on_upgrade(move |socket| handle_socket(socket, room, wire_format))
+        .into_response()
+}
+
+/// Map preview: the room's terrain in the `TYPE_INIT` environment encoding. Requires the
+/// proxy secret when one is configured, like the WebSocket route.
+async fn room_environment(
+    Path(room): Path<String>,
+    State(state): State<Arc<RoomModeState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if canonical_room_name(&room) != state.room_id {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(ErrorCode::UnknownRoom, "Unknown room")),
+        )
+            .into_response();
+    }
+    if !is_proxy_authorized(&state, &headers) {
+        return unauthorized_response();
+    }
+    let environment = state.room.environment_bytes().await;
+    (
+        [(header::CONTENT_TYPE, "application/octet-stream")],
+        environment,
+    )
+        .into_response()
+}
+
+/// Live score, rank, and length of one player by net id, for streamer overlays. Gated by the
+/// proxy secret like the environment preview.
+async fn room_player(
+    Path((room, net_id)): Path<(String, String)>,
+    State(state): State<Arc<RoomModeState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if canonical_room_name(&room) != state.room_id {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(ErrorCode::UnknownRoom, "Unknown room")),
+        )
+            .into_response();
+    }
+    if !is_proxy_authorized(&state, &headers) {
+        return unauthorized_response();
+    }
+    let snapshot = match net_id.parse::<u16>() {
+        Ok(net_id) => state.room.player_snapshot(net_id).await,
+        Err(_) => None,
+    };
+    match snapshot {
+        Some(snapshot) => Json(PlayerSnapshotResponse::from(snapshot)).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(
+                ErrorCode::PlayerNotFound,
+                "Player not found",
+            )),
+        )
+            .into_response(),
+    }
+}
+
+async fn room_replay(
+    Path(room): Path<String>,
+    State(state): State<Arc<RoomModeState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if !is_admin_authorized(&state, &headers) {
+        return unauthorized_response();
+    }
+    if canonical_room_name(&room) != state.room_id {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(ErrorCode::UnknownRoom, "Unknown room")),
+        )
+            .into_response();
+    }
+    let replay = state.room.export_recording().await;
+    ([(header::CONTENT_TYPE, "application/octet-stream")], replay).into_response()
+}
+
+async fn room_stats(
+    Path(room): Path<String>,
+    State(state): State<Arc<RoomModeState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if !is_admin_authorized(&state, &headers) {
+        return unauthorized_response();
+    }
+    if canonical_room_name(&room) != state.room_id {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(ErrorCode::UnknownRoom, "Unknown room")),
+        )
+            .into_response();
+    }
+    let stats = state.room.stats().await;
+    Json(RoomStatsResponse::from(stats)).into_response()
+}
+
+async fn room_snapshot(
+    State(state): State<Arc<RoomModeState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if !is_admin_authorized(&state, &headers) {
+        return unauthorized_response();
+    }
+    let snapshot = state.room.snapshot().await;
+    (
+        [(header::CONTENT_TYPE, "application/octet-stream")],
+        snapshot,
+    )
+        .into_response()
+}
+
+async fn restore_room_snapshot(
+    State(state): State<Arc<RoomModeState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    if !is_admin_authorized(&state, &headers) {
+        return unauthorized_response();
+    }
+    if let Err(error) = state.room.restore_snapshot(&body).await {
+        tracing::warn!(room_id = state.room_id, %error, "room snapshot restore rejected");
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                ErrorCode::InvalidSnapshot,
+                error.to_string(),
+            )),
+        )
+            .into_response();
+    }
+    Json(OkResponse { ok: true }).into_response()
+}
+
+async fn set_recording(
+    State(state): State<Arc<RoomModeState>>,
+    headers: HeaderMap,
+    payload: Result<Json<RecordRequest>, axum::extract::rejection::JsonRejection>,
+) -> impl IntoResponse {
+    if !is_admin_authorized(&state, &headers) {
+        return unauthorized_response();
+    }
+    let Json(payload) = match payload {
+        Ok(payload) => payload,
+        Err(_) => {
             return (
-                StatusCode::UNAUTHORIZED,
-                Json(ErrorResponse {
-                    ok: false,
-                    error: "Unauthorized".to_string(),
-                }),
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new(ErrorCode::InvalidJson, "Invalid JSON")),
             )
                 .into_response();
         }
+    };
+    state.room.set_recording(payload.enabled).await;
+    Json(OkResponse { ok: true }).into_response()
+}
+
+async fn set_draining(
+    State(state): State<Arc<RoomModeState>>,
+    headers: HeaderMap,
+    payload: Result<Json<DrainRequest>, axum::extract::rejection::JsonRejection>,
+) -> impl IntoResponse {
+    if !is_admin_authorized(&state, &headers) {
+        return unauthorized_response();
     }
-    let room = Arc::clone(&state.room);
-    ws.on_upgrade(move |socket| handle_socket(socket, room))
+    let Json(payload) = match payload {
+        Ok(payload) => payload,
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new(ErrorCode::InvalidJson, "Invalid JSON")),
+            )
+                .into_response();
+        }
+    };
+    match payload.migrate.filter(|_| payload.enabled) {
+        Some(migrate) => {
+            let notified = state
+                .room
+                .migrate_sessions(&migrate.room_id, &migrate.room_token)
+                .await;
+            tracing::info!(
+                room_id = state.room_id,
+                target_room_id = migrate.room_id,
+                notified,
+                "draining room into another"
+            );
+        }
+        None => state.room.set_draining(payload.enabled).await,
+    }
+    Json(OkResponse { ok: true }).into_response()
+}
+
+async fn announce(
+    State(state): State<Arc<RoomModeState>>,
+    headers: HeaderMap,
+    payload: Result<Json<AnnounceRequest>, axum::extract::rejection::JsonRejection>,
+) -> impl IntoResponse {
+    if !is_admin_authorized(&state, &headers) {
+        return unauthorized_response();
+    }
+    let Json(payload) = match payload {
+        Ok(payload) => payload,
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new(ErrorCode::InvalidJson, "Invalid JSON")),
+            )
+                .into_response();
+        }
+    };
+    let Some(delivered) = state.room.announce(&payload.text).await else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                ErrorCode::InvalidRequest,
+                "Announcement text is empty",
+            )),
+        )
+            .into_response();
+    };
+    tracing::info!(room_id = state.room_id, delivered, "announcement sent");
+    Json(AnnounceResponse {
+        ok: true,
+        delivered,
+    })
+    .into_response()
+}
+
+/// Public room routes require the proxy secret when one is configured.
+fn is_proxy_authorized(state: &RoomModeState, headers: &HeaderMap) -> bool {
+    let Some(proxy_secret) = &state.proxy_secret else {
+        return true;
+    };
+    let supplied_secret = headers
+        .get("x-room-proxy-secret")
+        .and_then(|value| value.to_str().ok());
+    supplied_secret == Some(proxy_secret.as_str())
+}
+
+fn is_admin_authorized(state: &RoomModeState, headers: &HeaderMap) -> bool {
+    let Some(admin_token) = &state.admin_token else {
+        return false;
+    };
+    let supplied = headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim);
+    // Constant-time so response timing doesn't reveal how much of a guess matched.
+    supplied.is_some_and(|supplied| bool::from(supplied.as_bytes().ct_eq(admin_token.as_bytes())))
+}
+
+fn unauthorized_response() -> axum::response::Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(ErrorResponse::new(ErrorCode::Unauthorized, "Unauthorized")),
+    )
         .into_response()
 }
 
+/// Liveness: the process is up and serving requests.
 async fn health() -> impl IntoResponse {
     Json(OkResponse { ok: true })
 }
+
+/// Readiness: the room's tick loop is idle or keeping up.
+async fn ready(State(state): State<Arc<RoomModeState>>) -> impl IntoResponse {
+    if state.room.is_tick_loop_healthy() {
+        return Json(OkResponse { ok: true }).into_response();
+    }
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(ErrorResponse::new(
+            ErrorCode::TickStalled,
+            "Tick loop stalled",
+        )),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_flag, HeartbeatBackoff, HeartbeatSchedule, HEARTBEAT_DISCONNECTED_AFTER_FAILURES,
+        HEARTBEAT_DISCONNECTED_INTERVAL, HEARTBEAT_INTERVAL, HEARTBEAT_MAX_BACKOFF,
+    };
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::time::Duration;
+
+    #[test]
+    fn env_flags_accept_both_spellings_and_reject_the_rest() {
+        for value in ["1", "true", " YES ", "On"] {
+            assert_eq!(parse_flag(value), Some(true), "{value}");
+        }
+        for value in ["0", "False", "no", " off"] {
+            assert_eq!(parse_flag(value), Some(false), "{value}");
+        }
+        for value in ["enabled", "2", "y"] {
+            assert_eq!(parse_flag(value), None, "{value}");
+        }
+    }
+
+    #[test]
+    fn heartbeat_first_delay_spreads_across_jitter_range() {
+        let schedule = HeartbeatSchedule {
+            interval: Duration::from_secs(5),
+            jitter: Duration::from_millis(800),
+        };
+        let mut rng = StdRng::seed_from_u64(7);
+        let delays: Vec<Duration> = (0..200).map(|_| schedule.first_delay(&mut rng)).collect();
+        assert!(delays.iter().all(|delay| *delay <= schedule.jitter));
+        assert!(delays
+            .iter()
+            .any(|delay| *delay < Duration::from_millis(200)));
+        assert!(delays
+            .iter()
+            .any(|delay| *delay > Duration::from_millis(600)));
+
+        let no_jitter = HeartbeatSchedule {
+            jitter: Duration::ZERO,
+            ..schedule
+        };
+        assert_eq!(no_jitter.first_delay(&mut rng), Duration::ZERO);
+    }
+
+    #[test]
+    fn heartbeat_backoff_starts_from_configured_interval() {
+        let mut backoff = HeartbeatBackoff::new(Duration::from_millis(500));
+        assert_eq!(backoff.next_delay(), Duration::from_millis(500));
+        backoff.record_failure();
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn heartbeat_backoff_grows_until_disconnected_then_resets() {
+        let mut backoff = HeartbeatBackoff::default();
+        assert_eq!(backoff.next_delay(), HEARTBEAT_INTERVAL);
+
+        let outcomes = [false, false, false, true, false];
+        let delays: Vec<Duration> = outcomes
+            .iter()
+            .map(|ok| {
+                if *ok {
+                    backoff.record_success();
+                } else {
+                    backoff.record_failure();
+                }
+                backoff.next_delay()
+            })
+            .collect();
+        assert_eq!(
+            delays,
+            [
+                Duration::from_secs(4),
+                Duration::from_secs(8),
+                Duration::from_secs(16),
+                HEARTBEAT_INTERVAL,
+                Duration::from_secs(4),
+            ]
+        );
+
+        for _ in 0..4 {
+            backoff.record_failure();
+        }
+        assert_eq!(backoff.next_delay(), HEARTBEAT_MAX_BACKOFF);
+        while !backoff.is_disconnected() {
+            backoff.record_failure();
+        }
+        assert_eq!(
+            backoff.consecutive_failures,
+            HEARTBEAT_DISCONNECTED_AFTER_FAILURES
+        );
+        assert_eq!(backoff.next_delay(), HEARTBEAT_DISCONNECTED_INTERVAL);
+
+        assert_eq!(
+            backoff.record_success(),
+            Some(HEARTBEAT_DISCONNECTED_AFTER_FAILURES)
+        );
+        assert_eq!(backoff.next_delay(), HEARTBEAT_INTERVAL);
+        assert_eq!(backoff.record_success(), None);
+    }
+}
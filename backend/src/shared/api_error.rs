@@ -0,0 +1,76 @@
+use serde::Serialize;
+
+/// Stable, machine-readable reason for a failed HTTP request. Clients should branch on this
+/// rather than on the human-readable message, which may change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidJson,
+    InvalidRequest,
+    InvalidQuery,
+    InvalidSnapshot,
+    Unauthorized,
+    DebugDisabled,
+    UnknownRoom,
+    UnknownTicket,
+    PlayerNotFound,
+    RoomFull,
+    TooManyRooms,
+    MatchmakeInProgress,
+    MatchmakeQueueFull,
+    ServerFull,
+    RoomAssignmentFailed,
+    TokenIssueFailed,
+    NameTooLong,
+    InvalidScore,
+    ScoreOutOfRange,
+    ScoreReceiptRequired,
+    InvalidScoreReceipt,
+    ScoreReceiptExpired,
+    ScoreReceiptUsed,
+    SubmissionFailed,
+    LeaderboardUnavailable,
+    LeaderboardDisabled,
+    DatabaseUnavailable,
+    TickStalled,
+}
+
+/// JSON body of every error response: `{ "ok": false, "code": "...", "error": "..." }`.
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    ok: bool,
+    code: ErrorCode,
+    error: String,
+}
+
+impl ErrorResponse {
+    pub fn new(code: ErrorCode, error: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            code,
+            error: error.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ErrorCode, ErrorResponse};
+
+    #[test]
+    fn error_response_serializes_snake_case_code_next_to_message() {
+        let body = serde_json::to_value(ErrorResponse::new(
+            ErrorCode::ScoreOutOfRange,
+            "Score out of range",
+        ))
+        .expect("serialize");
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "ok": false,
+                "code": "score_out_of_range",
+                "error": "Score out of range",
+            })
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use anyhow::{bail, Context};
+use axum::http::{HeaderValue, Method};
+use std::env;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+const ALLOWED_ORIGINS_ENV: &str = "ALLOWED_ORIGINS";
+
+/// CORS for every server mode. `ALLOWED_ORIGINS` (comma separated) restricts browsers to
+/// those origins; when it is unset or blank any origin is allowed, which suits local dev.
+pub fn cors_layer_from_env() -> anyhow::Result<CorsLayer> {
+    let allow_origin = match env::var(ALLOWED_ORIGINS_ENV) {
+        Ok(raw) if !raw.trim().is_empty() => {
+            let origins = parse_origin_list(ALLOWED_ORIGINS_ENV, &raw)?;
+            tracing::info!(?origins, "cors restricted to allowed origins");
+            AllowOrigin::list(origins)
+        }
+        _ => AllowOrigin::from(Any),
+    };
+    Ok(CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST])
+        .allow_headers(Any))
+}
+
+fn parse_origin_list(var_name: &str, raw: &str) -> anyhow::Result<Vec<HeaderValue>> {
+    let mut origins: Vec<HeaderValue> = Vec::new();
+    for token in raw.split(',') {
+        let trimmed = token.trim().trim_end_matches('/');
+        if trimmed.is_empty() {
+            bail!("{var_name} contains an empty origin segment");
+        }
+        if !(trimmed.starts_with("http://") || trimmed.starts_with("https://")) {
+            bail!("{var_name} origin '{trimmed}' must start with http:// or https://");
+        }
+        let origin = HeaderValue::from_str(trimmed)
+            .with_context(|| format!("{var_name} has invalid origin '{trimmed}'"))?;
+        if !origins.contains(&origin) {
+            origins.push(origin);
+        }
+    }
+    Ok(origins)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_origin_list;
+
+    #[test]
+    fn parse_origin_list_trims_and_deduplicates() {
+        let origins = parse_origin_list(
+            "ALLOWED_ORIGINS",
+            " https://game.example.com/ ,http://localhost:5173,https://game.example.com",
+        )
+        .unwrap();
+        assert_eq!(
+            origins,
+            vec!["https://game.example.com", "http://localhost:5173"]
+        );
+    }
+
+    #[test]
+    fn parse_origin_list_rejects_empty_segments_and_bare_hosts() {
+        assert!(parse_origin_list("ALLOWED_ORIGINS", "https://a.example.com,,").is_err());
+        assert!(parse_origin_list("ALLOWED_ORIGINS", " , ").is_err());
+        assert!(parse_origin_list("ALLOWED_ORIGINS", "game.example.com").is_err());
+    }
+}
@@ -1,2 +1,9 @@
+pub mod api_error;
+pub mod cors;
 pub mod names;
+pub mod rate_limiter;
+pub mod room_name;
 pub mod room_token;
+pub mod score_receipt;
+pub mod shutdown;
+pub mod tls;
@@ -1,4 +1,30 @@
+use std::sync::OnceLock;
+
+/// Longest player name, in characters, that is stored or sent to clients.
 pub const MAX_PLAYER_NAME_LENGTH: usize = 20;
+// Wire strings carry a `u8` byte length; a max-length name of 4-byte characters must still fit
+// so the protocol encoder never cuts a name short of what was stored.
+const _: () = assert!(MAX_PLAYER_NAME_LENGTH * 4 <= u8::MAX as usize);
+/// Longest chat line, in characters, relayed to other players.
+pub const MAX_CHAT_LENGTH: usize = 60;
+const _: () = assert!(MAX_CHAT_LENGTH * 4 <= u8::MAX as usize);
+/// Longest operator announcement, in characters, pushed to every session in a room.
+pub const MAX_ANNOUNCEMENT_LENGTH: usize = 200;
+/// Env var naming a file of blocked name fragments, one per line (`#` starts a comment).
+pub const NAME_BLOCKLIST_PATH_ENV: &str = "PLAYER_NAME_BLOCKLIST_PATH";
+/// Bots are named `Bot-<n>`; humans may not take that prefix.
+const RESERVED_BOT_PREFIX: &str = "bot-";
+
+/// Whether `name` would be cut short by `sanitize_player_name`. Runs of whitespace count as a
+/// single space and leading/trailing whitespace is ignored, matching what gets stored.
+pub fn exceeds_max_name_length(name: &str) -> bool {
+    name.split_whitespace()
+        .map(|word| word.chars().count())
+        .enumerate()
+        .map(|(index, len)| if index == 0 { len } else { len + 1 })
+        .sum::<usize>()
+        > MAX_PLAYER_NAME_LENGTH
+}
 
 pub fn sanitize_player_name(name: &str, fallback: &str) -> String {
     let cleaned = name.split_whitespace().collect::<Vec<_>>().join(" ");
@@ -7,3 +33,287 @@ pub fn sanitize_player_name(name: &str, fallback: &str) -> String {
     }
     cleaned.chars().take(MAX_PLAYER_NAME_LENGTH).collect()
 }
+
+/// Collapses whitespace, drops control and invisible characters and truncates to
+/// `MAX_CHAT_LENGTH`. Returns `None` when nothing printable is left.
+pub fn sanitize_chat_text(text: &str) -> Option<String> {
+    sanitize_line(text, MAX_CHAT_LENGTH)
+}
+
+/// Same cleanup as chat, bounded by `MAX_ANNOUNCEMENT_LENGTH`.
+pub fn sanitize_announcement_text(text: &str) -> Option<String> {
+    sanitize_line(text, MAX_ANNOUNCEMENT_LENGTH)
+}
+
+fn sanitize_line(text: &str, max_chars: usize) -> Option<String> {
+    let cleaned = text
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|ch| !ch.is_control() && !is_invisible(*ch))
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if cleaned.is_empty() {
+        return None;
+    }
+    Some(cleaned.chars().take(max_chars).collect())
+}
+
+/// Rules applied to human-chosen names on top of `sanitize_player_name`.
+#[derive(Debug, Default)]
+pub struct NamePolicy {
+    blocked: Vec<String>,
+}
+
+impl NamePolicy {
+    pub fn new<I, S>(blocked: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut blocked: Vec<String> = blocked
+            .into_iter()
+            .map(|word| skeleton(word.as_ref()))
+            .filter(|word| !word.is_empty())
+            .collect();
+        blocked.sort();
+        blocked.dedup();
+        Self { blocked }
+    }
+
+    /// Loads the blocklist named by `PLAYER_NAME_BLOCKLIST_PATH`. A missing or unreadable file
+    /// leaves only the reserved-prefix rule in place.
+    pub fn from_env() -> Self {
+        let Some(path) = std::env::var(NAME_BLOCKLIST_PATH_ENV)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+        else {
+            return Self::default();
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => {
+                let policy = Self::new(
+                    contents
+                        .lines()
+                        .map(|line| line.split('#').next().unwrap_or_default().trim())
+                        .filter(|line| !line.is_empty()),
+                );
+                tracing::info!(
+                    path,
+                    entries = policy.blocked.len(),
+                    "name blocklist loaded"
+                );
+                policy
+            }
+            Err(error) => {
+                tracing::warn!(path, %error, "failed to read name blocklist");
+                Self::default()
+            }
+        }
+    }
+
+    /// Sanitizes `name` and returns `fallback` if it impersonates a bot or contains a blocked
+    /// fragment. Matching ignores case, accents, separators and common lookalike characters.
+    pub fn apply(&self, name: &str, fallback: &str) -> String {
+        let sanitized = sanitize_player_name(name, fallback);
+        if self.is_allowed(&sanitized) {
+            sanitized
+        } else {
+            fallback.to_string()
+        }
+    }
+
+    fn is_allowed(&self, name: &str) -> bool {
+        let folded: String = name
+            .chars()
+            .filter(|ch| !ch.is_whitespace() && !is_invisible(*ch))
+            .map(fold_char)
+            .collect();
+        if folded.starts_with(RESERVED_BOT_PREFIX) {
+            return false;
+        }
+        self.allows_text(name)
+    }
+
+    /// Whether `text` is free of blocked fragments, matched the same way as names. The reserved
+    /// bot prefix is not checked, so chat can mention bots by name.
+    pub fn allows_text(&self, text: &str) -> bool {
+        let skeleton = skeleton(text);
+        !self
+            .blocked
+            .iter()
+            .any(|blocked| skeleton.contains(blocked.as_str()))
+    }
+}
+
+/// Process-wide policy, read from the environment on first use.
+pub fn name_policy() -> &'static NamePolicy {
+    static POLICY: OnceLock<NamePolicy> = OnceLock::new();
+    POLICY.get_or_init(NamePolicy::from_env)
+}
+
+/// Folded form used for blocklist matching: lookalikes mapped to ASCII, leetspeak digits read
+/// as letters, and everything that isn't a letter dropped.
+fn skeleton(value: &str) -> String {
+    value
+        .chars()
+        .map(fold_char)
+        .map(|ch| match ch {
+            '0' => 'o',
+            '1' | '!' | '|' => 'i',
+            '3' => 'e',
+            '4' | '@' => 'a',
+            '5' | '$' => 's',
+            '7' => 't',
+            other => other,
+        })
+        .filter(char::is_ascii_lowercase)
+        .collect()
+}
+
+fn is_invisible(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{00ad}' | '\u{034f}' | '\u{200b}'..='\u{200f}' | '\u{2060}'..='\u{2064}' | '\u{feff}'
+    )
+}
+
+/// Lowercases `ch` and maps fullwidth forms, accented Latin letters, Cyrillic/Greek lookalikes
+/// and dash variants onto their plain ASCII counterparts.
+fn fold_char(ch: char) -> char {
+    let ch = match ch {
+        '\u{ff01}'..='\u{ff5e}' => char::from_u32(ch as u32 - 0xfee0).unwrap_or(ch),
+        _ => ch,
+    };
+    let lower = ch.to_lowercase().next().unwrap_or(ch);
+    match lower {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' | 'а' | 'α' => 'a',
+        'ß' | 'в' | 'β' => 'b',
+        'ç' | 'ć' | 'č' | 'с' | 'ς' => 'c',
+        'ď' | 'đ' => 'd',
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' | 'е' | 'ё' | 'ε' => 'e',
+        'ğ' | 'ģ' => 'g',
+        'н' | 'η' => 'h',
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' | 'ı' | 'і' | 'ї' | 'ι' => 'i',
+        'ј' => 'j',
+        'к' | 'κ' => 'k',
+        'ł' | 'ľ' => 'l',
+        'м' | 'μ' => 'm',
+        'ñ' | 'ń' | 'ň' | 'ν' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' | 'о' | 'ο' => 'o',
+        'р' | 'ρ' => 'p',
+        'ř' => 'r',
+        'ś' | 'š' | 'ş' | 'ѕ' => 's',
+        'ť' | 'т' | 'τ' => 't',
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' | 'υ' => 'u',
+        'ѵ' => 'v',
+        'ш' | 'ω' => 'w',
+        'х' | 'χ' => 'x',
+        'ý' | 'ÿ' | 'у' | 'γ' => 'y',
+        'ź' | 'ż' | 'ž' | 'ζ' => 'z',
+        '\u{2010}'..='\u{2015}' | '\u{2212}' | '\u{fe63}' | '_' => '-',
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        exceeds_max_name_length, sanitize_announcement_text, sanitize_chat_text,
+        sanitize_player_name, NamePolicy, MAX_ANNOUNCEMENT_LENGTH, MAX_CHAT_LENGTH,
+        MAX_PLAYER_NAME_LENGTH,
+    };
+
+    #[test]
+    fn blocked_fragments_fall_back_regardless_of_case_or_separators() {
+        let policy = NamePolicy::new(["darn"]);
+        assert_eq!(policy.apply("Friendly", "Player"), "Friendly");
+        assert_eq!(policy.apply("darn", "Player"), "Player");
+        assert_eq!(policy.apply("xXDaRnXx", "Player"), "Player");
+        assert_eq!(policy.apply("d.a r_n", "Player"), "Player");
+        assert_eq!(policy.apply("d4rn", "Player"), "Player");
+    }
+
+    #[test]
+    fn humans_cannot_take_the_bot_prefix() {
+        let policy = NamePolicy::default();
+        assert_eq!(policy.apply("Bot-3", "Player"), "Player");
+        assert_eq!(policy.apply("  bot-helper", "Player"), "Player");
+        assert_eq!(policy.apply("Bot—3", "Player"), "Player");
+        assert_eq!(policy.apply("Bоt-3", "Player"), "Player");
+        assert_eq!(policy.apply("Botany", "Player"), "Botany");
+        assert_eq!(policy.apply("Robot-1", "Player"), "Robot-1");
+    }
+
+    #[test]
+    fn lookalike_characters_do_not_bypass_the_blocklist() {
+        let policy = NamePolicy::new(["darn"]);
+        // Cyrillic а, fullwidth Ｒ, accented ñ and a zero-width space.
+        assert_eq!(policy.apply("dаrn", "Player"), "Player");
+        assert_eq!(policy.apply("daＲn", "Player"), "Player");
+        assert_eq!(policy.apply("darñ", "Player"), "Player");
+        assert_eq!(policy.apply("da\u{200b}rn", "Player"), "Player");
+    }
+
+    #[test]
+    fn policy_still_sanitizes_length_and_whitespace() {
+        let policy = NamePolicy::default();
+        assert_eq!(
+            policy.apply("  many   spaces  ", "Player"),
+            sanitize_player_name("  many   spaces  ", "Player")
+        );
+        assert_eq!(policy.apply("   ", "Player"), "Player");
+    }
+
+    #[test]
+    fn max_length_check_matches_sanitized_length() {
+        let at_limit = "a".repeat(MAX_PLAYER_NAME_LENGTH);
+        let below_limit = "a".repeat(MAX_PLAYER_NAME_LENGTH - 1);
+        let above_limit = "a".repeat(MAX_PLAYER_NAME_LENGTH + 1);
+        assert!(!exceeds_max_name_length(&at_limit));
+        assert!(!exceeds_max_name_length(&below_limit));
+        assert!(exceeds_max_name_length(&above_limit));
+        assert_eq!(sanitize_player_name(&at_limit, "Player"), at_limit);
+        assert_eq!(
+            sanitize_player_name(&above_limit, "Player").chars().count(),
+            MAX_PLAYER_NAME_LENGTH
+        );
+
+        // Collapsed whitespace and multi-byte characters count the way they are stored.
+        let padded = format!("  {}   {}  ", "b".repeat(9), "é".repeat(10));
+        assert!(!exceeds_max_name_length(&padded));
+        assert!(exceeds_max_name_length(&format!(
+            "{} {}",
+            "b".repeat(10),
+            "é".repeat(10)
+        )));
+    }
+
+    #[test]
+    fn chat_text_is_cleaned_bounded_and_checked_against_the_blocklist() {
+        assert_eq!(
+            sanitize_chat_text("  hi\u{7}  there\u{200b} ").as_deref(),
+            Some("hi there")
+        );
+        assert_eq!(sanitize_chat_text(" \n\t "), None);
+        let long = "x".repeat(MAX_CHAT_LENGTH * 2);
+        assert_eq!(
+            sanitize_chat_text(&long).map(|text| text.chars().count()),
+            Some(MAX_CHAT_LENGTH)
+        );
+
+        let long = "x".repeat(MAX_ANNOUNCEMENT_LENGTH * 2);
+        assert_eq!(
+            sanitize_announcement_text(&long).map(|text| text.chars().count()),
+            Some(MAX_ANNOUNCEMENT_LENGTH)
+        );
+
+        let policy = NamePolicy::new(["darn"]);
+        assert!(policy.allows_text("bot-3 is fast"));
+        assert!(!policy.allows_text("well D4RN it"));
+    }
+}
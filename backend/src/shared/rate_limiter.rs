@@ -0,0 +1,127 @@
+use dashmap::DashMap;
+use std::borrow::Borrow;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicI64, AtomicU32, Ordering};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy)]
+struct Window {
+    started_at_ms: i64,
+    count: u32,
+}
+
+/// Fixed-window limiter keyed by anything hashable (session id, client IP, ...). Each key may
+/// pass `capacity` checks per window; its window starts at the first check and resets once
+/// `window` has elapsed. Keys are independent, and only the touched key's shard is locked.
+#[derive(Debug)]
+pub struct RateLimiter<K: Hash + Eq> {
+    capacity: AtomicU32,
+    window_ms: AtomicI64,
+    windows: DashMap<K, Window>,
+}
+
+impl<K: Hash + Eq> RateLimiter<K> {
+    pub fn new(capacity: u32, window: Duration) -> Self {
+        let limiter = Self {
+            capacity: AtomicU32::new(0),
+            window_ms: AtomicI64::new(0),
+            windows: DashMap::new(),
+        };
+        limiter.configure(capacity, window);
+        limiter
+    }
+
+    /// Changes the limit for every key. Windows already running keep their start time and
+    /// count, so a lowered capacity applies immediately.
+    pub fn configure(&self, capacity: u32, window: Duration) {
+        let window_ms = i64::try_from(window.as_millis()).unwrap_or(i64::MAX).max(1);
+        self.capacity.store(capacity, Ordering::Relaxed);
+        self.window_ms.store(window_ms, Ordering::Relaxed);
+    }
+
+    /// Counts one event for `key` at `now_ms` and reports whether it is within the limit.
+    /// Rejected events don't count against later windows.
+    pub fn check<Q>(&self, key: &Q, now_ms: i64) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        let capacity = self.capacity.load(Ordering::Relaxed);
+        let window_ms = self.window_ms.load(Ordering::Relaxed);
+        let fresh = Window {
+            started_at_ms: now_ms,
+            count: 0,
+        };
+        let mut window = match self.windows.get_mut(key) {
+            Some(window) => window,
+            None => self.windows.entry(key.to_owned()).or_insert(fresh),
+        };
+        if now_ms.saturating_sub(window.started_at_ms) >= window_ms {
+            *window = fresh;
+        }
+        if window.count >= capacity {
+            return false;
+        }
+        window.count += 1;
+        true
+    }
+
+    /// Forgets `key`, e.g. when its session disconnects.
+    pub fn remove<Q>(&self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.windows.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_a_burst_up_to_capacity() {
+        let limiter = RateLimiter::<String>::new(3, Duration::from_secs(5));
+        for _ in 0..3 {
+            assert!(limiter.check("a", 1_000));
+        }
+        assert!(!limiter.check("a", 1_000));
+        assert!(!limiter.check("a", 5_999));
+    }
+
+    #[test]
+    fn refills_once_the_window_elapses() {
+        let limiter = RateLimiter::<String>::new(2, Duration::from_millis(500));
+        assert!(limiter.check("a", 0));
+        assert!(limiter.check("a", 100));
+        assert!(!limiter.check("a", 499));
+        assert!(limiter.check("a", 500));
+        assert!(limiter.check("a", 600));
+        assert!(!limiter.check("a", 700));
+    }
+
+    #[test]
+    fn keys_are_limited_independently() {
+        let limiter = RateLimiter::<String>::new(1, Duration::from_secs(1));
+        assert!(limiter.check("a", 0));
+        assert!(!limiter.check("a", 0));
+        assert!(limiter.check("b", 0));
+
+        limiter.remove("a");
+        assert!(limiter.check("a", 0));
+    }
+
+    #[test]
+    fn configure_changes_the_limit_for_existing_keys() {
+        let limiter = RateLimiter::<u32>::new(5, Duration::from_secs(1));
+        assert!(limiter.check(&7, 0));
+        assert!(limiter.check(&7, 0));
+        limiter.configure(2, Duration::from_secs(10));
+        assert!(!limiter.check(&7, 1_500));
+        assert!(limiter.check(&7, 10_000));
+
+        limiter.configure(0, Duration::ZERO);
+        assert!(!limiter.check(&7, 10_000));
+    }
+}
@@ -0,0 +1,64 @@
+pub const DEFAULT_ROOM_NAME: &str = "main";
+
+pub fn sanitize_room_name(value: &str) -> String {
+    let mut cleaned = String::with_capacity(value.len().min(64));
+    for ch in value.chars() {
+        if cleaned.len() >= 64 {
+            break;
+        }
+        if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' {
+            cleaned.push(ch);
+        }
+    }
+    cleaned
+}
+
+/// The room a client-supplied name refers to. Every entry point (WebSocket path, matchmake,
+/// debug routes) goes through this so the same input always lands in the same room.
+pub fn canonical_room_name(value: &str) -> String {
+    let cleaned = sanitize_room_name(value);
+    if cleaned.is_empty() {
+        DEFAULT_ROOM_NAME.to_string()
+    } else {
+        cleaned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{canonical_room_name, sanitize_room_name, DEFAULT_ROOM_NAME};
+
+    #[test]
+    fn sanitize_room_name_preserves_generated_room_ids() {
+        let room_id = "room-e0d805ef307540a0b0315c6a8f787d47";
+        assert_eq!(sanitize_room_name(room_id), room_id);
+    }
+
+    #[test]
+    fn sanitize_room_name_strips_invalid_chars_and_bounds_length() {
+        let source = "room-abc!@#$%^&*()_+[]{}<>?/|`~xyz123456789012345678901234567890";
+        let cleaned = sanitize_room_name(source);
+        assert!(cleaned
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_'));
+        assert!(cleaned.len() <= 64);
+    }
+
+    #[test]
+    fn canonical_room_name_is_stable_across_entry_points() {
+        let cases = [
+            ("Lobby Één", "Lobbyn"),
+            ("  spaced room  ", "spacedroom"),
+            ("x!@#y_z-1", "xy_z-1"),
+            ("🐍🐍", DEFAULT_ROOM_NAME),
+            ("   ", DEFAULT_ROOM_NAME),
+            ("main", DEFAULT_ROOM_NAME),
+        ];
+        for (raw, expected) in cases {
+            let canonical = canonical_room_name(raw);
+            assert_eq!(canonical, expected, "raw name {raw:?}");
+            // A name handed out by matchmake must open the same room when used as a WS path.
+            assert_eq!(canonical_room_name(&canonical), canonical);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use anyhow::Context;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// A room's signed statement of the score a player had when they died. The leaderboard
+/// accepts a submission only for the score a valid receipt carries.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoreReceiptClaims {
+    #[serde(rename = "playerId")]
+    pub player_id: String,
+    pub score: i64,
+    #[serde(rename = "iat")]
+    pub issued_at_ms: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreReceiptError {
+    /// Not a `payload.signature` pair of valid base64 JSON.
+    Malformed,
+    /// The signature does not match the payload, e.g. because the score was edited.
+    BadSignature,
+    /// Issued longer ago than the verifier accepts, or in the future.
+    Expired,
+}
+
+pub fn sign_score_receipt(claims: &ScoreReceiptClaims, secret: &str) -> anyhow::Result<String> {
+    let payload = serde_json::to_vec(claims).context("failed to serialize score receipt")?;
+    let payload_b64 = URL_SAFE_NO_PAD.encode(payload);
+
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .context("failed to initialize score receipt signer")?;
+    mac.update(payload_b64.as_bytes());
+    let signature_b64 = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+
+    Ok(format!("{payload_b64}.{signature_b64}"))
+}
+
+/// Checks the signature before decoding the claims, then rejects receipts issued more than
+/// `max_age_ms` before `now_ms`. A small allowance covers clock skew between servers.
+pub fn verify_score_receipt(
+    receipt: &str,
+    secret: &str,
+    now_ms: i64,
+    max_age_ms: i64,
+) -> Result<ScoreReceiptClaims, ScoreReceiptError> {
+    const CLOCK_SKEW_MS: i64 = 5_000;
+
+    let (payload_b64, signature_b64) = receipt
+        .split_once('.')
+        .ok_or(ScoreReceiptError::Malformed)?;
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature_b64)
+        .map_err(|_| ScoreReceiptError::Malformed)?;
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .map_err(|_| ScoreReceiptError::BadSignature)?;
+    mac.update(payload_b64.as_bytes());
+    mac.verify_slice(&signature)
+        .map_err(|_| ScoreReceiptError::BadSignature)?;
+
+    let payload = URL_SAFE_NO_PAD
+        .decode(payload_b64)
+        .map_err(|_| ScoreReceiptError::Malformed)?;
+    let claims: ScoreReceiptClaims =
+        serde_json::from_slice(&payload).map_err(|_| ScoreReceiptError::Malformed)?;
+    let age_ms = now_ms.saturating_sub(claims.issued_at_ms);
+    if age_ms > max_age_ms || age_ms < -CLOCK_SKEW_MS {
+        return Err(ScoreReceiptError::Expired);
+    }
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims() -> ScoreReceiptClaims {
+        ScoreReceiptClaims {
+            player_id: "player-1".to_string(),
+            score: 420,
+            issued_at_ms: 1_000_000,
+        }
+    }
+
+    #[test]
+    fn valid_receipt_round_trips() {
+        let receipt = sign_score_receipt(&claims(), "secret").expect("signed");
+        assert_eq!(
+            verify_score_receipt(&receipt, "secret", 1_060_000, 600_000),
+            Ok(claims())
+        );
+        assert_eq!(
+            verify_score_receipt(&receipt, "other", 1_060_000, 600_000),
+            Err(ScoreReceiptError::BadSignature)
+        );
+        assert_eq!(
+            verify_score_receipt("not-a-receipt", "secret", 1_060_000, 600_000),
+            Err(ScoreReceiptError::Malformed)
+        );
+    }
+
+    #[test]
+    fn tampered_score_fails_the_signature() {
+        let receipt = sign_score_receipt(&claims(), "secret").expect("signed");
+        let (_, signature) = receipt.split_once('.').unwrap();
+        let inflated = ScoreReceiptClaims {
+            score: 999_999,
+            ..claims()
+        };
+        let forged_payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&inflated).unwrap());
+        assert_eq!(
+            verify_score_receipt(
+                &format!("{forged_payload}.{signature}"),
+                "secret",
+                1_060_000,
+                600_000
+            ),
+            Err(ScoreReceiptError::BadSignature)
+        );
+    }
+
+    #[test]
+    fn expired_receipt_is_rejected() {
+        let receipt = sign_score_receipt(&claims(), "secret").expect("signed");
+        assert!(verify_score_receipt(&receipt, "secret", 1_600_000, 600_000).is_ok());
+        assert_eq!(
+            verify_score_receipt(&receipt, "secret", 1_600_001, 600_000),
+            Err(ScoreReceiptError::Expired)
+        );
+        assert_eq!(
+            verify_score_receipt(&receipt, "secret", 900_000, 600_000),
+            Err(ScoreReceiptError::Expired)
+        );
+    }
+}
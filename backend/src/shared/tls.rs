@@ -0,0 +1,101 @@
+use anyhow::bail;
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use axum_server::Handle;
+use std::env;
+use std::future::Future;
+use std::path::PathBuf;
+use tokio::net::TcpListener;
+
+/// PEM certificate chain and private key for serving HTTPS/WSS without a TLS-terminating proxy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+impl TlsConfig {
+    /// Reads `TLS_CERT_PATH` and `TLS_KEY_PATH`. Neither set means plain HTTP; setting only one
+    /// is a startup error rather than a silent fallback.
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        let path = |name: &str| {
+            env::var(name)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        Self::from_paths(path("TLS_CERT_PATH"), path("TLS_KEY_PATH"))
+    }
+
+    fn from_paths(
+        cert_path: Option<String>,
+        key_path: Option<String>,
+    ) -> anyhow::Result<Option<Self>> {
+        match (cert_path, key_path) {
+            (Some(cert_path), Some(key_path)) => Ok(Some(Self {
+                cert_path: PathBuf::from(cert_path),
+                key_path: PathBuf::from(key_path),
+            })),
+            (None, None) => Ok(None),
+            _ => bail!("TLS_CERT_PATH and TLS_KEY_PATH must both be set or both be unset"),
+        }
+    }
+}
+
+/// Serves `app` on `listener` until `shutdown` resolves, letting in-flight requests finish.
+/// With `tls` set the listener speaks HTTPS/WSS using the configured certificate.
+pub async fn serve<F>(
+    listener: TcpListener,
+    app: Router,
+    tls: Option<&TlsConfig>,
+    shutdown: F,
+) -> anyhow::Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let Some(tls) = tls else {
+        axum::serve(listener, app)
+            .with_graceful_shutdown(shutdown)
+            .await?;
+        return Ok(());
+    };
+
+    // reqwest already links rustls with ring; make it the provider rather than leaving rustls to
+    // guess. Installing twice is harmless.
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let config = RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path).await?;
+    let handle = Handle::new();
+    let shutdown_handle = handle.clone();
+    tokio::spawn(async move {
+        shutdown.await;
+        shutdown_handle.graceful_shutdown(None);
+    });
+    axum_server::from_tcp_rustls(listener.into_std()?, config)
+        .handle(handle)
+        .serve(app.into_make_service())
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TlsConfig;
+    use std::path::PathBuf;
+
+    #[test]
+    fn tls_requires_cert_and_key_together() {
+        assert_eq!(TlsConfig::from_paths(None, None).unwrap(), None);
+        assert_eq!(
+            TlsConfig::from_paths(Some("cert.pem".to_string()), Some("key.pem".to_string()))
+                .unwrap(),
+            Some(TlsConfig {
+                cert_path: PathBuf::from("cert.pem"),
+                key_path: PathBuf::from("key.pem"),
+            })
+        );
+
+        let error = TlsConfig::from_paths(Some("cert.pem".to_string()), None).unwrap_err();
+        assert!(error.to_string().contains("TLS_CERT_PATH and TLS_KEY_PATH"));
+        assert!(TlsConfig::from_paths(None, Some("key.pem".to_string())).is_err());
+    }
+}
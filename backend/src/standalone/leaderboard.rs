@@ -0,0 +1,277 @@
+use futures_util::future::BoxFuture;
+use serde::Serialize;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::{PgPool, SqlitePool};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, sqlx::FromRow)]
+pub(super) struct LeaderboardEntry {
+    pub name: String,
+    pub score: i64,
+    pub created_at: i64,
+}
+
+/// Entries strictly above a score (best first) and entries at or below it (best first).
+pub(super) type Neighbors = (Vec<LeaderboardEntry>, Vec<LeaderboardEntry>);
+
+/// How far back `LeaderboardStore::top` looks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum LeaderboardWindow {
+    AllTime,
+    /// Only entries with `created_at` at or after this unix-ms cutoff.
+    Since(i64),
+}
+
+impl LeaderboardWindow {
+    /// Inclusive `created_at` lower bound; all-time binds the smallest timestamp.
+    pub(super) fn since_ms(self) -> i64 {
+        match self {
+            Self::AllTime => i64::MIN,
+            Self::Since(cutoff) => cutoff,
+        }
+    }
+}
+
+/// Persisted scores behind the leaderboard routes, so handlers don't depend on a database.
+///
+/// Ordering is score descending, then oldest first among ties. Methods return boxed futures
+/// so the store can sit behind `Arc<dyn LeaderboardStore>`.
+pub(super) trait LeaderboardStore: Send + Sync {
+    /// The best `limit` entries submitted within `window`.
+    fn top(
+        &self,
+        limit: i64,
+        window: LeaderboardWindow,
+    ) -> BoxFuture<'_, Result<Vec<LeaderboardEntry>, sqlx::Error>>;
+
+    /// Up to `window` entries strictly above `score` (best first) and up to `window` at or
+    /// below it.
+    fn around(&self, score: i64, window: i64) -> BoxFuture<'_, Result<Neighbors, sqlx::Error>>;
+
+    /// The 1-based rank `score` would hold; ties sort after existing entries.
+    fn rank(&self, score: i64) -> BoxFuture<'_, Result<i64, sqlx::Error>>;
+
+    fn submit(&self, entry: LeaderboardEntry) -> BoxFuture<'_, Result<(), sqlx::Error>>;
+
+    /// Readiness probe: the backing database answers queries.
+    fn ping(&self) -> BoxFuture<'_, Result<(), sqlx::Error>>;
+
+    /// Releases connections; later calls fail.
+    fn close(&self) -> BoxFuture<'_, ()>;
+}
+
+/// Opens the store named by `database_url` and runs its migrations. `sqlite:` URLs use
+/// SQLite and `postgres://` / `postgresql://` URLs use Postgres.
+pub(super) async fn connect_leaderboard_store(
+    database_url: &str,
+) -> anyhow::Result<Arc<dyn LeaderboardStore>> {
+    if database_url.starts_with("sqlite:") {
+        ensure_db_dir(database_url)?;
+        let db = SqlitePoolOptions::new()
+            .max_connections(5)
+            .connect(database_url)
+            .await?;
+        sqlx::migrate!("./migrations").run(&db).await?;
+        return Ok(Arc::new(SqliteLeaderboardStore::new(db)));
+    }
+    if database_url.starts_with("postgres://") || database_url.starts_with("postgresql://") {
+        let db = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url)
+            .await?;
+        sqlx::migrate!("./migrations_postgres").run(&db).await?;
+        return Ok(Arc::new(PostgresLeaderboardStore::new(db)));
+    }
+    let scheme = database_url.split(':').next().unwrap_or_default();
+    anyhow::bail!("unsupported DATABASE_URL scheme `{scheme}`; expected sqlite or postgres")
+}
+
+fn ensure_db_dir(database_url: &str) -> anyhow::Result<()> {
+    if database_url.starts_with("sqlite::memory:") {
+        return Ok(());
+    }
+    let path = database_url
+        .strip_prefix("sqlite://")
+        .or_else(|| database_url.strip_prefix("sqlite:"));
+    let Some(path) = path else { return Ok(()) };
+    if path.is_empty() || path == ":memory:" {
+        return Ok(());
+    }
+    let db_path = PathBuf::from(path);
+    if let Some(parent) = db_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if !db_path.exists() {
+        let _ = std::fs::File::create(&db_path)?;
+    }
+    Ok(())
+}
+
+pub(super) struct SqliteLeaderboardStore {
+    db: SqlitePool,
+}
+
+impl SqliteLeaderboardStore {
+    pub(super) fn new(db: SqlitePool) -> Self {
+        Self { db }
+    }
+}
+
+impl LeaderboardStore for SqliteLeaderboardStore {
+    fn top(
+        &self,
+        limit: i64,
+        window: LeaderboardWindow,
+    ) -> BoxFuture<'_, Result<Vec<LeaderboardEntry>, sqlx::Error>> {
+        Box::pin(async move {
+            sqlx::query_as(
+                "SELECT name, score, created_at FROM scores WHERE created_at >= ? \
+                 ORDER BY score DESC, created_at ASC LIMIT ?",
+            )
+            .bind(window.since_ms())
+            .bind(limit)
+            .fetch_all(&self.db)
+            .await
+        })
+    }
+
+    fn around(&self, score: i64, window: i64) -> BoxFuture<'_, Result<Neighbors, sqlx::Error>> {
+        Box::pin(async move {
+            // Keyset queries on the score index, so a deep rank never scans the whole table.
+            let mut above: Vec<LeaderboardEntry> = sqlx::query_as(
+                "SELECT name, score, created_at FROM scores WHERE score > ? \
+                 ORDER BY score ASC, created_at DESC LIMIT ?",
+            )
+            .bind(score)
+            .bind(window)
+            .fetch_all(&self.db)
+            .await?;
+            above.reverse();
+            let below = sqlx::query_as(
+                "SELECT name, score, created_at FROM scores WHERE score <= ? \
+                 ORDER BY score DESC, created_at ASC LIMIT ?",
+            )
+            .bind(score)
+            .bind(window)
+            .fetch_all(&self.db)
+            .await?;
+            Ok((above, below))
+        })
+    }
+
+    fn rank(&self, score: i64) -> BoxFuture<'_, Result<i64, sqlx::Error>> {
+        Box::pin(async move {
+            let ahead: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM scores WHERE score > ?")
+                .bind(score)
+                .fetch_one(&self.db)
+                .await?;
+            Ok(ahead + 1)
+        })
+    }
+
+    fn submit(&self, entry: LeaderboardEntry) -> BoxFuture<'_, Result<(), sqlx::Error>> {
+        Box::pin(async move {
+            sqlx::query("INSERT INTO scores (id, name, score, created_at) VALUES (?, ?, ?, ?)")
+                .bind(uuid::Uuid::new_v4().to_string())
+                .bind(entry.name)
+                .bind(entry.score)
+                .bind(entry.created_at)
+                .execute(&self.db)
+                .await
+                .map(|_| ())
+        })
+    }
+
+    fn ping(&self) -> BoxFuture<'_, Result<(), sqlx::Error>> {
+        Box::pin(async move { sqlx::query("SELECT 1").execute(&self.db).await.map(|_| ()) })
+    }
+
+    fn close(&self) -> BoxFuture<'_, ()> {
+        Box::pin(self.db.close())
+    }
+}
+
+pub(super) struct PostgresLeaderboardStore {
+    db: PgPool,
+}
+
+impl PostgresLeaderboardStore {
+    pub(super) fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+impl LeaderboardStore for PostgresLeaderboardStore {
+    fn top(
+        &self,
+        limit: i64,
+        window: LeaderboardWindow,
+    ) -> BoxFuture<'_, Result<Vec<LeaderboardEntry>, sqlx::Error>> {
+        Box::pin(async move {
+            sqlx::query_as(
+                "SELECT name, score, created_at FROM scores WHERE created_at >= $1 \
+                 ORDER BY score DESC, created_at ASC LIMIT $2",
+            )
+            .bind(window.since_ms())
+            .bind(limit)
+            .fetch_all(&self.db)
+            .await
+        })
+    }
+
+    fn around(&self, score: i64, window: i64) -> BoxFuture<'_, Result<Neighbors, sqlx::Error>> {
+        Box::pin(async move {
+            let mut above: Vec<LeaderboardEntry> = sqlx::query_as(
+                "SELECT name, score, created_at FROM scores WHERE score > $1 \
+                 ORDER BY score ASC, created_at DESC LIMIT $2",
+            )
+            .bind(score)
+            .bind(window)
+            .fetch_all(&self.db)
+            .await?;
+            above.reverse();
+            let below = sqlx::query_as(
+                "SELECT name, score, created_at FROM scores WHERE score <= $1 \
+                 ORDER BY score DESC, created_at ASC LIMIT $2",
+            )
+            .bind(score)
+            .bind(window)
+            .fetch_all(&self.db)
+            .await?;
+            Ok((above, below))
+        })
+    }
+
+    fn rank(&self, score: i64) -> BoxFuture<'_, Result<i64, sqlx::Error>> {
+        Box::pin(async move {
+            let ahead: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM scores WHERE score > $1")
+                .bind(score)
+                .fetch_one(&self.db)
+                .await?;
+            Ok(ahead + 1)
+        })
+    }
+
+    fn submit(&self, entry: LeaderboardEntry) -> BoxFuture<'_, Result<(), sqlx::Error>> {
+        Box::pin(async move {
+            sqlx::query("INSERT INTO scores (id, name, score, created_at) VALUES ($1, $2, $3, $4)")
+                .bind(uuid::Uuid::new_v4().to_string())
+                .bind(entry.name)
+                .bind(entry.score)
+                .bind(entry.created_at)
+                .execute(&self.db)
+                .await
+                .map(|_| ())
+        })
+    }
+
+    fn ping(&self) -> BoxFuture<'_, Result<(), sqlx::Error>> {
+        Box::pin(async move { sqlx::query("SELECT 1").execute(&self.db).await.map(|_| ()) })
+    }
+
+    fn close(&self) -> BoxFuture<'_, ()> {
+        Box::pin(self.db.close())
+    }
+}
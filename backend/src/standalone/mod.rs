@@ -1,35 +1,94 @@
-use crate::app::room_name::sanitize_room_name;
 use crate::app::time::now_millis;
-use crate::game::room::{DebugKillTarget, Room};
-use crate::shared::names::sanitize_player_name;
+use crate::game::room::{DebugKillTarget, Room, WireFormat};
+use crate::game::types::Point;
+use crate::shared::api_error::{ErrorCode, ErrorResponse};
+use crate::shared::cors::cors_layer_from_env;
+use crate::shared::names::{exceeds_max_name_length, name_policy, MAX_PLAYER_NAME_LENGTH};
+use crate::shared::room_name::{canonical_room_name, DEFAULT_ROOM_NAME};
 use crate::shared::room_token::{sign_room_token, RoomTokenClaims};
-use crate::transport::ws_session::handle_socket;
+use crate::shared::score_receipt::{verify_score_receipt, ScoreReceiptError};
+use crate::shared::shutdown::shutdown_signal;
+use crate::shared::tls::{serve, TlsConfig};
+use crate::transport::ws_session::{handle_socket, limit_inbound_size};
 use axum::{
     extract::{Path, Query, State, WebSocketUpgrade},
-    http::{Method, StatusCode},
+    http::StatusCode,
     response::IntoResponse,
     routing::{get, post},
     Json, Router,
 };
+use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
-use sqlx::{sqlite::SqlitePoolOptions, Row, SqlitePool};
 use std::collections::HashMap;
 use std::env;
+use std::future::Future;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tower_http::cors::{Any, CorsLayer};
+use std::time::Duration;
+
+mod leaderboard;
+
+use leaderboard::{
+    connect_leaderboard_store, LeaderboardEntry, LeaderboardStore, LeaderboardWindow,
+};
 
 const MAX_SCORE: i64 = 1_000_000;
 const DEFAULT_LIMIT: i64 = 10;
 const MAX_LIMIT: i64 = 50;
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+const DEFAULT_AROUND_WINDOW: i64 = 5;
+const MAX_AROUND_WINDOW: i64 = 25;
+const DEFAULT_MAX_ROOMS: usize = 64;
+const DEFAULT_SCORE_RECEIPT_MAX_AGE_SECS: i64 = 600;
+const ROOM_RECLAIM_INTERVAL: Duration = Duration::from_secs(30);
 
 #[derive(Clone)]
 struct AppState {
     rooms: DashMap<String, Arc<Room>>,
-    db: SqlitePool,
+    /// New room names are refused once this many rooms exist and none can be reclaimed.
+    max_rooms: usize,
+    /// `None` when `LEADERBOARD_ENABLED` is off; leaderboard routes then answer 503.
+    leaderboard: Option<Arc<dyn LeaderboardStore>>,
     debug_commands: bool,
     standalone_matchmake: StandaloneMatchmakeConfig,
+    /// Set from `SCORE_RECEIPT_SECRET`: rooms sign scores at death and leaderboard
+    /// submissions must carry a matching receipt.
+    score_receipts: Option<ScoreReceiptConfig>,
+}
+
+#[derive(Clone)]
+struct ScoreReceiptConfig {
+    secret: String,
+    max_age_ms: i64,
+    /// Signatures of accepted receipts, mapped to when they expire. A receipt counts for one
+    /// submission; entries are dropped once the receipt would be rejected as expired anyway.
+    spent: Arc<DashMap<String, i64>>,
+}
+
+impl ScoreReceiptConfig {
+    fn new(secret: String, max_age_ms: i64) -> Self {
+        Self {
+            secret,
+            max_age_ms,
+            spent: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Records `receipt` as used until `expires_at_ms`; false if it was already used.
+    fn spend(&self, receipt: &str, expires_at_ms: i64, now_ms: i64) -> bool {
+        self.spent.retain(|_, expires_at| *expires_at >= now_ms);
+        let signature = receipt
+            .rsplit_once('.')
+            .map_or(receipt, |(_, signature)| signature);
+        match self.spent.entry(signature.to_string()) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(expires_at_ms);
+                true
+            }
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -41,21 +100,25 @@ struct StandaloneMatchmakeConfig {
 }
 
 #[derive(Debug, Serialize)]
-struct LeaderboardEntry {
-    name: String,
-    score: i64,
-    created_at: i64,
+struct LeaderboardResponse {
+    scores: Vec<LeaderboardEntry>,
 }
 
+/// Neighbors of a score. `rank` is where the score would place (1 = top). `above` is ordered
+/// best first and ends at rank `rank - 1`; `below` starts at rank `rank` (ties with the score
+/// sort after it).
 #[derive(Debug, Serialize)]
-struct LeaderboardResponse {
-    scores: Vec<LeaderboardEntry>,
+struct LeaderboardAroundResponse {
+    rank: i64,
+    above: Vec<LeaderboardEntry>,
+    below: Vec<LeaderboardEntry>,
 }
 
 #[derive(Debug, Deserialize)]
 struct LeaderboardSubmission {
     name: Option<String>,
     score: Option<f64>,
+    receipt: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -64,13 +127,7 @@ struct OkResponse {
 }
 
 #[derive(Debug, Serialize)]
-struct ErrorResponse {
-    ok: bool,
-    error: String,
-}
-
-#[derive(Debug, Serialize)]
-struct DebugKillResponse {
+struct DebugPlayerResponse {
     ok: bool,
     #[serde(rename = "playerId")]
     player_id: Option<String>,
@@ -82,6 +139,13 @@ struct DebugKillQuery {
     target: Option<String>,
 }
 
+/// Exactly one of `length` (resize in place) or `head` (move, keeping length) is expected.
+#[derive(Debug, Deserialize)]
+struct DebugPlayerRequest {
+    length: Option<usize>,
+    head: Option<Point>,
+}
+
 #[derive(Debug, Deserialize, Default)]
 struct MatchmakeRequest {
     #[serde(rename = "preferredRoom")]
@@ -104,19 +168,23 @@ pub async fn run_standalone() -> anyhow::Result<()> {
         .ok()
         .and_then(|value| value.parse().ok())
         .unwrap_or(8787);
+    let tls = TlsConfig::from_env()?;
 
-    let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
-        let base = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-        let default_path = base.join("data").join("leaderboard.db");
-        format!("sqlite://{}", default_path.display())
-    });
-    ensure_db_dir(&database_url)?;
-
-    let db = SqlitePoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
-        .await?;
-    sqlx::migrate!("./migrations").run(&db).await?;
+    // With the leaderboard on, a database that won't connect or migrate aborts startup.
+    let leaderboard_enabled = env::var("LEADERBOARD_ENABLED")
+        .map(|value| !matches!(value.trim(), "0" | "false" | "FALSE"))
+        .unwrap_or(true);
+    let leaderboard = if leaderboard_enabled {
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            let base = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+            let default_path = base.join("data").join("leaderboard.db");
+            format!("sqlite://{}", default_path.display())
+        });
+        Some(connect_leaderboard_store(&database_url).await?)
+    } else {
+        tracing::info!("leaderboard disabled; skipping database");
+        None
+    };
 
     let debug_commands = env::var("ENABLE_DEBUG_COMMANDS")
         .map(|value| matches!(value.as_str(), "1" | "true" | "TRUE"))
@@ -140,10 +208,30 @@ pub async fn run_standalone() -> anyhow::Result<()> {
         .map(|value| value.trim().to_string())
         .filter(|value| !value.is_empty())
         .unwrap_or_else(|| "dev-room-token-secret".to_string());
+    let max_rooms = env::var("STANDALONE_MAX_ROOMS")
+        .ok()
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(DEFAULT_MAX_ROOMS);
+
+    let score_receipts = env::var("SCORE_RECEIPT_SECRET")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .map(|secret| {
+            let max_age_ms = env::var("SCORE_RECEIPT_MAX_AGE_SECS")
+                .ok()
+                .and_then(|value| value.trim().parse::<i64>().ok())
+                .filter(|value| *value > 0)
+                .unwrap_or(DEFAULT_SCORE_RECEIPT_MAX_AGE_SECS)
+                .saturating_mul(1000);
+            ScoreReceiptConfig::new(secret, max_age_ms)
+        });
 
     let state = Arc::new(AppState {
         rooms: DashMap::new(),
-        db,
+        max_rooms,
+        leaderboard: leaderboard.clone(),
         debug_commands,
         standalone_matchmake: StandaloneMatchmakeConfig {
             capacity: matchmake_capacity,
@@ -151,84 +239,173 @@ pub async fn run_standalone() -> anyhow::Result<()> {
             room_origin,
             room_token_secret,
         },
+        score_receipts,
     });
 
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods([Method::GET, Method::POST])
-        .allow_headers(Any);
+    let reclaim_state = Arc::clone(&state);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ROOM_RECLAIM_INTERVAL);
+        loop {
+            interval.tick().await;
+            reclaim_state.reclaim_idle_rooms();
+        }
+    });
 
-    let mut app: Router<Arc<AppState>> = Router::new()
-        .route("/api/health", get(health))
-        .route("/api/matchmake", post(matchmake_standalone))
-        .route(
-            "/api/leaderboard",
-            get(leaderboard_get).post(leaderboard_post),
-        )
-        .route("/api/room/:room", get(ws_handler))
-        .layer(cors);
+    let cors = cors_layer_from_env()?;
+
+    let mut app = public_routes().layer(cors);
 
     if debug_commands {
-        app = app.route("/api/debug/kill", post(debug_kill));
+        app = app
+            .route("/api/debug/kill", post(debug_kill))
+            .route("/api/debug/player", post(debug_player));
     }
 
     let app: Router = app.with_state(state);
 
     let address = format!("0.0.0.0:{port}");
-    tracing::info!("listening on {address}");
+    tracing::info!(tls = tls.is_some(), "listening on {address}");
 
     let listener = tokio::net::TcpListener::bind(&address).await?;
-    axum::serve(listener, app).await?;
+    serve_until(listener, app, tls, leaderboard, shutdown_signal()).await
+}
+
+/// Routes mounted whether or not debug commands are on.
+fn public_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/health", get(health))
+        .route("/api/ready", get(ready))
+        .route("/api/matchmake", post(matchmake_standalone))
+        .route(
+            "/api/leaderboard",
+            get(leaderboard_get).post(leaderboard_post),
+        )
+        .route("/api/leaderboard/around", get(leaderboard_around))
+        .route("/api/room/:room", get(ws_handler))
+}
 
+/// Serves until `shutdown` resolves, lets in-flight requests (such as leaderboard writes)
+/// finish, then closes the leaderboard store, if any.
+async fn serve_until<F>(
+    listener: tokio::net::TcpListener,
+    app: Router,
+    tls: Option<TlsConfig>,
+    leaderboard: Option<Arc<dyn LeaderboardStore>>,
+    shutdown: F,
+) -> anyhow::Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let served = serve(listener, app, tls.as_ref(), shutdown).await;
+    if let Some(leaderboard) = leaderboard {
+        leaderboard.close().await;
+        tracing::info!("server stopped; leaderboard store closed");
+    } else {
+        tracing::info!("server stopped");
+    }
+    served?;
     Ok(())
 }
 
 impl AppState {
-    fn room(&self, name: String) -> Arc<Room> {
-        match self.rooms.entry(name) {
+    /// Returns the named room, creating it if needed, or `None` when it doesn't exist yet and
+    /// the room cap is still reached after reclaiming idle rooms. The cap is checked before
+    /// creating, so simultaneous first requests for different names can overshoot it slightly.
+    fn room(&self, name: String) -> Option<Arc<Room>> {
+        if let Some(room) = self.rooms.get(&name) {
+            return Some(room.clone());
+        }
+        if self.rooms.len() >= self.max_rooms {
+            self.reclaim_idle_rooms();
+            if self.rooms.len() >= self.max_rooms {
+                tracing::warn!(
+                    room = name,
+                    max_rooms = self.max_rooms,
+                    "room creation refused at room cap"
+                );
+                return None;
+            }
+        }
+        let room = match self.rooms.entry(name) {
             dashmap::mapref::entry::Entry::Occupied(entry) => entry.get().clone(),
             dashmap::mapref::entry::Entry::Vacant(entry) => {
-                let room = if entry.key() == "main" {
-                    Arc::new(Room::new())
+                let mut room = if entry.key() == DEFAULT_ROOM_NAME {
+                    Room::new()
                 } else {
-                    Arc::new(Room::with_room_id(entry.key().clone()))
+                    Room::with_room_id(entry.key().clone())
                 };
+                if let Some(config) = &self.score_receipts {
+                    room = room.with_score_receipt_secret(config.secret.clone());
+                }
+                let room = Arc::new(room);
                 entry.insert(room.clone());
                 room
             }
+        };
+        Some(room)
+    }
+
+    /// Drops rooms nothing else references: no socket holds them and their tick loop has
+    /// exited, which it does once the last session leaves. Returns how many were removed.
+    fn reclaim_idle_rooms(&self) -> usize {
+        let before = self.rooms.len();
+        self.rooms.retain(|_, room| Arc::strong_count(room) > 1);
+        let reclaimed = before.saturating_sub(self.rooms.len());
+        if reclaimed > 0 {
+            tracing::info!(reclaimed, rooms = self.rooms.len(), "reclaimed idle rooms");
         }
+        reclaimed
     }
 }
 
-fn ensure_db_dir(database_url: &str) -> anyhow::Result<()> {
-    if database_url.starts_with("sqlite::memory:") {
-        return Ok(());
-    }
-    let path = database_url
-        .strip_prefix("sqlite://")
-        .or_else(|| database_url.strip_prefix("sqlite:"));
-    let Some(path) = path else { return Ok(()) };
-    if path.is_empty() || path == ":memory:" {
-        return Ok(());
-    }
-    let db_path = if path.starts_with('/') {
-        PathBuf::from(path)
-    } else {
-        PathBuf::from(path)
-    };
-    if let Some(parent) = db_path.parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-    if !db_path.exists() {
-        let _ = std::fs::File::create(&db_path)?;
-    }
-    Ok(())
+fn leaderboard_disabled_response() -> axum::response::Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(ErrorResponse::new(
+            ErrorCode::LeaderboardDisabled,
+            "Leaderboard is disabled",
+        )),
+    )
+        .into_response()
 }
 
+fn room_cap_response() -> axum::response::Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(ErrorResponse::new(
+            ErrorCode::TooManyRooms,
+            "Too many active rooms",
+        )),
+    )
+        .into_response()
+}
+
+/// Liveness: the process is up and serving requests.
 async fn health() -> impl IntoResponse {
     Json(OkResponse { ok: true })
 }
 
+/// Readiness: the leaderboard database answers queries (always ready when it is disabled).
+async fn ready(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let Some(leaderboard) = state.leaderboard.as_deref() else {
+        return Json(OkResponse { ok: true }).into_response();
+    };
+    match leaderboard.ping().await {
+        Ok(()) => Json(OkResponse { ok: true }).into_response(),
+        Err(error) => {
+            tracing::warn!(%error, "readiness check failed");
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(ErrorResponse::new(
+                    ErrorCode::DatabaseUnavailable,
+                    "Database unavailable",
+                )),
+            )
+                .into_response()
+        }
+    }
+}
+
 async fn matchmake_standalone(
     State(state): State<Arc<AppState>>,
     payload: Result<Json<MatchmakeRequest>, axum::extract::rejection::JsonRejection>,
@@ -238,10 +415,7 @@ async fn matchmake_standalone(
         Err(_) => {
             return (
                 StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    ok: false,
-                    error: "Invalid JSON".to_string(),
-                }),
+                Json(ErrorResponse::new(ErrorCode::InvalidJson, "Invalid JSON")),
             )
                 .into_response();
         }
@@ -250,9 +424,8 @@ async fn matchmake_standalone(
     let room_id = payload
         .preferred_room
         .as_deref()
-        .map(sanitize_room_name)
-        .filter(|value| !value.is_empty())
-        .unwrap_or_else(|| "main".to_string());
+        .map(canonical_room_name)
+        .unwrap_or_else(|| DEFAULT_ROOM_NAME.to_string());
 
     let expires_at = now_millis() + state.standalone_matchmake.token_ttl_secs * 1000;
     let claims = RoomTokenClaims {
@@ -266,10 +439,10 @@ async fn matchmake_standalone(
             tracing::error!(?error, "standalone matchmake token signing failed");
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    ok: false,
-                    error: "Failed to issue room token".to_string(),
-                }),
+                Json(ErrorResponse::new(
+                    ErrorCode::TokenIssueFailed,
+                    "Failed to issue room token",
+                )),
             )
                 .into_response();
         }
@@ -291,50 +464,102 @@ async fn leaderboard_get(
     State(state): State<Arc<AppState>>,
     Query(params): Query<HashMap<String, String>>,
 ) -> impl IntoResponse {
+    let Some(leaderboard) = state.leaderboard.as_deref() else {
+        return leaderboard_disabled_response();
+    };
     let limit = params
         .get("limit")
         .and_then(|value| value.parse::<i64>().ok())
         .unwrap_or(DEFAULT_LIMIT);
     let limit = limit.clamp(1, MAX_LIMIT);
+    let Some(window) = parse_leaderboard_window(params.get("window"), now_millis()) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                ErrorCode::InvalidQuery,
+                "window must be day, week or all",
+            )),
+        )
+            .into_response();
+    };
 
-    let rows = sqlx::query(
-        "SELECT name, score, created_at FROM scores ORDER BY score DESC, created_at ASC LIMIT ?",
-    )
-    .bind(limit)
-    .fetch_all(&state.db)
-    .await;
-
-    let rows = match rows {
-        Ok(rows) => rows,
+    let scores = match leaderboard.top(limit, window).await {
+        Ok(scores) => scores,
         Err(_) => {
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    ok: false,
-                    error: "Failed to load leaderboard".to_string(),
-                }),
+                Json(ErrorResponse::new(
+                    ErrorCode::LeaderboardUnavailable,
+                    "Failed to load leaderboard",
+                )),
             )
                 .into_response();
         }
     };
 
-    let scores = rows
-        .into_iter()
-        .filter_map(|row| {
-            let name: String = row.try_get("name").ok()?;
-            let score: i64 = row.try_get("score").ok()?;
-            let created_at: i64 = row.try_get("created_at").ok()?;
-            Some(LeaderboardEntry {
-                name,
-                score,
-                created_at,
-            })
-        })
-        .collect::<Vec<_>>();
-
     (StatusCode::OK, Json(LeaderboardResponse { scores })).into_response()
 }
 
+/// Maps the `window` query value to a cutoff relative to `now_ms`; missing means all time.
+fn parse_leaderboard_window(value: Option<&String>, now_ms: i64) -> Option<LeaderboardWindow> {
+    match value.map(|value| value.trim()) {
+        None | Some("all") => Some(LeaderboardWindow::AllTime),
+        Some("day") => Some(LeaderboardWindow::Since(now_ms - DAY_MS)),
+        Some("week") => Some(LeaderboardWindow::Since(now_ms - 7 * DAY_MS)),
+        Some(_) => None,
+    }
+}
+
+/// Returns up to `window` entries on each side of `score` plus the rank it would hold.
+async fn leaderboard_around(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    let Some(leaderboard) = state.leaderboard.as_deref() else {
+        return leaderboard_disabled_response();
+    };
+    let Some(score) = params
+        .get("score")
+        .and_then(|value| value.trim().parse::<i64>().ok())
+    else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                ErrorCode::InvalidQuery,
+                "score must be an integer",
+            )),
+        )
+            .into_response();
+    };
+    let window = params
+        .get("window")
+        .and_then(|value| value.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_AROUND_WINDOW)
+        .clamp(1, MAX_AROUND_WINDOW);
+
+    match load_leaderboard_around(leaderboard, score, window).await {
+        Ok(response) => (StatusCode::OK, Json(response)).into_response(),
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(
+                ErrorCode::LeaderboardUnavailable,
+                "Failed to load leaderboard",
+            )),
+        )
+            .into_response(),
+    }
+}
+
+async fn load_leaderboard_around(
+    leaderboard: &dyn LeaderboardStore,
+    score: i64,
+    window: i64,
+) -> Result<LeaderboardAroundResponse, sqlx::Error> {
+    let rank = leaderboard.rank(score).await?;
+    let (above, below) = leaderboard.around(score, window).await?;
+    Ok(LeaderboardAroundResponse { rank, above, below })
+}
+
 async fn debug_kill(
     State(state): State<Arc<AppState>>,
     Query(params): Query<DebugKillQuery>,
@@ -342,103 +567,192 @@ async fn debug_kill(
     if !state.debug_commands {
         return (
             StatusCode::FORBIDDEN,
-            Json(ErrorResponse {
-                ok: false,
-                error: "Debug commands disabled".to_string(),
-            }),
+            Json(ErrorResponse::new(
+                ErrorCode::DebugDisabled,
+                "Debug commands disabled",
+            )),
         )
             .into_response();
     }
 
-    let room_name = params.room.unwrap_or_else(|| "main".to_string());
-    let target = match params.target.as_deref() {
-        Some("bot") => DebugKillTarget::Bot,
-        Some("human") => DebugKillTarget::Human,
-        _ => DebugKillTarget::Any,
+    let room_name = params
+        .room
+        .as_deref()
+        .map(canonical_room_name)
+        .unwrap_or_else(|| DEFAULT_ROOM_NAME.to_string());
+    let target = parse_debug_target(params.target.as_deref());
+    let Some(room) = state.room(room_name) else {
+        return room_cap_response();
     };
-    let room = state.room(room_name);
 
     match room.debug_kill(target).await {
-        Some(player_id) => Json(DebugKillResponse {
+        Some(player_id) => Json(DebugPlayerResponse {
             ok: true,
             player_id: Some(player_id),
         })
         .into_response(),
         None => (
             StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                ok: false,
-                error: "No matching player to kill".to_string(),
-            }),
+            Json(ErrorResponse::new(
+                ErrorCode::PlayerNotFound,
+                "No matching player to kill",
+            )),
         )
             .into_response(),
     }
 }
 
+async fn debug_player(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DebugKillQuery>,
+    payload: Result<Json<DebugPlayerRequest>, axum::extract::rejection::JsonRejection>,
+) -> impl IntoResponse {
+    if !state.debug_commands {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::new(
+                ErrorCode::DebugDisabled,
+                "Debug commands disabled",
+            )),
+        )
+            .into_response();
+    }
+    let payload = match payload {
+        Ok(Json(payload)) => payload,
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new(ErrorCode::InvalidJson, "Invalid JSON")),
+            )
+                .into_response();
+        }
+    };
+
+    let room_name = params
+        .room
+        .as_deref()
+        .map(canonical_room_name)
+        .unwrap_or_else(|| DEFAULT_ROOM_NAME.to_string());
+    let target = parse_debug_target(params.target.as_deref());
+    let Some(room) = state.room(room_name) else {
+        return room_cap_response();
+    };
+
+    let player_id = match (payload.length, payload.head) {
+        (Some(length), None) => room.debug_set_length(target, length).await,
+        (None, Some(head)) => room.debug_teleport(target, head).await,
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new(
+                    ErrorCode::InvalidRequest,
+                    "Expected exactly one of length or head",
+                )),
+            )
+                .into_response();
+        }
+    };
+
+    match player_id {
+        Some(player_id) => Json(DebugPlayerResponse {
+            ok: true,
+            player_id: Some(player_id),
+        })
+        .into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(
+                ErrorCode::PlayerNotFound,
+                "No matching player",
+            )),
+        )
+            .into_response(),
+    }
+}
+
+fn parse_debug_target(target: Option<&str>) -> DebugKillTarget {
+    match target {
+        Some("bot") => DebugKillTarget::Bot,
+        Some("human") => DebugKillTarget::Human,
+        _ => DebugKillTarget::Any,
+    }
+}
+
 async fn leaderboard_post(
     State(state): State<Arc<AppState>>,
     payload: Result<Json<LeaderboardSubmission>, axum::extract::rejection::JsonRejection>,
 ) -> impl IntoResponse {
+    let Some(leaderboard) = state.leaderboard.as_deref() else {
+        return leaderboard_disabled_response();
+    };
     let Json(payload) = match payload {
         Ok(payload) => payload,
         Err(_) => {
             return (
                 StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    ok: false,
-                    error: "Invalid JSON".to_string(),
-                }),
+                Json(ErrorResponse::new(ErrorCode::InvalidJson, "Invalid JSON")),
             )
                 .into_response();
         }
     };
 
     let raw_name = payload.name.unwrap_or_else(|| "Player".to_string());
-    let name = sanitize_player_name(&raw_name, "Player");
+    if exceeds_max_name_length(&raw_name) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                ErrorCode::NameTooLong,
+                format!("Name must be at most {MAX_PLAYER_NAME_LENGTH} characters"),
+            )),
+        )
+            .into_response();
+    }
+    let name = name_policy().apply(&raw_name, "Player");
     let score_value = payload.score.unwrap_or(f64::NAN);
 
     if !score_value.is_finite() {
         return (
             StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                ok: false,
-                error: "Score must be a number".to_string(),
-            }),
+            Json(ErrorResponse::new(
+                ErrorCode::InvalidScore,
+                "Score must be a number",
+            )),
         )
             .into_response();
     }
 
     let score = score_value.floor() as i64;
-    if score < 0 || score > MAX_SCORE {
+    if !(0..=MAX_SCORE).contains(&score) {
         return (
             StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                ok: false,
-                error: "Score out of range".to_string(),
-            }),
+            Json(ErrorResponse::new(
+                ErrorCode::ScoreOutOfRange,
+                "Score out of range",
+            )),
         )
             .into_response();
     }
 
-    let id = uuid::Uuid::new_v4().to_string();
-    let created_at = now_millis();
+    if let Some(config) = &state.score_receipts {
+        if let Some(response) = score_receipt_rejection(config, payload.receipt.as_deref(), score) {
+            return response;
+        }
+    }
 
-    let result =
-        sqlx::query("INSERT INTO scores (id, name, score, created_at) VALUES (?, ?, ?, ?)")
-            .bind(id)
-            .bind(name)
-            .bind(score)
-            .bind(created_at)
-            .execute(&state.db)
-            .await;
+    let entry = LeaderboardEntry {
+        name,
+        score,
+        created_at: now_millis(),
+    };
+    let result = leaderboard.submit(entry).await;
 
     if result.is_err() {
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                ok: false,
-                error: "Submission failed".to_string(),
-            }),
+            Json(ErrorResponse::new(
+                ErrorCode::SubmissionFailed,
+                "Submission failed",
+            )),
         )
             .into_response();
     }
@@ -446,13 +760,727 @@ async fn leaderboard_post(
     (StatusCode::OK, Json(OkResponse { ok: true })).into_response()
 }
 
+/// The response refusing `score`, unless a room-issued receipt for exactly that score came
+/// with it and hasn't been used before.
+fn score_receipt_rejection(
+    config: &ScoreReceiptConfig,
+    receipt: Option<&str>,
+    score: i64,
+) -> Option<axum::response::Response> {
+    let now = now_millis();
+    let (code, message) = match receipt {
+        None => (ErrorCode::ScoreReceiptRequired, "Score receipt required"),
+        Some(receipt) => {
+            match verify_score_receipt(receipt, &config.secret, now, config.max_age_ms) {
+                Ok(claims) if claims.score == score => {
+                    let expires_at_ms = claims.issued_at_ms.saturating_add(config.max_age_ms);
+                    if config.spend(receipt, expires_at_ms, now) {
+                        return None;
+                    }
+                    (ErrorCode::ScoreReceiptUsed, "Score receipt already used")
+                }
+                Err(ScoreReceiptError::Expired) => {
+                    (ErrorCode::ScoreReceiptExpired, "Score receipt expired")
+                }
+                _ => (
+                    ErrorCode::InvalidScoreReceipt,
+                    "Score receipt does not match",
+                ),
+            }
+        }
+    };
+    Some(
+        (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::new(code, message)),
+        )
+            .into_response(),
+    )
+}
+
 async fn ws_handler(
     ws: WebSocketUpgrade,
     Path(room): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
     State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
-    let trimmed = room.trim();
-    let room_name = if trimmed.is_empty() { "main" } else { trimmed }.to_string();
-    let room = state.room(room_name);
-    ws.on_upgrade(move |socket| handle_socket(socket, room))
+    let Some(room) = state.room(canonical_room_name(&room)) else {
+        return room_cap_response();
+    };
+    if !room.has_session_capacity().await {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::new(
+                ErrorCode::RoomFull,
+                "Room is at session capacity",
+            )),
+        )
+            .into_response();
+    }
+    let wire_format = WireFormat::from_query(params.get("format").map(String::as_str));
+    limit_inbound_size(ws, &room)
+        .on_upgrade(move |socket| handle_socket(socket, room, wire_format))
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::leaderboard::{LeaderboardWindow, Neighbors, SqliteLeaderboardStore};
+    use super::{
+        leaderboard_around, leaderboard_get, leaderboard_post, now_millis, public_routes,
+        serve_until, AppState, LeaderboardEntry, LeaderboardStore, LeaderboardSubmission,
+        ScoreReceiptConfig, StandaloneMatchmakeConfig, DAY_MS,
+    };
+    use crate::shared::names::MAX_PLAYER_NAME_LENGTH;
+    use axum::{
+        extract::{Query, State},
+        http::StatusCode,
+        response::IntoResponse,
+        routing::get,
+        Json, Router,
+    };
+    use dashmap::DashMap;
+    use futures_util::future::BoxFuture;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /// In-memory stand-in for the database-backed stores.
+    #[derive(Default)]
+    struct FakeLeaderboardStore {
+        entries: Mutex<Vec<LeaderboardEntry>>,
+    }
+
+    impl FakeLeaderboardStore {
+        fn sorted(&self) -> Vec<LeaderboardEntry> {
+            let mut entries = self.entries.lock().unwrap().clone();
+            entries.sort_by(|a, b| b.score.cmp(&a.score).then(a.created_at.cmp(&b.created_at)));
+            entries
+        }
+    }
+
+    impl LeaderboardStore for FakeLeaderboardStore {
+        fn top(
+            &self,
+            limit: i64,
+            window: LeaderboardWindow,
+        ) -> BoxFuture<'_, Result<Vec<LeaderboardEntry>, sqlx::Error>> {
+            let mut entries = self.sorted();
+            entries.retain(|entry| entry.created_at >= window.since_ms());
+            entries.truncate(limit as usize);
+            Box::pin(async move { Ok(entries) })
+        }
+
+        fn around(&self, score: i64, window: i64) -> BoxFuture<'_, Result<Neighbors, sqlx::Error>> {
+            let (above, below): (Vec<_>, Vec<_>) = self
+                .sorted()
+                .into_iter()
+                .partition(|entry| entry.score > score);
+            let skip = above.len().saturating_sub(window as usize);
+            let above = above.into_iter().skip(skip).collect();
+            let below = below.into_iter().take(window as usize).collect();
+            Box::pin(async move { Ok((above, below)) })
+        }
+
+        fn rank(&self, score: i64) -> BoxFuture<'_, Result<i64, sqlx::Error>> {
+            let ahead = self
+                .entries
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|entry| entry.score > score)
+                .count();
+            Box::pin(async move { Ok(ahead as i64 + 1) })
+        }
+
+        fn submit(&self, entry: LeaderboardEntry) -> BoxFuture<'_, Result<(), sqlx::Error>> {
+            self.entries.lock().unwrap().push(entry);
+            Box::pin(async { Ok(()) })
+        }
+
+        fn ping(&self) -> BoxFuture<'_, Result<(), sqlx::Error>> {
+            Box::pin(async { Ok(()) })
+        }
+
+        fn close(&self) -> BoxFuture<'_, ()> {
+            Box::pin(async {})
+        }
+    }
+
+    async fn sqlite_store() -> SqliteLeaderboardStore {
+        let db = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite");
+        sqlx::migrate!("./migrations")
+            .run(&db)
+            .await
+            .expect("migrations");
+        SqliteLeaderboardStore::new(db)
+    }
+
+    async fn leaderboard_state() -> Arc<AppState> {
+        state_with_store(Arc::new(sqlite_store().await))
+    }
+
+    fn state_with_store(leaderboard: Arc<dyn LeaderboardStore>) -> Arc<AppState> {
+        state_with_leaderboard(Some(leaderboard))
+    }
+
+    fn state_with_leaderboard(leaderboard: Option<Arc<dyn LeaderboardStore>>) -> Arc<AppState> {
+        Arc::new(AppState {
+            rooms: DashMap::new(),
+            max_rooms: 2,
+            leaderboard,
+            debug_commands: false,
+            standalone_matchmake: StandaloneMatchmakeConfig {
+                capacity: 1,
+                token_ttl_secs: 60,
+                room_origin: "http://localhost".to_string(),
+                room_token_secret: "secret".to_string(),
+            },
+            score_receipts: None,
+        })
+    }
+
+    async fn submit_name(state: &Arc<AppState>, name: String) -> StatusCode {
+        let submission = LeaderboardSubmission {
+            name: Some(name),
+            score: Some(10.0),
+            receipt: None,
+        };
+        leaderboard_post(State(Arc::clone(state)), Ok(Json(submission)))
+            .await
+            .into_response()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn leaderboard_rejects_names_over_the_limit_instead_of_truncating() {
+        let state = leaderboard_state().await;
+        let below = "b".repeat(MAX_PLAYER_NAME_LENGTH - 1);
+        let at = "a".repeat(MAX_PLAYER_NAME_LENGTH);
+        let above = "c".repeat(MAX_PLAYER_NAME_LENGTH + 1);
+
+        assert_eq!(submit_name(&state, below.clone()).await, StatusCode::OK);
+        assert_eq!(submit_name(&state, at.clone()).await, StatusCode::OK);
+        assert_eq!(submit_name(&state, above).await, StatusCode::BAD_REQUEST);
+
+        let mut stored: Vec<String> = state
+            .leaderboard
+            .as_ref()
+            .expect("leaderboard enabled")
+            .top(10, LeaderboardWindow::AllTime)
+            .await
+            .expect("scores")
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+        stored.sort();
+        assert_eq!(stored, vec![at, below]);
+    }
+
+    async fn error_code(response: axum::response::Response) -> (StatusCode, String) {
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body");
+        let json: serde_json::Value = serde_json::from_slice(&body).expect("json body");
+        assert_eq!(json["ok"], false);
+        (status, json["code"].as_str().expect("code").to_string())
+    }
+
+    #[tokio::test]
+    async fn leaderboard_errors_carry_stable_codes() {
+        use axum::extract::FromRequest;
+
+        let state = leaderboard_state().await;
+        let submission = LeaderboardSubmission {
+            name: Some("Player".to_string()),
+            score: Some(5_000_000.0),
+            receipt: None,
+        };
+        let response = leaderboard_post(State(Arc::clone(&state)), Ok(Json(submission)))
+            .await
+            .into_response();
+        assert_eq!(
+            error_code(response).await,
+            (StatusCode::BAD_REQUEST, "score_out_of_range".to_string())
+        );
+
+        let request = axum::http::Request::builder()
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from("{not json"))
+            .expect("request");
+        let payload = Json::<LeaderboardSubmission>::from_request(request, &()).await;
+        assert!(payload.is_err());
+        let response = leaderboard_post(State(Arc::clone(&state)), payload)
+            .await
+            .into_response();
+        assert_eq!(
+            error_code(response).await,
+            (StatusCode::BAD_REQUEST, "invalid_json".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn leaderboard_requires_a_matching_unexpired_score_receipt() {
+        use crate::shared::score_receipt::{sign_score_receipt, ScoreReceiptClaims};
+
+        let mut state = Arc::into_inner(leaderboard_state().await).expect("sole owner");
+        state.score_receipts = Some(ScoreReceiptConfig::new("receipts".to_string(), 60_000));
+        let state = Arc::new(state);
+        let receipt_for = |score: i64, issued_at_ms: i64| {
+            let claims = ScoreReceiptClaims {
+                player_id: "player-1".to_string(),
+                score,
+                issued_at_ms,
+            };
+            sign_score_receipt(&claims, "receipts").expect("signed")
+        };
+        let submit = |score: f64, receipt: Option<String>| {
+            let state = Arc::clone(&state);
+            async move {
+                let submission = LeaderboardSubmission {
+                    name: Some("Player".to_string()),
+                    score: Some(score),
+                    receipt,
+                };
+                leaderboard_post(State(state), Ok(Json(submission)))
+                    .await
+                    .into_response()
+            }
+        };
+
+        let valid = receipt_for(420, now_millis());
+        assert_eq!(
+            submit(420.0, Some(valid.clone())).await.status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            error_code(submit(9_000.0, Some(valid)).await).await,
+            (StatusCode::FORBIDDEN, "invalid_score_receipt".to_string())
+        );
+
+        // The signature of a 420 receipt under a payload claiming 9000.
+        let honest = receipt_for(420, now_millis());
+        let inflated = receipt_for(9_000, now_millis());
+        let tampered = format!(
+            "{}.{}",
+            inflated.split('.').next().unwrap(),
+            honest.split('.').nth(1).unwrap()
+        );
+        assert_eq!(
+            error_code(submit(9_000.0, Some(tampered)).await).await,
+            (StatusCode::FORBIDDEN, "invalid_score_receipt".to_string())
+        );
+
+        let expired = receipt_for(420, now_millis() - 120_000);
+        assert_eq!(
+            error_code(submit(420.0, Some(expired)).await).await,
+            (StatusCode::FORBIDDEN, "score_receipt_expired".to_string())
+        );
+        assert_eq!(
+            error_code(submit(420.0, None).await).await,
+            (StatusCode::FORBIDDEN, "score_receipt_required".to_string())
+        );
+
+        let stored = state
+            .leaderboard
+            .as_ref()
+            .expect("leaderboard enabled")
+            .top(10, LeaderboardWindow::AllTime)
+            .await
+            .expect("top");
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].score, 420);
+    }
+
+    #[tokio::test]
+    async fn leaderboard_rejects_a_replayed_score_receipt() {
+        use crate::shared::score_receipt::{sign_score_receipt, ScoreReceiptClaims};
+
+        let mut state = Arc::into_inner(leaderboard_state().await).expect("sole owner");
+        state.score_receipts = Some(ScoreReceiptConfig::new("receipts".to_string(), 60_000));
+        let state = Arc::new(state);
+        let submit = |receipt: String| {
+            let state = Arc::clone(&state);
+            async move {
+                let submission = LeaderboardSubmission {
+                    name: Some("Player".to_string()),
+                    score: Some(420.0),
+                    receipt: Some(receipt),
+                };
+                leaderboard_post(State(state), Ok(Json(submission)))
+                    .await
+                    .into_response()
+            }
+        };
+        let claims = ScoreReceiptClaims {
+            player_id: "player-1".to_string(),
+            score: 420,
+            issued_at_ms: now_millis(),
+        };
+        let receipt = sign_score_receipt(&claims, "receipts").expect("signed");
+
+        assert_eq!(submit(receipt.clone()).await.status(), StatusCode::OK);
+        assert_eq!(
+            error_code(submit(receipt).await).await,
+            (StatusCode::FORBIDDEN, "score_receipt_used".to_string())
+        );
+
+        // A later death issues a fresh receipt, which is accepted once as well.
+        let next = ScoreReceiptClaims {
+            issued_at_ms: claims.issued_at_ms + 1,
+            ..claims
+        };
+        let next = sign_score_receipt(&next, "receipts").expect("signed");
+        assert_eq!(submit(next).await.status(), StatusCode::OK);
+
+        let stored = state
+            .leaderboard
+            .as_ref()
+            .expect("leaderboard enabled")
+            .top(10, LeaderboardWindow::AllTime)
+            .await
+            .expect("top");
+        assert_eq!(stored.len(), 2);
+    }
+
+    async fn around(state: &Arc<AppState>, query: &[(&str, &str)]) -> serde_json::Value {
+        let params: HashMap<String, String> = query
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        let response = leaderboard_around(State(Arc::clone(state)), Query(params))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body");
+        serde_json::from_slice(&body).expect("json body")
+    }
+
+    fn scores_of(entries: &serde_json::Value) -> Vec<i64> {
+        entries
+            .as_array()
+            .expect("entries")
+            .iter()
+            .map(|entry| entry["score"].as_i64().expect("score"))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn leaderboard_around_returns_neighbors_and_rank() {
+        let state = leaderboard_state().await;
+        for score in (10..=200).step_by(10) {
+            let submission = LeaderboardSubmission {
+                name: Some(format!("P{score}")),
+                score: Some(score as f64),
+                receipt: None,
+            };
+            let response = leaderboard_post(State(Arc::clone(&state)), Ok(Json(submission)))
+                .await
+                .into_response();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        // The eight scores 130..=200 are ahead of 125, so it would place ninth.
+        let json = around(&state, &[("score", "125"), ("window", "3")]).await;
+        assert_eq!(json["rank"], 9);
+        assert_eq!(scores_of(&json["above"]), vec![150, 140, 130]);
+        assert_eq!(scores_of(&json["below"]), vec![120, 110, 100]);
+
+        // An exact tie sorts after the submitted score.
+        let json = around(&state, &[("score", "120"), ("window", "2")]).await;
+        assert_eq!(json["rank"], 9);
+        assert_eq!(scores_of(&json["above"]), vec![140, 130]);
+        assert_eq!(scores_of(&json["below"]), vec![120, 110]);
+
+        // Windows are cut short at either end of the table.
+        let json = around(&state, &[("score", "500"), ("window", "2")]).await;
+        assert_eq!(json["rank"], 1);
+        assert_eq!(scores_of(&json["above"]), Vec::<i64>::new());
+        assert_eq!(scores_of(&json["below"]), vec![200, 190]);
+        let json = around(&state, &[("score", "15")]).await;
+        assert_eq!(json["rank"], 20);
+        assert_eq!(scores_of(&json["above"]), vec![60, 50, 40, 30, 20]);
+        assert_eq!(scores_of(&json["below"]), vec![10]);
+    }
+
+    #[tokio::test]
+    async fn leaderboard_around_requires_a_score() {
+        let state = leaderboard_state().await;
+        let params = HashMap::from([("score".to_string(), "lots".to_string())]);
+        let response = leaderboard_around(State(Arc::clone(&state)), Query(params))
+            .await
+            .into_response();
+        assert_eq!(
+            error_code(response).await,
+            (StatusCode::BAD_REQUEST, "invalid_query".to_string())
+        );
+    }
+
+    async fn top(state: &Arc<AppState>, limit: &str) -> Vec<(String, i64)> {
+        let params = HashMap::from([("limit".to_string(), limit.to_string())]);
+        let response = leaderboard_get(State(Arc::clone(state)), Query(params))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body");
+        let json: serde_json::Value = serde_json::from_slice(&body).expect("json body");
+        json["scores"]
+            .as_array()
+            .expect("scores")
+            .iter()
+            .map(|entry| {
+                (
+                    entry["name"].as_str().expect("name").to_string(),
+                    entry["score"].as_i64().expect("score"),
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn submitted_scores_come_back_from_top_best_first() {
+        let state = state_with_store(Arc::new(FakeLeaderboardStore::default()));
+        for (name, score) in [("low", 5.0), ("high", 90.9), ("mid", 40.0), ("zero", 0.0)] {
+            let submission = LeaderboardSubmission {
+                name: Some(name.to_string()),
+                score: Some(score),
+                receipt: None,
+            };
+            let response = leaderboard_post(State(Arc::clone(&state)), Ok(Json(submission)))
+                .await
+                .into_response();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        assert_eq!(
+            top(&state, "10").await,
+            vec![
+                ("high".to_string(), 90),
+                ("mid".to_string(), 40),
+                ("low".to_string(), 5),
+                ("zero".to_string(), 0),
+            ]
+        );
+        assert_eq!(
+            top(&state, "2").await,
+            vec![("high".to_string(), 90), ("mid".to_string(), 40)]
+        );
+    }
+
+    #[tokio::test]
+    async fn top_breaks_score_ties_by_earliest_submission() {
+        let store = Arc::new(FakeLeaderboardStore::default());
+        for (name, score, created_at) in
+            [("later", 50, 300), ("other", 20, 100), ("first", 50, 200)]
+        {
+            store
+                .submit(LeaderboardEntry {
+                    name: name.to_string(),
+                    score,
+                    created_at,
+                })
+                .await
+                .expect("submit");
+        }
+        let state = state_with_store(store);
+
+        assert_eq!(
+            top(&state, "10").await,
+            vec![
+                ("first".to_string(), 50),
+                ("later".to_string(), 50),
+                ("other".to_string(), 20),
+            ]
+        );
+        let json = around(&state, &[("score", "50"), ("window", "1")]).await;
+        assert_eq!(json["rank"], 1);
+        assert_eq!(json["below"][0]["name"], "first");
+    }
+
+    #[tokio::test]
+    async fn top_window_drops_entries_submitted_before_the_cutoff() {
+        let state = leaderboard_state().await;
+        let store = state.leaderboard.as_ref().expect("leaderboard enabled");
+        let now = now_millis();
+        for (name, score, age_ms) in [
+            ("ancient", 90, 30 * DAY_MS),
+            ("last_week", 60, 3 * DAY_MS),
+            ("today", 30, 1_000),
+        ] {
+            store
+                .submit(LeaderboardEntry {
+                    name: name.to_string(),
+                    score,
+                    created_at: now - age_ms,
+                })
+                .await
+                .expect("submit");
+        }
+
+        let names = |entries: Vec<LeaderboardEntry>| -> Vec<String> {
+            entries.into_iter().map(|entry| entry.name).collect()
+        };
+        let since = |age_ms: i64| LeaderboardWindow::Since(now - age_ms);
+        assert_eq!(
+            names(
+                store
+                    .top(10, LeaderboardWindow::AllTime)
+                    .await
+                    .expect("top")
+            ),
+            vec!["ancient", "last_week", "today"]
+        );
+        assert_eq!(
+            names(store.top(10, since(7 * DAY_MS)).await.expect("top")),
+            vec!["last_week", "today"]
+        );
+        assert_eq!(
+            names(store.top(10, since(DAY_MS)).await.expect("top")),
+            vec!["today"]
+        );
+
+        let params = HashMap::from([("window".to_string(), "week".to_string())]);
+        let response = leaderboard_get(State(Arc::clone(&state)), Query(params))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body");
+        let json: serde_json::Value = serde_json::from_slice(&body).expect("json body");
+        assert_eq!(json["scores"].as_array().expect("scores").len(), 2);
+        let params = HashMap::from([("window".to_string(), "month".to_string())]);
+        let response = leaderboard_get(State(Arc::clone(&state)), Query(params))
+            .await
+            .into_response();
+        assert_eq!(
+            error_code(response).await,
+            (StatusCode::BAD_REQUEST, "invalid_query".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn room_creation_is_refused_at_the_cap_but_existing_rooms_still_serve() {
+        let state = leaderboard_state().await;
+        let _alpha = state.room("alpha".to_string()).expect("first room");
+        let _beta = state.room("beta".to_string()).expect("second room");
+
+        assert!(state.room("gamma".to_string()).is_none());
+        assert!(state.room("alpha".to_string()).is_some());
+        assert_eq!(state.rooms.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn idle_rooms_are_reclaimed_to_make_room_for_new_ones() {
+        let state = leaderboard_state().await;
+        let alpha = state.room("alpha".to_string()).expect("first room");
+        drop(state.room("beta".to_string()).expect("second room"));
+
+        assert_eq!(state.reclaim_idle_rooms(), 1);
+        assert!(state.rooms.contains_key("alpha"));
+        assert!(!state.rooms.contains_key("beta"));
+
+        drop(state.room("beta".to_string()).expect("recreated room"));
+        drop(alpha);
+        let _gamma = state
+            .room("gamma".to_string())
+            .expect("room after reclaiming");
+        assert_eq!(state.rooms.len(), 1);
+        assert!(state.rooms.contains_key("gamma"));
+    }
+
+    #[tokio::test]
+    async fn readiness_fails_once_database_is_unavailable() {
+        let store = sqlite_store().await;
+        assert!(store.ping().await.is_ok());
+
+        store.close().await;
+        assert!(store.ping().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn disabled_leaderboard_keeps_game_routes_and_answers_503() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind");
+        let base = format!("http://{}", listener.local_addr().expect("addr"));
+        let app = public_routes().with_state(state_with_leaderboard(None));
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(serve_until(listener, app, None, None, async move {
+            let _ = stop_rx.await;
+        }));
+
+        let client = reqwest::Client::new();
+        for path in ["/api/health", "/api/ready"] {
+            let response = client
+                .get(format!("{base}{path}"))
+                .send()
+                .await
+                .expect("request");
+            assert_eq!(response.status(), StatusCode::OK, "{path}");
+        }
+        // Without upgrade headers the WS route rejects the request, but it is mounted.
+        let response = client
+            .get(format!("{base}/api/room/main"))
+            .send()
+            .await
+            .expect("request");
+        assert_ne!(response.status(), StatusCode::NOT_FOUND);
+
+        let requests = [
+            client.get(format!("{base}/api/leaderboard")),
+            client.get(format!("{base}/api/leaderboard/around?score=10")),
+            client
+                .post(format!("{base}/api/leaderboard"))
+                .json(&serde_json::json!({ "name": "Player", "score": 10 })),
+        ];
+        for request in requests {
+            let response = request.send().await.expect("request");
+            assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+            let json: serde_json::Value = response.json().await.expect("json body");
+            assert_eq!(json["code"], "leaderboard_disabled");
+        }
+
+        stop_tx.send(()).expect("server still running");
+        server.await.expect("server task").expect("server result");
+    }
+
+    #[tokio::test]
+    async fn shutdown_signal_stops_server_and_closes_pool() {
+        let db = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite");
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind");
+        let app = Router::new().route("/api/health", get(|| async { "ok" }));
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+
+        let store = Arc::new(SqliteLeaderboardStore::new(db.clone()));
+        let server = tokio::spawn(serve_until(listener, app, None, Some(store), async move {
+            let _ = stop_rx.await;
+        }));
+        assert!(!db.is_closed());
+
+        stop_tx.send(()).expect("server still running");
+        tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .expect("server stops after shutdown signal")
+            .expect("server task")
+            .expect("server result");
+        assert!(db.is_closed());
+    }
 }
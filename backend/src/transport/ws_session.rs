@@ -1,18 +1,79 @@
-use crate::game::room::Room;
-use axum::extract::ws::{Message, WebSocket};
+use crate::game::room::{Room, WireFormat};
+use crate::protocol::{self, ServerError};
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade};
 use futures_util::{SinkExt, StreamExt};
 use std::sync::Arc;
+use std::time::Duration;
 
-pub async fn handle_socket(socket: WebSocket, room: Arc<Room>) {
+/// How long a closing session waits for queued high-priority frames (such as a
+/// `TYPE_ERROR` explaining a rejected join) to reach the client.
+const CLOSE_FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Caps inbound message and frame sizes at the transport. The cap sits at twice the room's
+/// limit so messages just over it still arrive and get a `TYPE_ERROR` from the room, while
+/// anything far larger is refused by the socket before it is buffered.
+pub fn limit_inbound_size(ws: WebSocketUpgrade, room: &Room) -> WebSocketUpgrade {
+    let transport_limit = room.max_inbound_message_bytes().saturating_mul(2);
+    ws.max_message_size(transport_limit)
+        .max_frame_size(transport_limit)
+}
+
+/// Sends a `TYPE_ERROR` frame followed by a close frame carrying the same reason.
+pub async fn reject_socket(mut socket: WebSocket, error: ServerError, code: u16) {
+    let _ = socket
+        .send(Message::Binary(protocol::encode_error(error)))
+        .await;
+    let _ = socket
+        .send(Message::Close(Some(CloseFrame {
+            code,
+            reason: error.reason().into(),
+        })))
+        .await;
+}
+
+pub async fn handle_socket(socket: WebSocket, room: Arc<Room>, wire_format: WireFormat) {
+    // The upgrade handlers already refuse full rooms with a 503; this catches sockets that
+    // raced past that check.
+    let Some(session) = room.add_session(wire_format).await else {
+        reject_socket(socket, ServerError::RoomFull, close_code::AGAIN).await;
+        return;
+    };
     let (mut sender, mut receiver) = socket.split();
-    let session = room.add_session().await;
     let session_id = session.session_id;
+    let state_as_text = session.wire_format == WireFormat::Json;
     let inbound = session.inbound;
     let outbound_state = session.outbound_state;
     let mut outbound_hi_rx = session.outbound_hi_rx;
     let mut outbound_lo_rx = session.outbound_lo_rx;
 
-    let send_task = tokio::spawn(async move {
+    // The first frame must be a `Hello`; anything else, or an unsupported version, is answered
+    // with a `TYPE_ERROR` frame and a protocol-error close before any `Join` is processed.
+    let handshake = match receiver.next().await {
+        Some(Ok(Message::Binary(data))) => match protocol::decode_handshake(&data) {
+            Ok(client_version) => room.handle_hello(&session_id, client_version).await,
+            Err(error) => Err(error),
+        },
+        Some(Ok(Message::Text(_))) => Err(ServerError::HelloRequired),
+        _ => {
+            room.remove_session(&session_id).await;
+            return;
+        }
+    };
+    if let Err(error) = handshake {
+        let _ = sender
+            .send(Message::Binary(protocol::encode_error(error)))
+            .await;
+        let _ = sender
+            .send(Message::Close(Some(CloseFrame {
+                code: close_code::PROTOCOL,
+                reason: error.reason().into(),
+            })))
+            .await;
+        room.remove_session(&session_id).await;
+        return;
+    }
+
+    let mut send_task = tokio::spawn(async move {
         use std::collections::VecDeque;
 
         let mut pending_hi: VecDeque<Vec<u8>> = VecDeque::new();
@@ -21,7 +82,17 @@ pub async fn handle_socket(socket: WebSocket, room: Arc<Room>) {
 
         loop {
             tokio::select! {
-                Some(payload) = outbound_hi_rx.recv() => {
+                payload = outbound_hi_rx.recv() => {
+                    let Some(payload) = payload else {
+                        // The session was removed; flush what it queued and close cleanly.
+                        while let Some(payload) = pending_hi.pop_front() {
+                            if sender.send(Message::Binary(payload)).await.is_err() {
+                                return;
+                            }
+                        }
+                        let _ = sender.send(Message::Close(None)).await;
+                        return;
+                    };
                     pending_hi.push_back(payload);
                 }
                 Some(payload) = outbound_lo_rx.recv() => {
@@ -47,7 +118,12 @@ pub async fn handle_socket(socket: WebSocket, room: Arc<Room>) {
             }
 
             if let Some(payload) = pending_state.take() {
-                if sender.send(Message::Binary(payload)).await.is_err() {
+                let message = if state_as_text {
+                    Message::Text(String::from_utf8_lossy(&payload).into_owned())
+                } else {
+                    Message::Binary(payload)
+                };
+                if sender.send(message).await.is_err() {
                     return;
                 }
             }
@@ -63,18 +139,17 @@ pub async fn handle_socket(socket: WebSocket, room: Arc<Room>) {
     while let Some(result) = receiver.next().await {
         let Ok(message) = result else { break };
         match message {
-            Message::Binary(data) => {
+            Message::Binary(data)
                 if !room
                     .handle_binary_message(&session_id, &inbound, &data)
-                    .await
-                {
-                    break;
-                }
+                    .await =>
+            {
+                break;
             }
-            Message::Text(text) => {
-                if !room.handle_text_message(&session_id, &inbound, &text).await {
-                    break;
-                }
+            Message::Text(text)
+                if !room.handle_text_message(&session_id, &inbound, &text).await =>
+            {
+                break;
             }
             Message::Close(_) => break,
             _ => {}
@@ -82,5 +157,10 @@ pub async fn handle_socket(socket: WebSocket, room: Arc<Room>) {
     }
 
     room.remove_session(&session_id).await;
-    send_task.abort();
+    if tokio::time::timeout(CLOSE_FLUSH_TIMEOUT, &mut send_task)
+        .await
+        .is_err()
+    {
+        send_task.abort();
+    }
 }
@@ -94,6 +94,7 @@ const LOCAL_STORAGE_MIN_DPR = 'spherical_snake_min_dpr'
 const LOCAL_STORAGE_MAX_DPR = 'spherical_snake_max_dpr'
 const LEGACY_LOCAL_STORAGE_RENDERER = 'spherical_snake_renderer'
 const LEGACY_LOCAL_STORAGE_WEBGPU_MSAA_SAMPLES = 'spherical_snake_webgpu_msaa_samples'
+const ANNOUNCEMENT_VISIBLE_MS = 12_000
 
 export default function App() {
   const RAF_SLOW_FRAME_THRESHOLD_MS = 50
@@ -293,6 +294,7 @@ export default function App() {
   const [roomName, setRoomName] = useState(getInitialRoom)
   const [roomInput, setRoomInput] = useState(getInitialRoom)
   const [connectionStatus, setConnectionStatus] = useState('Connecting')
+  const [announcement, setAnnouncement] = useState<string | null>(null)
   const [menuPhase, setMenuPhase] = useState<MenuPhase>('preplay')
   const [menuOverlayExiting, setMenuOverlayExiting] = useState(false)
   const [showPlayAgain, setShowPlayAgain] = useState(false)
@@ -313,6 +315,7 @@ export default function App() {
     terrainTessellation: terrainTessellationDebug,
   })
   const dayNightDebugModeRef = useRef<DayNightDebugMode>(dayNightDebugMode)
+  const serverDayPhaseRef = useRef<number | null>(null)
   const playerIdRef = useRef<string | null>(playerId)
   const playerNameRef = useRef(playerName)
   const netDebugEnabled = useMemo(getNetDebugEnabled, [])
@@ -355,6 +358,12 @@ export default function App() {
     }
   }, [])
 
+  useEffect(() => {
+    if (announcement === null) return
+    const timer = window.setTimeout(() => setAnnouncement(null), ANNOUNCEMENT_VISIBLE_MS)
+    return () => window.clearTimeout(timer)
+  }, [announcement])
+
   useEffect(() => {
     if (typeof window === 'undefined') return
     try {
@@ -379,6 +388,11 @@ export default function App() {
   }, [gameState, playerId])
 
   const score = localPlayer?.score ?? 0
+  const sessionBest = Math.max(score, gameState?.sessionBest ?? 0)
+  const respawnInMs =
+    localPlayer && !localPlayer.alive && gameState?.respawnAt != null
+      ? Math.max(0, gameState.respawnAt - gameState.now)
+      : null
   const playersOnline = gameState?.totalPlayers ?? 0
   const realtimeLeaderboard = useMemo<RealtimeLeaderboardEntry[]>(() => {
     const players = gameState?.players ?? []
@@ -713,8 +727,10 @@ export default function App() {
     setMenuOverlayExiting,
     pointerRef,
     webglRef,
+    serverDayPhaseRef,
     clearBoostInputs,
     setConnectionStatus,
+    setAnnouncement,
     setGameState,
     setEnvironment,
     setMenuPhase,
@@ -797,6 +813,7 @@ export default function App() {
     environmentRef,
     debugFlagsRef,
     dayNightDebugModeRef,
+    serverDayPhaseRef,
     adaptiveQualityRef,
     syncPelletConsumeTargetsToRenderer,
     handleWheel,
@@ -958,6 +975,11 @@ export default function App() {
               <div className='touch-joystick__base' />
               <div className='touch-joystick__knob' />
             </div>
+            {announcement && (
+              <div className='announcement-banner' role='status'>
+                {announcement}
+              </div>
+            )}
           </div>
           {showMenuOverlay && menuUiMode === 'home' && (
             <MenuOverlay
@@ -1050,12 +1072,22 @@ export default function App() {
               <span>Your length: </span>
               <span className='player-stats-value'>{Math.max(0, score)}</span>
             </div>
+            <div className='player-stats-line'>
+              <span>Session best: </span>
+              <span className='player-stats-value'>{sessionBest}</span>
+            </div>
             <div className='player-stats-line'>
               <span>Your rank: </span>
               <span className='player-stats-value'>
                 {localRealtimeRank ?? '-'} of {playersOnline}
               </span>
             </div>
+            {respawnInMs !== null && (
+              <div className='player-stats-line'>
+                <span>Respawn in </span>
+                <span className='player-stats-value'>{(respawnInMs / 1000).toFixed(1)}s</span>
+              </div>
+            )}
           </div>
         )}
       </div>
@@ -1231,15 +1231,22 @@ export function useNetRuntime(options: UseNetRuntimeOptions): UseNetRuntimeResul
         serverOffsetRef.current = currentOffset + clampedDelta * offsetSmoothing
       }
 
+      const timing = state.timing ?? null
       const lastSnapshotTime = lastSnapshotTimeRef.current
-      if (lastSnapshotTime !== null) {
+      if (timing && timing.prevSeq !== state.seq && timing.prevSeq === latestSeq) {
+        // Consecutive frames: the server's interval is exact, including ticks it skipped.
+        const delta = timing.prevIntervalMs
+        if (delta > 0 && delta < 1000) {
+          tickIntervalRef.current = tickIntervalRef.current * 0.8 + delta * 0.2
+        }
+      } else if (lastSnapshotTime !== null) {
         const delta = state.now - lastSnapshotTime
         if (delta > 0 && delta < 1000) {
           tickIntervalRef.current = tickIntervalRef.current * 0.8 + delta * 0.2
         }
       }
       lastSnapshotTimeRef.current = state.now
-      serverTickMsRef.current = Math.max(16, tickIntervalRef.current)
+      serverTickMsRef.current = Math.max(16, timing?.tickMs ?? tickIntervalRef.current)
 
       const lastReceivedAt = lastSnapshotReceivedAtRef.current
       let latestIntervalMs = receiveIntervalMsRef.current
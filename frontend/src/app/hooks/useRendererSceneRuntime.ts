@@ -70,6 +70,7 @@ type UseRendererSceneRuntimeOptions = {
   environmentRef: MutableRefObject<Environment | null>
   debugFlagsRef: MutableRefObject<DebugFlags>
   dayNightDebugModeRef: MutableRefObject<DayNightDebugMode>
+  serverDayPhaseRef: MutableRefObject<number | null>
   adaptiveQualityRef: MutableRefObject<AdaptiveQualityState>
   syncPelletConsumeTargetsToRenderer: () => void
   handleWheel: (event: WheelEvent) => void
@@ -167,6 +168,7 @@ export function useRendererSceneRuntime(options: UseRendererSceneRuntimeOptions)
     environmentRef,
     debugFlagsRef,
     dayNightDebugModeRef,
+    serverDayPhaseRef,
     adaptiveQualityRef,
     syncPelletConsumeTargetsToRenderer,
     handleWheel,
@@ -287,6 +289,7 @@ export function useRendererSceneRuntime(options: UseRendererSceneRuntimeOptions)
         }
         webgl.setDebugFlags?.(debugFlagsRef.current)
         webgl.setDayNightDebugMode?.(dayNightDebugModeRef.current)
+        webgl.setServerDayPhase?.(serverDayPhaseRef.current)
         syncPelletConsumeTargetsToRenderer()
 
         const handleResize = () => {
@@ -1,14 +1,26 @@
 /* eslint-disable @typescript-eslint/no-explicit-any */
 import { useEffect } from 'react'
 import type { GameStateSnapshot } from '@game/types'
-import { decodeServerMessage, resetDeltaDecoderState } from '@game/wsProtocol'
+import {
+  ERROR_RESPAWN_COOLDOWN,
+  decodeServerMessage,
+  encodeHello,
+  encodeResync,
+  resetDeltaDecoderState,
+  takeDeltaResyncRequest,
+} from '@game/wsProtocol'
 import { storePlayerId } from '@game/storage'
+import { setMovementTuning } from '@game/prediction/parity/constants'
 import { resolveWebSocketUrl } from '@services/backend'
 import { requestMatchmake } from '@services/matchmake'
 import { MAX_EXTRAPOLATION_MS, MENU_CAMERA_DISTANCE, MENU_CAMERA_VERTICAL_OFFSET, resolveNetTuning } from '@app/core/constants'
 import { MENU_CAMERA, MENU_CAMERA_TARGET } from '@app/core/menuCamera'
 import { applyPelletsToSnapshotBuffer, rebuildPelletsArray } from '@app/orchestration/connectionHandlers'
 
+// Set by a server `migrate` frame; the next connect to that room joins with the handed-over
+// token instead of going through matchmaking again.
+let pendingMigration: { roomId: string; roomToken: string } | null = null
+
 export function useSocketConnectionRuntime(options: any): void {
   const {
     roomName,
@@ -80,8 +92,10 @@ export function useSocketConnectionRuntime(options: any): void {
     setMenuOverlayExiting,
     pointerRef,
     webglRef,
+    serverDayPhaseRef,
     clearBoostInputs,
     setConnectionStatus,
+    setAnnouncement,
     setGameState,
     setEnvironment,
     setMenuPhase,
@@ -98,11 +112,16 @@ export function useSocketConnectionRuntime(options: any): void {
   useEffect(() => {
     let reconnectTimer: number | null = null
     let cancelled = false
+    // Why the server last refused us; kept across reconnect attempts until a join succeeds.
+    let rejectionMessage: string | null = null
 
     const connect = async () => {
       if (cancelled) return
       snapshotBufferRef.current = []
       serverOffsetRef.current = null
+      // Rooms without a day/night cycle never send a world frame, so fall back to the local clock.
+      serverDayPhaseRef.current = null
+      webglRef.current?.setServerDayPhase?.(null)
       serverTickMsRef.current = 50
       lastSnapshotTimeRef.current = null
       lastSnapshotReceivedAtRef.current = null
@@ -198,17 +217,27 @@ export function useSocketConnectionRuntime(options: any): void {
 
       let assignedRoom = roomName
       let roomToken = ''
-      try {
-        const assignment = await requestMatchmake(roomName)
-        assignedRoom = assignment.roomId
-        roomToken = assignment.roomToken
-      } catch {
-        if (cancelled) return
-        setConnectionStatus('Reconnecting')
-        reconnectTimer = window.setTimeout(() => {
-          void connect()
-        }, 1500)
-        return
+      const migration = pendingMigration
+      pendingMigration = null
+      if (migration && migration.roomId === roomName) {
+        roomToken = migration.roomToken
+      } else {
+        try {
+          const assignment = await requestMatchmake(roomName, (queuePosition) => {
+            if (!cancelled) setConnectionStatus(`Finding a game... (#${queuePosition} in queue)`)
+          })
+          assignedRoom = assignment.roomId
+          roomToken = assignment.roomToken
+        } catch {
+          if (cancelled) return
+          setConnectionStatus(
+            rejectionMessage ? `Disconnected: ${rejectionMessage}. Reconnecting` : 'Reconnecting',
+          )
+          reconnectTimer = window.setTimeout(() => {
+            void connect()
+          }, 1500)
+          return
+        }
       }
 
       if (cancelled) return
@@ -229,6 +258,7 @@ export function useSocketConnectionRuntime(options: any): void {
 
       socket.addEventListener('open', () => {
         setConnectionStatus('Connected')
+        socket.send(encodeHello())
         sendJoin(socket, true)
         startInputLoop()
       })
@@ -254,8 +284,39 @@ export function useSocketConnectionRuntime(options: any): void {
           playerMetaRef.current,
           playerIdByNetIdRef.current,
         )
+        if (takeDeltaResyncRequest() && socket.readyState === WebSocket.OPEN) {
+          socket.send(encodeResync())
+        }
         if (!decoded) return
 
+        if (decoded.type === 'error') {
+          // An early respawn is refused without closing the socket; the server respawns the
+          // player itself once the cooldown ends, and state frames carry the countdown.
+          if (decoded.code === ERROR_RESPAWN_COOLDOWN) return
+          rejectionMessage = decoded.message
+          return
+        }
+
+        if (decoded.type === 'migrate') {
+          // Changing the room tears this socket down and reconnects to the target room.
+          pendingMigration = { roomId: decoded.roomId, roomToken: decoded.roomToken }
+          setConnectionStatus('Moving to a busier room')
+          setRoomInput(decoded.roomId)
+          setRoomName(decoded.roomId)
+          return
+        }
+
+        if (decoded.type === 'world') {
+          serverDayPhaseRef.current = decoded.dayPhase
+          webglRef.current?.setServerDayPhase?.(decoded.dayPhase)
+          return
+        }
+
+        if (decoded.type === 'announce') {
+          setAnnouncement(decoded.text)
+          return
+        }
+
         if (decoded.type === 'pellet_reset') {
           pelletMapRef.current = new Map(decoded.pellets.map((pellet) => [pellet.id, pellet]))
           clearPelletConsumeTargets()
@@ -306,6 +367,7 @@ export function useSocketConnectionRuntime(options: any): void {
         }
 
         if (decoded.type === 'init') {
+          rejectionMessage = null
           setPlayerId(decoded.playerId)
           playerIdRef.current = decoded.playerId
           storePlayerId(decoded.playerId)
@@ -319,6 +381,7 @@ export function useSocketConnectionRuntime(options: any): void {
               receiveIntervalMs: normalizedTickMs,
             }
           }
+          setMovementTuning(decoded.movement)
           setEnvironment(decoded.environment)
           clearPelletConsumeTargets()
           const state: GameStateSnapshot = { ...decoded.state, pellets: pelletsArrayRef.current }
@@ -336,7 +399,9 @@ export function useSocketConnectionRuntime(options: any): void {
 
       socket.addEventListener('close', () => {
         if (cancelled) return
-        setConnectionStatus('Reconnecting')
+        setConnectionStatus(
+          rejectionMessage ? `Disconnected: ${rejectionMessage}. Reconnecting` : 'Reconnecting',
+        )
         reconnectTimer = window.setTimeout(() => {
           void connect()
         }, 1500)
@@ -2,12 +2,10 @@ import { clamp, cross, dot, normalize } from '../math'
 import type { Environment, Point } from '../types'
 import type { PredictionCommand } from './types'
 import {
-  BASE_SPEED,
-  BOOST_MULTIPLIER,
+  getMovementTuning,
+  movementSubstepCount,
   TICK_MS,
   steeringGainForSpeed,
-  TURN_SUBSTEPS_BOOST,
-  TURN_SUBSTEPS_NORMAL,
   turnRateFor,
 } from './parity/constants'
 import { applySnakeWithCollisions } from './parity/collision'
@@ -269,12 +267,10 @@ export function replayPredictedSnake(options: ReplayPredictionOptions): ReplayPr
     if (tickDurationMs <= 0) continue
 
     const targetAxis = tickInput?.axis ? normalize(tickInput.axis) : axis
-    const speedFactor = tickInput?.boost && boostAllowed ? BOOST_MULTIPLIER : 1
-    const stepCount =
-      tickInput?.boost && boostAllowed
-        ? Math.max(1, TURN_SUBSTEPS_BOOST)
-        : Math.max(1, TURN_SUBSTEPS_NORMAL)
-    const stepVelocity = (BASE_SPEED * speedFactor) / stepCount
+    const movement = getMovementTuning()
+    const speedFactor = tickInput?.boost && boostAllowed ? movement.boostMultiplier : 1
+    const stepCount = movementSubstepCount(speedFactor)
+    const stepVelocity = (movement.baseSpeed * speedFactor) / stepCount
     const turnPerTick = turnRateFor(baseState.nodes.length, speedFactor)
     const turnPerSubstepCap = turnPerTick / stepCount
     const steeringGain = steeringGainForSpeed(speedFactor)
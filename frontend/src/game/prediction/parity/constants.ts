@@ -8,7 +8,10 @@ export const NODE_QUEUE_SIZE = 9
 export const MOVE_SPEED_MULTIPLIER = 1.75
 export const BASE_SPEED = ((NODE_ANGLE * 2) / (NODE_QUEUE_SIZE + 1)) * MOVE_SPEED_MULTIPLIER
 export const BOOST_MULTIPLIER = 2.16
-export const TURN_RATE = 0.115
+export const TICK_MS = 50
+// Radians per second; the per-tick value mirrors the backend's conversion at TICK_MS.
+export const TURN_RATE_PER_SEC = 2.3
+export const TURN_RATE = (TURN_RATE_PER_SEC * TICK_MS) / 1000
 export const STARTING_LENGTH = 8
 export const TURN_SCANG_BASE = 0.13
 export const TURN_SCANG_RANGE = 0.87
@@ -22,13 +25,34 @@ export const TURN_RESPONSE_GAIN_BOOST_PER_SEC = 4.6
 export const TURN_RATE_MIN_MULTIPLIER = 0.22
 export const TURN_RATE_MAX_MULTIPLIER = 4
 export const TURN_SUBSTEPS_NORMAL = 2
-export const TURN_SUBSTEPS_BOOST = 4
-export const TICK_MS = 50
 export const SNAKE_RADIUS = 0.045
 export const TREE_TRUNK_RADIUS = 0.036
 export const CONTACT_ITERATIONS = 4
 export const STICK_THRESHOLD = 0.01
 
+export type MovementTuning = {
+  baseSpeed: number
+  boostMultiplier: number
+}
+
+// The room's movement tuning from `TYPE_INIT`; older servers don't send it and use these.
+let movementTuning: MovementTuning = { baseSpeed: BASE_SPEED, boostMultiplier: BOOST_MULTIPLIER }
+
+export function getMovementTuning(): MovementTuning {
+  return movementTuning
+}
+
+export function setMovementTuning(next: MovementTuning | null): void {
+  movementTuning = next ?? { baseSpeed: BASE_SPEED, boostMultiplier: BOOST_MULTIPLIER }
+}
+
+// Mirrors the backend `MovementConfig::substep_count`: substeps grow with the distance covered
+// so each one moves about as far as a default normal-speed substep.
+export function movementSubstepCount(speedFactor: number): number {
+  const distanceRatio = (movementTuning.baseSpeed * speedFactor) / BASE_SPEED
+  return Math.max(1, Math.round(TURN_SUBSTEPS_NORMAL * distanceRatio))
+}
+
 export function snakeContactAngularRadiusForScale(girthScale: number): number {
   return (SNAKE_RADIUS / PLANET_RADIUS) * Math.max(0, girthScale)
 }
@@ -55,7 +79,7 @@ export function turnRateFor(snakeLen: number, speedFactor: number): number {
   const baselineScang = slitherScangForLen(STARTING_LENGTH)
   const baselineSpang = slitherSpangForSpeed(1)
   const baseline = Math.max(1e-6, baselineScang * baselineSpang)
-  const boostWindow = Math.max(1e-6, BOOST_MULTIPLIER - 1)
+  const boostWindow = Math.max(1e-6, movementTuning.boostMultiplier - 1)
   const boostBlend = clamp((safeSpeedFactor - 1) / boostWindow, 0, 1)
   const boostTurnMult = 1 + Math.max(0, TURN_BOOST_TURN_RATE_MULTIPLIER - 1) * boostBlend
   const normalized = (scang * spang) / baseline
@@ -69,7 +93,7 @@ export function turnRateFor(snakeLen: number, speedFactor: number): number {
 
 export function steeringGainForSpeed(speedFactor: number): number {
   const safeSpeedFactor = Number.isFinite(speedFactor) ? Math.max(0, speedFactor) : 0
-  const boostWindow = Math.max(1e-6, BOOST_MULTIPLIER - 1)
+  const boostWindow = Math.max(1e-6, movementTuning.boostMultiplier - 1)
   const blend = clamp((safeSpeedFactor - 1) / boostWindow, 0, 1)
   return (
     TURN_RESPONSE_GAIN_NORMAL_PER_SEC +
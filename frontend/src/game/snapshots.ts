@@ -393,6 +393,8 @@ function blendSnapshots(a: GameStateSnapshot, b: GameStateSnapshot, t: number):
     players,
     totalPlayers: t < 0.5 ? a.totalPlayers : b.totalPlayers,
     ackInputSeq: t < 0.5 ? (a.ackInputSeq ?? null) : (b.ackInputSeq ?? null),
+    sessionBest: t < 0.5 ? (a.sessionBest ?? null) : (b.sessionBest ?? null),
+    respawnAt: t < 0.5 ? (a.respawnAt ?? null) : (b.respawnAt ?? null),
   }
 }
 
@@ -86,6 +86,20 @@ export type GameStateSnapshot = {
   players: PlayerSnapshot[]
   totalPlayers: number
   ackInputSeq?: number | null
+  sessionBest?: number | null
+  // Server time (ms) the local player may respawn at, sent while it is dead.
+  respawnAt?: number | null
+  timing?: StateFrameTiming | null
+}
+
+// Server-authored interpolation timing from a state frame header. `prevSeq`/`prevIntervalMs`
+// describe the frame broadcast just before this one; they only apply when the client also
+// received that frame (`prevSeq` equals its last seq). On the first frame `prevSeq === seq`.
+export type StateFrameTiming = {
+  tickMs: number
+  renderDelayMs: number
+  prevSeq: number
+  prevIntervalMs: number
 }
 
 export type Quaternion = {
@@ -7,8 +7,9 @@ import type {
   Point,
 } from './types'
 import { bytesToHexColor, parseHexColor } from '@shared/color/hex'
-import { Reader, readSkinColors } from './wsProtocol/reader'
+import { Reader, readPelletPalette, readSkinColors } from './wsProtocol/reader'
 import { readEnvironment } from './wsProtocol/environment'
+import type { MovementTuning } from './prediction/parity/constants'
 
 export type PlayerMeta = {
   name: string
@@ -16,12 +17,15 @@ export type PlayerMeta = {
   skinColors?: string[]
 }
 
-const VERSION = 21
+const VERSION = 23
 
 const TYPE_JOIN = 0x01
 const TYPE_INPUT = 0x02
 const TYPE_RESPAWN = 0x03
 const TYPE_VIEW = 0x04
+const TYPE_HELLO = 0x05
+const TYPE_RESYNC = 0x06
+const TYPE_PING = 0x09
 
 const TYPE_INIT = 0x10
 const TYPE_STATE = 0x11
@@ -30,6 +34,15 @@ const TYPE_PELLET_DELTA = 0x13
 const TYPE_PELLET_RESET = 0x14
 const TYPE_STATE_DELTA = 0x15
 const TYPE_PELLET_CONSUME = 0x16
+const TYPE_WORLD = 0x17
+const TYPE_ERROR = 0x18
+const TYPE_MIGRATE = 0x1a
+const TYPE_ANNOUNCE = 0x1c
+const TYPE_PONG = 0x1d
+const TYPE_SCORE_RECEIPT = 0x1e
+
+// Non-fatal: an early `Respawn` was refused; the socket stays open.
+export const ERROR_RESPAWN_COOLDOWN = 8
 
 const FLAG_JOIN_PLAYER_ID = 1 << 0
 const FLAG_JOIN_NAME = 1 << 1
@@ -43,18 +56,23 @@ const FLAG_VIEW_CENTER = 1 << 0
 const FLAG_VIEW_RADIUS = 1 << 1
 const FLAG_VIEW_CAMERA_DISTANCE = 1 << 2
 
+// Set on `TYPE_INIT` when the join resumed the existing player (same net id and snake).
+const FLAG_INIT_RESUMED = 1 << 0
+
 const SNAKE_DETAIL_FULL = 0
 const SNAKE_DETAIL_WINDOW = 1
 const SNAKE_DETAIL_STUB = 2
 
 const MAX_STRING_BYTES = 255
 const PELLET_NORMAL_MAX = 32767
+// Must match the backend's `PELLET_SIZE_ENCODE_MIN`/`PELLET_SIZE_ENCODE_MAX` (small through death pellets).
 const PELLET_SIZE_MIN = 0.55
 const PELLET_SIZE_MAX = 2.85
 const VIEW_RADIUS_MIN = 0.2
 const VIEW_RADIUS_MAX = 1.4
 const VIEW_CAMERA_DISTANCE_MIN = 4
 const VIEW_CAMERA_DISTANCE_MAX = 10
+const DEFAULT_GIRTH_MAX_SCALE = 2
 
 const textEncoder = new TextEncoder()
 
@@ -62,9 +80,13 @@ export type DecodedMessage =
   | {
       type: 'init'
       playerId: string
+      resumed: boolean
       state: GameStateSnapshot
       environment: Environment
       tickMs: number
+      pelletPalette: string[] | null
+      movement: MovementTuning | null
+      minSurvivalLength: number | null
     }
   | { type: 'state'; state: GameStateSnapshot }
   | { type: 'pellet_reset'; now: number; seq: number; pellets: PelletSnapshot[] }
@@ -83,8 +105,16 @@ export type DecodedMessage =
       consumes: Array<{ pelletId: number; targetNetId: number }>
     }
   | { type: 'meta' }
+  | { type: 'error'; code: number; message: string; retryAfterMs: number | null }
+  | { type: 'migrate'; roomId: string; roomToken: string }
+  | { type: 'announce'; text: string }
+  | { type: 'pong'; clientTime: number; serverTime: number }
+  | { type: 'scoreReceipt'; score: number; receipt: string }
+  | { type: 'world'; dayPhase: number }
 
 const DELTA_FRAME_KEYFRAME = 1 << 0
+const DELTA_FRAME_SESSION_BEST = 1 << 1
+const DELTA_FRAME_RESPAWN_AT = 1 << 2
 
 const DELTA_FIELD_FLAGS = 1 << 0
 const DELTA_FIELD_SCORE = 1 << 1
@@ -118,22 +148,47 @@ type CachedPlayerState = {
 const deltaDecoderState: {
   initialized: boolean
   awaitKeyframe: boolean
+  resyncRequested: boolean
   lastSeq: number | null
   players: Map<number, CachedPlayerState>
 } = {
   initialized: false,
   awaitKeyframe: false,
+  resyncRequested: false,
   lastSeq: null,
   players: new Map(),
 }
 
+// Upper bound of the girth encoding, announced by the room in the `TYPE_INIT` trailer.
+let girthMaxScale = DEFAULT_GIRTH_MAX_SCALE
+
+function girthScaleFromQ(girthQ: number): number {
+  return 1 + (girthQ / 255) * (girthMaxScale - 1)
+}
+
 export function resetDeltaDecoderState() {
   deltaDecoderState.initialized = false
   deltaDecoderState.awaitKeyframe = false
+  deltaDecoderState.resyncRequested = false
   deltaDecoderState.lastSeq = null
   deltaDecoderState.players.clear()
 }
 
+function awaitDeltaKeyframe() {
+  if (!deltaDecoderState.awaitKeyframe) {
+    deltaDecoderState.resyncRequested = true
+  }
+  deltaDecoderState.awaitKeyframe = true
+}
+
+// True once each time the delta decoder loses sync; the caller should answer with `encodeResync`
+// so the server sends a keyframe right away instead of at its next scheduled one.
+export function takeDeltaResyncRequest(): boolean {
+  const requested = deltaDecoderState.resyncRequested
+  deltaDecoderState.resyncRequested = false
+  return requested
+}
+
 export function encodeJoin(
   name: string | null,
   playerId: string | null,
@@ -241,6 +296,14 @@ export function encodeView(
   return buffer
 }
 
+export function encodeHello(): ArrayBuffer {
+  const buffer = new ArrayBuffer(5)
+  const view = new DataView(buffer)
+  const offset = writeHeader(view, 0, TYPE_HELLO, 0)
+  view.setUint8(offset, VERSION)
+  return buffer
+}
+
 export function encodeRespawn(): ArrayBuffer {
   const buffer = new ArrayBuffer(4)
   const view = new DataView(buffer)
@@ -248,6 +311,23 @@ export function encodeRespawn(): ArrayBuffer {
   return buffer
 }
 
+export function encodeResync(): ArrayBuffer {
+  const buffer = new ArrayBuffer(4)
+  const view = new DataView(buffer)
+  writeHeader(view, 0, TYPE_RESYNC, 0)
+  return buffer
+}
+
+// `clientTime` is echoed back verbatim in the pong (wrapped to u32), alongside the server's
+// wall clock in ms, so the caller can measure round trip and clock offset.
+export function encodePing(clientTime: number): ArrayBuffer {
+  const buffer = new ArrayBuffer(8)
+  const view = new DataView(buffer)
+  const offset = writeHeader(view, 0, TYPE_PING, 0)
+  view.setUint32(offset, clientTime >>> 0, true)
+  return buffer
+}
+
 export function decodeServerMessage(
   buffer: ArrayBuffer,
   meta: Map<string, PlayerMeta>,
@@ -255,15 +335,16 @@ export function decodeServerMessage(
 ): DecodedMessage | null {
   const reader = new Reader(buffer)
   const version = reader.readU8()
-  if (version === null || version !== VERSION) return null
   const messageType = reader.readU8()
-  if (messageType === null) return null
+  if (version === null || messageType === null) return null
+  // Error frames are readable at any version so a mismatch can still be explained.
+  if (version !== VERSION && messageType !== TYPE_ERROR) return null
   const flags = reader.readU16()
   if (flags === null) return null
 
   switch (messageType) {
     case TYPE_INIT:
-      return decodeInit(reader, meta, idByNetId)
+      return decodeInit(reader, meta, idByNetId, (flags & FLAG_INIT_RESUMED) !== 0)
     case TYPE_STATE:
       return decodeState(reader, meta, idByNetId)
     case TYPE_STATE_DELTA:
@@ -277,6 +358,18 @@ export function decodeServerMessage(
       return decodePelletDelta(reader)
     case TYPE_PELLET_CONSUME:
       return decodePelletConsume(reader)
+    case TYPE_WORLD:
+      return decodeWorld(reader)
+    case TYPE_ERROR:
+      return decodeError(reader)
+    case TYPE_MIGRATE:
+      return decodeMigrate(reader)
+    case TYPE_ANNOUNCE:
+      return decodeAnnounce(reader)
+    case TYPE_PONG:
+      return decodePong(reader)
+    case TYPE_SCORE_RECEIPT:
+      return decodeScoreReceipt(reader)
     default:
       return null
   }
@@ -286,6 +379,7 @@ function decodeInit(
   reader: Reader,
   meta: Map<string, PlayerMeta>,
   idByNetId: Map<number, string>,
+  resumed: boolean,
 ): DecodedMessage | null {
   const playerId = reader.readUuid()
   const now = reader.readI64()
@@ -310,11 +404,44 @@ function decodeInit(
   if (!players) return null
   const environment = readEnvironment(reader)
   if (!environment) return null
+  const announcedGirthMax = reader.readF32()
+  const nextGirthMax =
+    announcedGirthMax !== null && Number.isFinite(announcedGirthMax) && announcedGirthMax >= 1
+      ? announcedGirthMax
+      : DEFAULT_GIRTH_MAX_SCALE
+  if (nextGirthMax !== girthMaxScale) {
+    // Players in this frame were decoded against the previous bound.
+    const previousRange = girthMaxScale - 1
+    for (const player of players) {
+      const t = previousRange > 0 ? (player.girthScale - 1) / previousRange : 0
+      player.girthScale = 1 + t * (nextGirthMax - 1)
+    }
+    girthMaxScale = nextGirthMax
+  }
+  const pelletPalette = readPelletPalette(reader)
+  // Older servers stop before the room's movement tuning.
+  const baseSpeed = reader.readF32()
+  const boostMultiplier = reader.readF32()
+  const movement =
+    baseSpeed !== null &&
+    boostMultiplier !== null &&
+    Number.isFinite(baseSpeed) &&
+    baseSpeed > 0 &&
+    Number.isFinite(boostMultiplier) &&
+    boostMultiplier >= 1
+      ? { baseSpeed, boostMultiplier }
+      : null
+  // Length the room never shrinks a snake below (boost drain, drowning); older servers omit it.
+  const minSurvivalLength = reader.readU16()
 
   return {
     type: 'init',
     playerId,
+    resumed,
     tickMs,
+    pelletPalette,
+    movement,
+    minSurvivalLength,
     state: { now, seq, pellets: [], players, totalPlayers, ackInputSeq: null },
     environment,
   }
@@ -345,6 +472,10 @@ function decodeStateDelta(
 ): DecodedMessage | null {
   const now = reader.readI64()
   const seq = reader.readU32()
+  const tickMs = reader.readU16()
+  const renderDelayMs = reader.readU16()
+  const prevSeq = reader.readU32()
+  const prevIntervalMs = reader.readU16()
   const totalPlayers = reader.readU16()
   const ackInputSeq = reader.readU16()
   const frameFlags = reader.readU8()
@@ -352,6 +483,10 @@ function decodeStateDelta(
   if (
     now === null ||
     seq === null ||
+    tickMs === null ||
+    renderDelayMs === null ||
+    prevSeq === null ||
+    prevIntervalMs === null ||
     totalPlayers === null ||
     ackInputSeq === null ||
     frameFlags === null ||
@@ -363,11 +498,11 @@ function decodeStateDelta(
   const keyframe = (frameFlags & DELTA_FRAME_KEYFRAME) !== 0
   if (!keyframe) {
     if (!deltaDecoderState.initialized || deltaDecoderState.awaitKeyframe) {
-      deltaDecoderState.awaitKeyframe = true
+      awaitDeltaKeyframe()
       return null
     }
     if (deltaDecoderState.lastSeq !== null && seq !== ((deltaDecoderState.lastSeq + 1) >>> 0)) {
-      deltaDecoderState.awaitKeyframe = true
+      awaitDeltaKeyframe()
       return null
     }
   }
@@ -397,19 +532,19 @@ function decodeStateDelta(
       tailExt === null ||
       tailTip === undefined
     ) {
-      deltaDecoderState.awaitKeyframe = true
+      awaitDeltaKeyframe()
       return null
     }
 
     const snakeState = readDeltaSnakeState(reader, fieldMask, previous)
     if (!snakeState) {
-      deltaDecoderState.awaitKeyframe = true
+      awaitDeltaKeyframe()
       return null
     }
 
     const digestions = readDeltaDigestions(reader, fieldMask, previous?.digestions)
     if (!digestions) {
-      deltaDecoderState.awaitKeyframe = true
+      awaitDeltaKeyframe()
       return null
     }
 
@@ -422,7 +557,7 @@ function decodeStateDelta(
       score,
       scoreFraction,
       oxygen,
-      girthScale: 1 + (girthQ / 255) * 1,
+      girthScale: girthScaleFromQ(girthQ),
       tailExtension: tailExt,
       tailTip: resolvedTailTip,
       snakeDetail: snakeState.snakeDetail,
@@ -434,6 +569,24 @@ function decodeStateDelta(
     orderedNetIds.push(netId)
   }
 
+  let sessionBest: number | null = null
+  if ((frameFlags & DELTA_FRAME_SESSION_BEST) !== 0) {
+    sessionBest = reader.readVarI32()
+    if (sessionBest === null) {
+      awaitDeltaKeyframe()
+      return null
+    }
+  }
+
+  let respawnAt: number | null = null
+  if ((frameFlags & DELTA_FRAME_RESPAWN_AT) !== 0) {
+    respawnAt = reader.readI64()
+    if (respawnAt === null) {
+      awaitDeltaKeyframe()
+      return null
+    }
+  }
+
   deltaDecoderState.players = nextPlayers
   deltaDecoderState.lastSeq = seq
   deltaDecoderState.initialized = true
@@ -449,7 +602,17 @@ function decodeStateDelta(
 
   return {
     type: 'state',
-    state: { now, seq, pellets: [], players, totalPlayers, ackInputSeq },
+    state: {
+      now,
+      seq,
+      pellets: [],
+      players,
+      totalPlayers,
+      ackInputSeq,
+      sessionBest,
+      respawnAt,
+      timing: { tickMs, renderDelayMs, prevSeq, prevIntervalMs },
+    },
   }
 }
 
@@ -541,7 +704,8 @@ function readDeltaQ8AsRaw(
     return reader.readU8()
   }
   if (previousScale === undefined) return null
-  const q = Math.round((previousScale - 1) * 255)
+  const range = girthMaxScale - 1
+  const q = range > 0 ? Math.round(((previousScale - 1) / range) * 255) : 0
   return Math.max(0, Math.min(255, q))
 }
 
@@ -760,7 +924,7 @@ function readPlayerStates(
     const isBoosting = (flags & 0x02) !== 0
     const scoreFraction = Math.min(0.999_999, Math.max(0, scoreFractionQ / 65535))
     const oxygen = Math.min(1, Math.max(0, oxygenQ / 65535))
-    const girthScale = 1 + (girthQ / 255) * 1
+    const girthScale = girthScaleFromQ(girthQ)
     const tailExtension = Math.min(1, Math.max(0, tailExtQ / 65535))
     const tailTipRaw = decodeOctI16ToPoint(tailTipOx, tailTipOy)
 
@@ -964,3 +1128,52 @@ function uuidToBytes(uuid: string): Uint8Array | null {
   }
   return bytes
 }
+
+function decodeError(reader: Reader): DecodedMessage | null {
+  const code = reader.readU8()
+  const minVersion = reader.readU8()
+  const maxVersion = reader.readU8()
+  if (code === null || minVersion === null || maxVersion === null) return null
+  const message = reader.readString()
+  if (message === null) return null
+  let retryAfterMs: number | null = null
+  if (code === ERROR_RESPAWN_COOLDOWN) {
+    retryAfterMs = reader.readU32()
+    if (retryAfterMs === null) return null
+  }
+  return { type: 'error', code, message, retryAfterMs }
+}
+
+function decodeMigrate(reader: Reader): DecodedMessage | null {
+  const roomId = reader.readString()
+  const roomToken = reader.readLongString()
+  if (roomId === null || roomToken === null) return null
+  return { type: 'migrate', roomId, roomToken }
+}
+
+function decodeAnnounce(reader: Reader): DecodedMessage | null {
+  const text = reader.readLongString()
+  if (text === null) return null
+  return { type: 'announce', text }
+}
+
+function decodePong(reader: Reader): DecodedMessage | null {
+  const clientTime = reader.readU32()
+  const serverTime = reader.readI64()
+  if (clientTime === null || serverTime === null) return null
+  return { type: 'pong', clientTime, serverTime }
+}
+
+// Server day phase in [0, 1), quantized to 1/256 steps; 0 is midday and 0.5 is midnight.
+function decodeWorld(reader: Reader): DecodedMessage | null {
+  const phaseQ = reader.readU8()
+  if (phaseQ === null) return null
+  return { type: 'world', dayPhase: phaseQ / 256 }
+}
+
+function decodeScoreReceipt(reader: Reader): DecodedMessage | null {
+  const score = reader.readI64()
+  const receipt = reader.readLongString()
+  if (score === null || receipt === null) return null
+  return { type: 'scoreReceipt', score, receipt }
+}
@@ -82,6 +82,15 @@ export class Reader {
     return textDecoder.decode(bytes)
   }
 
+  readLongString(): string | null {
+    const length = this.readU16()
+    if (length === null) return null
+    if (!this.ensure(length)) return null
+    const bytes = new Uint8Array(this.view.buffer, this.offset, length)
+    this.offset += length
+    return textDecoder.decode(bytes)
+  }
+
   readVarU32(): number | null {
     let result = 0
     let shift = 0
@@ -121,3 +130,19 @@ export function readSkinColors(reader: Reader): string[] | undefined | null {
   }
   return out
 }
+
+const MAX_PELLET_PALETTE_COLORS = 32
+
+export function readPelletPalette(reader: Reader): string[] | null {
+  const count = reader.readU8()
+  if (count === null || count === 0 || count > MAX_PELLET_PALETTE_COLORS) return null
+  const out: string[] = []
+  for (let i = 0; i < count; i += 1) {
+    const r = reader.readU8()
+    const g = reader.readU8()
+    const b = reader.readU8()
+    if (r === null || g === null || b === null) return null
+    out.push(bytesToHexColor(r, g, b))
+  }
+  return out
+}
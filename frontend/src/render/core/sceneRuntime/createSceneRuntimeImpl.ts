@@ -108,6 +108,7 @@ export const createScene = async (canvas: HTMLCanvasElement): Promise<RenderScen
     dayNightState,
     getDayNightInfo,
     setDayNightDebugMode,
+    setServerDayPhase,
     skyGroup,
     skyGradient,
     skyTopTemp,
@@ -880,6 +881,7 @@ export const createScene = async (canvas: HTMLCanvasElement): Promise<RenderScen
     setEnvironment,
     setDebugFlags,
     setDayNightDebugMode,
+    setServerDayPhase,
     dispose,
   }
 }
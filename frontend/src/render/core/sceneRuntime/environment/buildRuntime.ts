@@ -438,7 +438,13 @@ export const buildEnvironmentRuntime = (
   state.treeTrunkMaterial = trunkMaterial
   state.cactusMaterial = cactusBodyMaterial
   state.cactusArmMaterial = cactusArmMat
-  const treeInstanceCount = Math.max(0, TREE_COUNT - MOUNTAIN_COUNT)
+  // Rooms can configure denser terrain than the defaults, so size instance buffers to fit the
+  // server's layout when it sends one.
+  const dataForestCount = data?.trees?.filter((tree) => tree.widthScale >= 0).length ?? 0
+  const dataCactusCount = (data?.trees?.length ?? 0) - dataForestCount
+  const treeInstanceCount = Math.max(TREE_COUNT - MOUNTAIN_COUNT, dataForestCount, dataCactusCount)
+  const treeMeshCapacity = Math.max(TREE_COUNT, treeInstanceCount)
+  const mountainMeshCapacity = Math.max(MOUNTAIN_COUNT, data?.mountains?.length ?? 0)
 
   for (let i = 0; i < treeTierHeights.length; i += 1) {
     const height = treeTierHeights[i]
@@ -446,7 +452,7 @@ export const buildEnvironmentRuntime = (
     const geometry = new THREE.ConeGeometry(radius, height, 6, 1)
     geometry.translate(0, height / 2, 0)
     state.treeTierGeometries.push(geometry)
-    const mesh = new THREE.InstancedMesh(geometry, leafMaterial, TREE_COUNT)
+    const mesh = new THREE.InstancedMesh(geometry, leafMaterial, treeMeshCapacity)
     mesh.instanceMatrix.setUsage(THREE.StaticDrawUsage)
     mesh.frustumCulled = false
     mesh.count = treeInstanceCount
@@ -462,7 +468,7 @@ export const buildEnvironmentRuntime = (
     1,
   )
   state.treeTrunkGeometry.translate(0, TREE_TRUNK_HEIGHT / 2, 0)
-  state.treeTrunkMesh = new THREE.InstancedMesh(state.treeTrunkGeometry, trunkMaterial, TREE_COUNT)
+  state.treeTrunkMesh = new THREE.InstancedMesh(state.treeTrunkGeometry, trunkMaterial, treeMeshCapacity)
   state.treeTrunkMesh.instanceMatrix.setUsage(THREE.StaticDrawUsage)
   state.treeTrunkMesh.frustumCulled = false
   state.treeTrunkMesh.count = treeInstanceCount
@@ -495,7 +501,7 @@ export const buildEnvironmentRuntime = (
     CACTUS_TUBE_RADIAL_SEGMENTS,
     false,
   )
-  state.cactusTrunkMesh = new THREE.InstancedMesh(state.cactusTrunkGeometry, cactusBodyMaterial, TREE_COUNT)
+  state.cactusTrunkMesh = new THREE.InstancedMesh(state.cactusTrunkGeometry, cactusBodyMaterial, treeMeshCapacity)
   state.cactusTrunkMesh.instanceMatrix.setUsage(THREE.StaticDrawUsage)
   state.cactusTrunkMesh.frustumCulled = false
   state.cactusTrunkMesh.count = 0
@@ -518,7 +524,7 @@ export const buildEnvironmentRuntime = (
       false,
     )
     state.cactusPartGeometries.push(geometry)
-    const mesh = new THREE.InstancedMesh(geometry, cactusArmMat, TREE_COUNT)
+    const mesh = new THREE.InstancedMesh(geometry, cactusArmMat, treeMeshCapacity)
     mesh.instanceMatrix.setUsage(THREE.StaticDrawUsage)
     mesh.frustumCulled = false
     mesh.count = 0
@@ -566,7 +572,7 @@ export const buildEnvironmentRuntime = (
     const geometry = new THREE.SphereGeometry(spec.radius, 8, 6)
     geometry.translate(spec.point.x, spec.point.y, spec.point.z)
     state.cactusPartGeometries.push(geometry)
-    const mesh = new THREE.InstancedMesh(geometry, spec.material, TREE_COUNT)
+    const mesh = new THREE.InstancedMesh(geometry, spec.material, treeMeshCapacity)
     mesh.instanceMatrix.setUsage(THREE.StaticDrawUsage)
     mesh.frustumCulled = false
     mesh.count = 0
@@ -583,7 +589,7 @@ export const buildEnvironmentRuntime = (
   for (let i = 0; i < MOUNTAIN_VARIANTS; i += 1) {
     const geometry = createMountainGeometry(0x3f2a9b1 + i * 57)
     state.mountainGeometries.push(geometry)
-    const mesh = new THREE.InstancedMesh(geometry, state.mountainMaterial, MOUNTAIN_COUNT)
+    const mesh = new THREE.InstancedMesh(geometry, state.mountainMaterial, mountainMeshCapacity)
     mesh.instanceMatrix.setUsage(THREE.StaticDrawUsage)
     mesh.frustumCulled = false
     mesh.count = 0
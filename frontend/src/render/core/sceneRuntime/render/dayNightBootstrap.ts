@@ -16,6 +16,7 @@ export type DayNightBootstrap = {
     dayNightFactor: number
     dayNightCycleMs: number
     dayNightSourceNowMs: number | null
+    serverDayPhase: number | null
     lastSkyGradientFactor: number
     lastPlanetScreenCenterX: number
     lastPlanetScreenCenterY: number
@@ -29,6 +30,7 @@ export type DayNightBootstrap = {
     sourceNowMs: number | null
   }
   setDayNightDebugMode: (mode: DayNightDebugMode) => void
+  setServerDayPhase: (phase: number | null) => void
   skyGroup: THREE.Group
   skyGradient: ReturnType<typeof createSkyGradientTexture>
   skyTopTemp: THREE.Color
@@ -65,6 +67,7 @@ export const createDayNightBootstrap = async (
   let dayNightFactor = 1
   let dayNightCycleMs = SCENE_CONSTANTS.DAY_NIGHT_CYCLE_MS
   let dayNightSourceNowMs: number | null = null
+  let serverDayPhase: number | null = null
   let lastSkyGradientFactor = Number.NaN
   let lastPlanetScreenCenterX = 0.5
   let lastPlanetScreenCenterY = 0.5
@@ -100,6 +103,12 @@ export const createDayNightBootstrap = async (
     set dayNightSourceNowMs(value: number | null) {
       dayNightSourceNowMs = value
     },
+    get serverDayPhase() {
+      return serverDayPhase
+    },
+    set serverDayPhase(value: number | null) {
+      serverDayPhase = value
+    },
     get lastSkyGradientFactor() {
       return lastSkyGradientFactor
     },
@@ -320,6 +329,9 @@ export const createDayNightBootstrap = async (
       }
       dayNightDebugMode = 'auto'
     },
+    setServerDayPhase: (phase: number | null) => {
+      serverDayPhase = phase !== null && Number.isFinite(phase) ? phase : null
+    },
     skyGroup,
     skyGradient,
     skyTopTemp,
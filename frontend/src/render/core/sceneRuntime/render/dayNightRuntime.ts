@@ -54,6 +54,7 @@ type DayNightState = {
   dayNightFactor: number
   dayNightCycleMs: number
   dayNightSourceNowMs: number | null
+  serverDayPhase: number | null
   lastSkyGradientFactor: number
   lastPlanetScreenCenterX: number
   lastPlanetScreenCenterY: number
@@ -149,9 +150,14 @@ export const createDayNightRuntime = (
       state.dayNightDebugMode === 'accelerated'
         ? DAY_NIGHT_CYCLE_ACCELERATED_MS
         : DAY_NIGHT_CYCLE_MS
-    const wrapped =
-      ((sourceNowMs % state.dayNightCycleMs) + state.dayNightCycleMs) % state.dayNightCycleMs
-    state.dayNightPhase = wrapped / state.dayNightCycleMs
+    if (state.dayNightDebugMode === 'auto' && state.serverDayPhase !== null) {
+      // The server counts from midday while the sky wave below starts at midnight.
+      state.dayNightPhase = (((state.serverDayPhase + 0.5) % 1) + 1) % 1
+    } else {
+      const wrapped =
+        ((sourceNowMs % state.dayNightCycleMs) + state.dayNightCycleMs) % state.dayNightCycleMs
+      state.dayNightPhase = wrapped / state.dayNightCycleMs
+    }
     const daylightWave = Math.sin(state.dayNightPhase * DAY_NIGHT_TAU - Math.PI * 0.5) * 0.5 + 0.5
     state.dayNightFactor = smoothstep(DAY_NIGHT_DAY_EDGE_START, DAY_NIGHT_DAY_EDGE_END, daylightWave)
     return state.dayNightFactor
@@ -107,6 +107,7 @@ export type RenderScene = {
     terrainTessellation?: boolean
   }) => void
   setDayNightDebugMode: (mode: DayNightDebugMode) => void
+  setServerDayPhase?: (phase: number | null) => void
   dispose: () => void
 }
 
@@ -5,24 +5,88 @@ export type MatchmakeResponse = {
   roomToken: string
   capacity: number
   expiresAt: number
+  // Control-plane region the room runs in; standalone backends omit it.
+  region?: string
 }
 
 type MatchmakeRequest = {
   preferredRoom?: string
+  region?: string
+  // Lets the control plane recognize a retry and return the original seat instead of a new one.
+  idempotencyKey?: string
 }
 
-export async function requestMatchmake(preferredRoom?: string): Promise<MatchmakeResponse> {
-  const body: MatchmakeRequest = {}
+type MatchmakeQueuedResponse = {
+  ticket: string
+  queuePosition: number
+}
+
+const MATCHMAKE_STATUS_POLL_MS = 2000
+const MATCHMAKE_NETWORK_RETRIES = 1
+
+export type MatchmakeQueueListener = (queuePosition: number) => void
+
+export async function requestMatchmake(
+  preferredRoom?: string,
+  onQueued?: MatchmakeQueueListener,
+): Promise<MatchmakeResponse> {
+  const body: MatchmakeRequest = { idempotencyKey: newIdempotencyKey() }
   if (preferredRoom && preferredRoom.trim()) {
     body.preferredRoom = preferredRoom.trim()
   }
-  const response = await fetch(resolveApiUrl('/api/matchmake'), {
-    method: 'POST',
-    headers: { 'Content-Type': 'application/json' },
-    body: JSON.stringify(body),
-  })
+  const response = await postMatchmake(body)
   if (!response.ok) {
     throw new Error(`Matchmake request failed (${response.status})`)
   }
+  if (response.status === 202) {
+    const queued = (await response.json()) as MatchmakeQueuedResponse
+    return pollMatchmakeTicket(queued, onQueued)
+  }
   return (await response.json()) as MatchmakeResponse
 }
+
+const newIdempotencyKey = () => {
+  try {
+    return crypto.randomUUID()
+  } catch {
+    return `${Date.now()}-${Math.floor(Math.random() * 1e9)}`
+  }
+}
+
+// Network failures are retried with the same idempotency key, so a request that reached the
+// control plane before the connection dropped doesn't reserve a second seat.
+async function postMatchmake(body: MatchmakeRequest): Promise<Response> {
+  for (let attempt = 0; ; attempt += 1) {
+    try {
+      return await fetch(resolveApiUrl('/api/matchmake'), {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify(body),
+      })
+    } catch (error) {
+      if (attempt >= MATCHMAKE_NETWORK_RETRIES) throw error
+    }
+  }
+}
+
+// The control plane queues requests while it can't provision rooms; poll until a seat frees up.
+async function pollMatchmakeTicket(
+  queued: MatchmakeQueuedResponse,
+  onQueued?: MatchmakeQueueListener,
+): Promise<MatchmakeResponse> {
+  let position = queued.queuePosition
+  for (;;) {
+    onQueued?.(position)
+    await new Promise((resolve) => window.setTimeout(resolve, MATCHMAKE_STATUS_POLL_MS))
+    const statusUrl = resolveApiUrl(`/api/matchmake/status?ticket=${encodeURIComponent(queued.ticket)}`)
+    const response = await fetch(statusUrl)
+    if (!response.ok) {
+      throw new Error(`Matchmake status request failed (${response.status})`)
+    }
+    if (response.status === 202) {
+      position = ((await response.json()) as MatchmakeQueuedResponse).queuePosition
+      continue
+    }
+    return (await response.json()) as MatchmakeResponse
+  }
+}
@@ -12,6 +12,9 @@ export default {
     if (url.pathname === '/api/matchmake') {
       return proxyMatchmake(request, env)
     }
+    if (url.pathname === '/api/matchmake/status') {
+      return proxyMatchmakeStatus(request, env, url)
+    }
     if (url.pathname.startsWith('/api/room/')) {
       return proxyRoomWebSocket(request, env, url)
     }
@@ -31,6 +34,18 @@ async function proxyMatchmake(request: Request, env: Env): Promise<Response> {
   return fetch(upstreamRequest)
 }
 
+async function proxyMatchmakeStatus(request: Request, env: Env, url: URL): Promise<Response> {
+  if (request.method !== 'GET') {
+    return new Response('Method Not Allowed', { status: 405 })
+  }
+  if (!env.CONTROL_PLANE_ORIGIN) {
+    return new Response('CONTROL_PLANE_ORIGIN not configured', { status: 500 })
+  }
+  const upstreamUrl = new URL('/api/matchmake/status', env.CONTROL_PLANE_ORIGIN)
+  upstreamUrl.search = url.search
+  return fetch(new Request(upstreamUrl.toString(), request))
+}
+
 async function proxyRoomWebSocket(request: Request, env: Env, url: URL): Promise<Response> {
   if (!env.ROOM_TOKEN_SECRET || !env.ROOM_PROXY_SECRET) {
     return new Response('Worker secrets not configured', { status: 500 })